@@ -4,14 +4,23 @@
 #![warn(unused_crate_dependencies, unused_extern_crates)]
 #![warn(variant_size_differences)]
 
-use std::{ffi::OsStr, fs::File, ops::Deref, panic, path::PathBuf};
+use std::{
+	ffi::OsStr,
+	fs::File,
+	io::{stdin, stdout, Read, Write},
+	panic,
+	path::{Path, PathBuf},
+};
 
-use clap::{crate_version, Args, Parser, Subcommand};
+use clap::{crate_version, Args, Parser, Subcommand, ValueEnum};
 use env_logger::{fmt::TimestampPrecision, Builder};
 use humantime::Duration;
+use ignore::WalkBuilder;
 use log::warn;
 use miette::{IntoDiagnostic, Report, Result};
-use shackle::{error::InternalError, Error, Message, Model, Solver, Status};
+use shackle::{error::InternalError, DataSource, Error, InputLang, Message, Model, Solver, Status};
+use shackle_fmt::MiniZincFormatOptions;
+use similar::TextDiff;
 
 /// The main function is the entry point for the `shackle` executable.
 ///
@@ -57,6 +66,7 @@ fn main() -> Result<()> {
 		SubCommand::Compile(c) => c.dispatch(),
 		SubCommand::Solve(s) => s.dispatch(),
 		SubCommand::Check(c) => c.dispatch(),
+		SubCommand::Fmt(f) => f.dispatch(),
 	}) {
 		Err(_) => Err(InternalError::new("Panic occurred during execution").into()),
 		Ok(res) => res,
@@ -85,6 +95,7 @@ enum SubCommand {
 	Compile(Box<Compile>),
 	Solve(Box<Solve>),
 	Check(Box<Check>),
+	Fmt(Box<Fmt>),
 }
 
 /// Solve the given model instance using the given solver
@@ -94,6 +105,24 @@ struct Solve {
 	statistics: bool,
 	#[arg(long)]
 	time_limit: Option<Duration>,
+	/// Find all solutions (or all improving solutions for optimisation problems)
+	#[arg(short = 'a', long)]
+	all_solutions: bool,
+	/// Stop after finding the given number of solutions
+	#[arg(short = 'n', long, value_name = "N")]
+	num_solutions: Option<usize>,
+	/// Seed for the random number generator of the solver
+	#[arg(short = 'r', long, value_name = "SEED")]
+	random_seed: Option<u64>,
+	/// Number of threads the solver is allowed to use
+	#[arg(short = 'p', long, value_name = "N")]
+	parallel: Option<usize>,
+	/// Allow the solver to ignore the search annotations of the model
+	#[arg(short = 'f', long)]
+	free_search: bool,
+	/// Flag passed directly to the solver (e.g., `--solver-flag --restart=luby`)
+	#[arg(long, value_name = "FLAG[=VALUE]", value_parser = parse_solver_flag, allow_hyphen_values = true)]
+	solver_flag: Vec<(String, Option<String>)>,
 	#[command(flatten)]
 	base: Compile,
 }
@@ -102,20 +131,38 @@ impl Solve {
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, data) = self.base.sort_files()?;
+		let (model, data) = self.base.sort_files(&mut stdin())?;
 		let slv = self.base.solver()?;
 
 		// Construct model, typecheck, and compile into program
 		let model = Model::from_file(model);
 		let mut program = model.compile(&slv)?;
 
-		program.add_data_files(data.iter().map(|f| f.deref()))?;
+		program.add_data(&data)?;
 
 		// Set program options
 		if let Some(time_limit) = self.time_limit {
 			program = program.with_time_limit(time_limit.into());
 		}
 		program = program.with_statistics(self.statistics);
+		if self.all_solutions {
+			program = program.with_all_solutions();
+		}
+		if let Some(n) = self.num_solutions {
+			program = program.with_num_solutions(n);
+		}
+		if let Some(seed) = self.random_seed {
+			program = program.with_seed(seed);
+		}
+		if let Some(n) = self.parallel {
+			program = program.with_threads(n);
+		}
+		if self.free_search {
+			program = program.with_free_search();
+		}
+		for (flag, value) in &self.solver_flag {
+			program = program.with_solver_flag(flag, value.clone());
+		}
 
 		// Run resulting program and show results
 		let display_fn = |x: &Message| {
@@ -135,6 +182,14 @@ impl Solve {
 	}
 }
 
+/// Split a solver flag given on the command line into the flag and its value
+fn parse_solver_flag(s: &str) -> std::result::Result<(String, Option<String>), String> {
+	match s.split_once('=') {
+		Some((flag, value)) => Ok((flag.to_owned(), Some(value.to_owned()))),
+		None => Ok((s.to_owned(), None)),
+	}
+}
+
 /// Check model files for correctness
 #[derive(Args)]
 struct Check {
@@ -150,35 +205,222 @@ impl Check {
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, data) = self.base.sort_files()?;
+		let (model, data) = self.base.sort_files(&mut stdin())?;
 
 		let slv = self.base.solver()?;
 		let model = Model::from_file(model);
 		let errors = model.check(&slv, &data, self.check_complete);
 
-		if errors.is_empty() {
-			Ok(())
-		} else {
-			Err(Error::try_from(errors).unwrap().into())
+		match Error::try_from(errors) {
+			Ok(err) => Err(err.into()),
+			Err(_) => Ok(()),
 		}
 	}
 }
 
+/// Format model files
+///
+/// MiniZinc and Essence' models are supported. The formatting options are read
+/// from the nearest `.shackle-fmt.toml` file in the directory of each model or
+/// one of its parents. Directories are searched recursively for models,
+/// skipping the files matched by ignore files such as `.gitignore`.
+#[derive(Args)]
+struct Fmt {
+	/// Only check whether the files are formatted, without changing them
+	#[arg(long)]
+	check: bool,
+	/// Print the changes as a unified diff, without changing the files
+	#[arg(long)]
+	diff: bool,
+	/// Format the model read from the standard input, writing the result to
+	/// the standard output
+	#[arg(long, conflicts_with = "paths")]
+	stdin: bool,
+	/// Path used to find the formatting options and the language of the model
+	/// read from the standard input (MiniZinc with the default options if not given)
+	#[arg(long, value_name = "PATH", requires = "stdin")]
+	stdin_filepath: Option<PathBuf>,
+	/// Model files, or directories containing model files
+	#[arg(required_unless_present = "stdin")]
+	paths: Vec<PathBuf>,
+}
+
+impl Fmt {
+	/// The dispatch method formats each of the given files in place, or
+	/// reports the files which are not formatted.
+	pub fn dispatch(&self) -> Result<()> {
+		self.run(&mut stdin(), &mut stdout())
+	}
+
+	/// Format the files or the standard input, writing any output to `out`
+	fn run(&self, stdin: &mut impl Read, out: &mut impl Write) -> Result<()> {
+		if self.stdin {
+			return self.format_stdin(stdin, out);
+		}
+		let mut errors: Vec<Error> = Vec::new();
+		let mut unformatted = Vec::new();
+		for (f, lang) in self.files()? {
+			let options = MiniZincFormatOptions::for_file(&f).into_diagnostic()?;
+			let source = std::fs::read_to_string(&f).into_diagnostic()?;
+			let formatted = match shackle_fmt::try_format(&source, lang, Some(&f), &options) {
+				Ok(formatted) => formatted,
+				Err(e) => {
+					errors.push(e.into());
+					continue;
+				}
+			};
+			if formatted == source {
+				continue;
+			}
+			if self.diff {
+				write_diff(out, &f.display().to_string(), &source, &formatted)?;
+			}
+			if self.check {
+				unformatted.push(format!("`{}'", f.display()));
+			} else if !self.diff {
+				std::fs::write(&f, formatted).into_diagnostic()?;
+			}
+		}
+		if let Ok(err) = Error::try_from(errors) {
+			return Err(err.into());
+		}
+		if !unformatted.is_empty() {
+			return Err(Report::msg(format!(
+				"the following files are not formatted: {}",
+				unformatted.join(", ")
+			)));
+		}
+		Ok(())
+	}
+
+	/// Format the model given on the standard input
+	fn format_stdin(&self, stdin: &mut impl Read, out: &mut impl Write) -> Result<()> {
+		let mut source = String::new();
+		stdin.read_to_string(&mut source).into_diagnostic()?;
+		let (options, lang) = match &self.stdin_filepath {
+			Some(path) => (
+				MiniZincFormatOptions::for_file(path).into_diagnostic()?,
+				model_lang(path).unwrap_or(InputLang::MiniZinc),
+			),
+			None => (MiniZincFormatOptions::default(), InputLang::MiniZinc),
+		};
+		let path = self.stdin_filepath.as_deref();
+		let formatted = shackle_fmt::try_format(&source, lang, path, &options)?;
+		if self.diff {
+			if formatted != source {
+				let name = path.map_or("<stdin>".to_owned(), |p| p.display().to_string());
+				write_diff(out, &name, &source, &formatted)?;
+			}
+		} else if !self.check {
+			write!(out, "{}", formatted).into_diagnostic()?;
+		}
+		if self.check && formatted != source {
+			return Err(Report::msg("the standard input is not formatted"));
+		}
+		Ok(())
+	}
+
+	/// Collect the model files to format along with their languages, searching
+	/// the given directories recursively
+	fn files(&self) -> Result<Vec<(PathBuf, InputLang)>> {
+		let mut files = Vec::new();
+		for path in self.paths.iter() {
+			if path.is_dir() {
+				let walk = WalkBuilder::new(path)
+					.sort_by_file_name(|a, b| a.cmp(b))
+					.build();
+				for entry in walk {
+					let entry = entry.into_diagnostic()?;
+					if !entry.file_type().is_some_and(|t| t.is_file()) {
+						continue;
+					}
+					if let Some(lang) = model_lang(entry.path()) {
+						files.push((entry.into_path(), lang));
+					}
+				}
+			} else if let Some(lang) = model_lang(path) {
+				files.push((path.clone(), lang));
+			} else {
+				return Err(Report::msg(format!(
+					"file `{}' has an unsupported file type",
+					path.display()
+				)));
+			}
+		}
+		Ok(files)
+	}
+}
+
+/// Get the language of a model file from its extension
+fn model_lang(path: &Path) -> Option<InputLang> {
+	match path.extension().and_then(OsStr::to_str) {
+		Some("mzn") => Some(InputLang::MiniZinc),
+		Some("eprime") => Some(InputLang::EPrime),
+		_ => None,
+	}
+}
+
+/// Write the changes made by formatting as a unified diff
+fn write_diff(out: &mut impl Write, name: &str, source: &str, formatted: &str) -> Result<()> {
+	write!(
+		out,
+		"{}",
+		TextDiff::from_lines(source, formatted)
+			.unified_diff()
+			.header(name, name)
+	)
+	.into_diagnostic()
+}
+
 /// Compile the given model to a shackle intermediate format
 #[derive(Args)]
 pub struct Compile {
 	#[arg(long, default_value = "gecode")]
 	solver: String,
+	/// Data given directly on the command line (e.g., `-D "n = 5;"`)
+	#[arg(short = 'D', long = "cmdline-data", value_name = "DZN")]
+	cmdline_data: Vec<String>,
+	/// Data file to be used, or `-' to read the data from the standard input
+	#[arg(long, value_name = "FILE")]
+	data: Vec<PathBuf>,
+	/// Format of the data read from the standard input (required when using `--data -')
+	#[arg(long, value_name = "FORMAT")]
+	stdin_data_format: Option<DataFormat>,
 	#[arg(required = true)]
 	files: Vec<PathBuf>,
 }
 
+/// Format of data given on the standard input
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DataFormat {
+	/// DataZinc
+	Dzn,
+	/// JSON
+	Json,
+	/// Essence' parameters
+	Param,
+}
+
+impl From<DataFormat> for InputLang {
+	fn from(format: DataFormat) -> Self {
+		match format {
+			DataFormat::Dzn => InputLang::DataZinc,
+			DataFormat::Json => InputLang::Json,
+			DataFormat::Param => InputLang::EPrimeParam,
+		}
+	}
+}
+
 impl Compile {
 	/// Sort through the files in the command line arguments and split the model
 	/// from the data
-	pub fn sort_files(&self) -> Result<(PathBuf, Vec<PathBuf>)> {
+	///
+	/// Data given as `-' is read from `stdin`, using the format given by
+	/// `--stdin-data-format`.
+	pub fn sort_files(&self, stdin: &mut impl Read) -> Result<(PathBuf, Vec<DataSource>)> {
 		let mut model_file: Option<PathBuf> = None;
-		let mut data = Vec::with_capacity(self.files.len() - 1);
+		let mut data =
+			Vec::with_capacity(self.files.len() - 1 + self.data.len() + self.cmdline_data.len());
 		for f in self.files.iter() {
 			match f.extension().and_then(OsStr::to_str) {
 				Some("mzn") | Some("eprime") => {
@@ -192,7 +434,9 @@ impl Compile {
 						model_file = Some(f.clone())
 					}
 				}
-				Some("json") | Some("dzn") => data.push(f.clone()),
+				Some("json") | Some("dzn") | Some("param") => {
+					data.push(DataSource::File(f.clone()))
+				}
 				_ => {
 					return Err(Report::msg(format!(
 						"file `{}' has an unsupported file type",
@@ -201,6 +445,40 @@ impl Compile {
 				}
 			}
 		}
+		let mut read_stdin = false;
+		for f in self.data.iter() {
+			if f.as_os_str() == "-" {
+				if read_stdin {
+					return Err(Report::msg(
+						"the standard input can only be used once as a data source",
+					));
+				}
+				read_stdin = true;
+				let Some(format) = self.stdin_data_format else {
+					return Err(Report::msg(
+						"the format of data read from the standard input must be given using `--stdin-data-format'",
+					));
+				};
+				let mut text = String::new();
+				stdin.read_to_string(&mut text).into_diagnostic()?;
+				data.push(DataSource::Text(text, format.into()));
+			} else if matches!(
+				f.extension().and_then(OsStr::to_str),
+				Some("json") | Some("dzn") | Some("param")
+			) {
+				data.push(DataSource::File(f.clone()))
+			} else {
+				return Err(Report::msg(format!(
+					"data file `{}' has an unsupported file type",
+					f.to_str().unwrap_or_default()
+				)));
+			}
+		}
+		data.extend(
+			self.cmdline_data
+				.iter()
+				.map(|d| DataSource::Text(d.clone(), InputLang::DataZinc)),
+		);
 		if let Some(f) = model_file {
 			Ok((f, data))
 		} else {
@@ -222,7 +500,7 @@ impl Compile {
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, _data) = self.sort_files()?;
+		let (model, _data) = self.sort_files(&mut stdin())?;
 
 		let filename = model.with_extension("shackle.mzn");
 
@@ -234,3 +512,200 @@ impl Compile {
 		prg.write(&mut file).into_diagnostic()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, io::Cursor, path::PathBuf};
+
+	use clap::Parser;
+	use miette::Result;
+	use shackle::{DataSource, InputLang};
+
+	use super::{Cli, Compile, Fmt, Solve, SubCommand};
+
+	fn parse_compile(args: &[&str]) -> Box<Compile> {
+		let cli = Cli::try_parse_from(["shackle", "compile"].iter().chain(args)).unwrap();
+		let SubCommand::Compile(c) = cli.subcmd else {
+			unreachable!()
+		};
+		c
+	}
+
+	fn parse_solve(args: &[&str]) -> Box<Solve> {
+		let cli = Cli::try_parse_from(["shackle", "solve"].iter().chain(args)).unwrap();
+		let SubCommand::Solve(s) = cli.subcmd else {
+			unreachable!()
+		};
+		s
+	}
+
+	/// Run the fmt subcommand with the given standard input, returning its result and output
+	fn run_fmt(args: &[&str], input: &str) -> (Result<()>, String) {
+		let cli = Cli::try_parse_from(["shackle", "fmt"].iter().chain(args)).unwrap();
+		let SubCommand::Fmt(f) = cli.subcmd else {
+			unreachable!()
+		};
+		let mut out = Vec::new();
+		let result = f.run(&mut Cursor::new(input), &mut out);
+		(result, String::from_utf8(out).unwrap())
+	}
+
+	#[test]
+	fn test_sort_files_stdin() {
+		let c = parse_compile(&["--data", "-", "--stdin-data-format", "json", "model.mzn"]);
+		let (model, data) = c.sort_files(&mut Cursor::new(r#"{"n": 1}"#)).unwrap();
+		assert_eq!(model, PathBuf::from("model.mzn"));
+		assert_eq!(
+			data,
+			[DataSource::Text(r#"{"n": 1}"#.to_owned(), InputLang::Json)]
+		);
+
+		// The format of the data cannot be guessed
+		let c = parse_compile(&["--data", "-", "model.mzn"]);
+		let err = c.sort_files(&mut Cursor::new("n = 1;")).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"the format of data read from the standard input must be given using `--stdin-data-format'"
+		);
+	}
+
+	#[test]
+	fn test_solve_solver_options() {
+		let s = parse_solve(&["model.mzn"]);
+		assert!(!s.all_solutions && !s.free_search);
+		assert_eq!(
+			(s.num_solutions, s.random_seed, s.parallel),
+			(None, None, None)
+		);
+		assert!(s.solver_flag.is_empty());
+
+		let s = parse_solve(&[
+			"-a",
+			"-n",
+			"3",
+			"-r",
+			"42",
+			"-p",
+			"4",
+			"-f",
+			"--solver-flag",
+			"--restart=luby",
+			"--solver-flag",
+			"--verbose-solving",
+			"model.mzn",
+		]);
+		assert!(s.all_solutions && s.free_search);
+		assert_eq!(
+			(s.num_solutions, s.random_seed, s.parallel),
+			(Some(3), Some(42), Some(4))
+		);
+		assert_eq!(
+			s.solver_flag,
+			[
+				("--restart".to_owned(), Some("luby".to_owned())),
+				("--verbose-solving".to_owned(), None)
+			]
+		);
+		assert_eq!(s.base.files, [PathBuf::from("model.mzn")]);
+	}
+
+	#[test]
+	fn test_fmt_stdin() {
+		let (result, out) = run_fmt(&["--stdin"], "int:x=1;");
+		assert!(result.is_ok());
+		assert_eq!(out, "int: x = 1;\n");
+
+		let (result, out) = run_fmt(&["--stdin", "--check"], "int:x=1;");
+		assert_eq!(
+			result.unwrap_err().to_string(),
+			"the standard input is not formatted"
+		);
+		assert_eq!(out, "");
+		let (result, _) = run_fmt(&["--stdin", "--check"], "int: x = 1;\n");
+		assert!(result.is_ok());
+
+		let (result, out) = run_fmt(&["--stdin", "--diff"], "int:x=1;\n");
+		assert!(result.is_ok());
+		assert!(out.starts_with("--- <stdin>\n+++ <stdin>\n"));
+		assert!(out.contains("-int:x=1;\n+int: x = 1;\n"));
+
+		// A syntax error is reported instead of formatting the model
+		let (result, out) = run_fmt(&["--stdin"], "int: x = ;");
+		assert_eq!(result.unwrap_err().to_string(), "Syntax Error");
+		assert_eq!(out, "");
+	}
+
+	#[test]
+	fn test_fmt_stdin_filepath() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(
+			dir.path().join(".shackle-fmt.toml"),
+			"max_blank_lines = 2\n",
+		)
+		.unwrap();
+		let model = "int: x = 1;\n\n\nint: y = 2;\n";
+
+		// The options are found using the given path
+		let path = dir.path().join("model.mzn");
+		let path = path.to_str().unwrap();
+		let (result, _) = run_fmt(&["--stdin", "--check", "--stdin-filepath", path], model);
+		assert!(result.is_ok());
+		let (result, _) = run_fmt(&["--stdin", "--check"], model);
+		assert!(result.is_err());
+
+		// The path can only be used for the standard input
+		assert!(Cli::try_parse_from(["shackle", "fmt", "--stdin-filepath", path, path]).is_err());
+	}
+
+	#[test]
+	fn test_fmt_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let dir = dir.path();
+		fs::create_dir_all(dir.join("sub")).unwrap();
+		fs::create_dir_all(dir.join("ignored")).unwrap();
+		fs::write(dir.join(".ignore"), "ignored/\n").unwrap();
+		for f in ["a.mzn", "sub/b.mzn", "ignored/c.mzn", "notes.txt"] {
+			fs::write(dir.join(f), "int:x=1;\n").unwrap();
+		}
+		let contents = |f: &str| fs::read_to_string(dir.join(f)).unwrap();
+		let d = dir.to_str().unwrap();
+
+		// Directories are searched recursively, skipping ignored files
+		let (result, out) = run_fmt(&["--check", d], "");
+		assert_eq!(
+			result.unwrap_err().to_string(),
+			format!(
+				"the following files are not formatted: `{}', `{}'",
+				dir.join("a.mzn").display(),
+				dir.join("sub/b.mzn").display()
+			)
+		);
+		assert_eq!(out, "");
+
+		let (result, out) = run_fmt(&["--diff", d], "");
+		assert!(result.is_ok());
+		for f in ["a.mzn", "sub/b.mzn"] {
+			assert!(out.contains(&format!("--- {}\n", dir.join(f).display())));
+		}
+		assert!(!out.contains("c.mzn"));
+		assert_eq!(contents("a.mzn"), "int:x=1;\n");
+
+		let (result, out) = run_fmt(&[d], "");
+		assert!(result.is_ok());
+		assert_eq!(out, "");
+		assert_eq!(contents("a.mzn"), "int: x = 1;\n");
+		assert_eq!(contents("sub/b.mzn"), "int: x = 1;\n");
+		assert_eq!(contents("ignored/c.mzn"), "int:x=1;\n");
+		assert_eq!(contents("notes.txt"), "int:x=1;\n");
+		assert!(run_fmt(&["--check", d], "").0.is_ok());
+
+		// Syntax errors are reported for all files
+		fs::write(dir.join("d.mzn"), "int: x = ;").unwrap();
+		let (result, _) = run_fmt(&[d], "");
+		assert_eq!(result.unwrap_err().to_string(), "Syntax Error");
+
+		// Files must be models
+		let (result, _) = run_fmt(&[dir.join("notes.txt").to_str().unwrap()], "");
+		assert!(result.is_err());
+	}
+}
@@ -134,6 +134,10 @@ id_registry!(
 	conj: "/\\",
 	disj: "\\/",
 	imp: "->",
+	rimp: "<-",
+	equiv: "<->",
+	not,
+	clause,
 	card,
 	mzn_get_enum,
 	mzn_defining_set,
@@ -162,5 +166,21 @@ id_registry!(
 	mzn_slice_internal,
 	mzn_construct_partial,
 	mzn_destruct_partial,
+	mzn_array_access_valid,
+	assert,
+	promise_total,
+	div,
+	mod_: "mod",
+	float_div: "/",
+	ne: "!=",
+	ctx_root,
+	ctx_pos,
+	ctx_neg,
+	ctx_mix,
+	promise_ctx_monotone,
+	promise_ctx_antitone,
+	element,
+	if_then_else,
 	mzn_unreachable,
+	main,
 );
@@ -69,6 +69,31 @@ pub struct FileError {
 	pub other: Vec<FileError>,
 }
 
+/// An error starting or communicating with an external process
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Could not run {command:?}: {message}")]
+#[diagnostic(code(shackle::process_error))]
+pub struct ProcessError {
+	/// The command that was executed
+	pub command: String,
+	/// The underlying error message
+	pub message: String,
+}
+
+/// A solver option that is not supported by the selected solver
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("The solver {solver} does not support the {flag} flag")]
+#[diagnostic(
+	code(shackle::unsupported_solver_flag),
+	help("The flags supported by a solver are listed in its configuration file.")
+)]
+pub struct UnsupportedSolverFlag {
+	/// The identifier of the solver
+	pub solver: String,
+	/// The flag that is not supported
+	pub flag: String,
+}
+
 /// A syntax error
 #[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
 #[error("Syntax Error")]
@@ -304,6 +329,8 @@ pub struct NoMatchingFunction {
 	/// The span associated with the error
 	#[label("{msg}")]
 	pub span: SourceSpan,
+	/// The name of the function if no function with this name exists
+	pub undefined: Option<String>,
 }
 
 /// Ambiguous call
@@ -458,6 +485,8 @@ pub struct NonExhaustivePatternMatching {
 	/// The span associated with the error
 	#[label("{msg}")]
 	pub span: SourceSpan,
+	/// The case which is not covered
+	pub pattern: String,
 }
 
 /// Invalid numeric literal
@@ -490,6 +519,108 @@ pub struct TypeSpecialisationRecursionLimit {
 	pub span: SourceSpan,
 }
 
+/// A partially defined expression where a total expression is required
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Partially defined expression")]
+#[diagnostic(
+	code(shackle::partial_expression),
+	help("Use 'default' to give a value for when the expression is undefined.")
+)]
+pub struct PartialExpression {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The error message
+	pub msg: String,
+	/// The span associated with the error
+	#[label("{msg}")]
+	pub span: SourceSpan,
+}
+
+/// An error raised while evaluating a model
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Evaluation error")]
+#[diagnostic(code(shackle::evaluation_error))]
+pub struct EvaluationError {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The error message
+	pub msg: String,
+	/// The span associated with the error
+	#[label("{msg}")]
+	pub span: SourceSpan,
+}
+
+/// An expression which cannot be lowered to MIR
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Unsupported expression")]
+#[diagnostic(code(shackle::unsupported_expression))]
+pub struct UnsupportedExpression {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The error message
+	pub msg: String,
+	/// The span associated with the error
+	#[label("{msg}")]
+	pub span: SourceSpan,
+}
+
+/// Data is missing for inputs or enumerated types
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Missing data for {} input(s)", missing.len())]
+#[diagnostic(
+	code(shackle::missing_input_data),
+	help("Provide values for these inputs using data files.")
+)]
+pub struct MissingInputData {
+	/// The inputs that have not been assigned
+	#[related]
+	pub missing: Vec<MissingAssignment>,
+}
+
+/// An input or enumerated type that has not been assigned
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("No value was assigned to '{identifier}'")]
+#[diagnostic(code(shackle::missing_assignment))]
+pub struct MissingAssignment {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The span associated with the error
+	#[label("{identifier} of type {ty} is declared here")]
+	pub span: SourceSpan,
+	/// The identifier which has not been assigned
+	pub identifier: String,
+	/// The declared type of the identifier
+	pub ty: String,
+}
+
+/// Data was given in a language that is not a data input language
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("{lang} is not a data input language")]
+#[diagnostic(
+	code(shackle::invalid_data_language),
+	help("Data can be given in DataZinc, JSON, or Essence' parameter format.")
+)]
+pub struct InvalidDataLanguage {
+	/// The language in which the data was given
+	pub lang: String,
+}
+
+/// A model was given in a language that is not a model language
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("{lang} is not a model language")]
+#[diagnostic(
+	code(shackle::invalid_model_language),
+	help("Models can be written in MiniZinc or Essence'.")
+)]
+pub struct InvalidModelLanguage {
+	/// The language in which the model was given
+	pub lang: String,
+}
+
 /// Main Shackle error type
 #[derive(Error, Diagnostic, Debug, PartialEq, Eq, Clone)]
 pub enum Error {
@@ -501,6 +632,14 @@ pub enum Error {
 	#[error(transparent)]
 	#[diagnostic(transparent)]
 	FileError(#[from] FileError),
+	/// An external process error
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	ProcessError(#[from] ProcessError),
+	/// An unsupported solver option
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	UnsupportedSolverFlag(#[from] UnsupportedSolverFlag),
 	/// A syntax error
 	#[error(transparent)]
 	#[diagnostic(transparent)]
@@ -593,6 +732,37 @@ pub enum Error {
 	#[error(transparent)]
 	#[diagnostic(transparent)]
 	TypeSpecialisationRecursionLimit(#[from] TypeSpecialisationRecursionLimit),
+	/// Partially defined expression where a total one is required
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	PartialExpression(#[from] PartialExpression),
+	/// Data is missing for inputs
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	MissingInputData(#[from] MissingInputData),
+	/// Data given in a language that is not a data input language
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	InvalidDataLanguage(#[from] InvalidDataLanguage),
+	/// Model given in a language that is not a model language
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	InvalidModelLanguage(#[from] InvalidModelLanguage),
+	/// Error during evaluation
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	EvaluationError(#[from] EvaluationError),
+	/// Expression which cannot be lowered to MIR
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	UnsupportedExpression(#[from] UnsupportedExpression),
+	/// Flattening a model which uses the standard library
+	#[error("Flattening models which use the standard library is not supported yet.")]
+	#[diagnostic(
+		code(shackle::flatten_stdlib_unsupported),
+		help("Compile the model without the standard library, declaring the FlatZinc builtins it uses.")
+	)]
+	FlattenStandardLibraryUnsupported,
 	/// An internal error
 	#[error("Internal Error - Please report this issue to the Shackle developers")]
 	InternalError(#[from] InternalError),
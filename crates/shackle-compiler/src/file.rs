@@ -43,6 +43,8 @@ pub enum InputLang {
 	DataZinc,
 	/// JSON data input language
 	Json,
+	/// Essence' parameter data input language
+	EPrimeParam,
 }
 
 impl InputLang {
@@ -58,6 +60,7 @@ impl InputLang {
 			Some("eprime") => Self::EPrime,
 			Some("dzn") => Self::DataZinc,
 			Some("json") => Self::Json,
+			Some("param") => Self::EPrimeParam,
 			_ => Self::MiniZinc,
 		}
 	}
@@ -97,6 +100,14 @@ impl SourceFile {
 		})
 	}
 
+	/// Create a new source file from the given path and contents
+	pub fn with_path(path: PathBuf, source: Arc<String>) -> Self {
+		Self(SourceFileInner::Text {
+			name: Some(path),
+			source,
+		})
+	}
+
 	/// Create a new introduced source file
 	pub fn introduced(name: &'static str) -> Self {
 		Self(SourceFileInner::Introduced(name))
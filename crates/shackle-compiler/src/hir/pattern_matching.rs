@@ -95,6 +95,7 @@ pub fn check_case_exhaustiveness(
 						src,
 						span,
 						msg: format!("Case '{}' not covered", pat),
+						pattern: pat,
 					}
 					.into(),
 				);
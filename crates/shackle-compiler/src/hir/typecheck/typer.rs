@@ -333,6 +333,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 										.collect::<Vec<_>>()
 										.join(", ")
 								),
+								undefined: None,
 							},
 						);
 						return self.types.error;
@@ -1750,6 +1751,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 						"No function with name '{}' could be found.",
 						i.pretty_print(db)
 					),
+					undefined: Some(i.pretty_print(db)),
 				},
 			);
 			self.ctx
@@ -1935,8 +1937,15 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 
 				let (src, span) =
 					NodeRef::from(EntityRef::new(db, self.item, expr)).source_span(db);
-				self.ctx
-					.add_diagnostic(self.item, NoMatchingFunction { src, span, msg });
+				self.ctx.add_diagnostic(
+					self.item,
+					NoMatchingFunction {
+						src,
+						span,
+						msg,
+						undefined: None,
+					},
+				);
 				self.ctx
 					.add_expression(ExpressionRef::new(self.item, expr), self.types.error);
 				error
@@ -2061,6 +2070,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 										name.pretty_print(db),
 										expected.pretty_print(db.upcast())
 									),
+									undefined: None,
 								},
 							);
 							None
@@ -2079,6 +2089,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 									c.overload.params().len(),
 									arguments.len()
 								),
+								undefined: None,
 							},
 						);
 					}
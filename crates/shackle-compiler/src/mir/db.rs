@@ -0,0 +1,16 @@
+#![allow(missing_docs)]
+
+//! Salsa database for MIR operations
+
+use std::sync::Arc;
+
+use super::Model;
+use crate::{db::Upcast, thir::db::Thir, Result};
+
+/// MIR queries
+#[salsa::query_group(MirStorage)]
+pub trait Mir: Thir + Upcast<dyn Thir> {
+	/// Lower the final THIR to MIR
+	#[salsa::invoke(super::lower::lower_model)]
+	fn model_mir(&self) -> Result<Arc<Model>>;
+}
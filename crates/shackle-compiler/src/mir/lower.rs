@@ -0,0 +1,947 @@
+//! Functionality for converting the final THIR into MIR.
+//!
+//! The THIR is first prepared for lowering by the transformations in
+//! [`crate::thir::transform::mir_transforms`].
+//!
+//! The following is performed during lowering:
+//! - A `main` entrypoint function is generated which takes the model parameters
+//!   as arguments and contains the top-level declarations and constraints in a
+//!   root `let` expression
+//! - Top-level declarations are ordered so that they are declared before use,
+//!   including uses in the bodies of called functions
+//! - Nested expressions are bound to fresh identifiers so that values are in
+//!   A-normal form
+//! - Multi-branch if-then-else expressions are rewritten into nested ones
+//! - Calls to `forall` over comprehensions in root context become root-level
+//!   `forall` expressions
+//!
+//! Expressions which cannot be represented in the MIR (such as `case`
+//! expressions) are reported as errors.
+
+use std::sync::Arc;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::{
+	db::Mir, ty::Ty, Annotation, AnnotationId, AnnotationRef, Array, Call, Callable, Comprehension,
+	Constraint, Declaration, Domain, Expression, ExpressionData, Function, FunctionId, Generator,
+	Goal, IfThenElse, Let, LetItem, LiteralData, Model, Set, Solve, Tuple, TupleAccess, Value,
+	ValueData,
+};
+use crate::{
+	constants::IdentifierRegistry,
+	diagnostics::UnsupportedExpression,
+	hir::{BooleanLiteral, Identifier},
+	thir::{
+		self,
+		db::Thir,
+		source::Origin,
+		traverse::{visit_callable, visit_declaration, visit_function, Visitor},
+		Annotations, DomainData, ResolvedIdentifier,
+	},
+	utils::maybe_grow_stack,
+	Result,
+};
+
+/// Lower the final THIR into MIR
+pub fn lower_model(db: &dyn Mir) -> Result<Arc<Model>> {
+	log::info!("Lowering model to MIR");
+	let model = db.lowering_thir()?;
+	let mut lowerer = MirLowerer::new(db.upcast(), &model);
+	lowerer.collect_annotations();
+	lowerer.collect_functions();
+	lowerer.collect_main();
+	if let Some(e) = lowerer.error {
+		return Err(e.into());
+	}
+	Ok(Arc::new(lowerer.result))
+}
+
+/// Collects the top-level declarations a declaration depends on, including those used by the
+/// bodies of the functions it (transitively) calls
+#[derive(Default)]
+struct Dependencies {
+	declarations: Vec<thir::DeclarationId>,
+	functions: FxHashSet<thir::FunctionId>,
+}
+
+impl Visitor<'_> for Dependencies {
+	fn visit_identifier(&mut self, model: &thir::Model, identifier: &ResolvedIdentifier) {
+		if let ResolvedIdentifier::Declaration(d) = identifier {
+			if model[*d].top_level() {
+				self.declarations.push(*d);
+			}
+		}
+	}
+
+	fn visit_callable(&mut self, model: &thir::Model, callable: &thir::Callable) {
+		if let thir::Callable::Function(f) = callable {
+			if self.functions.insert(*f) {
+				visit_function(self, model, *f, true);
+			}
+			return;
+		}
+		visit_callable(self, model, callable);
+	}
+}
+
+/// Lowers THIR items to MIR
+struct MirLowerer<'a> {
+	db: &'a dyn Thir,
+	ids: Arc<IdentifierRegistry>,
+	model: &'a thir::Model,
+	result: Model,
+	annotations: FxHashMap<thir::AnnotationId, AnnotationId>,
+	functions: FxHashMap<thir::FunctionId, FunctionId>,
+	names: FxHashMap<thir::DeclarationId, Identifier>,
+	temporaries: u32,
+	declarations: u32,
+	error: Option<UnsupportedExpression>,
+}
+
+impl<'a> MirLowerer<'a> {
+	fn new(db: &'a dyn Thir, model: &'a thir::Model) -> Self {
+		Self {
+			db,
+			ids: db.identifier_registry(),
+			model,
+			result: Model::default(),
+			annotations: FxHashMap::default(),
+			functions: FxHashMap::default(),
+			names: FxHashMap::default(),
+			temporaries: 0,
+			declarations: 0,
+			error: None,
+		}
+	}
+
+	/// Record an expression which cannot be lowered, keeping only the first error
+	fn unsupported(&mut self, origin: Origin, msg: &str) {
+		if self.error.is_none() {
+			let (src, span) = origin.source_span(self.db);
+			self.error = Some(UnsupportedExpression {
+				src,
+				msg: msg.to_owned(),
+				span,
+			});
+		}
+	}
+
+	/// Add the annotation items
+	fn collect_annotations(&mut self) {
+		for (idx, annotation) in self.model.annotations() {
+			let name = annotation.name.unwrap_or_else(|| {
+				Identifier::new(format!("_ANN_{}", Into::<u32>::into(idx)), self.db.upcast())
+			});
+			let parameter_count = annotation
+				.parameters
+				.as_ref()
+				.map(|ps| ps.len() as u16)
+				.unwrap_or_default();
+			let id = self.result.add_annotation(Annotation {
+				name,
+				parameter_count,
+			});
+			self.annotations.insert(idx, id);
+		}
+	}
+
+	/// Add the function items
+	fn collect_functions(&mut self) {
+		// Add signatures first so that calls can refer to any function
+		for (idx, function) in self.model.all_functions() {
+			self.declarations = 0;
+			let name = match function.mangled_param_tys() {
+				Some(tys) => function.name().mangled(self.db, tys.iter().copied()),
+				None => function.name().as_identifier(self.db),
+			};
+			let parameters = function
+				.parameters()
+				.iter()
+				.map(|p| Declaration {
+					ty: self.ty(self.model[*p].ty()),
+					domain: None,
+					name: self.name(*p),
+					definition: None,
+					annotations: Vec::new(),
+					origin: self.model[*p].origin(),
+				})
+				.collect();
+			let id = self.result.add_function(Function {
+				name,
+				unmangled_name: function.name().as_identifier(self.db),
+				return_type: self.ty(function.return_type()),
+				parameters,
+				body: None,
+				origin: function.origin(),
+			});
+			self.functions.insert(idx, id);
+		}
+		for (idx, function) in self.model.all_functions() {
+			if let Some(body) = function.body() {
+				// Parameters have already been named
+				self.temporaries = 0;
+				self.declarations = function.parameters().len() as u32;
+				let body = self.lower_expression(body);
+				self.result[self.functions[&idx]].body = Some(body);
+			}
+		}
+	}
+
+	/// Add the `main` function containing the top-level items
+	fn collect_main(&mut self) {
+		self.temporaries = 0;
+		self.declarations = 0;
+		let mut parameters = Vec::new();
+		let mut items = Vec::new();
+		for declaration in self.sort_declarations() {
+			let decl = &self.model[declaration];
+			if decl.definition().is_none() && decl.ty().known_par(self.db.upcast()) {
+				parameters.push(Declaration {
+					ty: self.ty(decl.ty()),
+					domain: None,
+					name: self.name(declaration),
+					definition: None,
+					annotations: Vec::new(),
+					origin: decl.origin(),
+				});
+			} else {
+				self.lower_declaration(declaration, &mut items);
+			}
+		}
+		for (idx, _) in self.model.top_level_constraints() {
+			self.lower_constraint(idx, &mut items);
+		}
+		if let Some(solve) = self.model.solve() {
+			let goal = match solve.goal() {
+				thir::Goal::Satisfy => Goal::Satisfy,
+				thir::Goal::Minimize { objective } => Goal::Minimize(self.name(*objective)),
+				thir::Goal::Maximize { objective } => Goal::Maximize(self.name(*objective)),
+			};
+			let annotations = self.lower_annotations(solve.annotations(), &mut items);
+			self.result.set_solve(Solve { goal, annotations });
+		}
+		let origin = Origin::Introduced("main");
+		let body = Expression::new(
+			Let {
+				items,
+				result: None,
+			},
+			self.par_bool(),
+			origin,
+		);
+		let main = self.result.add_function(Function {
+			name: self.ids.main,
+			unmangled_name: self.ids.main,
+			return_type: self.par_bool(),
+			parameters,
+			body: Some(body),
+			origin,
+		});
+		self.result.set_entrypoint(main);
+	}
+
+	/// Order the top-level declarations so that they are declared before they are used
+	fn sort_declarations(&self) -> Vec<thir::DeclarationId> {
+		let mut sorted = Vec::new();
+		let mut visited = FxHashSet::default();
+		for (idx, _) in self.model.top_level_declarations() {
+			let mut todo = vec![(idx, false)];
+			while let Some((declaration, dependencies_done)) = todo.pop() {
+				if dependencies_done {
+					sorted.push(declaration);
+					continue;
+				}
+				if !visited.insert(declaration) {
+					continue;
+				}
+				todo.push((declaration, true));
+				let mut dependencies = Dependencies::default();
+				visit_declaration(&mut dependencies, self.model, declaration);
+				todo.extend(
+					dependencies
+						.declarations
+						.into_iter()
+						.rev()
+						.filter(|d| !visited.contains(d))
+						.map(|d| (d, false)),
+				);
+			}
+		}
+		sorted
+	}
+
+	fn lower_declaration(&mut self, idx: thir::DeclarationId, items: &mut Vec<LetItem>) {
+		let declaration = &self.model[idx];
+		let annotations = self.lower_annotations(declaration.annotations(), items);
+		// The domain of a defined variable constrains its definition
+		let domain = if !declaration.ty().known_par(self.db.upcast()) {
+			self.lower_domain(declaration.domain(), items)
+		} else {
+			None
+		};
+		let definition = declaration
+			.definition()
+			.map(|def| self.lower_expression_into(def, items));
+		items.push(
+			Declaration {
+				ty: self.ty(declaration.ty()),
+				domain,
+				name: self.name(idx),
+				definition,
+				annotations,
+				origin: declaration.origin(),
+			}
+			.into(),
+		);
+	}
+
+	fn lower_constraint(&mut self, idx: thir::ConstraintId, items: &mut Vec<LetItem>) {
+		let constraint = &self.model[idx];
+		let annotations = self.lower_annotations(constraint.annotations(), items);
+		let expression = self.lower_root(constraint.expression());
+		items.push(
+			Constraint {
+				expression,
+				annotations,
+			}
+			.into(),
+		);
+	}
+
+	fn lower_annotations(
+		&mut self,
+		annotations: &Annotations,
+		items: &mut Vec<LetItem>,
+	) -> Vec<AnnotationRef> {
+		annotations
+			.iter()
+			.map(|ann| match &**ann {
+				thir::ExpressionData::Identifier(ResolvedIdentifier::Annotation(a)) => {
+					AnnotationRef::Reference(self.annotations[a])
+				}
+				_ => AnnotationRef::Identifier(self.lower_identifier(ann, items)),
+			})
+			.collect()
+	}
+
+	fn lower_domain(&mut self, domain: &thir::Domain, items: &mut Vec<LetItem>) -> Option<Domain> {
+		match &**domain {
+			DomainData::Bounded(e) => {
+				let value = self.lower_value(e, items);
+				match (value.identifier(), value.data) {
+					(Some(i), _) => Some(Domain::Identifier(i)),
+					(_, ValueData::Set(s)) => Some(Domain::Set(s)),
+					(_, data) => Some(Domain::Identifier(
+						self.bind(Value::new(data, value.ty, value.origin), items),
+					)),
+				}
+			}
+			DomainData::Array(dim, element) => {
+				let index_sets = match &***dim {
+					DomainData::Tuple(ds) => ds.iter().collect::<Vec<_>>(),
+					_ => vec![&**dim],
+				}
+				.into_iter()
+				.map(|d| self.lower_domain(d, items))
+				.collect::<Option<Vec<_>>>();
+				let Some(index_sets) = index_sets else {
+					self.unsupported(
+						domain.origin(),
+						"Fresh array variables must have bounded index sets",
+					);
+					return None;
+				};
+				let element = self.lower_domain(element, items).map(Box::new);
+				Some(Domain::Array(index_sets, element))
+			}
+			DomainData::Set(element) => self.lower_domain(element, items),
+			DomainData::Tuple(_) | DomainData::Record(_) | DomainData::Unbounded => None,
+		}
+	}
+
+	/// Lower an expression in root context
+	fn lower_root(&mut self, expression: &thir::Expression) -> Expression {
+		maybe_grow_stack(|| self.lower_root_inner(expression))
+	}
+
+	fn lower_root_inner(&mut self, expression: &thir::Expression) -> Expression {
+		let origin = expression.origin();
+		let mut items = Vec::new();
+		let result = match &**expression {
+			thir::ExpressionData::Let(l) => {
+				for item in l.items.iter() {
+					match item {
+						thir::LetItem::Constraint(c) => self.lower_constraint(*c, &mut items),
+						thir::LetItem::Declaration(d) => self.lower_declaration(*d, &mut items),
+					}
+				}
+				self.lower_root(&l.in_expression)
+			}
+			thir::ExpressionData::IfThenElse(ite) if !ite.has_var_condition(self.db) => {
+				self.lower_if_then_else(&ite.branches, &ite.else_result, true, &mut items)
+			}
+			thir::ExpressionData::Call(c) if self.is_forall_comprehension(c) => {
+				match &*c.arguments[0] {
+					thir::ExpressionData::ArrayComprehension(comprehension) => Expression::new(
+						ExpressionData::Forall(self.lower_comprehension(comprehension, true)),
+						self.par_bool(),
+						origin,
+					),
+					_ => unreachable!(),
+				}
+			}
+			_ => self.lower_expression_into(expression, &mut items),
+		};
+		if items.is_empty() {
+			return result;
+		}
+		if !matches!(
+			result.data,
+			ExpressionData::Value(ValueData::Literal(LiteralData::Boolean(BooleanLiteral(
+				true
+			))))
+		) {
+			items.push(
+				Constraint {
+					expression: result,
+					annotations: Vec::new(),
+				}
+				.into(),
+			);
+		}
+		Expression::new(
+			Let {
+				items,
+				result: None,
+			},
+			self.par_bool(),
+			origin,
+		)
+	}
+
+	/// Whether this is a call to `forall` with a comprehension argument
+	fn is_forall_comprehension(&self, call: &thir::Call) -> bool {
+		match &call.function {
+			thir::Callable::Function(f) if self.model[*f].name() == self.ids.forall => {
+				call.arguments.len() == 1
+					&& matches!(
+						&*call.arguments[0],
+						thir::ExpressionData::ArrayComprehension(c) if c.indices.is_none()
+					)
+			}
+			_ => false,
+		}
+	}
+
+	/// Lower an expression in its own scope
+	fn lower_expression(&mut self, expression: &thir::Expression) -> Expression {
+		let mut items = Vec::new();
+		let result = self.lower_expression_into(expression, &mut items);
+		if items.is_empty() {
+			return result;
+		}
+		let ty = result.ty.clone();
+		let origin = result.origin;
+		let value = match result.data {
+			ExpressionData::Value(v) => Value::new(v, result.ty, result.origin),
+			_ => {
+				let name = self.bind(result, &mut items);
+				Value::new(LiteralData::Identifier(name), ty.clone(), origin)
+			}
+		};
+		Expression::new(
+			Let {
+				items,
+				result: Some(value),
+			},
+			ty,
+			origin,
+		)
+	}
+
+	/// Lower an expression into a value, adding any required declarations to `items`
+	fn lower_value(&mut self, expression: &thir::Expression, items: &mut Vec<LetItem>) -> Value {
+		let result = self.lower_expression_into(expression, items);
+		match result.data {
+			ExpressionData::Value(v) => Value::new(v, result.ty, result.origin),
+			_ => {
+				let ty = result.ty.clone();
+				let origin = result.origin;
+				let name = self.bind(result, items);
+				Value::new(LiteralData::Identifier(name), ty, origin)
+			}
+		}
+	}
+
+	/// Lower an expression into an identifier, adding any required declarations to `items`
+	fn lower_identifier(
+		&mut self,
+		expression: &thir::Expression,
+		items: &mut Vec<LetItem>,
+	) -> Identifier {
+		let value = self.lower_value(expression, items);
+		match value.identifier() {
+			Some(i) => i,
+			None => self.bind(value, items),
+		}
+	}
+
+	/// Bind the given expression to a fresh identifier (unique within the current function)
+	fn bind(&mut self, expression: impl Into<Expression>, items: &mut Vec<LetItem>) -> Identifier {
+		let expression = expression.into();
+		self.temporaries += 1;
+		let name = Identifier::new(format!("_T{}", self.temporaries), self.db.upcast());
+		items.push(
+			Declaration {
+				ty: expression.ty.clone(),
+				domain: None,
+				name,
+				origin: expression.origin,
+				definition: Some(expression),
+				annotations: Vec::new(),
+			}
+			.into(),
+		);
+		name
+	}
+
+	/// Lower an expression, adding the declarations for any nested expressions to `items`
+	fn lower_expression_into(
+		&mut self,
+		expression: &thir::Expression,
+		items: &mut Vec<LetItem>,
+	) -> Expression {
+		maybe_grow_stack(|| self.lower_expression_inner(expression, items))
+	}
+
+	fn lower_expression_inner(
+		&mut self,
+		expression: &thir::Expression,
+		items: &mut Vec<LetItem>,
+	) -> Expression {
+		let ty = self.ty(expression.ty());
+		let origin = expression.origin();
+		let data: ExpressionData = match &**expression {
+			thir::ExpressionData::Absent => LiteralData::Bottom.into(),
+			thir::ExpressionData::BooleanLiteral(b) => LiteralData::Boolean(*b).into(),
+			thir::ExpressionData::IntegerLiteral(i) => LiteralData::Integer(*i).into(),
+			thir::ExpressionData::FloatLiteral(f) => LiteralData::Float(*f).into(),
+			thir::ExpressionData::StringLiteral(s) => LiteralData::String(s.clone()).into(),
+			thir::ExpressionData::Infinity => LiteralData::Infinity.into(),
+			thir::ExpressionData::Identifier(i) => match i {
+				ResolvedIdentifier::Declaration(d) => LiteralData::Identifier(self.name(*d)).into(),
+				ResolvedIdentifier::Annotation(a) => {
+					LiteralData::Identifier(self.result[self.annotations[a]].name).into()
+				}
+				ResolvedIdentifier::Enumeration(_) | ResolvedIdentifier::EnumerationMember(_) => {
+					unreachable!("Enums should have been erased")
+				}
+			},
+			thir::ExpressionData::ArrayLiteral(al) => ValueData::Array(Array {
+				members: al.iter().map(|e| self.lower_value(e, items)).collect(),
+			})
+			.into(),
+			thir::ExpressionData::SetLiteral(sl) => ValueData::Set(Set {
+				members: sl.iter().map(|e| self.lower_value(e, items)).collect(),
+			})
+			.into(),
+			thir::ExpressionData::TupleLiteral(tl) => ValueData::Tuple(Tuple {
+				members: tl.iter().map(|e| self.lower_value(e, items)).collect(),
+			})
+			.into(),
+			thir::ExpressionData::TupleAccess(ta) => ValueData::TupleAccess(TupleAccess {
+				tuple: self.lower_identifier(&ta.tuple, items),
+				field: ta.field,
+			})
+			.into(),
+			thir::ExpressionData::ArrayComprehension(c) => {
+				self.lower_comprehension(c, false).into()
+			}
+			thir::ExpressionData::IfThenElse(ite) => {
+				return self.lower_if_then_else(&ite.branches, &ite.else_result, false, items);
+			}
+			thir::ExpressionData::Call(c) => {
+				let function = match &c.function {
+					thir::Callable::Function(f) => Callable::Function(self.functions[f]),
+					thir::Callable::Annotation(a) => Callable::Annotation(self.annotations[a]),
+					_ => unreachable!("Call to {:?} should have been removed", c.function),
+				};
+				Call {
+					function,
+					arguments: c
+						.arguments
+						.iter()
+						.map(|arg| self.lower_value(arg, items))
+						.collect(),
+				}
+				.into()
+			}
+			thir::ExpressionData::Let(l) => {
+				let mut let_items = Vec::new();
+				for item in l.items.iter() {
+					match item {
+						thir::LetItem::Constraint(c) => self.lower_constraint(*c, &mut let_items),
+						thir::LetItem::Declaration(d) => self.lower_declaration(*d, &mut let_items),
+					}
+				}
+				let result = self.lower_value(&l.in_expression, &mut let_items);
+				if let_items.is_empty() {
+					return result.into();
+				}
+				Let {
+					items: let_items,
+					result: Some(result),
+				}
+				.into()
+			}
+			thir::ExpressionData::RecordLiteral(_) | thir::ExpressionData::RecordAccess(_) => {
+				unreachable!("Records should have been erased")
+			}
+			thir::ExpressionData::SetComprehension(_) => {
+				unreachable!("Set comprehensions should have been desugared")
+			}
+			thir::ExpressionData::Case(_) => {
+				self.unsupported(origin, "Case expressions are not supported yet");
+				LiteralData::Bottom.into()
+			}
+			thir::ExpressionData::Lambda(_) => {
+				unreachable!("Lambdas should have been removed")
+			}
+		};
+		Expression::new(data, ty, origin)
+	}
+
+	/// Lower an if-then-else into nested if-then-else expressions with only an else branch
+	fn lower_if_then_else(
+		&mut self,
+		branches: &[thir::Branch],
+		else_result: &thir::Expression,
+		root: bool,
+		items: &mut Vec<LetItem>,
+	) -> Expression {
+		let (first, rest) = branches.split_first().expect("No branches in if-then-else");
+		let condition = self.lower_value(&first.condition, items);
+		let lower_branch = |this: &mut Self, e: &thir::Expression| {
+			if root {
+				this.lower_root(e)
+			} else {
+				this.lower_expression(e)
+			}
+		};
+		let then = lower_branch(self, &first.result);
+		let else_expression = if rest.is_empty() {
+			lower_branch(self, else_result)
+		} else {
+			// Later conditions can only be evaluated if the previous ones fail
+			let mut else_items = Vec::new();
+			let nested = self.lower_if_then_else(rest, else_result, root, &mut else_items);
+			if else_items.is_empty() {
+				nested
+			} else {
+				let ty = nested.ty.clone();
+				let origin = nested.origin;
+				let result = if root {
+					else_items.push(
+						Constraint {
+							expression: nested,
+							annotations: Vec::new(),
+						}
+						.into(),
+					);
+					None
+				} else {
+					let name = self.bind(nested, &mut else_items);
+					Some(Value::new(
+						LiteralData::Identifier(name),
+						ty.clone(),
+						origin,
+					))
+				};
+				Expression::new(
+					Let {
+						items: else_items,
+						result,
+					},
+					ty,
+					origin,
+				)
+			}
+		};
+		let ty = if root {
+			self.par_bool()
+		} else {
+			then.ty.clone()
+		};
+		let origin = then.origin;
+		Expression::new(
+			IfThenElse {
+				condition,
+				then: Box::new(then),
+				else_expression: Box::new(else_expression),
+			},
+			ty,
+			origin,
+		)
+	}
+
+	/// Lower a comprehension, with the template in root context if `root` is set
+	fn lower_comprehension(
+		&mut self,
+		comprehension: &thir::ArrayComprehension,
+		root: bool,
+	) -> Comprehension {
+		let generators = comprehension
+			.generators
+			.iter()
+			.map(|g| match g {
+				thir::Generator::Iterator {
+					declarations,
+					collection,
+					where_clause,
+				} => Generator::Iterator {
+					names: declarations.iter().map(|d| self.name(*d)).collect(),
+					collection: self.lower_expression(collection),
+					where_clause: where_clause.as_ref().map(|w| self.lower_expression(w)),
+				},
+				thir::Generator::Assignment {
+					assignment,
+					where_clause,
+				} => Generator::Assignment {
+					name: self.name(*assignment),
+					definition: self.lower_expression(
+						self.model[*assignment]
+							.definition()
+							.expect("Generator assignment has no definition"),
+					),
+					where_clause: where_clause.as_ref().map(|w| self.lower_expression(w)),
+				},
+			})
+			.collect();
+		let indices = comprehension
+			.indices
+			.as_ref()
+			.map(|i| Box::new(self.lower_expression(i)));
+		let expression = if root {
+			self.lower_root(&comprehension.template)
+		} else {
+			self.lower_expression(&comprehension.template)
+		};
+		Comprehension {
+			indices,
+			expression: Box::new(expression),
+			generators,
+		}
+	}
+
+	/// Get the name of a declaration, creating one (unique within the current function) if it is anonymous
+	fn name(&mut self, declaration: thir::DeclarationId) -> Identifier {
+		if let Some(name) = self.names.get(&declaration) {
+			return *name;
+		}
+		let name = self.model[declaration].name().unwrap_or_else(|| {
+			self.declarations += 1;
+			Identifier::new(format!("_DECL_{}", self.declarations), self.db.upcast())
+		});
+		self.names.insert(declaration, name);
+		name
+	}
+
+	fn ty(&self, ty: crate::ty::Ty) -> Ty {
+		Ty::from_ty(self.db.upcast(), ty)
+	}
+
+	fn par_bool(&self) -> Ty {
+		Ty::Bool {
+			dim: 0,
+			is_var: false,
+			is_set: false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use crate::mir::test::check;
+
+	#[test]
+	fn test_lower_main() {
+		check(
+			r#"
+				int: n;
+				var 1..n: x;
+				array [1..3] of var int: y;
+				constraint x < n + 1 /\ y[2] = x;
+				solve maximize x;
+			"#,
+			expect!([r#"
+    function bool: main(int: n) = let {
+      set of int: _T1 = '..<int, int>'(1, n);
+      var int: x in _T1 :: 'output';
+      set of int: _T2 = '..<int, int>'(1, 3);
+      array [int] of var int: y in array [_T2] of _ :: 'output';
+      var int: _objective = x;
+      constraint let {
+        int: _T15 = '+<int, int>'(n, 1);
+        var bool: _T16 = '<<var $T, $T>'(x, _T15);
+        var int: _T19 = let {
+          int: _DECL_2 = 2;
+          var int: _T18 = let {
+            constraint 'mzn_array_access_valid<array [int] of var int, int>'(y, _DECL_2);
+            var int: _T17 = mzn_element_internal(y, _DECL_2);
+          } in _T17;
+        } in _T18;
+        var bool: _T20 = '=<any $T, any $T>'(_T19, x);
+        constraint '/\<var bool, var bool>'(_T16, _T20);
+      } in root;
+    } in root;
+    solve maximize _objective;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_let() {
+		check(
+			r#"
+				var int: x;
+				constraint let { var int: z = x * 2 } in z > 3;
+				var int: y = let { var int: z = x + 1; constraint z > 0 } in z * z;
+			"#,
+			expect!([r#"
+    function bool: main() = let {
+      var int: x :: 'output';
+      var int: y = let {
+        var int: z = '+<var int, var int>'(x, 1);
+        constraint '><var $T, $T>'(z, 0);
+        var int: _T1 = '*<var int, var int>'(z, z);
+      } in _T1;
+      constraint let {
+        var int: z = '*<var int, var int>'(x, 2);
+        constraint '><var $T, $T>'(z, 3);
+      } in root;
+    } in root;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_function() {
+		check(
+			r#"
+				function var int: f(var int: a) = a * a + 1;
+				var int: x;
+				constraint f(x) > 2;
+			"#,
+			expect!([r#"
+    function var int: f(var int: a) = let {
+      var int: _T1 = '*<var int, var int>'(a, a);
+      var int: _T2 = '+<var int, var int>'(_T1, 1);
+    } in _T2;
+    function bool: main() = let {
+      var int: x :: 'output';
+      constraint let {
+        var int: _T13 = f(x);
+        constraint '><var $T, $T>'(_T13, 2);
+      } in root;
+    } in root;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_function_dependencies() {
+		check(
+			r#"
+				function var int: f(var int: a) = a + y;
+				var int: x = f(1);
+				var int: y;
+			"#,
+			expect!([r#"
+    function var int: f(var int: a, var int: _DECL_1) = '+<var int, var int>'(a, _DECL_1);
+    function bool: main() = let {
+      var int: y :: 'output';
+      var int: x = f(1, y);
+    } in root;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_if_then_else() {
+		check(
+			r#"
+				int: n;
+				var int: x;
+				constraint if n > 2 then x > 1 elseif n < 0 then x < 2 * n else true endif;
+				var int: y = if n > 0 then x + 1 else x * 2 endif;
+			"#,
+			expect!([r#"
+    function bool: main(int: n) = let {
+      var int: x :: 'output';
+      bool: _T1 = '><$T, $T>'(n, 0);
+      var int: y = if _T1 then '+<var int, var int>'(x, 1) else '*<var int, var int>'(x, 2) endif;
+      constraint let {
+        bool: _T14 = '><$T, $T>'(n, 2);
+        constraint if _T14 then '><var $T, $T>'(x, 1) else let {
+          bool: _T15 = '<<$T, $T>'(n, 0);
+          constraint if _T15 then let {
+            int: _T16 = '*<int, int>'(2, n);
+            constraint '<<var $T, $T>'(x, _T16);
+          } in root else true endif;
+        } in root endif;
+      } in root;
+    } in root;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_case() {
+		check(
+			r#"
+				int: n;
+				int: y = case n of 1 => 2, _ => 3 endcase;
+			"#,
+			expect!([r#"Unsupported expression"#]),
+		)
+	}
+
+	#[test]
+	fn test_lower_forall() {
+		check(
+			r#"
+				int: n;
+				array [1..n] of var int: x;
+				constraint forall (i in 1..n where i > 1) (x[i - 1] < x[i]);
+			"#,
+			expect!([r#"
+    function bool: main(int: n) = let {
+      set of int: _T1 = '..<int, int>'(1, n);
+      array [int] of var int: x in array [_T1] of _ :: 'output';
+      constraint forall [let {
+        var int: _T16 = let {
+          int: _DECL_2 = '-<int, int>'(i, 1);
+          var int: _T15 = let {
+            constraint 'mzn_array_access_valid<array [int] of var int, int>'(x, _DECL_2);
+            var int: _T14 = mzn_element_internal(x, _DECL_2);
+          } in _T14;
+        } in _T15;
+        var int: _T18 = let {
+          constraint 'mzn_array_access_valid<array [int] of var int, int>'(x, i);
+          var int: _T17 = mzn_element_internal(x, i);
+        } in _T17;
+        constraint '<<var $T, var $T>'(_T16, _T18);
+      } in root | i in '..<int, int>'(1, n) where '><$T, $T>'(i, 1)];
+    } in root;
+    solve satisfy;
+"#]),
+		)
+	}
+}
@@ -1,5 +1,13 @@
 //! Mid-level IR
+//!
+//! The MIR is the representation of a MicroZinc program. All values are in
+//! A-normal form: calls, if-then-else expressions, comprehensions and lets only
+//! take values as their direct arguments, with any nested computations bound
+//! to identifiers in a surrounding `let`.
 
+pub mod db;
+pub mod lower;
+pub mod pretty_print;
 pub mod ty;
 
 use ty::Ty;
@@ -7,29 +15,86 @@ use ty::Ty;
 use crate::{
 	hir::{BooleanLiteral, FloatLiteral, Identifier, IntegerLiteral, StringLiteral},
 	thir::source::Origin,
-	utils::arena::{Arena, ArenaIndex},
+	utils::{
+		arena::{Arena, ArenaIndex},
+		impl_enum_from, impl_index,
+	},
 };
 
 /// A mid-level IR program (MicroZinc)
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Model {
-	_entrypoint: Expression,
-	_annotations: Arena<Annotation>,
-	_functions: Arena<Function>,
+	entrypoint: Option<FunctionId>,
+	annotations: Arena<Annotation>,
+	functions: Arena<Function>,
+	solve: Solve,
+}
+
+impl Model {
+	/// Get the entrypoint function (`main`)
+	pub fn entrypoint(&self) -> FunctionId {
+		self.entrypoint.expect("Model has no entrypoint")
+	}
+
+	/// Set the entrypoint function
+	pub fn set_entrypoint(&mut self, function: FunctionId) {
+		self.entrypoint = Some(function);
+	}
+
+	/// Get the annotation items
+	pub fn annotations(&self) -> impl Iterator<Item = (AnnotationId, &Annotation)> {
+		self.annotations.iter()
+	}
+
+	/// Add an annotation item
+	pub fn add_annotation(&mut self, annotation: Annotation) -> AnnotationId {
+		self.annotations.insert(annotation)
+	}
+
+	/// Get the function items
+	pub fn functions(&self) -> impl Iterator<Item = (FunctionId, &Function)> {
+		self.functions.iter()
+	}
+
+	/// Add a function item
+	pub fn add_function(&mut self, function: Function) -> FunctionId {
+		self.functions.insert(function)
+	}
+
+	/// Get the solve goal
+	pub fn solve(&self) -> &Solve {
+		&self.solve
+	}
+
+	/// Set the solve goal
+	pub fn set_solve(&mut self, solve: Solve) {
+		self.solve = solve;
+	}
 }
 
+impl_index!(Model[self, index: AnnotationId] -> Annotation { self.annotations[index] });
+impl_index!(Model[self, index: FunctionId] -> Function { self.functions[index] });
+
 /// An annotation item
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Annotation {
-	_name: Identifier,
-	_parameter_count: u16,
+	/// The name of the annotation
+	pub name: Identifier,
+	/// The number of parameters (0 if this is an atom)
+	pub parameter_count: u16,
 }
 
 /// A constraint item
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Constraint {
-	_expression: Expression,
-	_annotations: Vec<AnnotationRef>,
+	/// The constrained expression
+	pub expression: Expression,
+	/// The annotations on this constraint
+	pub annotations: Vec<AnnotationRef>,
 }
 
 /// An annotation
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AnnotationRef {
 	/// Identifier for declaration with a RHS expression
 	Identifier(Identifier),
@@ -41,36 +106,108 @@ pub enum AnnotationRef {
 pub type AnnotationId = ArenaIndex<Annotation>;
 
 /// A declaration item
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Declaration {
-	_ty: Ty,
-	_domain: Option<Domain>,
-	_name: Identifier,
-	_definition: Option<Expression>,
+	/// The type of the declaration
+	pub ty: Ty,
+	/// The domain of a variable (constraining its definition if it has one)
+	pub domain: Option<Domain>,
+	/// The name of the declaration
+	pub name: Identifier,
+	/// The right hand side definition
+	pub definition: Option<Expression>,
+	/// The annotations on this declaration
+	pub annotations: Vec<AnnotationRef>,
+	/// The origin of the declaration
+	pub origin: Origin,
 }
 
 /// A domain
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Domain {
 	/// Identifier for declaration with a RHS expression
 	Identifier(Identifier),
 	/// Fully evaluated set domain
 	Set(Set),
+	/// Array domain with the given index sets and element domain
+	Array(Vec<Domain>, Option<Box<Domain>>),
 }
 
 /// A function item
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Function {
-	_name: Identifier,
-	_parameters: Vec<Identifier>,
-	_body: Option<Expression>,
+	/// The name of the function
+	pub name: Identifier,
+	/// The name of the function before name mangling
+	pub unmangled_name: Identifier,
+	/// The return type of the function
+	pub return_type: Ty,
+	/// The parameters of the function
+	pub parameters: Vec<Declaration>,
+	/// The body of the function (`None` if this is a builtin)
+	pub body: Option<Expression>,
+	/// The origin of the function
+	pub origin: Origin,
+}
+
+/// The ID of a function item
+pub type FunctionId = ArenaIndex<Function>;
+
+/// The solve goal
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Solve {
+	/// The objective
+	pub goal: Goal,
+	/// The annotations on the solve item
+	pub annotations: Vec<AnnotationRef>,
+}
+
+/// The objective of a solve item
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum Goal {
+	/// Satisfaction problem
+	#[default]
+	Satisfy,
+	/// Minimize the declaration with the given name
+	Minimize(Identifier),
+	/// Maximize the declaration with the given name
+	Maximize(Identifier),
 }
 
 /// An expression
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Expression {
-	_data: ExpressionData,
-	_ty: Ty,
-	_origin: Origin,
+	/// The expression data
+	pub data: ExpressionData,
+	/// The type of the expression
+	pub ty: Ty,
+	/// The origin of the expression
+	pub origin: Origin,
+}
+
+impl Expression {
+	/// Create a new expression
+	pub fn new(data: impl Into<ExpressionData>, ty: Ty, origin: Origin) -> Self {
+		Self {
+			data: data.into(),
+			ty,
+			origin,
+		}
+	}
+}
+
+impl From<Value> for Expression {
+	fn from(value: Value) -> Self {
+		Self {
+			data: ExpressionData::Value(value.data),
+			ty: value.ty,
+			origin: value.origin,
+		}
+	}
 }
 
 /// The expression data
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExpressionData {
 	/// A let expression
 	Let(Let),
@@ -86,13 +223,29 @@ pub enum ExpressionData {
 	Forall(Comprehension),
 }
 
+impl_enum_from!(ExpressionData::Let(Let));
+impl_enum_from!(ExpressionData::Call(Call));
+impl_enum_from!(ExpressionData::IfThenElse(IfThenElse));
+impl_enum_from!(ExpressionData::Comprehension(Comprehension));
+impl_enum_from!(ExpressionData::Value(ValueData));
+
+impl From<LiteralData> for ExpressionData {
+	fn from(literal: LiteralData) -> Self {
+		ExpressionData::Value(ValueData::Literal(literal))
+	}
+}
+
 /// A let expression
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Let {
-	_items: Vec<LetItem>,
-	_result: Option<Identifier>,
+	/// The items in the let expression
+	pub items: Vec<LetItem>,
+	/// The resulting value, or `None` if this let is in root context
+	pub result: Option<Value>,
 }
 
 /// An item in a let expression
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LetItem {
 	/// A constraint
 	Constraint(Constraint),
@@ -100,22 +253,29 @@ pub enum LetItem {
 	Declaration(Declaration),
 }
 
+impl_enum_from!(LetItem::Constraint(Constraint));
+impl_enum_from!(LetItem::Declaration(Declaration));
+
 /// A tuple literal
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Tuple {
 	/// Tuple members
 	pub members: Vec<Value>,
 }
 /// An array literal
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Array {
 	/// Array literal members
 	pub members: Vec<Value>,
 }
 /// A set literal
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Set {
 	/// Set literal members
 	pub members: Vec<Value>,
 }
 /// An array access
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ArrayAccess {
 	/// The array being indexed
 	pub array: Identifier,
@@ -124,6 +284,7 @@ pub struct ArrayAccess {
 }
 
 /// A tuple field access
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TupleAccess {
 	/// The tuple being accessed
 	pub tuple: Identifier,
@@ -131,10 +292,20 @@ pub struct TupleAccess {
 	pub field: IntegerLiteral,
 }
 
+/// Target of a call
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Callable {
+	/// Call to a function item
+	Function(FunctionId),
+	/// Call to an annotation constructor
+	Annotation(AnnotationId),
+}
+
 /// A call
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Call {
 	/// The function being called
-	pub function: Identifier,
+	pub function: Callable,
 	/// The arguments
 	pub arguments: Vec<Value>,
 }
@@ -142,6 +313,7 @@ pub struct Call {
 /// An if-then-else expression
 ///
 /// This only has an if-then and else branch, so may need to be nested
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct IfThenElse {
 	/// The (par) condition
 	pub condition: Value,
@@ -152,6 +324,7 @@ pub struct IfThenElse {
 }
 
 /// A comprehension
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Comprehension {
 	/// The indices of the generated expression
 	pub indices: Option<Box<Expression>>,
@@ -162,6 +335,7 @@ pub struct Comprehension {
 }
 
 /// A generator in a comprehension
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Generator {
 	/// An iterator such as `i, j in foo where bar`
 	Iterator {
@@ -184,13 +358,18 @@ pub enum Generator {
 }
 
 /// A literal
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Literal {
-	_data: LiteralData,
-	_ty: Ty,
-	_origin: Origin,
+	/// The literal data
+	pub data: LiteralData,
+	/// The type of the literal
+	pub ty: Ty,
+	/// The origin of the literal
+	pub origin: Origin,
 }
 
 /// The literal data
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LiteralData {
 	/// Bottom (cannot be evaluated)
 	Bottom,
@@ -208,14 +387,54 @@ pub enum LiteralData {
 	Identifier(Identifier),
 }
 
+impl_enum_from!(LiteralData::Boolean(BooleanLiteral));
+impl_enum_from!(LiteralData::Integer(IntegerLiteral));
+impl_enum_from!(LiteralData::Float(FloatLiteral));
+impl_enum_from!(LiteralData::String(StringLiteral));
+impl_enum_from!(LiteralData::Identifier(Identifier));
+
 /// A value
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Value {
-	_data: ValueData,
-	_ty: Ty,
-	_origin: Origin,
+	/// The value data
+	pub data: ValueData,
+	/// The type of the value
+	pub ty: Ty,
+	/// The origin of the value
+	pub origin: Origin,
+}
+
+impl Value {
+	/// Create a new value
+	pub fn new(data: impl Into<ValueData>, ty: Ty, origin: Origin) -> Self {
+		Self {
+			data: data.into(),
+			ty,
+			origin,
+		}
+	}
+
+	/// Get the identifier if this value is one
+	pub fn identifier(&self) -> Option<Identifier> {
+		match &self.data {
+			ValueData::Literal(LiteralData::Identifier(i)) => Some(*i),
+			_ => None,
+		}
+	}
+}
+
+impl From<Literal> for Value {
+	fn from(literal: Literal) -> Self {
+		Self {
+			data: ValueData::Literal(literal.data),
+			ty: literal.ty,
+			origin: literal.origin,
+		}
+	}
 }
 
 /// The value data
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ValueData {
 	/// A literal
 	Literal(LiteralData),
@@ -230,3 +449,92 @@ pub enum ValueData {
 	/// A tuple access
 	TupleAccess(TupleAccess),
 }
+
+impl_enum_from!(ValueData::Literal(LiteralData));
+impl_enum_from!(ValueData::Tuple(Tuple));
+impl_enum_from!(ValueData::Set(Set));
+impl_enum_from!(ValueData::Array(Array));
+impl_enum_from!(ValueData::ArrayAccess(ArrayAccess));
+impl_enum_from!(ValueData::TupleAccess(TupleAccess));
+
+#[cfg(test)]
+pub mod test {
+	use std::sync::Arc;
+
+	use expect_test::Expect;
+
+	use super::{db::Mir, pretty_print::PrettyPrinter, Expression, ExpressionData, LetItem};
+	use crate::{
+		db::{CompilerDatabase, FileReader, Inputs},
+		file::{InputFile, InputLang},
+		hir::ids::NodeRef,
+		thir::source::Origin,
+	};
+
+	/// Lower a model to MIR, and verify the result matches an expected value.
+	///
+	/// The expected value only includes functions, and items in `main`, which are from the `source`
+	/// (i.e. not from stdlib).
+	pub fn check(source: &str, expected: Expect) {
+		let mut db = CompilerDatabase::default();
+		db.set_input_files(Arc::new(vec![InputFile::String(
+			source.to_owned(),
+			InputLang::MiniZinc,
+		)]));
+		let model_ref = db.input_models()[0];
+		let in_source = |origin: Origin| match origin.node() {
+			Some(NodeRef::Item(item)) => item.model_ref(&db) == model_ref,
+			Some(NodeRef::Entity(entity)) => entity.item(&db).model_ref(&db) == model_ref,
+			Some(NodeRef::Model(m)) => m == model_ref,
+			None => true,
+		};
+		let pretty = match db.model_mir() {
+			Ok(model) => {
+				let mut model = (*model).clone();
+				let entrypoint = model.entrypoint();
+				let main = &mut model[entrypoint];
+				main.parameters.retain(|p| in_source(p.origin));
+				if let Some(Expression {
+					data: ExpressionData::Let(l),
+					..
+				}) = &mut main.body
+				{
+					l.items.retain(|item| match item {
+						LetItem::Constraint(c) => in_source(c.expression.origin),
+						LetItem::Declaration(d) => in_source(d.origin),
+					});
+				}
+				let printer = PrettyPrinter::new(&db, &model);
+				let mut pretty = String::new();
+				for (idx, function) in model.functions() {
+					if in_source(function.origin) {
+						pretty.push_str(&printer.pretty_print_function(idx));
+						pretty.push_str(";\n");
+					}
+				}
+				pretty.push_str(&printer.pretty_print_solve());
+				pretty.push_str(";\n");
+				pretty
+			}
+			Err(e) => e.to_string(),
+		};
+		expected.assert_eq(&pretty);
+	}
+
+	/// Lower a model to MIR, and verify the result matches an expected value.
+	///
+	/// Turns off stdlib inclusion.
+	pub fn check_no_stdlib(source: &str, expected: Expect) {
+		let mut db = CompilerDatabase::default();
+		db.set_ignore_stdlib(true);
+		db.set_input_files(Arc::new(vec![InputFile::String(
+			source.to_owned(),
+			InputLang::MiniZinc,
+		)]));
+		let pretty = match db.model_mir() {
+			Ok(model) => PrettyPrinter::new(&db, &model).pretty_print(),
+			Err(e) => e.to_string(),
+		};
+		expected.assert_eq(&pretty);
+	}
+}
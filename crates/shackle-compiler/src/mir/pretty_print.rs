@@ -0,0 +1,329 @@
+//! Pretty printing of MIR as MicroZinc
+//!
+
+use std::fmt::Write;
+
+use super::{
+	AnnotationRef, Call, Callable, Comprehension, Constraint, Declaration, Domain, Expression,
+	ExpressionData, FunctionId, Generator, Goal, Let, LetItem, LiteralData, Model, Value,
+	ValueData,
+};
+use crate::{hir::db::Hir, utils::maybe_grow_stack};
+
+/// Pretty prints MIR as MicroZinc
+pub struct PrettyPrinter<'a> {
+	db: &'a dyn Hir,
+	model: &'a Model,
+}
+
+impl<'a> PrettyPrinter<'a> {
+	/// Create a new pretty printer
+	pub fn new(db: &'a dyn Hir, model: &'a Model) -> Self {
+		Self { db, model }
+	}
+
+	/// Pretty print the model
+	pub fn pretty_print(&self) -> String {
+		let mut buf = String::new();
+		for (_, annotation) in self.model.annotations() {
+			let name = annotation.name.pretty_print(self.db);
+			if annotation.parameter_count == 0 {
+				writeln!(&mut buf, "annotation {};", name).unwrap();
+			} else {
+				writeln!(
+					&mut buf,
+					"annotation {}/{};",
+					name, annotation.parameter_count
+				)
+				.unwrap();
+			}
+		}
+		for (idx, _) in self.model.functions() {
+			writeln!(&mut buf, "{};", self.pretty_print_function(idx)).unwrap();
+		}
+		writeln!(&mut buf, "{};", self.pretty_print_solve()).unwrap();
+		buf
+	}
+
+	/// Pretty print a function item
+	pub fn pretty_print_function(&self, idx: FunctionId) -> String {
+		let function = &self.model[idx];
+		let mut buf = format!(
+			"function {}: {}({})",
+			function.return_type.pretty_print(),
+			function.name.pretty_print(self.db),
+			function
+				.parameters
+				.iter()
+				.map(|p| self.pretty_print_declaration(p, 0))
+				.collect::<Vec<_>>()
+				.join(", ")
+		);
+		if let Some(body) = &function.body {
+			write!(&mut buf, " = {}", self.pretty_print_expression(body, 0)).unwrap();
+		}
+		buf
+	}
+
+	/// Pretty print the solve item
+	pub fn pretty_print_solve(&self) -> String {
+		let solve = self.model.solve();
+		let mut buf = "solve".to_owned();
+		write!(
+			&mut buf,
+			"{}",
+			self.pretty_print_annotations(&solve.annotations)
+		)
+		.unwrap();
+		match &solve.goal {
+			Goal::Satisfy => write!(&mut buf, " satisfy").unwrap(),
+			Goal::Minimize(i) => write!(&mut buf, " minimize {}", i.pretty_print(self.db)).unwrap(),
+			Goal::Maximize(i) => write!(&mut buf, " maximize {}", i.pretty_print(self.db)).unwrap(),
+		}
+		buf
+	}
+
+	fn pretty_print_declaration(&self, declaration: &Declaration, indent: usize) -> String {
+		let mut buf = declaration.ty.pretty_print();
+		write!(&mut buf, ": {}", declaration.name.pretty_print(self.db)).unwrap();
+		if let Some(domain) = &declaration.domain {
+			write!(&mut buf, " in {}", self.pretty_print_domain(domain)).unwrap();
+		}
+		write!(
+			&mut buf,
+			"{}",
+			self.pretty_print_annotations(&declaration.annotations)
+		)
+		.unwrap();
+		if let Some(def) = &declaration.definition {
+			write!(&mut buf, " = {}", self.pretty_print_expression(def, indent)).unwrap();
+		}
+		buf
+	}
+
+	fn pretty_print_constraint(&self, constraint: &Constraint, indent: usize) -> String {
+		format!(
+			"constraint {}{}",
+			self.pretty_print_expression(&constraint.expression, indent),
+			self.pretty_print_annotations(&constraint.annotations)
+		)
+	}
+
+	fn pretty_print_annotations(&self, annotations: &[AnnotationRef]) -> String {
+		annotations
+			.iter()
+			.map(|ann| match ann {
+				AnnotationRef::Identifier(i) => format!(" :: {}", i.pretty_print(self.db)),
+				AnnotationRef::Reference(a) => {
+					format!(" :: {}", self.model[*a].name.pretty_print(self.db))
+				}
+			})
+			.collect()
+	}
+
+	fn pretty_print_domain(&self, domain: &Domain) -> String {
+		match domain {
+			Domain::Identifier(i) => i.pretty_print(self.db),
+			Domain::Set(s) => format!(
+				"{{{}}}",
+				s.members
+					.iter()
+					.map(|v| self.pretty_print_value(v))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			Domain::Array(index_sets, element) => format!(
+				"array [{}] of {}",
+				index_sets
+					.iter()
+					.map(|d| self.pretty_print_domain(d))
+					.collect::<Vec<_>>()
+					.join(", "),
+				element
+					.as_ref()
+					.map(|d| self.pretty_print_domain(d))
+					.unwrap_or_else(|| "_".to_owned())
+			),
+		}
+	}
+
+	/// Pretty print an expression
+	pub fn pretty_print_expression(&self, expression: &Expression, indent: usize) -> String {
+		maybe_grow_stack(|| self.pretty_print_expression_inner(expression, indent))
+	}
+
+	fn pretty_print_expression_inner(&self, expression: &Expression, indent: usize) -> String {
+		match &expression.data {
+			ExpressionData::Let(l) => self.pretty_print_let(l, indent),
+			ExpressionData::Call(c) => self.pretty_print_call(c),
+			ExpressionData::IfThenElse(ite) => format!(
+				"if {} then {} else {} endif",
+				self.pretty_print_value(&ite.condition),
+				self.pretty_print_expression(&ite.then, indent),
+				self.pretty_print_expression(&ite.else_expression, indent)
+			),
+			ExpressionData::Comprehension(c) => self.pretty_print_comprehension(c, indent),
+			ExpressionData::Value(v) => self.pretty_print_value_data(v),
+			ExpressionData::Forall(c) => {
+				format!("forall {}", self.pretty_print_comprehension(c, indent))
+			}
+		}
+	}
+
+	fn pretty_print_let(&self, l: &Let, indent: usize) -> String {
+		let mut buf = String::new();
+		writeln!(&mut buf, "let {{").unwrap();
+		let inner = "  ".repeat(indent + 1);
+		for item in l.items.iter() {
+			let item = match item {
+				LetItem::Constraint(c) => self.pretty_print_constraint(c, indent + 1),
+				LetItem::Declaration(d) => self.pretty_print_declaration(d, indent + 1),
+			};
+			writeln!(&mut buf, "{}{};", inner, item).unwrap();
+		}
+		write!(&mut buf, "{}}} in ", "  ".repeat(indent)).unwrap();
+		match &l.result {
+			Some(v) => write!(&mut buf, "{}", self.pretty_print_value(v)).unwrap(),
+			None => write!(&mut buf, "root").unwrap(),
+		}
+		buf
+	}
+
+	fn pretty_print_call(&self, call: &Call) -> String {
+		let name = match &call.function {
+			Callable::Function(f) => self.model[*f].name,
+			Callable::Annotation(a) => self.model[*a].name,
+		};
+		format!(
+			"{}({})",
+			name.pretty_print(self.db),
+			call.arguments
+				.iter()
+				.map(|v| self.pretty_print_value(v))
+				.collect::<Vec<_>>()
+				.join(", ")
+		)
+	}
+
+	fn pretty_print_comprehension(&self, c: &Comprehension, indent: usize) -> String {
+		let mut buf = String::new();
+		write!(&mut buf, "[").unwrap();
+		if let Some(i) = &c.indices {
+			write!(&mut buf, "{}: ", self.pretty_print_expression(i, indent)).unwrap();
+		}
+		let gs = c
+			.generators
+			.iter()
+			.map(|g| self.pretty_print_generator(g, indent))
+			.collect::<Vec<_>>()
+			.join(", ");
+		write!(
+			&mut buf,
+			"{} | {}]",
+			self.pretty_print_expression(&c.expression, indent),
+			gs
+		)
+		.unwrap();
+		buf
+	}
+
+	fn pretty_print_generator(&self, g: &Generator, indent: usize) -> String {
+		let (mut buf, where_clause) = match g {
+			Generator::Iterator {
+				names,
+				collection,
+				where_clause,
+			} => (
+				format!(
+					"{} in {}",
+					names
+						.iter()
+						.map(|n| n.pretty_print(self.db))
+						.collect::<Vec<_>>()
+						.join(", "),
+					self.pretty_print_expression(collection, indent)
+				),
+				where_clause,
+			),
+			Generator::Assignment {
+				name,
+				definition,
+				where_clause,
+			} => (
+				format!(
+					"{} = {}",
+					name.pretty_print(self.db),
+					self.pretty_print_expression(definition, indent)
+				),
+				where_clause,
+			),
+		};
+		if let Some(w) = where_clause {
+			write!(
+				&mut buf,
+				" where {}",
+				self.pretty_print_expression(w, indent)
+			)
+			.unwrap();
+		}
+		buf
+	}
+
+	/// Pretty print a value
+	pub fn pretty_print_value(&self, value: &Value) -> String {
+		self.pretty_print_value_data(&value.data)
+	}
+
+	fn pretty_print_value_data(&self, value: &ValueData) -> String {
+		let join = |vs: &[Value]| {
+			vs.iter()
+				.map(|v| self.pretty_print_value(v))
+				.collect::<Vec<_>>()
+				.join(", ")
+		};
+		match value {
+			ValueData::Literal(l) => self.pretty_print_literal(l),
+			ValueData::Tuple(t) => format!("({},)", join(&t.members)),
+			ValueData::Set(s) => format!("{{{}}}", join(&s.members)),
+			ValueData::Array(a) => format!("[{}]", join(&a.members)),
+			ValueData::ArrayAccess(aa) => format!(
+				"{}[{}]",
+				aa.array.pretty_print(self.db),
+				aa.indices
+					.iter()
+					.map(|l| self.pretty_print_literal(&l.data))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			ValueData::TupleAccess(ta) => {
+				format!("{}.{}", ta.tuple.pretty_print(self.db), ta.field.0)
+			}
+		}
+	}
+
+	fn pretty_print_literal(&self, literal: &LiteralData) -> String {
+		match literal {
+			LiteralData::Bottom => "<>".to_owned(),
+			LiteralData::Boolean(b) => {
+				if b.0 {
+					"true".to_owned()
+				} else {
+					"false".to_owned()
+				}
+			}
+			LiteralData::Integer(i) => format!("{}", i.0),
+			LiteralData::Float(f) => {
+				let value = f.value();
+				if value.fract() == 0.0 {
+					// Ensure this is is printed as a float literal and not an integer
+					format!("{}.0", value)
+				} else {
+					format!("{}", value)
+				}
+			}
+			LiteralData::String(s) => format!("{:?}", s.value(self.db)),
+			LiteralData::Infinity => "infinity".to_owned(),
+			LiteralData::Identifier(i) => i.pretty_print(self.db),
+		}
+	}
+}
@@ -1,8 +1,14 @@
 //! Module containing mid-level IR type representation
-use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{
+	db::Interner,
+	ty::{self, TyData, VarType},
+};
 
 /// A mid-level IR type
 #[allow(variant_size_differences)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Ty {
 	/// Type of bottom
 	Bottom {
@@ -53,6 +59,177 @@ pub enum Ty {
 		/// Dimensions (0 if not an array)
 		dim: u8,
 		/// Types of the fields
-		fields: Rc<[Ty]>,
+		fields: Arc<[Ty]>,
 	},
+	/// Type of a polymorphic parameter of a builtin function
+	Any,
+}
+
+impl Ty {
+	/// Get the MIR type for a THIR type.
+	///
+	/// Option types, enums and records must have already been erased.
+	pub fn from_ty(db: &dyn Interner, ty: ty::Ty) -> Self {
+		match ty.lookup(db) {
+			TyData::Array { dim, element, .. } => {
+				let dims = match dim.lookup(db) {
+					TyData::Tuple(_, fs) => fs.len(),
+					TyData::TyVar(_, _, _) => return Ty::Any,
+					_ => 1,
+				};
+				Self::from_ty(db, element).with_dim(dims as u8)
+			}
+			TyData::Set(inst, _, element) => {
+				let is_var = inst == VarType::Var;
+				match element.lookup(db) {
+					TyData::Boolean(_, _) => Ty::Bool {
+						dim: 0,
+						is_var,
+						is_set: true,
+					},
+					TyData::Integer(_, _) | TyData::Enum(_, _, _) => Ty::Int {
+						dim: 0,
+						is_var,
+						is_set: true,
+					},
+					TyData::Float(_, _) => Ty::Float {
+						dim: 0,
+						is_var,
+						is_set: true,
+					},
+					TyData::Bottom(_) => Ty::Bottom {
+						dim: 0,
+						is_set: true,
+					},
+					TyData::TyVar(_, _, _) => Ty::Any,
+					_ => unreachable!("Invalid set element type {}", element.pretty_print(db)),
+				}
+			}
+			TyData::Boolean(inst, _) => Ty::Bool {
+				dim: 0,
+				is_var: inst == VarType::Var,
+				is_set: false,
+			},
+			TyData::Integer(inst, _) | TyData::Enum(inst, _, _) => Ty::Int {
+				dim: 0,
+				is_var: inst == VarType::Var,
+				is_set: false,
+			},
+			TyData::Float(inst, _) => Ty::Float {
+				dim: 0,
+				is_var: inst == VarType::Var,
+				is_set: false,
+			},
+			TyData::String(_) => Ty::String { dim: 0 },
+			TyData::Annotation(_) => Ty::Ann { dim: 0 },
+			TyData::Bottom(_) => Ty::Bottom {
+				dim: 0,
+				is_set: false,
+			},
+			TyData::Tuple(_, fields) => Ty::Tuple {
+				dim: 0,
+				fields: fields.iter().map(|f| Self::from_ty(db, *f)).collect(),
+			},
+			TyData::TyVar(_, _, _) => Ty::Any,
+			TyData::Record(_, _) | TyData::Function(_, _) | TyData::Error => unreachable!(
+				"Type {} cannot be represented in the MIR",
+				ty.pretty_print(db)
+			),
+		}
+	}
+
+	/// Get this type with the given number of array dimensions
+	pub fn with_dim(self, dims: u8) -> Self {
+		match self {
+			Ty::Bottom { is_set, .. } => Ty::Bottom { dim: dims, is_set },
+			Ty::Bool { is_var, is_set, .. } => Ty::Bool {
+				dim: dims,
+				is_var,
+				is_set,
+			},
+			Ty::Int { is_var, is_set, .. } => Ty::Int {
+				dim: dims,
+				is_var,
+				is_set,
+			},
+			Ty::Float { is_var, is_set, .. } => Ty::Float {
+				dim: dims,
+				is_var,
+				is_set,
+			},
+			Ty::String { .. } => Ty::String { dim: dims },
+			Ty::Ann { .. } => Ty::Ann { dim: dims },
+			Ty::Tuple { fields, .. } => Ty::Tuple { dim: dims, fields },
+			Ty::Any => Ty::Any,
+		}
+	}
+
+	/// The number of array dimensions (0 if not an array)
+	pub fn dim(&self) -> u8 {
+		match self {
+			Ty::Bottom { dim, .. }
+			| Ty::Bool { dim, .. }
+			| Ty::Int { dim, .. }
+			| Ty::Float { dim, .. }
+			| Ty::String { dim }
+			| Ty::Ann { dim }
+			| Ty::Tuple { dim, .. } => *dim,
+			Ty::Any => 0,
+		}
+	}
+
+	/// Whether this is a decision variable type
+	pub fn is_var(&self) -> bool {
+		match self {
+			Ty::Bool { is_var, .. } | Ty::Int { is_var, .. } | Ty::Float { is_var, .. } => *is_var,
+			Ty::Tuple { fields, .. } => fields.iter().any(|f| f.is_var()),
+			_ => false,
+		}
+	}
+
+	/// Pretty print this type
+	pub fn pretty_print(&self) -> String {
+		let base = match self {
+			Ty::Bottom { is_set, .. } => set_of(*is_set, "bot".to_owned()),
+			Ty::Bool { is_var, is_set, .. } => var_set_of(*is_var, *is_set, "bool"),
+			Ty::Int { is_var, is_set, .. } => var_set_of(*is_var, *is_set, "int"),
+			Ty::Float { is_var, is_set, .. } => var_set_of(*is_var, *is_set, "float"),
+			Ty::String { .. } => "string".to_owned(),
+			Ty::Ann { .. } => "ann".to_owned(),
+			Ty::Tuple { fields, .. } => format!(
+				"tuple({})",
+				fields
+					.iter()
+					.map(|f| f.pretty_print())
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			Ty::Any => return "any".to_owned(),
+		};
+		match self.dim() {
+			0 => base,
+			dim => format!(
+				"array [{}] of {}",
+				(0..dim).map(|_| "int").collect::<Vec<_>>().join(", "),
+				base
+			),
+		}
+	}
+}
+
+fn set_of(is_set: bool, base: String) -> String {
+	if is_set {
+		format!("set of {}", base)
+	} else {
+		base
+	}
+}
+
+fn var_set_of(is_var: bool, is_set: bool, base: &str) -> String {
+	let ty = set_of(is_set, base.to_owned());
+	if is_var {
+		format!("var {}", ty)
+	} else {
+		ty
+	}
 }
@@ -4,7 +4,10 @@
 
 use std::sync::{Arc, RwLock, RwLockReadGuard};
 
-use super::{transform::thir_transforms, Model};
+use super::{
+	transform::{mir_transforms, thir_transforms},
+	Model,
+};
 use crate::{db::Upcast, diagnostics::Diagnostics, hir::db::Hir, Error, Result};
 
 /// THIR queries
@@ -17,6 +20,10 @@ pub trait Thir: Hir + Upcast<dyn Hir> {
 	/// Get the THIR after all THIR rewritings have been done
 	fn final_thir(&self) -> Result<Arc<Model>>;
 
+	/// Get the final THIR with the rewritings which prepare it for lowering
+	/// into MIR applied
+	fn lowering_thir(&self) -> Result<Arc<Model>>;
+
 	/// Check that the pretty printed THIR is a valid model
 	#[salsa::invoke(super::sanity_check::sanity_check_thir)]
 	fn sanity_check_thir(&self) -> Arc<Diagnostics<Error>>;
@@ -72,3 +79,8 @@ fn final_thir(db: &dyn Thir) -> Result<Arc<Model>> {
 	let model = db.model_thir();
 	thir_transforms()(db, model.take()).map(Arc::new)
 }
+
+fn lowering_thir(db: &dyn Thir) -> Result<Arc<Model>> {
+	let model = db.final_thir()?;
+	mir_transforms()(db, (*model).clone()).map(Arc::new)
+}
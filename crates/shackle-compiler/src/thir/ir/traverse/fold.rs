@@ -593,6 +593,38 @@ pub fn add_model<'a, T: Marker, U: Marker, F: Folder<'a, U, T> + ?Sized>(
 	}
 }
 
+/// Add the annotation items, and the function items up to the last function named in `helpers`.
+///
+/// Used by transforms which introduce calls to the `helpers`, so that they exist in the destination model
+/// before any item using them is folded, while keeping the original item order.
+pub fn add_helper_functions<'a, T: Marker, U: Marker, F: Folder<'a, U, T> + ?Sized>(
+	folder: &mut F,
+	db: &'a dyn Thir,
+	model: &'a Model<T>,
+	helpers: &[Identifier],
+) {
+	for item in model.top_level_items() {
+		if let ItemId::Annotation(a) = item {
+			folder.add_annotation(db, model, a);
+		}
+	}
+	let functions = model
+		.top_level_items()
+		.filter_map(|item| match item {
+			ItemId::Function(f) => Some(f),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	if let Some(last) = functions
+		.iter()
+		.rposition(|f| helpers.iter().any(|h| model[*f].name() == *h))
+	{
+		for f in functions[..=last].iter() {
+			folder.add_function(db, model, *f);
+		}
+	}
+}
+
 /// Fold an item
 pub fn add_item<'a, T: Marker, U: Marker, F: Folder<'a, U, T> + ?Sized>(
 	folder: &mut F,
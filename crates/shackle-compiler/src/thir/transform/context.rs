@@ -0,0 +1,827 @@
+//! Context analysis
+//!
+//! Determines the Boolean context (root, positive, negative or mixed) in which each
+//! `var bool` call and `let` expression is evaluated, and labels them with the
+//! corresponding `ctx_root`, `ctx_pos`, `ctx_neg` or `ctx_mix` annotation.
+//!
+//! Functions with bodies which return `var bool` are specialised for the contexts
+//! they are called in, following the MiniZinc convention used to reify calls:
+//! - `foo_imp(args, b)` constrains `b -> foo(args)`, for calls in a positive context
+//! - `foo_reif(args, b)` constrains `b <-> foo(args)`, for calls in a negative or mixed context
+//!
+//! Calls keep using the original function, so that the backend can pick the version
+//! for the context by name. A version is not generated if a function with its name and
+//! signature already exists (e.g. one from the standard library), since that is the
+//! one which will be used.
+//!
+//! The bodies of the specialised versions are analysed in the context of the call,
+//! so the labels inside them reflect how the function is actually used.
+//!
+//! The contexts are found once by analysing the model, and the rewriting only labels
+//! expressions and generates functions using the analysed contexts.
+//!
+//! Runs when preparing the final THIR for lowering into MIR, after totalisation, since
+//! the definedness of partial expressions is conjoined into the nearest Boolean context,
+//! and after the decomposition of var expressions, so that the calls it introduces are
+//! labelled.
+
+use std::sync::Arc;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+	constants::{IdentifierRegistry, TypeRegistry},
+	thir::{
+		db::Thir,
+		traverse::{
+			add_annotation, add_function, fold_expression, visit_callable, visit_constraint,
+			visit_declaration, visit_domain, visit_expression, visit_generator, visit_model,
+			visit_record_access, visit_record_literal, visit_set_comprehension, visit_set_literal,
+			visit_tuple_access, visit_tuple_literal, Folder, ReplacementMap, Visitor,
+		},
+		AnnotationId, Branch, Call, Callable, Case, ConstraintId, Declaration, DeclarationId,
+		Domain, Expression, ExpressionData, Function, FunctionId, FunctionName, Generator,
+		Identifier, IfThenElse, Item, ItemId, Let, LetItem, LookupCall, Marker, Model,
+		RecordAccess, RecordLiteral, SetComprehension, SetLiteral, TupleAccess, TupleLiteral,
+	},
+	utils::{maybe_grow_stack, refmap::RefMap},
+	Result,
+};
+
+/// The Boolean context in which an expression is evaluated
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Context {
+	/// The expression must hold
+	Root,
+	/// The expression may only be made true
+	Positive,
+	/// The expression may only be made false
+	Negative,
+	/// The expression may be made true or false
+	Mixed,
+}
+
+impl Context {
+	/// The context of a monotone subexpression
+	pub fn positive(self) -> Self {
+		match self {
+			Context::Root | Context::Positive => Context::Positive,
+			Context::Negative => Context::Negative,
+			Context::Mixed => Context::Mixed,
+		}
+	}
+
+	/// The context of an antitone subexpression
+	pub fn negative(self) -> Self {
+		match self {
+			Context::Root | Context::Positive => Context::Negative,
+			Context::Negative => Context::Positive,
+			Context::Mixed => Context::Mixed,
+		}
+	}
+
+	/// The context of the specialised function version called in this context, if any
+	pub fn specialisation(self) -> Option<Self> {
+		match self {
+			Context::Root => None,
+			Context::Positive => Some(Context::Positive),
+			Context::Negative | Context::Mixed => Some(Context::Mixed),
+		}
+	}
+
+	/// The suffix given to a function specialised for this context
+	pub fn suffix(self) -> &'static str {
+		match self {
+			Context::Root => "",
+			Context::Positive => "_imp",
+			Context::Negative | Context::Mixed => "_reif",
+		}
+	}
+}
+
+/// The name of the version of the function specialised for the given context.
+///
+/// Based on the mangled name of overloaded functions, since that is the name the function is called by.
+fn specialised_name(db: &dyn Thir, model: &Model, f: FunctionId, context: Context) -> Identifier {
+	let function = &model[f];
+	let name = match function.mangled_param_tys() {
+		Some(tys) => function.name().mangled(db, tys.iter().copied()),
+		None => function.name().as_identifier(db),
+	};
+	Identifier::new(
+		format!("{}{}", name.lookup(db.upcast()), context.suffix()),
+		db.upcast(),
+	)
+}
+
+/// Find the existing functions which have the name and signature of a specialised version of a function
+fn existing_specialisations(
+	db: &dyn Thir,
+	tys: &TypeRegistry,
+	model: &Model,
+) -> FxHashMap<(FunctionId, Context), FunctionId> {
+	let mut functions: FxHashMap<FunctionName, Vec<FunctionId>> = FxHashMap::default();
+	for (idx, function) in model.top_level_functions() {
+		functions.entry(function.name()).or_default().push(idx);
+	}
+	let mut existing = FxHashMap::default();
+	for (f, function) in model.top_level_functions() {
+		if !is_specialisable(tys, model, f) {
+			continue;
+		}
+		let params = function
+			.parameters()
+			.iter()
+			.map(|p| model[*p].ty())
+			.chain([tys.var_bool])
+			.collect::<Vec<_>>();
+		for context in [Context::Positive, Context::Mixed] {
+			let name = FunctionName::new(specialised_name(db, model, f, context));
+			let found = functions.get(&name).and_then(|candidates| {
+				candidates.iter().copied().find(|g| {
+					model[*g].return_type() == tys.var_bool
+						&& model[*g]
+							.parameters()
+							.iter()
+							.map(|p| model[*p].ty())
+							.eq(params.iter().copied())
+				})
+			});
+			if let Some(g) = found {
+				existing.insert((f, context), g);
+			}
+		}
+	}
+	existing
+}
+
+/// Get the contexts of the arguments of a call in the given context
+fn argument_contexts(
+	ids: &IdentifierRegistry,
+	model: &Model,
+	call: &Call,
+	context: Context,
+) -> Vec<Context> {
+	let n = call.arguments.len();
+	let Callable::Function(f) = &call.function else {
+		return vec![Context::Mixed; n];
+	};
+	let function = &model[*f];
+	let name = function.name();
+	if name == ids.conj || name == ids.forall {
+		vec![context; n]
+	} else if name == ids.disj || name == ids.exists {
+		vec![context.positive(); n]
+	} else if name == ids.not {
+		vec![context.negative(); n]
+	} else if (name == ids.imp || name == ids.rimp || name == ids.clause) && n == 2 {
+		if name == ids.imp {
+			vec![context.negative(), context.positive()]
+		} else {
+			vec![context.positive(), context.negative()]
+		}
+	} else if function.annotations().has(model, ids.promise_ctx_monotone) {
+		vec![context.positive(); n]
+	} else if function.annotations().has(model, ids.promise_ctx_antitone) {
+		vec![context.negative(); n]
+	} else {
+		vec![Context::Mixed; n]
+	}
+}
+
+/// Whether the function can be specialised for the contexts it is called in
+fn is_specialisable(tys: &TypeRegistry, model: &Model, f: FunctionId) -> bool {
+	model[f].top_level() && model[f].body().is_some() && model[f].return_type() == tys.var_bool
+}
+
+/// The context in which the body of the original function is analysed
+fn own_context(tys: &TypeRegistry, model: &Model, f: FunctionId) -> Context {
+	if is_specialisable(tys, model, f) {
+		Context::Root
+	} else {
+		Context::Mixed
+	}
+}
+
+/// Whether this if-then-else has a `var` condition
+fn has_var_condition<T: Marker>(tys: &TypeRegistry, branches: &[Branch<T>]) -> bool {
+	branches.iter().any(|b| b.condition.ty() == tys.var_bool)
+}
+
+/// The function body being analysed along with its context, or `None` outside of function bodies
+type Scope = Option<(FunctionId, Context)>;
+
+/// Finds the context of each labelled expression and the contexts in which each function is called
+struct ContextAnalyser<'a> {
+	ids: Arc<IdentifierRegistry>,
+	tys: Arc<TypeRegistry>,
+	context: Context,
+	scope: Scope,
+	/// Contexts of the `var bool` calls and `let` expressions in each scope
+	contexts: FxHashMap<Scope, RefMap<'a, Expression, Context>>,
+	/// Specialised contexts of functions which need to be generated
+	specialisations: FxHashMap<FunctionId, FxHashSet<Context>>,
+	/// Existing specialised versions of functions
+	existing: FxHashMap<(FunctionId, Context), FunctionId>,
+	/// Function bodies which have been analysed in a context
+	visited: FxHashSet<(FunctionId, Context)>,
+	/// Function bodies to analyse
+	todo: Vec<(FunctionId, Context)>,
+}
+
+impl<'a> Visitor<'a> for ContextAnalyser<'a> {
+	fn visit_model(&mut self, model: &'a Model) {
+		for (f, function) in model.all_functions() {
+			if function.body().is_some() {
+				self.analyse_body(f, own_context(&self.tys, model, f));
+			}
+		}
+		visit_model(self, model);
+		while let Some((f, context)) = self.todo.pop() {
+			self.scope = Some((f, context));
+			self.context = context;
+			self.visit_expression(model, model[f].body().unwrap());
+		}
+		self.scope = None;
+	}
+
+	fn visit_function(&mut self, _model: &'a Model, _function: FunctionId) {
+		// Bodies are analysed once the contexts they are used in are known
+	}
+
+	fn visit_constraint(&mut self, model: &'a Model, constraint: ConstraintId) {
+		let context = self.context;
+		if model[constraint].top_level() {
+			self.context = Context::Root;
+		}
+		visit_constraint(self, model, constraint);
+		self.context = context;
+	}
+
+	fn visit_declaration(&mut self, model: &'a Model, declaration: DeclarationId) {
+		let context = self.context;
+		self.context = Context::Mixed;
+		visit_declaration(self, model, declaration);
+		self.context = context;
+	}
+
+	fn visit_expression(&mut self, model: &'a Model, expression: &'a Expression) {
+		maybe_grow_stack(|| {
+			let context = self.context;
+			if matches!(
+				&**expression,
+				ExpressionData::Call(_) | ExpressionData::Let(_)
+			) && expression.ty() == self.tys.var_bool
+			{
+				self.contexts
+					.entry(self.scope)
+					.or_default()
+					.insert(expression, context);
+			}
+			visit_expression(self, model, expression);
+			self.context = context;
+		})
+	}
+
+	fn visit_call(&mut self, model: &'a Model, call: &'a Call) {
+		let context = self.context;
+		if let Callable::Function(f) = &call.function {
+			if let Some(specialisation) = context.specialisation() {
+				if is_specialisable(&self.tys, model, *f)
+					&& !self.existing.contains_key(&(*f, specialisation))
+				{
+					self.specialisations
+						.entry(*f)
+						.or_default()
+						.insert(specialisation);
+					self.analyse_body(*f, specialisation);
+				}
+			}
+		}
+		self.context = Context::Mixed;
+		visit_callable(self, model, &call.function);
+		for (arg, ctx) in call
+			.arguments
+			.iter()
+			.zip(argument_contexts(&self.ids, model, call, context))
+		{
+			self.context = ctx;
+			self.visit_expression(model, arg);
+		}
+		self.context = context;
+	}
+
+	fn visit_let(&mut self, model: &'a Model, l: &'a Let) {
+		let context = self.context;
+		let constraint_context = if l.in_expression.ty() == self.tys.var_bool
+			|| l.in_expression.ty() == self.tys.par_bool
+		{
+			context
+		} else {
+			// Constraints in non-Boolean lets are enforced in the root
+			Context::Root
+		};
+		for item in l.items.iter() {
+			match item {
+				LetItem::Constraint(c) => {
+					self.context = constraint_context;
+					visit_constraint(self, model, *c);
+				}
+				LetItem::Declaration(d) => self.visit_declaration(model, *d),
+			}
+		}
+		self.context = context;
+		self.visit_expression(model, &l.in_expression);
+	}
+
+	fn visit_if_then_else(&mut self, model: &'a Model, ite: &'a IfThenElse) {
+		let context = self.context;
+		let result_context = if has_var_condition(&self.tys, &ite.branches) {
+			context.positive()
+		} else {
+			context
+		};
+		for branch in ite.branches.iter() {
+			self.context = Context::Mixed;
+			self.visit_expression(model, &branch.condition);
+			self.context = result_context;
+			self.visit_expression(model, &branch.result);
+		}
+		self.context = result_context;
+		self.visit_expression(model, &ite.else_result);
+		self.context = context;
+	}
+
+	fn visit_case(&mut self, model: &'a Model, c: &'a Case) {
+		let context = self.context;
+		self.context = Context::Mixed;
+		self.visit_expression(model, &c.scrutinee);
+		for branch in c.branches.iter() {
+			self.context = Context::Mixed;
+			self.visit_pattern(model, &branch.pattern);
+			self.context = context;
+			self.visit_expression(model, &branch.result);
+		}
+		self.context = context;
+	}
+
+	fn visit_generator(&mut self, model: &'a Model, generator: &'a Generator) {
+		let context = self.context;
+		self.context = Context::Mixed;
+		visit_generator(self, model, generator);
+		self.context = context;
+	}
+
+	fn visit_domain(&mut self, model: &'a Model, domain: &'a Domain) {
+		let context = self.context;
+		self.context = Context::Mixed;
+		visit_domain(self, model, domain);
+		self.context = context;
+	}
+
+	fn visit_set_literal(&mut self, model: &'a Model, sl: &'a SetLiteral) {
+		self.context = Context::Mixed;
+		visit_set_literal(self, model, sl);
+	}
+
+	fn visit_set_comprehension(&mut self, model: &'a Model, c: &'a SetComprehension) {
+		self.context = Context::Mixed;
+		visit_set_comprehension(self, model, c);
+	}
+
+	fn visit_tuple_literal(&mut self, model: &'a Model, tl: &'a TupleLiteral) {
+		self.context = Context::Mixed;
+		visit_tuple_literal(self, model, tl);
+	}
+
+	fn visit_record_literal(&mut self, model: &'a Model, rl: &'a RecordLiteral) {
+		self.context = Context::Mixed;
+		visit_record_literal(self, model, rl);
+	}
+
+	fn visit_tuple_access(&mut self, model: &'a Model, ta: &'a TupleAccess) {
+		self.context = Context::Mixed;
+		visit_tuple_access(self, model, ta);
+	}
+
+	fn visit_record_access(&mut self, model: &'a Model, ra: &'a RecordAccess) {
+		self.context = Context::Mixed;
+		visit_record_access(self, model, ra);
+	}
+}
+
+impl ContextAnalyser<'_> {
+	fn analyse_body(&mut self, f: FunctionId, context: Context) {
+		if self.visited.insert((f, context)) {
+			self.todo.push((f, context));
+		}
+	}
+}
+
+/// Labels expressions with the contexts found by the analyser and generates the specialised functions
+struct ContextRewriter<'a, Dst: Marker> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst>,
+	ids: Arc<IdentifierRegistry>,
+	tys: Arc<TypeRegistry>,
+	scope: Scope,
+	/// Contexts of the labelled expressions in each scope
+	contexts: FxHashMap<Scope, RefMap<'a, Expression, Context>>,
+	/// Context annotations in the destination model
+	annotations: FxHashMap<Context, AnnotationId<Dst>>,
+	/// Specialised contexts of functions
+	specialisations: FxHashMap<FunctionId, Vec<Context>>,
+	/// Specialised versions of functions
+	specialised: FxHashMap<(FunctionId, Context), FunctionId<Dst>>,
+}
+
+impl<'a, Dst: Marker> Folder<'a, Dst> for ContextRewriter<'a, Dst> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst> {
+		&mut self.replacement_map
+	}
+
+	fn add_annotation(&mut self, db: &'a dyn Thir, model: &'a Model, a: AnnotationId) {
+		if self.replacement_map.get_annotation(a).is_none() {
+			add_annotation(self, db, model, a);
+		}
+	}
+
+	fn add_function(&mut self, db: &'a dyn Thir, model: &'a Model, f: FunctionId) {
+		let idx = add_function(self, db, model, f);
+		let Some(contexts) = self.specialisations.get(&f).cloned() else {
+			return;
+		};
+		let origin = model[f].origin();
+		let mut after = ItemId::from(idx);
+		for context in contexts {
+			// Create `foo_imp(args, b)` or `foo_reif(args, b)`
+			let mut function = Function::new(
+				FunctionName::new(specialised_name(db, model, f, context)),
+				self.fold_domain(db, model, model[f].domain()),
+			);
+			function.annotations_mut().extend(
+				model[f]
+					.annotations()
+					.iter()
+					.map(|ann| self.fold_expression(db, model, ann)),
+			);
+			for p in model[f].parameters() {
+				self.add_parameter_declaration(db, model, *p);
+				let param = self.fold_declaration_id(db, model, *p);
+				function.add_parameter(param);
+			}
+			let b = self.model.add_declaration(Item::new(
+				Declaration::new(false, Domain::unbounded(db, origin, self.tys.var_bool)),
+				origin,
+			));
+			function.add_parameter(b);
+			let specialised = self
+				.model
+				.add_function_after(Item::new(function, origin), after);
+			self.specialised.insert((f, context), specialised);
+			after = specialised.into();
+		}
+	}
+
+	fn fold_function_body(&mut self, db: &'a dyn Thir, model: &'a Model, f: FunctionId) {
+		let scope = self.scope;
+		let mut versions = vec![(
+			self.fold_function_id(db, model, f),
+			own_context(&self.tys, model, f),
+		)];
+		if let Some(contexts) = self.specialisations.get(&f) {
+			versions.extend(contexts.iter().map(|c| (self.specialised[&(f, *c)], *c)));
+		}
+		for (i, (dst, ctx)) in versions.into_iter().enumerate() {
+			// Each version has its own parameters
+			let parameters = self.model[dst].parameters().to_vec();
+			for (src, p) in model[f].parameters().iter().zip(parameters) {
+				self.replacement_map.insert_declaration(*src, p);
+			}
+			self.scope = Some((f, ctx));
+			let mut body = self.fold_expression(db, model, model[f].body().unwrap());
+			if i > 0 {
+				// Constrain the extra parameter `b` of the specialised version
+				let origin = body.origin();
+				let b = *self.model[dst].parameters().last().unwrap();
+				let function = if ctx == Context::Positive {
+					self.ids.imp
+				} else {
+					self.ids.equiv
+				};
+				body = Expression::new(
+					db,
+					&self.model,
+					origin,
+					LookupCall {
+						function: function.into(),
+						arguments: vec![Expression::new(db, &self.model, origin, b), body],
+					},
+				);
+				if let Some(ann) = self.annotations.get(&Context::Root) {
+					let ann = Expression::new(db, &self.model, origin, *ann);
+					body.annotations_mut().push(ann);
+				}
+			}
+			let function = &mut self.model[dst];
+			function.set_body(body);
+			function.validate(db);
+		}
+		self.scope = scope;
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model,
+		expression: &'a Expression,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			let mut result = fold_expression(self, db, model, expression);
+			let context = self
+				.contexts
+				.get(&self.scope)
+				.and_then(|contexts| contexts.get(expression));
+			if let Some(ann) = context.and_then(|context| self.annotations.get(context)) {
+				let ann = Expression::new(db, &self.model, expression.origin(), *ann);
+				result.annotations_mut().push(ann);
+			}
+			result
+		})
+	}
+}
+
+/// Label Boolean expressions with their contexts and specialise functions for the contexts they are called in
+pub fn analyse_contexts(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Analysing Boolean contexts");
+	let ids = db.identifier_registry();
+	let tys = db.type_registry();
+
+	let mut analyser = ContextAnalyser {
+		ids: ids.clone(),
+		tys: tys.clone(),
+		context: Context::Mixed,
+		scope: None,
+		contexts: FxHashMap::default(),
+		specialisations: FxHashMap::default(),
+		existing: existing_specialisations(db, &tys, &model),
+		visited: FxHashSet::default(),
+		todo: Vec::new(),
+	};
+	analyser.visit_model(&model);
+
+	let mut r = ContextRewriter {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+		ids,
+		tys,
+		scope: None,
+		contexts: analyser.contexts,
+		annotations: FxHashMap::default(),
+		specialisations: analyser
+			.specialisations
+			.into_iter()
+			.map(|(f, contexts)| {
+				let mut contexts = contexts.into_iter().collect::<Vec<_>>();
+				contexts.sort();
+				(f, contexts)
+			})
+			.collect(),
+		specialised: FxHashMap::default(),
+	};
+	// Context annotations must exist before any expression is labelled
+	for item in model.top_level_items() {
+		if let ItemId::Annotation(a) = item {
+			r.add_annotation(db, &model, a);
+		}
+	}
+	let names = [
+		(Context::Root, r.ids.ctx_root),
+		(Context::Positive, r.ids.ctx_pos),
+		(Context::Negative, r.ids.ctx_neg),
+		(Context::Mixed, r.ids.ctx_mix),
+	];
+	let annotations = r
+		.model
+		.annotations()
+		.filter_map(|(idx, ann)| {
+			names
+				.iter()
+				.find(|(_, name)| ann.name == Some(*name))
+				.map(|(context, _)| (*context, idx))
+		})
+		.collect();
+	r.annotations = annotations;
+	r.add_model(db, &model);
+	Ok(r.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::analyse_contexts;
+	use crate::thir::transform::test::check_no_stdlib;
+
+	#[test]
+	fn test_context_builtins() {
+		check_no_stdlib(
+			analyse_contexts,
+			r#"
+				annotation ctx_root;
+				annotation ctx_pos;
+				annotation ctx_neg;
+				annotation ctx_mix;
+				function var bool: '/\'(var bool: x, var bool: y);
+				function var bool: '\/'(var bool: x, var bool: y);
+				function var bool: 'not'(var bool: x);
+				function var bool: '->'(var bool: x, var bool: y);
+				function var bool: '>'(var int: x, var int: y);
+				function var int: bool2int(var bool: x);
+				var int: x;
+				var int: y;
+				constraint x > 1 /\ y > 1;
+				constraint x > 2 \/ not (y > 2);
+				constraint (x > 3) -> (y > 3);
+				constraint bool2int(x > 4) > y;
+				var bool: b = x > 5;
+			"#,
+			expect!([r#"
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: '/\'(var bool: x, var bool: y);
+    function var bool: '\/'(var bool: x, var bool: y);
+    function var bool: 'not'(var bool: x);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: '>'(var int: x, var int: y);
+    function var int: bool2int(var bool: x);
+    var int: x;
+    var int: y;
+    constraint '/\'('>'(x, 1) :: (ctx_root), '>'(y, 1) :: (ctx_root)) :: (ctx_root);
+    constraint '\/'('>'(x, 2) :: (ctx_pos), 'not'('>'(y, 2) :: (ctx_neg)) :: (ctx_pos)) :: (ctx_root);
+    constraint '->'('>'(x, 3) :: (ctx_neg), '>'(y, 3) :: (ctx_pos)) :: (ctx_root);
+    constraint '>'(bool2int('>'(x, 4) :: (ctx_mix)), y) :: (ctx_root);
+    var bool: b = '>'(x, 5) :: (ctx_mix);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_context_specialise() {
+		check_no_stdlib(
+			analyse_contexts,
+			r#"
+				annotation ctx_root;
+				annotation ctx_pos;
+				annotation ctx_neg;
+				annotation ctx_mix;
+				function var bool: '\/'(var bool: x, var bool: y);
+				function var bool: 'not'(var bool: x);
+				function var bool: '>'(var int: x, var int: y);
+				function var bool: '->'(var bool: x, var bool: y);
+				function var bool: '<->'(var bool: x, var bool: y);
+				predicate foo(var int: x) = x > 0;
+				var int: x;
+				constraint foo(x);
+				constraint foo(x) \/ x > 2;
+				constraint not foo(x);
+			"#,
+			expect!([r#"
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: '\/'(var bool: x, var bool: y);
+    function var bool: 'not'(var bool: x);
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: '<->'(var bool: x, var bool: y);
+    function var bool: foo(var int: x) = '>'(x, 0) :: (ctx_root);
+    function var bool: foo_imp(var int: x, var bool: _DECL_12) = '->'(_DECL_12, '>'(x, 0) :: (ctx_pos)) :: (ctx_root);
+    function var bool: foo_reif(var int: x, var bool: _DECL_14) = '<->'(_DECL_14, '>'(x, 0) :: (ctx_mix)) :: (ctx_root);
+    var int: x;
+    constraint foo(x) :: (ctx_root);
+    constraint '\/'(foo(x) :: (ctx_pos), '>'(x, 2) :: (ctx_pos)) :: (ctx_root);
+    constraint 'not'(foo(x) :: (ctx_neg)) :: (ctx_root);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_context_specialise_existing() {
+		check_no_stdlib(
+			analyse_contexts,
+			r#"
+				annotation ctx_root;
+				annotation ctx_pos;
+				annotation ctx_neg;
+				annotation ctx_mix;
+				function var bool: 'not'(var bool: x);
+				function var bool: '>'(var int: x, var int: y);
+				function var bool: '->'(var bool: x, var bool: y);
+				predicate foo(var int: x) = x > 0;
+				predicate foo_reif(var int: x, var bool: b);
+				var int: x;
+				constraint not foo(x);
+				constraint foo(x) -> x > 2;
+			"#,
+			expect!([r#"
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: 'not'(var bool: x);
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: foo(var int: x) = '>'(x, 0) :: (ctx_root);
+    function var bool: foo_reif(var int: x, var bool: b);
+    var int: x;
+    constraint 'not'(foo(x) :: (ctx_neg)) :: (ctx_root);
+    constraint '->'(foo(x) :: (ctx_neg), '>'(x, 2) :: (ctx_pos)) :: (ctx_root);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_context_let() {
+		check_no_stdlib(
+			analyse_contexts,
+			r#"
+				annotation ctx_root;
+				annotation ctx_pos;
+				annotation ctx_neg;
+				annotation ctx_mix;
+				function var bool: '\/'(var bool: x, var bool: y);
+				function var bool: '>'(var int: x, var int: y);
+				function var int: foo(var int: z) = let {
+					var int: w;
+					constraint w > z;
+				} in w;
+				var int: x;
+				constraint x > 1 \/ let {
+					var int: y;
+					constraint y > x;
+				} in y > 2;
+				constraint foo(x) > 3;
+			"#,
+			expect!([r#"
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: '\/'(var bool: x, var bool: y);
+    function var bool: '>'(var int: x, var int: y);
+    function var int: foo(var int: z) = let {
+      var int: w;
+      constraint '>'(w, z) :: (ctx_root);
+    } in w;
+    var int: x;
+    constraint '\/'('>'(x, 1) :: (ctx_pos), let {
+      var int: y;
+      constraint '>'(y, x) :: (ctx_pos);
+    } in '>'(y, 2) :: (ctx_pos) :: (ctx_pos)) :: (ctx_root);
+    constraint '>'(foo(x), 3) :: (ctx_root);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_context_if_then_else() {
+		check_no_stdlib(
+			analyse_contexts,
+			r#"
+				annotation ctx_root;
+				annotation ctx_pos;
+				annotation ctx_neg;
+				annotation ctx_mix;
+				function var bool: '>'(var int: x, var int: y);
+				var int: x;
+				bool: p;
+				constraint if x > 4 then x > 5 else x > 6 endif;
+				constraint if p then x > 7 else x > 8 endif;
+			"#,
+			expect!([r#"
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: '>'(var int: x, var int: y);
+    var int: x;
+    bool: p;
+    constraint if '>'(x, 4) :: (ctx_mix) then '>'(x, 5) :: (ctx_pos) else '>'(x, 6) :: (ctx_pos) endif;
+    constraint if p then '>'(x, 7) :: (ctx_root) else '>'(x, 8) :: (ctx_root) endif;
+    solve satisfy;
+"#]),
+		)
+	}
+}
@@ -5,17 +5,19 @@
 //! It is the responsibility of implementors to know what constructs are expected to be present at the stage they run.
 
 use self::{
-	capturing_fn::decapture_model, comprehension::desugar_comprehension,
+	capturing_fn::decapture_model, comprehension::desugar_comprehension, context::analyse_contexts,
 	domain_constraint::rewrite_domains, erase_enum::erase_enum, erase_opt::erase_opt,
 	erase_record::erase_record, function_dispatch::function_dispatch, inlining::inline_functions,
 	name_mangle::mangle_names, output::generate_output, top_down_type::top_down_type,
-	type_specialise::type_specialise,
+	totalise::totalise, type_specialise::type_specialise,
+	var_decomposition::decompose_var_expressions,
 };
 use super::{db::Thir, Model};
 use crate::Result;
 
 pub mod capturing_fn;
 pub mod comprehension;
+pub mod context;
 pub mod domain_constraint;
 pub mod erase_enum;
 pub mod erase_opt;
@@ -25,7 +27,9 @@ pub mod inlining;
 pub mod name_mangle;
 pub mod output;
 pub mod top_down_type;
+pub mod totalise;
 pub mod type_specialise;
+pub mod var_decomposition;
 
 /// A THIR transform function
 pub type TransformFn = fn(&dyn Thir, Model) -> Result<Model>;
@@ -57,6 +61,11 @@ pub fn thir_transforms() -> impl FnMut(&dyn Thir, Model) -> Result<Model> {
 	])
 }
 
+/// Get the transformer which prepares the final THIR for lowering into MIR
+pub fn mir_transforms() -> impl FnMut(&dyn Thir, Model) -> Result<Model> {
+	transformer(vec![totalise, decompose_var_expressions, analyse_contexts])
+}
+
 #[cfg(test)]
 pub mod test {
 	use std::sync::Arc;
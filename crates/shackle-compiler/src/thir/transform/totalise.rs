@@ -0,0 +1,1446 @@
+//! Totalisation of partial functions and expressions
+//!
+//! Implements the relational semantics by turning partially defined expressions
+//! into a definedness flag and a value.
+//! - Partial functions return `tuple(bool, T)` instead of `T`
+//! - Calls to partial builtins (`div`, `mod`, `/` and array access) produce a definedness
+//!   check, and par calls are guarded so they are only evaluated when defined
+//! - Constraints in non-Boolean `let` expressions become part of the definedness
+//! - Definedness is conjoined into the nearest Boolean context
+//! - Definedness which has no Boolean context to go to (top-level declarations,
+//!   annotations, bodies of `:: promise_total` functions) is enforced in the root
+//! - Par partial comprehension generators and output expressions are checked at runtime
+//!   using `assert`, while var ones are static errors
+//! - Calls to `mzn_destruct_partial` produce the `(defined, value)` pair
+//!
+//! Constrained argument types must have been rewritten into `let` constraints
+//! and `:: mzn_inline` functions must have been inlined.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+	constants::{IdentifierRegistry, TypeRegistry},
+	diagnostics::PartialExpression,
+	hir::{BooleanLiteral, FloatLiteral, IntegerLiteral, OptType},
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{
+			add_annotation, add_helper_functions, fold_declaration, fold_expression,
+			fold_function_body, fold_generator, fold_output, Folder, ReplacementMap,
+		},
+		AnnotationId, ArrayComprehension, ArrayLiteral, Branch, Call, Callable, Constraint,
+		Declaration, DeclarationId, Domain, DummyValue, Expression, ExpressionData, FunctionId,
+		Generator, IfThenElse, Item, Let, LetItem, LookupCall, Marker, Model, Output, RecordAccess,
+		RecordLiteral, SetLiteral, StringLiteral, TupleAccess, TupleLiteral,
+	},
+	ty::Ty,
+	utils::{maybe_grow_stack, refmap::RefMap},
+	Result,
+};
+
+/// The definedness of a partial expression being totalised
+struct Partial<T: Marker> {
+	/// Items which must be in scope for the definedness and value
+	items: Vec<LetItem<T>>,
+	/// Conditions under which the expression is defined
+	defined: Vec<Expression<T>>,
+}
+
+impl<T: Marker> Default for Partial<T> {
+	fn default() -> Self {
+		Self {
+			items: Vec::new(),
+			defined: Vec::new(),
+		}
+	}
+}
+
+struct Totaliser<'a, Dst: Marker, Src: Marker = ()> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst, Src>,
+	ids: Arc<IdentifierRegistry>,
+	tys: Arc<TypeRegistry>,
+	partial_functions: FxHashSet<FunctionId<Src>>,
+	partiality: RefMap<'a, Expression<Src>, bool>,
+	/// Expressions which must be total, along with the message if they are undefined
+	checked: RefMap<'a, Expression<Src>, &'static str>,
+	error: Option<(Origin, &'static str)>,
+}
+
+impl<'a, Dst: Marker, Src: Marker> Folder<'a, Dst, Src> for Totaliser<'a, Dst, Src> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst, Src> {
+		&mut self.replacement_map
+	}
+
+	fn add_function(&mut self, db: &'a dyn Thir, model: &'a Model<Src>, f: FunctionId<Src>) {
+		if model[f].name() == self.ids.mzn_construct_partial
+			|| model[f].name() == self.ids.mzn_destruct_partial
+		{
+			// Remove mzn_construct_partial/mzn_destruct_partial
+			return;
+		}
+		if self.replacement_map.get_function(f).is_some() {
+			// Already added as a function used to express definedness
+			return;
+		}
+		let mut function = self.fold_function(db, model, &model[f]);
+		if self.partial_functions.contains(&f) {
+			// Return definedness along with the value
+			let origin = model[f].origin();
+			let return_type = function.return_type();
+			let defined = if return_type.known_par(db.upcast()) {
+				self.tys.par_bool
+			} else {
+				self.tys.var_bool
+			};
+			function.set_domain(Domain::tuple(
+				db,
+				origin,
+				OptType::NonOpt,
+				[
+					Domain::unbounded(db, origin, defined),
+					Domain::unbounded(db, origin, return_type),
+				],
+			));
+		}
+		let idx = self
+			.model
+			.add_function(Item::new(function, model[f].origin()));
+		self.replacement_map.insert_function(f, idx);
+	}
+
+	fn add_annotation(&mut self, db: &'a dyn Thir, model: &'a Model<Src>, a: AnnotationId<Src>) {
+		if self.replacement_map.get_annotation(a).is_none() {
+			add_annotation(self, db, model, a);
+		}
+	}
+
+	fn fold_declaration(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		d: &'a Declaration<Src>,
+	) -> Declaration<Dst> {
+		if let Some(def) = d.definition() {
+			if d.top_level()
+				&& d.annotations().has(model, self.ids.output_only)
+				&& self.is_partial(model, def)
+			{
+				self.checked.insert(def, "Output expressions must be total");
+			}
+		}
+		fold_declaration(self, db, model, d)
+	}
+
+	fn fold_output(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		o: &'a Output<Src>,
+	) -> Output<Dst> {
+		if self.is_partial(model, o.expression()) {
+			self.checked
+				.insert(o.expression(), "Output expressions must be total");
+		}
+		fold_output(self, db, model, o)
+	}
+
+	fn fold_generator(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		generator: &'a Generator<Src>,
+	) -> Generator<Dst> {
+		let collection = match generator {
+			Generator::Iterator { collection, .. } => Some(collection),
+			Generator::Assignment { assignment, .. } => model[*assignment].definition(),
+		};
+		if let Some(collection) = collection {
+			if self.is_partial(model, collection) {
+				self.checked
+					.insert(collection, "Comprehension generators must be total");
+			}
+		}
+		fold_generator(self, db, model, generator)
+	}
+
+	fn fold_function_body(&mut self, db: &'a dyn Thir, model: &'a Model<Src>, f: FunctionId<Src>) {
+		if !self.partial_functions.contains(&f) {
+			fold_function_body(self, db, model, f);
+			return;
+		}
+		let body = model[f].body().unwrap();
+		let mut partial = Partial::default();
+		let value = self.totalise(db, model, body, &mut partial);
+		let folded = self.into_pair(db, body.origin(), partial, value);
+		let dst = self.fold_function_id(db, model, f);
+		let function = &mut self.model[dst];
+		function.set_body(folded);
+		function.validate(db);
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			let origin = expression.origin();
+			if let Some(msg) = self.checked.get(expression).copied() {
+				return self.totalise_checked(db, model, expression, msg);
+			}
+			if let ExpressionData::Call(c) = &**expression {
+				if let Callable::Function(f) = &c.function {
+					if model[*f].name() == self.ids.mzn_destruct_partial {
+						// Make definedness of argument explicit
+						let mut partial = Partial::default();
+						let value = self.totalise_child(db, model, &c.arguments[0], &mut partial);
+						return self.into_pair(db, origin, partial, value);
+					}
+				}
+			}
+			if self.is_bool(expression.ty()) {
+				if self.has_partial_child(model, expression) {
+					// Boolean context, so conjoin definedness
+					let mut partial = Partial::default();
+					let value = self.totalise(db, model, expression, &mut partial);
+					return self.into_bool(db, origin, partial, value);
+				}
+			} else if self.is_partial(model, expression) {
+				// No Boolean context, so definedness must hold in the root
+				let mut partial = Partial::default();
+				let value = self.totalise(db, model, expression, &mut partial);
+				return self.into_root(db, origin, partial, value);
+			}
+			fold_expression(self, db, model, expression)
+		})
+	}
+}
+
+impl<'a, Src: Marker, Dst: Marker> Totaliser<'a, Dst, Src> {
+	fn is_bool(&self, ty: Ty) -> bool {
+		ty == self.tys.par_bool
+			|| ty == self.tys.var_bool
+			|| ty == self.tys.par_opt_bool
+			|| ty == self.tys.var_opt_bool
+	}
+
+	/// Record a static error for a partial expression which must be total
+	fn not_total(
+		&mut self,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+		msg: &'static str,
+	) {
+		if self.error.is_none() {
+			let origin = self.partial_origin(model, expression);
+			self.error = Some((origin, msg));
+		}
+	}
+
+	/// Get the origin of the outermost partial subexpression which comes from the source
+	fn partial_origin(&mut self, model: &'a Model<Src>, expression: &'a Expression<Src>) -> Origin {
+		if let (Origin::Introduced(_), ExpressionData::Call(c)) =
+			(expression.origin(), &**expression)
+		{
+			// E.g. the concatenation of output items
+			if let Some(arg) = c.arguments.iter().find(|arg| self.is_partial(model, arg)) {
+				return self.partial_origin(model, arg);
+			}
+		}
+		expression.origin()
+	}
+
+	/// Totalise an expression which must be total, asserting its definedness at runtime
+	///
+	/// Definedness which is not known to be par cannot be checked, so is a static error.
+	fn totalise_checked(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+		msg: &'static str,
+	) -> Expression<Dst> {
+		let origin = expression.origin();
+		let mut partial = Partial::default();
+		let value = self.totalise(db, model, expression, &mut partial);
+		if partial
+			.defined
+			.iter()
+			.any(|defined| !defined.ty().known_par(db.upcast()))
+		{
+			self.not_total(model, expression, msg);
+			return self.into_root(db, origin, partial, value);
+		}
+		if partial.defined.is_empty() {
+			return self.with_items(db, origin, partial.items, value);
+		}
+		let defined = std::mem::take(&mut partial.defined);
+		let defined = self.conjunction(db, origin, defined);
+		let msg = Expression::new(
+			db,
+			&self.model,
+			origin,
+			StringLiteral::new(msg, db.upcast()),
+		);
+		let result = Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.assert.into(),
+				arguments: vec![defined, msg, value],
+			},
+		);
+		self.with_items(db, origin, partial.items, result)
+	}
+
+	/// Find the partial functions (which have bodies) by iterating to a fixpoint
+	fn find_partial_functions(&mut self, model: &'a Model<Src>) {
+		loop {
+			// Partiality depends on the partial functions found so far
+			self.partiality = RefMap::default();
+			let found = model
+				.all_functions()
+				.filter(|(f, function)| {
+					!self.partial_functions.contains(f)
+						&& !self.is_bool(function.return_type())
+						&& !function.annotations().has(model, self.ids.promise_total)
+						&& function
+							.body()
+							.map(|body| self.is_partial(model, body))
+							.unwrap_or(false)
+				})
+				.map(|(f, _)| f)
+				.collect::<Vec<_>>();
+			if found.is_empty() {
+				break;
+			}
+			self.partial_functions.extend(found);
+		}
+	}
+
+	/// Whether this call is to a builtin function which is partial for the given arguments
+	fn is_partial_builtin(&self, model: &'a Model<Src>, c: &'a Call<Src>) -> bool {
+		let Callable::Function(f) = &c.function else {
+			return false;
+		};
+		if model[*f].body().is_some() {
+			return false;
+		}
+		let name = model[*f].name();
+		if name == self.ids.array_access {
+			return true;
+		}
+		if name == self.ids.div || name == self.ids.mod_ || name == self.ids.float_div {
+			// Division by a non-zero literal is always defined
+			return match &*c.arguments[1] {
+				ExpressionData::IntegerLiteral(i) => i.0 == 0,
+				ExpressionData::FloatLiteral(f) => f.value() == 0.0,
+				_ => true,
+			};
+		}
+		false
+	}
+
+	/// Whether this expression is partially defined
+	///
+	/// The result is cached so that each subexpression is only inspected once.
+	fn is_partial(&mut self, model: &'a Model<Src>, expression: &'a Expression<Src>) -> bool {
+		if self.is_bool(expression.ty()) {
+			return false;
+		}
+		if let Some(partial) = self.partiality.get(expression) {
+			return *partial;
+		}
+		let partial = match &**expression {
+			ExpressionData::Call(c) => match &c.function {
+				Callable::Function(f) => {
+					model[*f].name() == self.ids.mzn_construct_partial
+						|| self.partial_functions.contains(f)
+						|| self.is_partial_builtin(model, c)
+				}
+				_ => false,
+			},
+			ExpressionData::Let(l) => l
+				.items
+				.iter()
+				.any(|item| matches!(item, LetItem::Constraint(_))),
+			_ => false,
+		};
+		let partial = partial || self.has_partial_child(model, expression);
+		self.partiality.insert(expression, partial);
+		partial
+	}
+
+	/// Whether any of the immediate subexpressions which are not Boolean contexts is partial
+	fn has_partial_child(
+		&mut self,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+	) -> bool {
+		maybe_grow_stack(|| match &**expression {
+			ExpressionData::Call(c) => {
+				if let Callable::Function(f) = &c.function {
+					if model[*f].name() == self.ids.mzn_destruct_partial {
+						return false;
+					}
+				}
+				c.arguments.iter().any(|arg| self.is_partial(model, arg))
+			}
+			ExpressionData::Let(l) => {
+				l.items.iter().any(|item| match item {
+					LetItem::Declaration(d) => model[*d]
+						.definition()
+						.map(|def| self.is_partial(model, def))
+						.unwrap_or(false),
+					_ => false,
+				}) || self.is_partial(model, &l.in_expression)
+			}
+			ExpressionData::IfThenElse(ite) => {
+				ite.branches
+					.iter()
+					.any(|b| self.is_partial(model, &b.result))
+					|| self.is_partial(model, &ite.else_result)
+			}
+			ExpressionData::ArrayComprehension(c) => self.is_partial(model, &c.template),
+			ExpressionData::ArrayLiteral(al) => al.iter().any(|e| self.is_partial(model, e)),
+			ExpressionData::SetLiteral(sl) => sl.iter().any(|e| self.is_partial(model, e)),
+			ExpressionData::TupleLiteral(tl) => tl.iter().any(|e| self.is_partial(model, e)),
+			ExpressionData::RecordLiteral(rl) => rl.iter().any(|(_, e)| self.is_partial(model, e)),
+			ExpressionData::TupleAccess(ta) => self.is_partial(model, &ta.tuple),
+			ExpressionData::RecordAccess(ra) => self.is_partial(model, &ra.record),
+			_ => false,
+		})
+	}
+
+	/// Totalise the expression if it is partial, otherwise fold it
+	fn totalise_child(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+		partial: &mut Partial<Dst>,
+	) -> Expression<Dst> {
+		if self.is_partial(model, expression) {
+			self.totalise(db, model, expression, partial)
+		} else {
+			self.fold_expression(db, model, expression)
+		}
+	}
+
+	/// Totalise the expression into a `(defined, value)` pair
+	fn totalise_pair(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+	) -> Expression<Dst> {
+		let mut partial = Partial::default();
+		let value = self.totalise_child(db, model, expression, &mut partial);
+		self.into_pair(db, expression.origin(), partial, value)
+	}
+
+	/// Totalise an expression, adding its definedness to `partial` and returning its value
+	fn totalise(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		expression: &'a Expression<Src>,
+		partial: &mut Partial<Dst>,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			let origin = expression.origin();
+			let mut result = match &**expression {
+				ExpressionData::Call(c) => self.totalise_call(db, model, origin, c, partial),
+				ExpressionData::Let(l) => {
+					// Items are hoisted, constraints become part of the definedness
+					for item in l.items.iter() {
+						match item {
+							LetItem::Constraint(c) => {
+								let mut constraint =
+									self.fold_expression(db, model, model[*c].expression());
+								constraint.annotations_mut().extend(
+									model[*c]
+										.annotations()
+										.iter()
+										.map(|ann| self.fold_expression(db, model, ann)),
+								);
+								partial.defined.push(constraint);
+							}
+							LetItem::Declaration(d) => {
+								let idx = self.totalise_declaration(db, model, *d, partial);
+								partial.items.push(LetItem::Declaration(idx));
+							}
+						}
+					}
+					self.totalise_child(db, model, &l.in_expression, partial)
+				}
+				ExpressionData::IfThenElse(ite) => {
+					// Each branch gives a pair so definedness does not escape the branch
+					let branches = ite
+						.branches
+						.iter()
+						.map(|b| {
+							Branch::new(
+								self.fold_expression(db, model, &b.condition),
+								self.totalise_pair(db, model, &b.result),
+							)
+						})
+						.collect();
+					let else_result = Box::new(self.totalise_pair(db, model, &ite.else_result));
+					let pair = Expression::new(
+						db,
+						&self.model,
+						origin,
+						IfThenElse {
+							branches,
+							else_result,
+						},
+					);
+					self.from_pair(db, pair, partial)
+				}
+				ExpressionData::ArrayComprehension(c) => {
+					// Generate array of pairs, then separate out definedness and values
+					let generators = c
+						.generators
+						.iter()
+						.map(|g| self.fold_generator(db, model, g))
+						.collect();
+					let indices = c
+						.indices
+						.as_ref()
+						.map(|i| Box::new(self.fold_expression(db, model, i)));
+					let template = Box::new(self.totalise_pair(db, model, &c.template));
+					let pairs = Expression::new(
+						db,
+						&self.model,
+						origin,
+						ArrayComprehension {
+							generators,
+							indices,
+							template,
+						},
+					);
+					let pairs = self.bind(db, pairs, partial);
+					let defined = self.project(db, origin, pairs.clone(), 1);
+					partial.defined.push(Expression::new(
+						db,
+						&self.model,
+						origin,
+						LookupCall {
+							function: self.ids.forall.into(),
+							arguments: vec![defined],
+						},
+					));
+					let values = self.project(db, origin, pairs.clone(), 2);
+					if c.indices.is_some() {
+						Expression::new(
+							db,
+							&self.model,
+							origin,
+							LookupCall {
+								function: self.ids.array_xd.into(),
+								arguments: vec![pairs, values],
+							},
+						)
+					} else {
+						values
+					}
+				}
+				ExpressionData::ArrayLiteral(al) => {
+					let members = al
+						.iter()
+						.map(|e| self.totalise_child(db, model, e, partial))
+						.collect();
+					Expression::new(db, &self.model, origin, ArrayLiteral(members))
+				}
+				ExpressionData::SetLiteral(sl) => {
+					let members = sl
+						.iter()
+						.map(|e| self.totalise_child(db, model, e, partial))
+						.collect();
+					Expression::new(db, &self.model, origin, SetLiteral(members))
+				}
+				ExpressionData::TupleLiteral(tl) => {
+					let fields = tl
+						.iter()
+						.map(|e| self.totalise_child(db, model, e, partial))
+						.collect();
+					Expression::new(db, &self.model, origin, TupleLiteral(fields))
+				}
+				ExpressionData::RecordLiteral(rl) => {
+					let fields = rl
+						.iter()
+						.map(|(i, e)| (*i, self.totalise_child(db, model, e, partial)))
+						.collect();
+					Expression::new(db, &self.model, origin, RecordLiteral(fields))
+				}
+				ExpressionData::TupleAccess(ta) => {
+					let tuple = Box::new(self.totalise_child(db, model, &ta.tuple, partial));
+					Expression::new(
+						db,
+						&self.model,
+						origin,
+						TupleAccess {
+							tuple,
+							field: ta.field,
+						},
+					)
+				}
+				ExpressionData::RecordAccess(ra) => {
+					let record = Box::new(self.totalise_child(db, model, &ra.record, partial));
+					Expression::new(
+						db,
+						&self.model,
+						origin,
+						RecordAccess {
+							record,
+							field: ra.field,
+						},
+					)
+				}
+				_ => return fold_expression(self, db, model, expression),
+			};
+			result.annotations_mut().extend(
+				expression
+					.annotations()
+					.iter()
+					.map(|ann| self.fold_expression(db, model, ann)),
+			);
+			result
+		})
+	}
+
+	fn totalise_call(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		origin: Origin,
+		c: &'a Call<Src>,
+		partial: &mut Partial<Dst>,
+	) -> Expression<Dst> {
+		let mut arguments = c
+			.arguments
+			.iter()
+			.map(|arg| self.totalise_child(db, model, arg, partial))
+			.collect::<Vec<_>>();
+		if let Callable::Function(f) = &c.function {
+			if model[*f].name() == self.ids.mzn_construct_partial {
+				let pair = arguments.pop().unwrap();
+				return self.from_pair(db, pair, partial);
+			}
+		}
+		let function = self.fold_callable(db, model, &c.function);
+		let is_partial_function =
+			matches!(&c.function, Callable::Function(f) if self.partial_functions.contains(f));
+		if is_partial_function {
+			let pair = Expression::new(
+				db,
+				&self.model,
+				origin,
+				Call {
+					function,
+					arguments,
+				},
+			);
+			return self.from_pair(db, pair, partial);
+		}
+		if !self.is_partial_builtin(model, c) {
+			return Expression::new(
+				db,
+				&self.model,
+				origin,
+				Call {
+					function,
+					arguments,
+				},
+			);
+		}
+
+		let arguments = arguments
+			.into_iter()
+			.map(|arg| self.bind(db, arg, partial))
+			.collect::<Vec<_>>();
+		let check = match &c.function {
+			Callable::Function(f) if model[*f].name() == self.ids.array_access => LookupCall {
+				function: self.ids.mzn_array_access_valid.into(),
+				arguments: arguments.clone(),
+			},
+			_ => {
+				let divisor = arguments[1].clone();
+				let zero = if divisor.ty().is_float(db.upcast()) {
+					Expression::new(db, &self.model, origin, FloatLiteral::new(0.0))
+				} else {
+					Expression::new(db, &self.model, origin, IntegerLiteral(0))
+				};
+				LookupCall {
+					function: self.ids.ne.into(),
+					arguments: vec![divisor, zero],
+				}
+			}
+		};
+		let check = Expression::new(db, &self.model, origin, check);
+		partial.defined.push(check.clone());
+		let call = Expression::new(
+			db,
+			&self.model,
+			origin,
+			Call {
+				function,
+				arguments,
+			},
+		);
+		if call.ty().known_par(db.upcast()) {
+			// Par builtins must not be evaluated when undefined
+			let dummy = Expression::new(db, &self.model, origin, DummyValue(call.ty()));
+			return Expression::new(
+				db,
+				&self.model,
+				origin,
+				IfThenElse {
+					branches: vec![Branch::new(check, call)],
+					else_result: Box::new(dummy),
+				},
+			);
+		}
+		call
+	}
+
+	/// Add the declaration, hoisting out the definedness of its definition
+	fn totalise_declaration(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model<Src>,
+		d: DeclarationId<Src>,
+		partial: &mut Partial<Dst>,
+	) -> DeclarationId<Dst> {
+		let src = &model[d];
+		let Some(def) = src.definition().filter(|def| self.is_partial(model, *def)) else {
+			self.add_variable_declaration(db, model, d);
+			return self.fold_declaration_id(db, model, d);
+		};
+		let value = self.totalise(db, model, def, partial);
+		let mut declaration =
+			Declaration::new(src.top_level(), self.fold_domain(db, model, src.domain()));
+		if let Some(name) = src.name() {
+			declaration.set_name(name);
+		}
+		declaration.annotations_mut().extend(
+			src.annotations()
+				.iter()
+				.map(|ann| self.fold_expression(db, model, ann)),
+		);
+		declaration.set_definition(value);
+		declaration.validate(db);
+		let idx = self
+			.model
+			.add_declaration(Item::new(declaration, src.origin()));
+		self.replacement_map.insert_declaration(d, idx);
+		idx
+	}
+
+	/// Create the array `[p.field | p in pairs]`
+	fn project(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		pairs: Expression<Dst>,
+		field: i64,
+	) -> Expression<Dst> {
+		let elem = pairs.ty().elem_ty(db.upcast()).unwrap();
+		let p = self.model.add_declaration(Item::new(
+			Declaration::new(false, Domain::unbounded(db, origin, elem)),
+			origin,
+		));
+		let template = Expression::new(
+			db,
+			&self.model,
+			origin,
+			TupleAccess {
+				tuple: Box::new(Expression::new(db, &self.model, origin, p)),
+				field: IntegerLiteral(field),
+			},
+		);
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			ArrayComprehension::new(
+				[Generator::Iterator {
+					declarations: vec![p],
+					collection: pairs,
+					where_clause: None,
+				}],
+				template,
+			),
+		)
+	}
+
+	/// Bind the expression to a new declaration unless it is an identifier or literal
+	fn bind(
+		&mut self,
+		db: &'a dyn Thir,
+		expression: Expression<Dst>,
+		partial: &mut Partial<Dst>,
+	) -> Expression<Dst> {
+		if matches!(
+			&*expression,
+			ExpressionData::Identifier(_)
+				| ExpressionData::BooleanLiteral(_)
+				| ExpressionData::IntegerLiteral(_)
+				| ExpressionData::FloatLiteral(_)
+				| ExpressionData::StringLiteral(_)
+		) {
+			return expression;
+		}
+		let origin = expression.origin();
+		let declaration = Declaration::from_expression(db, false, expression);
+		let idx = self.model.add_declaration(Item::new(declaration, origin));
+		partial.items.push(LetItem::Declaration(idx));
+		Expression::new(db, &self.model, origin, idx)
+	}
+
+	/// Split a `(defined, value)` pair, adding the definedness to `partial` and returning the value
+	fn from_pair(
+		&mut self,
+		db: &'a dyn Thir,
+		pair: Expression<Dst>,
+		partial: &mut Partial<Dst>,
+	) -> Expression<Dst> {
+		let origin = pair.origin();
+		let pair = self.bind(db, pair, partial);
+		partial.defined.push(Expression::new(
+			db,
+			&self.model,
+			origin,
+			TupleAccess {
+				tuple: Box::new(pair.clone()),
+				field: IntegerLiteral(1),
+			},
+		));
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			TupleAccess {
+				tuple: Box::new(pair),
+				field: IntegerLiteral(2),
+			},
+		)
+	}
+
+	/// Create the conjunction of the given Boolean expressions
+	fn conjunction(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		conjuncts: impl IntoIterator<Item = Expression<Dst>>,
+	) -> Expression<Dst> {
+		let mut conjuncts = conjuncts.into_iter();
+		let Some(first) = conjuncts.next() else {
+			return Expression::new(db, &self.model, origin, BooleanLiteral(true));
+		};
+		conjuncts.fold(first, |acc, e| {
+			Expression::new(
+				db,
+				&self.model,
+				origin,
+				LookupCall {
+					function: self.ids.conj.into(),
+					arguments: vec![acc, e],
+				},
+			)
+		})
+	}
+
+	fn with_items(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		items: Vec<LetItem<Dst>>,
+		expression: Expression<Dst>,
+	) -> Expression<Dst> {
+		if items.is_empty() {
+			return expression;
+		}
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			Let {
+				items,
+				in_expression: Box::new(expression),
+			},
+		)
+	}
+
+	/// Create `let { items } in (defined, value)`
+	fn into_pair(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		partial: Partial<Dst>,
+		value: Expression<Dst>,
+	) -> Expression<Dst> {
+		let defined = self.conjunction(db, origin, partial.defined);
+		let pair = Expression::new(db, &self.model, origin, TupleLiteral(vec![defined, value]));
+		self.with_items(db, origin, partial.items, pair)
+	}
+
+	/// Create `let { items } in defined /\ value`
+	fn into_bool(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		partial: Partial<Dst>,
+		value: Expression<Dst>,
+	) -> Expression<Dst> {
+		let result = self.conjunction(db, origin, partial.defined.into_iter().chain([value]));
+		self.with_items(db, origin, partial.items, result)
+	}
+
+	/// Create `let { items; constraint defined } in value`
+	fn into_root(
+		&mut self,
+		db: &'a dyn Thir,
+		origin: Origin,
+		mut partial: Partial<Dst>,
+		value: Expression<Dst>,
+	) -> Expression<Dst> {
+		if !partial.defined.is_empty() {
+			let defined = std::mem::take(&mut partial.defined);
+			let defined = self.conjunction(db, origin, defined);
+			let constraint = self
+				.model
+				.add_constraint(Item::new(Constraint::new(false, defined), origin));
+			partial.items.push(LetItem::Constraint(constraint));
+		}
+		self.with_items(db, origin, partial.items, value)
+	}
+}
+
+/// Totalise partial functions and expressions using the relational semantics
+pub fn totalise(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Totalising partial functions");
+	let mut t = Totaliser {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+		ids: db.identifier_registry(),
+		tys: db.type_registry(),
+		partial_functions: FxHashSet::default(),
+		partiality: RefMap::default(),
+		checked: RefMap::default(),
+		error: None,
+	};
+	t.find_partial_functions(&model);
+	// Functions used to express definedness (and the annotations they may use) must
+	// exist before any item using them is folded
+	let helpers = [
+		t.ids.conj,
+		t.ids.forall,
+		t.ids.ne,
+		t.ids.mzn_array_access_valid,
+		t.ids.array_xd,
+		t.ids.assert,
+	];
+	add_helper_functions(&mut t, db, &model, &helpers);
+	t.add_model(db, &model);
+	if let Some((origin, msg)) = t.error {
+		let (src, span) = origin.source_span(db);
+		return Err(PartialExpression {
+			src,
+			msg: msg.to_owned(),
+			span,
+		}
+		.into());
+	}
+	Ok(t.model)
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use super::totalise;
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		file::{InputFile, InputLang},
+		thir::{
+			db::Thir,
+			transform::{domain_constraint::rewrite_domains, test::check_no_stdlib, transformer},
+		},
+	};
+
+	#[test]
+	fn test_totalise_partial_builtin() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function var int: 'div'(var int: x, var int: y);
+				function bool: '!='(int: x, int: y);
+				function var bool: '!='(var int: x, var int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function var bool: '/\'(var bool: x, var bool: y);
+				function var bool: '>'(var int: x, var int: y);
+				int: a;
+				int: b;
+				var int: c;
+				constraint a div b > 1;
+				constraint c div a > 1;
+				int: d = a div 2;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function var int: 'div'(var int: x, var int: y);
+    function bool: '!='(int: x, int: y);
+    function var bool: '!='(var int: x, var int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function var bool: '/\'(var bool: x, var bool: y);
+    function var bool: '>'(var int: x, var int: y);
+    int: a;
+    int: b;
+    var int: c;
+    constraint '/\'('!='(b, 0), '>'(if '!='(b, 0) then 'div'(a, b) else 0 endif, 1));
+    constraint '/\'('!='(a, 0), '>'('div'(c, a), 1));
+    int: d = 'div'(a, 2);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_array_access() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: '[]'(array [int] of int: x, int: i);
+				function bool: mzn_array_access_valid(array [int] of int: x, int: i);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '>'(int: x, int: y);
+				function int: '+'(int: x, int: y);
+				array [int] of int: a;
+				int: i;
+				constraint a[i] > 1;
+				int: x = a[i + 1];
+			"#,
+			expect!([r#"
+    function int: '[]'(array [int] of int: x, int: i);
+    function bool: mzn_array_access_valid(array [int] of int: x, int: i);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '>'(int: x, int: y);
+    function int: '+'(int: x, int: y);
+    array [int] of int: a;
+    int: i;
+    constraint '/\'(mzn_array_access_valid(a, i), '>'(if mzn_array_access_valid(a, i) then '[]'(a, i) else 0 endif, 1));
+    int: x = let {
+      int: _DECL_13 = '+'(i, 1);
+      constraint mzn_array_access_valid(a, _DECL_13);
+    } in if mzn_array_access_valid(a, _DECL_13) then '[]'(a, _DECL_13) else 0 endif;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_partial_function() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '>'(int: x, int: y);
+				function int: foo(int: x) = 10 div x;
+				function int: bar(int: x) = foo(x);
+				function int: qux(int: x) = 10 div 2;
+				constraint bar(1) > 0;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '>'(int: x, int: y);
+    function tuple(bool, int): foo(int: x) = ('!='(x, 0), if '!='(x, 0) then 'div'(10, x) else 0 endif);
+    function tuple(bool, int): bar(int: x) = let {
+      tuple(bool, int): _DECL_13 = foo(x);
+    } in ((_DECL_13).1, (_DECL_13).2);
+    function int: qux(int: x) = 'div'(10, 2);
+    constraint let {
+      tuple(bool, int): _DECL_12 = bar(1);
+    } in '/\'((_DECL_12).1, '>'((_DECL_12).2, 0));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_promise_total() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				annotation promise_total;
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '>'(int: x, int: y);
+				function int: foo(int: x) :: promise_total = 10 div x;
+				constraint foo(1) > 0;
+			"#,
+			expect!([r#"
+    annotation promise_total;
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '>'(int: x, int: y);
+    function int: foo(int: x) :: (promise_total) = let {
+      constraint '!='(x, 0);
+    } in if '!='(x, 0) then 'div'(10, x) else 0 endif;
+    constraint '>'(foo(1), 0);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_constrained_argument() {
+		check_no_stdlib(
+			transformer(vec![rewrite_domains, totalise]),
+			r#"
+				function set of int: '..'(int: a, int: b);
+				test mzn_domain_constraint(string: name, int: x, set of int: s);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '>'(int: x, int: y);
+				function int: foo(1..3: x) = x;
+				constraint foo(1) > 0;
+			"#,
+			expect!([r#"
+    function set of int: '..'(int: a, int: b);
+    function bool: mzn_domain_constraint(string: name, int: x, set of int: s);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '>'(int: x, int: y);
+    set of int: _DECL_10 = '..'(1, 3);
+    function tuple(bool, int): foo(int: x) = (mzn_domain_constraint("x", x, _DECL_10), x);
+    constraint let {
+      tuple(bool, int): _DECL_12 = foo(1);
+    } in '/\'((_DECL_12).1, '>'((_DECL_12).2, 0));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_let() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function var bool: '/\'(var bool: x, var bool: y);
+				function var bool: '>'(var int: x, var int: y);
+				function var int: foo(var int: y) = let {
+					var int: x;
+					constraint x > y;
+				} in x;
+				var int: z;
+				constraint let {
+					var int: w = foo(z);
+				} in w > 2;
+			"#,
+			expect!([r#"
+    function var bool: '/\'(var bool: x, var bool: y);
+    function var bool: '>'(var int: x, var int: y);
+    function tuple(var bool, var int): foo(var int: y) = let {
+      var int: x;
+    } in ('>'(x, y), x);
+    var int: z;
+    constraint let {
+      tuple(var bool, var int): _DECL_7 = foo(z);
+      var int: w = (_DECL_7).2;
+    } in '/\'((_DECL_7).1, '>'(w, 2));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_if_then_else() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '>'(int: x, int: y);
+				bool: p;
+				int: a;
+				constraint (if p then 1 div a else 2 endif) > 0;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '>'(int: x, int: y);
+    bool: p;
+    int: a;
+    constraint let {
+      tuple(bool, int): _DECL_11 = if p then ('!='(a, 0), if '!='(a, 0) then 'div'(1, a) else 0 endif) else (true, 2) endif;
+    } in '/\'((_DECL_11).1, '>'((_DECL_11).2, 0));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_literals() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: 'in'(int: x, set of int: y);
+				function bool: '='(array [int] of int: x, array [int] of int: y);
+				function bool: '='(tuple(int, int): x, tuple(int, int): y);
+				function bool: '='(int: x, int: y);
+				int: a;
+				constraint 1 in {1 div a, 2};
+				constraint [1 div a, 2] = [];
+				constraint (1 div a, 2) = (1, 2);
+				constraint (1 div a, 2).2 = 2;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: 'in'(int: x, set of int: y);
+    function bool: '='(array [int] of int: x, array [int] of int: y);
+    function bool: '='(tuple(int, int): x, tuple(int, int): y);
+    function bool: '='(int: x, int: y);
+    int: a;
+    constraint '/\'('!='(a, 0), 'in'(1, {if '!='(a, 0) then 'div'(1, a) else 0 endif, 2}));
+    constraint '/\'('!='(a, 0), '='([if '!='(a, 0) then 'div'(1, a) else 0 endif, 2], []));
+    constraint '/\'('!='(a, 0), '='((if '!='(a, 0) then 'div'(1, a) else 0 endif, 2), (1, 2)));
+    constraint '/\'('!='(a, 0), '='(((if '!='(a, 0) then 'div'(1, a) else 0 endif, 2)).2, 2));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_comprehension() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: forall(array [int] of bool: x);
+				function set of int: '..'(int: a, int: b);
+				function int: sum(array [int] of int: x);
+				function bool: '>'(int: x, int: y);
+				function int: '-'(int: x, int: y);
+				int: a;
+				constraint sum([10 div (i - a) | i in 1..3]) > 0;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: forall(array [int] of bool: x);
+    function set of int: '..'(int: a, int: b);
+    function int: sum(array [int] of int: x);
+    function bool: '>'(int: x, int: y);
+    function int: '-'(int: x, int: y);
+    int: a;
+    constraint let {
+      array [int] of tuple(bool, int): _DECL_18 = [let {
+      int: _DECL_17 = '-'(i, a);
+    } in ('!='(_DECL_17, 0), if '!='(_DECL_17, 0) then 'div'(10, _DECL_17) else 0 endif) | i in '..'(1, 3)];
+    } in '/\'(forall([(_DECL_19).1 | _DECL_19 in _DECL_18]), '>'(sum([(_DECL_20).2 | _DECL_20 in _DECL_18]), 0));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_access() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function bool: '='(int: x, int: y);
+				int: a;
+				tuple(int, int): t = (1 div a, 2);
+				constraint t.1 = 1;
+				constraint (p: 1 div a, q: 2).p = 1;
+				int: b = (p: 1 div a, q: 2).q;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    function bool: '='(int: x, int: y);
+    int: a;
+    tuple(int, int): t = let {
+      constraint '!='(a, 0);
+    } in (if '!='(a, 0) then 'div'(1, a) else 0 endif, 2);
+    constraint '='((t).1, 1);
+    constraint '/\'('!='(a, 0), '='(((p: if '!='(a, 0) then 'div'(1, a) else 0 endif, q: 2)).p, 1));
+    int: b = let {
+      constraint '!='(a, 0);
+    } in ((p: if '!='(a, 0) then 'div'(1, a) else 0 endif, q: 2)).q;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_annotation() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				annotation foo(int: x);
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				int: a;
+				var int: y :: foo(1 div a);
+			"#,
+			expect!([r#"
+    annotation foo(int: x);
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    int: a;
+    var int: y :: (let {
+      constraint '!='(a, 0);
+    } in foo(if '!='(a, 0) then 'div'(1, a) else 0 endif));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_partial_generator() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function set of int: '..'(int: a, int: b);
+				function bool: forall(array [int] of bool: x);
+				function set of int: assert(bool: b, string: msg, set of int: x);
+				int: a;
+				constraint forall([true | i in 1..10 div a]);
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function set of int: '..'(int: a, int: b);
+    function bool: forall(array [int] of bool: x);
+    function set of int: assert(bool: b, string: msg, set of int: x);
+    int: a;
+    constraint forall([true | i in assert('!='(a, 0), "Comprehension generators must be total", '..'(1, if '!='(a, 0) then 'div'(10, a) else 0 endif))]);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_partial_output() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function string: show(int: x);
+				function array [int] of string: assert(bool: b, string: msg, array [int] of string: x);
+				int: a;
+				output [show(1 div a)];
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function string: show(int: x);
+    function array [int] of string: assert(bool: b, string: msg, array [int] of string: x);
+    int: a;
+    output assert('!='(a, 0), "Output expressions must be total", [show(if '!='(a, 0) then 'div'(1, a) else 0 endif)]);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_var_partial_output() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function var int: '[]'(array [int] of var int: x, var int: i);
+				function var bool: mzn_array_access_valid(array [int] of var int: x, var int: i);
+				function string: show(var int: x);
+				array [int] of var int: x;
+				var int: i;
+				output [show(x[i])];
+			"#,
+			expect!([r#"Partially defined expression"#]),
+		)
+	}
+
+	#[test]
+	fn test_totalise_stdlib() {
+		let mut db = CompilerDatabase::default();
+		db.set_input_files(Arc::new(vec![InputFile::String(
+			r#"
+				enum Foo = F(1..3) ++ {G};
+				var Foo: y;
+				int: n = 3;
+				array [1..n] of set of int: adj = [{2}, {3}, {1}];
+				array [1..n] of var 1..n: x;
+				constraint forall (i in 1..n, j in adj[i]) (x[i] != x[j]);
+				output [show(x[i]) | i in 1..n];
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		)]));
+		if let Err(e) = db.lowering_thir() {
+			panic!("{}", e);
+		}
+	}
+
+	#[test]
+	fn test_totalise_destruct_partial() {
+		check_no_stdlib(
+			totalise,
+			r#"
+				function int: 'div'(int: x, int: y);
+				function bool: '!='(int: x, int: y);
+				function bool: '/\'(bool: x, bool: y);
+				function tuple(bool, $T): mzn_destruct_partial($T: x);
+				int: a;
+				int: b = let {
+					any: x = mzn_destruct_partial(1 div a);
+				} in if x.1 then x.2 else 0 endif;
+			"#,
+			expect!([r#"
+    function int: 'div'(int: x, int: y);
+    function bool: '!='(int: x, int: y);
+    function bool: '/\'(bool: x, bool: y);
+    int: a;
+    int: b = let {
+      tuple(bool, int): x = ('!='(a, 0), if '!='(a, 0) then 'div'(1, a) else 0 endif);
+    } in if (x).1 then (x).2 else 0 endif;
+    solve satisfy;
+"#]),
+		)
+	}
+}
@@ -0,0 +1,847 @@
+//! Decomposition of var if-then-else expressions and var array access
+//!
+//! The MIR only supports par conditionals, so:
+//! - If-then-else expressions with var conditions are rewritten into a call to the
+//!   `if_then_else` global with the reified branch conditions
+//! - Leading branches with par conditions are kept as a par if-then-else
+//! - Tuple valued conditionals are decomposed into a conditional for each field
+//! - Boolean conditionals are rewritten into the disjunction of the results conjoined with
+//!   the conditions under which they are selected, so that no free Boolean variable is
+//!   introduced (which would only be correct in the root context)
+//! - Declarations in the `let` of a non-Boolean branch are hoisted out of the conditional,
+//!   and its constraints are only enforced when the branch is selected. Hoisted variables
+//!   with a domain are declared unconstrained, and their domain and definition are also
+//!   only enforced when the branch is selected (so that e.g. an empty domain in a branch
+//!   which is not selected does not make the model unsatisfiable)
+//! - Calls to the builtin array access with a var index are rewritten into `element` calls
+//!
+//! Conditionals and array accesses for which there is no suitable `if_then_else` or `element`
+//! function (e.g. array valued conditionals) are left unchanged.
+//!
+//! Must run after totalisation, which guards partial var expressions using var conditionals,
+//! and before context analysis, so that the calls introduced are labelled with their contexts.
+
+use std::sync::Arc;
+
+use crate::{
+	constants::{IdentifierRegistry, TypeRegistry},
+	hir::BooleanLiteral,
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{
+			add_annotation, add_function, add_helper_functions, fold_expression, Folder,
+			ReplacementMap,
+		},
+		AnnotationId, ArrayLiteral, Branch, Call, Callable, Constraint, Declaration, DeclarationId,
+		Domain, DomainData, Expression, ExpressionData, FunctionId, IfThenElse, IntegerLiteral,
+		Item, Let, LetItem, LookupCall, Marker, Model, TupleAccess, TupleLiteral,
+	},
+	ty::Ty,
+	utils::maybe_grow_stack,
+	Result,
+};
+
+struct VarDecomposer<Dst: Marker, Src: Marker = ()> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst, Src>,
+	ids: Arc<IdentifierRegistry>,
+	tys: Arc<TypeRegistry>,
+}
+
+impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for VarDecomposer<Dst, Src> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst, Src> {
+		&mut self.replacement_map
+	}
+
+	fn add_annotation(&mut self, db: &dyn Thir, model: &Model<Src>, a: AnnotationId<Src>) {
+		if self.replacement_map.get_annotation(a).is_none() {
+			add_annotation(self, db, model, a);
+		}
+	}
+
+	fn add_function(&mut self, db: &dyn Thir, model: &Model<Src>, f: FunctionId<Src>) {
+		if self.replacement_map.get_function(f).is_none() {
+			add_function(self, db, model, f);
+		}
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		expression: &Expression<Src>,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			let origin = expression.origin();
+			let mut result = match &**expression {
+				ExpressionData::IfThenElse(ite)
+					if ite.has_var_condition(db)
+						&& (expression.ty() == self.tys.var_bool
+							|| self.can_select(db, expression.ty())) =>
+				{
+					self.decompose_if_then_else(db, model, ite, expression.ty(), origin)
+				}
+				ExpressionData::Call(c) if self.is_var_array_access(db, model, c) => {
+					let arguments = c
+						.arguments
+						.iter()
+						.map(|arg| self.fold_expression(db, model, arg))
+						.collect();
+					Expression::new(
+						db,
+						&self.model,
+						origin,
+						LookupCall {
+							function: self.ids.element.into(),
+							arguments,
+						},
+					)
+				}
+				_ => return fold_expression(self, db, model, expression),
+			};
+			result.annotations_mut().extend(
+				expression
+					.annotations()
+					.iter()
+					.map(|ann| self.fold_expression(db, model, ann)),
+			);
+			result
+		})
+	}
+}
+
+impl<Dst: Marker, Src: Marker> VarDecomposer<Dst, Src> {
+	/// Whether this is a call to the builtin array access with a var index which can be
+	/// rewritten into a call to `element`
+	fn is_var_array_access(&self, db: &dyn Thir, model: &Model<Src>, call: &Call<Src>) -> bool {
+		match &call.function {
+			Callable::Function(f) => {
+				model[*f].name() == self.ids.array_access
+					&& model[*f].body().is_none()
+					&& call.arguments[1].ty().contains_var(db.upcast())
+					&& self
+						.model
+						.lookup_function(
+							db,
+							self.ids.element.into(),
+							&call
+								.arguments
+								.iter()
+								.map(|arg| arg.ty())
+								.collect::<Vec<_>>(),
+						)
+						.is_ok()
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether there is an `if_then_else` function for selecting a value of this type
+	///
+	/// Boolean values are not selected, since the result would be a free Boolean variable.
+	fn can_select(&self, db: &dyn Thir, ty: Ty) -> bool {
+		if ty.is_array(db.upcast()) || ty.is_record(db.upcast()) || ty.is_bool(db.upcast()) {
+			return false;
+		}
+		if let Some(fields) = ty.fields(db.upcast()) {
+			return fields.into_iter().all(|f| self.can_select(db, f));
+		}
+		let conditions = Ty::array(db.upcast(), self.tys.par_int, self.tys.var_bool);
+		let results = Ty::array(db.upcast(), self.tys.par_int, ty);
+		match (conditions, results) {
+			(Some(c), Some(r)) => self
+				.model
+				.lookup_function(db, self.ids.if_then_else.into(), &[c, r, ty])
+				.is_ok(),
+			_ => false,
+		}
+	}
+
+	/// Decompose an if-then-else with a var condition
+	fn decompose_if_then_else(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		ite: &IfThenElse<Src>,
+		ty: Ty,
+		origin: Origin,
+	) -> Expression<Dst> {
+		let split = ite
+			.branches
+			.iter()
+			.position(|b| b.condition.ty() == self.tys.var_bool)
+			.expect("No var condition in if-then-else");
+		if split == 0 {
+			return self.decompose_branches(db, model, &ite.branches, &ite.else_result, ty, origin);
+		}
+		// Keep the leading par conditions as a par if-then-else
+		let branches = ite.branches[..split]
+			.iter()
+			.map(|b| {
+				Branch::new(
+					self.fold_expression(db, model, &b.condition),
+					self.fold_expression(db, model, &b.result),
+				)
+			})
+			.collect();
+		let else_result = self.decompose_branches(
+			db,
+			model,
+			&ite.branches[split..],
+			&ite.else_result,
+			ty,
+			origin,
+		);
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			IfThenElse {
+				branches,
+				else_result: Box::new(else_result),
+			},
+		)
+	}
+
+	/// Decompose the given branches into a call to `if_then_else`
+	fn decompose_branches(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		branches: &[Branch<Src>],
+		else_result: &Expression<Src>,
+		ty: Ty,
+		origin: Origin,
+	) -> Expression<Dst> {
+		if ty == self.tys.var_bool {
+			return self.decompose_bool_branches(db, model, branches, else_result, origin);
+		}
+		let mut items = Vec::new();
+		let mut conditions = Vec::with_capacity(branches.len());
+		let mut results = Vec::with_capacity(branches.len() + 1);
+		for b in branches {
+			let condition = self.fold_expression(db, model, &b.condition);
+			let condition = self.bind(db, condition, &mut items);
+			conditions.push(condition);
+			let result = self.decompose_branch(db, model, &b.result, &conditions, true, &mut items);
+			results.push(result);
+		}
+		let result = self.decompose_branch(db, model, else_result, &conditions, false, &mut items);
+		results.push(result);
+		let value = self.select(db, &conditions, results, ty, origin, &mut items);
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			Let {
+				items,
+				in_expression: Box::new(value),
+			},
+		)
+	}
+
+	/// Decompose the given branches of a Boolean conditional into the disjunction of
+	/// `selected /\ result` for each branch
+	fn decompose_bool_branches(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		branches: &[Branch<Src>],
+		else_result: &Expression<Src>,
+		origin: Origin,
+	) -> Expression<Dst> {
+		let mut items = Vec::new();
+		let mut conditions = Vec::with_capacity(branches.len());
+		let mut disjuncts = Vec::with_capacity(branches.len() + 1);
+		for b in branches {
+			let condition = self.fold_expression(db, model, &b.condition);
+			let condition = self.bind(db, condition, &mut items);
+			conditions.push(condition);
+			let selected = self.selected(db, &conditions, true, origin);
+			let result = self.fold_expression(db, model, &b.result);
+			disjuncts.push(self.conjunction(db, selected, result, origin));
+		}
+		let selected = self.selected(db, &conditions, false, origin);
+		let result = self.fold_expression(db, model, else_result);
+		disjuncts.push(self.conjunction(db, selected, result, origin));
+		let array = Expression::new(db, &self.model, origin, ArrayLiteral(disjuncts));
+		let value = Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.exists.into(),
+				arguments: vec![array],
+			},
+		);
+		if items.is_empty() {
+			return value;
+		}
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			Let {
+				items,
+				in_expression: Box::new(value),
+			},
+		)
+	}
+
+	/// Fold the result of a branch, hoisting the items of its `let` into `items`.
+	///
+	/// The branch is selected if the last of the `conditions` holds (or if `selected_by_last`
+	/// is false, if none of them hold), and none of the previous ones do.
+	fn decompose_branch(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		result: &Expression<Src>,
+		conditions: &[Expression<Dst>],
+		selected_by_last: bool,
+		items: &mut Vec<LetItem<Dst>>,
+	) -> Expression<Dst> {
+		let mut result = result;
+		let mut selected: Option<Expression<Dst>> = None;
+		// Constraints in Boolean lets are part of the branch's value so must not be hoisted
+		while let ExpressionData::Let(l) = &**result {
+			if result.ty().is_bool(db.upcast()) {
+				break;
+			}
+			for item in l.items.iter() {
+				match item {
+					LetItem::Declaration(d) => {
+						self.add_variable_declaration(db, model, *d);
+						let idx = self.fold_declaration_id(db, model, *d);
+						items.push(LetItem::Declaration(idx));
+						if let Some(expression) = self.unconstrain(db, idx) {
+							let origin = model[*d].origin();
+							let guard = self.guard(
+								db,
+								&mut selected,
+								conditions,
+								selected_by_last,
+								origin,
+								items,
+							);
+							let constraint = self.implication(db, guard, expression, origin);
+							let idx = self.model.add_constraint(Item::new(
+								Constraint::new(false, constraint),
+								origin,
+							));
+							items.push(LetItem::Constraint(idx));
+						}
+					}
+					LetItem::Constraint(c) => {
+						let origin = model[*c].origin();
+						let guard = self.guard(
+							db,
+							&mut selected,
+							conditions,
+							selected_by_last,
+							origin,
+							items,
+						);
+						let expression = self.fold_expression(db, model, model[*c].expression());
+						let implication = self.implication(db, guard, expression, origin);
+						let mut constraint = Constraint::new(false, implication);
+						constraint.annotations_mut().extend(
+							model[*c]
+								.annotations()
+								.iter()
+								.map(|ann| self.fold_expression(db, model, ann)),
+						);
+						let idx = self.model.add_constraint(Item::new(constraint, origin));
+						items.push(LetItem::Constraint(idx));
+					}
+				}
+			}
+			result = &l.in_expression;
+		}
+		self.fold_expression(db, model, result)
+	}
+
+	/// Remove the domain and definition of a hoisted var declaration with a bounded domain.
+	///
+	/// Returns the constraint which enforces them instead. Declarations without a domain keep
+	/// their definition, since it can always be satisfied.
+	fn unconstrain(&mut self, db: &dyn Thir, idx: DeclarationId<Dst>) -> Option<Expression<Dst>> {
+		let declaration = &self.model[idx];
+		if declaration.ty().known_par(db.upcast()) {
+			return None;
+		}
+		let DomainData::Bounded(domain) = &**declaration.domain() else {
+			return None;
+		};
+		let domain = (**domain).clone();
+		let origin = declaration.origin();
+		let unbounded = Domain::unbounded(db, origin, declaration.ty());
+		let definition = self.model[idx].take_definition();
+		self.model[idx].set_domain(unbounded);
+		let variable = Expression::new(db, &self.model, origin, idx);
+		let mut constraint = Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.in_.into(),
+				arguments: vec![variable.clone(), domain],
+			},
+		);
+		if let Some(definition) = definition {
+			let equal = Expression::new(
+				db,
+				&self.model,
+				origin,
+				LookupCall {
+					function: self.ids.eq.into(),
+					arguments: vec![variable, definition],
+				},
+			);
+			constraint = self.conjunction(db, constraint, equal, origin);
+		}
+		Some(constraint)
+	}
+
+	/// Get the condition under which the branch is selected, binding it on first use
+	fn guard(
+		&mut self,
+		db: &dyn Thir,
+		selected: &mut Option<Expression<Dst>>,
+		conditions: &[Expression<Dst>],
+		selected_by_last: bool,
+		origin: Origin,
+		items: &mut Vec<LetItem<Dst>>,
+	) -> Expression<Dst> {
+		if let Some(s) = selected {
+			return s.clone();
+		}
+		let s = self.selected(db, conditions, selected_by_last, origin);
+		let s = self.bind(db, s, items);
+		*selected = Some(s.clone());
+		s
+	}
+
+	/// Create the implication `guard -> expression`
+	fn implication(
+		&self,
+		db: &dyn Thir,
+		guard: Expression<Dst>,
+		expression: Expression<Dst>,
+		origin: Origin,
+	) -> Expression<Dst> {
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.imp.into(),
+				arguments: vec![guard, expression],
+			},
+		)
+	}
+
+	/// Create the conjunction `left /\ right`
+	fn conjunction(
+		&self,
+		db: &dyn Thir,
+		left: Expression<Dst>,
+		right: Expression<Dst>,
+		origin: Origin,
+	) -> Expression<Dst> {
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.conj.into(),
+				arguments: vec![left, right],
+			},
+		)
+	}
+
+	/// Create the condition under which a branch is selected
+	fn selected(
+		&mut self,
+		db: &dyn Thir,
+		conditions: &[Expression<Dst>],
+		selected_by_last: bool,
+		origin: Origin,
+	) -> Expression<Dst> {
+		let (condition, previous) = match conditions.split_last() {
+			Some((last, rest)) if selected_by_last => (Some(last.clone()), rest),
+			_ => (None, conditions),
+		};
+		let mut conjuncts = condition.into_iter().collect::<Vec<_>>();
+		for c in previous {
+			conjuncts.push(Expression::new(
+				db,
+				&self.model,
+				origin,
+				LookupCall {
+					function: self.ids.not.into(),
+					arguments: vec![c.clone()],
+				},
+			));
+		}
+		if conjuncts.len() == 1 {
+			return conjuncts.pop().unwrap();
+		}
+		let array = Expression::new(db, &self.model, origin, ArrayLiteral(conjuncts));
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.forall.into(),
+				arguments: vec![array],
+			},
+		)
+	}
+
+	/// Select the result of the first branch whose condition holds (or the else result)
+	fn select(
+		&mut self,
+		db: &dyn Thir,
+		conditions: &[Expression<Dst>],
+		results: Vec<Expression<Dst>>,
+		ty: Ty,
+		origin: Origin,
+		items: &mut Vec<LetItem<Dst>>,
+	) -> Expression<Dst> {
+		if let Some(fields) = ty.fields(db.upcast()) {
+			// Select each field separately
+			let results = results
+				.into_iter()
+				.map(|r| self.bind(db, r, items))
+				.collect::<Vec<_>>();
+			let members = fields
+				.into_iter()
+				.enumerate()
+				.map(|(i, field)| {
+					let field_results = results
+						.iter()
+						.map(|r| {
+							Expression::new(
+								db,
+								&self.model,
+								origin,
+								TupleAccess {
+									tuple: Box::new(r.clone()),
+									field: IntegerLiteral(i as i64 + 1),
+								},
+							)
+						})
+						.collect();
+					self.select(db, conditions, field_results, field, origin, items)
+				})
+				.collect();
+			return Expression::new(db, &self.model, origin, TupleLiteral(members));
+		}
+		let declaration = Declaration::new(false, Domain::unbounded(db, origin, ty));
+		let idx = self.model.add_declaration(Item::new(declaration, origin));
+		items.push(LetItem::Declaration(idx));
+		let conditions = conditions
+			.iter()
+			.cloned()
+			.chain([Expression::new(
+				db,
+				&self.model,
+				origin,
+				BooleanLiteral(true),
+			)])
+			.collect();
+		let call = Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.if_then_else.into(),
+				arguments: vec![
+					Expression::new(db, &self.model, origin, ArrayLiteral(conditions)),
+					Expression::new(db, &self.model, origin, ArrayLiteral(results)),
+					Expression::new(db, &self.model, origin, idx),
+				],
+			},
+		);
+		let constraint = self
+			.model
+			.add_constraint(Item::new(Constraint::new(false, call), origin));
+		items.push(LetItem::Constraint(constraint));
+		Expression::new(db, &self.model, origin, idx)
+	}
+
+	/// Bind an expression to a declaration unless it is an identifier or literal
+	fn bind(
+		&mut self,
+		db: &dyn Thir,
+		expression: Expression<Dst>,
+		items: &mut Vec<LetItem<Dst>>,
+	) -> Expression<Dst> {
+		if matches!(
+			&*expression,
+			ExpressionData::Identifier(_)
+				| ExpressionData::BooleanLiteral(_)
+				| ExpressionData::IntegerLiteral(_)
+				| ExpressionData::FloatLiteral(_)
+				| ExpressionData::StringLiteral(_)
+		) {
+			return expression;
+		}
+		let origin = expression.origin();
+		let declaration = Declaration::from_expression(db, false, expression);
+		let idx = self.model.add_declaration(Item::new(declaration, origin));
+		items.push(LetItem::Declaration(idx));
+		Expression::new(db, &self.model, origin, idx)
+	}
+}
+
+/// Decompose var if-then-else expressions and var array access
+pub fn decompose_var_expressions(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Decomposing var conditionals and array access");
+	let mut d = VarDecomposer {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+		ids: db.identifier_registry(),
+		tys: db.type_registry(),
+	};
+	// Functions used by the decompositions (and the annotations they may use) must
+	// exist before any item using them is folded
+	let helpers = [
+		d.ids.element,
+		d.ids.if_then_else,
+		d.ids.forall,
+		d.ids.exists,
+		d.ids.not,
+		d.ids.imp,
+		d.ids.in_,
+		d.ids.eq,
+		d.ids.conj,
+	];
+	add_helper_functions(&mut d, db, &model, &helpers);
+	d.add_model(db, &model);
+	Ok(d.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::decompose_var_expressions;
+	use crate::thir::transform::test::check_no_stdlib;
+
+	#[test]
+	fn test_decompose_var_if_then_else() {
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function bool: '>'(int: x, int: y);
+				function var bool: '>'(var int: x, var int: y);
+				predicate if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+				int: n;
+				var int: x;
+				var int: y = if x > 0 then 1 elseif x > 10 then 2 else x endif;
+				var int: z = if n > 0 then 1 elseif x > 0 then 2 else 3 endif;
+			"#,
+			expect!([r#"
+    function bool: '>'(int: x, int: y);
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+    int: n;
+    var int: x;
+    var int: y = let {
+      var bool: _DECL_10 = '>'(x, 0);
+      var bool: _DECL_11 = '>'(x, 10);
+      var int: _DECL_12;
+      constraint if_then_else([_DECL_10, _DECL_11, true], [1, 2, x], _DECL_12);
+    } in _DECL_12;
+    var int: z = if '>'(n, 0) then 1 else let {
+      var bool: _DECL_14 = '>'(x, 0);
+      var int: _DECL_15;
+      constraint if_then_else([_DECL_14, true], [2, 3], _DECL_15);
+    } in _DECL_15 endif;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_decompose_var_if_then_else_let() {
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function var bool: '>'(var int: x, var int: y);
+				function var int: '+'(var int: x, var int: y);
+				function var bool: 'not'(var bool: x);
+				function var bool: forall(array [int] of var bool: x);
+				function var bool: '->'(var bool: x, var bool: y);
+				predicate if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+				var int: x;
+				var int: y = if x > 0 then let {
+					var int: a = x + 1;
+					constraint a > 2;
+				} in a elseif x > 5 then 1 else let {
+					var int: b;
+					constraint b > x;
+				} in b endif;
+			"#,
+			expect!([r#"
+    function var bool: '>'(var int: x, var int: y);
+    function var int: '+'(var int: x, var int: y);
+    function var bool: 'not'(var bool: x);
+    function var bool: forall(array [int] of var bool: x);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+    var int: x;
+    var int: y = let {
+      var bool: _DECL_13 = '>'(x, 0);
+      var int: a = '+'(x, 1);
+      constraint '->'(_DECL_13, '>'(a, 2));
+      var bool: _DECL_15 = '>'(x, 5);
+      var int: b;
+      var bool: _DECL_17 = forall(['not'(_DECL_13), 'not'(_DECL_15)]);
+      constraint '->'(_DECL_17, '>'(b, x));
+      var int: _DECL_18;
+      constraint if_then_else([_DECL_13, _DECL_15, true], [a, 1, b], _DECL_18);
+    } in _DECL_18;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_decompose_var_if_then_else_let_domain() {
+		// The domain of a is empty if n < 1, which must only matter if the branch is selected
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function set of int: '..'(int: a, int: b);
+				function var bool: '>'(var int: x, var int: y);
+				function var bool: 'in'(var int: x, set of int: s);
+				function var bool: '='(var int: x, var int: y);
+				function var bool: '/\'(var bool: x, var bool: y);
+				function var bool: '->'(var bool: x, var bool: y);
+				predicate if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+				int: n;
+				var int: x;
+				var int: y = if x > 0 then let {
+					var 1..n: a;
+					var 1..n: b = a;
+				} in b else 0 endif;
+			"#,
+			expect!([r#"
+    function set of int: '..'(int: a, int: b);
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: 'in'(var int: x, set of int: s);
+    function var bool: '='(var int: x, var int: y);
+    function var bool: '/\'(var bool: x, var bool: y);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+    int: n;
+    var int: x;
+    var int: y = let {
+      var bool: _DECL_18 = '>'(x, 0);
+      var int: a;
+      constraint '->'(_DECL_18, 'in'(a, '..'(1, n)));
+      var int: b;
+      constraint '->'(_DECL_18, '/\'('in'(b, '..'(1, n)), '='(b, a)));
+      var int: _DECL_21;
+      constraint if_then_else([_DECL_18, true], [b, 0], _DECL_21);
+    } in _DECL_21;
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_decompose_var_if_then_else_tuple() {
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function var bool: '>'(var int: x, var int: y);
+				predicate if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+				var int: x;
+				tuple(var int, var int): t = if x > 0 then (1, x) else (x, 2) endif;
+			"#,
+			expect!([r#"
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: if_then_else(array [int] of var bool: c, array [int] of var int: x, var int: y);
+    var int: x;
+    tuple(var int, var int): t = let {
+      var bool: _DECL_7 = '>'(x, 0);
+      tuple(int, var int): _DECL_8 = (1, x);
+      tuple(var int, int): _DECL_9 = (x, 2);
+      var int: _DECL_10;
+      constraint if_then_else([_DECL_7, true], [(_DECL_8).1, (_DECL_9).1], _DECL_10);
+      var int: _DECL_11;
+      constraint if_then_else([_DECL_7, true], [(_DECL_8).2, (_DECL_9).2], _DECL_11);
+    } in (_DECL_10, _DECL_11);
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_decompose_var_if_then_else_bool() {
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function var bool: '>'(var int: x, var int: y);
+				function var bool: '/\'(var bool: x, var bool: y);
+				function var bool: 'not'(var bool: x);
+				function var bool: forall(array [int] of var bool: x);
+				function var bool: exists(array [int] of var bool: x);
+				predicate if_then_else(array [int] of var bool: c, array [int] of var bool: x, var bool: y);
+				var int: x;
+				var int: y;
+				constraint not (if x > 0 then y > 1 elseif x > 5 then y > 2 else y > 3 endif);
+			"#,
+			expect!([r#"
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: '/\'(var bool: x, var bool: y);
+    function var bool: 'not'(var bool: x);
+    function var bool: forall(array [int] of var bool: x);
+    function var bool: exists(array [int] of var bool: x);
+    function var bool: if_then_else(array [int] of var bool: c, array [int] of var bool: x, var bool: y);
+    var int: x;
+    var int: y;
+    constraint 'not'(let {
+      var bool: _DECL_12 = '>'(x, 0);
+      var bool: _DECL_13 = '>'(x, 5);
+    } in exists(['/\'(_DECL_12, '>'(y, 1)), '/\'(forall([_DECL_13, 'not'(_DECL_12)]), '>'(y, 2)), '/\'(forall(['not'(_DECL_12), 'not'(_DECL_13)]), '>'(y, 3))]));
+    solve satisfy;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_decompose_var_array_access() {
+		check_no_stdlib(
+			decompose_var_expressions,
+			r#"
+				function var int: '[]'(array [int] of var int: x, var int: i);
+				function var int: element(array [int] of var int: x, var int: i);
+				array [int] of var int: a;
+				int: i;
+				var int: j;
+				var int: y = a[i];
+				var int: z = a[j];
+			"#,
+			expect!([r#"
+    function var int: '[]'(array [int] of var int: x, var int: i);
+    function var int: element(array [int] of var int: x, var int: i);
+    array [int] of var int: a;
+    int: i;
+    var int: j;
+    var int: y = '[]'(a, i);
+    var int: z = element(a, j);
+    solve satisfy;
+"#]),
+		)
+	}
+}
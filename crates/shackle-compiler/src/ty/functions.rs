@@ -471,6 +471,29 @@ impl OverloadedFunction {
 		}
 	}
 
+	/// Whether the given argument types are compatible with the leading parameters of this function.
+	///
+	/// Used to find the overloads which may still match a call which is only partially written.
+	/// Type-inst variables are not required to be instantiated consistently.
+	pub fn matches_prefix(&self, db: &dyn Interner, args: &[Ty]) -> bool {
+		let params = self.params();
+		args.len() <= params.len()
+			&& args
+				.iter()
+				.zip(params.iter())
+				.all(|(arg, param)| match self {
+					OverloadedFunction::Function(_) => arg.is_subtype_of(db, *param),
+					OverloadedFunction::PolymorphicFunction(_) => {
+						PolymorphicFunctionType::collect_instantiations(
+							db,
+							&mut |_, _| true,
+							*arg,
+							*param,
+						)
+					}
+				})
+	}
+
 	/// Instantiate this function using the given type parameter types
 	pub fn instantiate(
 		&self,
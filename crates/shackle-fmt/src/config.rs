@@ -0,0 +1,100 @@
+//! Reading formatting options from configuration files
+
+use std::{
+	fmt::Display,
+	path::{Path, PathBuf},
+};
+
+use crate::MiniZincFormatOptions;
+
+/// Name of the file containing the formatting options for a project
+pub const CONFIG_FILE_NAME: &str = ".shackle-fmt.toml";
+
+/// Error reading a formatting configuration file
+#[derive(Debug)]
+pub enum ConfigError {
+	/// The file could not be read
+	Io(PathBuf, std::io::Error),
+	/// The file contains invalid options
+	Parse(PathBuf, toml::de::Error),
+}
+
+impl Display for ConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ConfigError::Io(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+			ConfigError::Parse(path, e) => {
+				write!(f, "Invalid options in {}: {}", path.display(), e)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Find the configuration file which applies to the given source file
+///
+/// This is the nearest `.shackle-fmt.toml` in the directory of the file or
+/// one of its ancestors.
+pub fn find_config_file(path: &Path) -> Option<PathBuf> {
+	let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+	path.ancestors()
+		.skip(1)
+		.map(|dir| dir.join(CONFIG_FILE_NAME))
+		.find(|config| config.is_file())
+}
+
+impl MiniZincFormatOptions {
+	/// Parse formatting options from the contents of a configuration file
+	///
+	/// Options which are not given take their default values.
+	pub fn from_toml(source: &str) -> Result<Self, toml::de::Error> {
+		toml::from_str(source)
+	}
+
+	/// Read formatting options from the given configuration file
+	pub fn from_config_file(path: &Path) -> Result<Self, ConfigError> {
+		let source =
+			std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_owned(), e))?;
+		Self::from_toml(&source).map_err(|e| ConfigError::Parse(path.to_owned(), e))
+	}
+
+	/// Get the formatting options for the given source file
+	///
+	/// Uses the nearest configuration file if there is one, otherwise the
+	/// default options.
+	pub fn for_file(path: &Path) -> Result<Self, ConfigError> {
+		match find_config_file(path) {
+			Some(config) => Self::from_config_file(&config),
+			None => Ok(Self::default()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Array2dLayout, MiniZincFormatOptions, OperatorPosition};
+
+	#[test]
+	fn test_config() {
+		let options = MiniZincFormatOptions::from_toml(
+			r#"
+line_width = 100
+use_tabs = false
+operator_position = "leading"
+array_2d_layout = "aligned"
+max_blank_lines = 2
+"#,
+		)
+		.unwrap();
+		assert_eq!(options.core.line_width, 100);
+		assert!(!options.core.use_tabs);
+		assert_eq!(options.core.indent_size, 4);
+		assert_eq!(options.operator_position, OperatorPosition::Leading);
+		assert_eq!(options.array_2d_layout, Array2dLayout::Aligned);
+		assert_eq!(options.max_blank_lines, 2);
+		assert!(!options.sort_includes);
+
+		assert!(MiniZincFormatOptions::from_toml("array_2d_layout = \"diagonal\"").is_err());
+	}
+}
@@ -1,14 +1,15 @@
-use shackle_compiler::syntax::minizinc;
+use shackle_compiler::syntax::{ast::AstNode, minizinc};
 use tree_sitter_minizinc::Precedence;
 
 use crate::{
 	format::{Format, MiniZincFormatter},
 	ir::Element,
+	Array2dLayout,
 };
 
 impl Format for minizinc::ArrayLiteral {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> Element {
-		formatter.format_list("[", "]", self.members())
+		formatter.format_collection("[", "]", self.members())
 	}
 }
 
@@ -28,6 +29,13 @@ impl Format for minizinc::ArrayLiteralMember {
 
 impl Format for minizinc::ArrayLiteral2D {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> Element {
+		let layout = formatter.options().array_2d_layout;
+		if layout == Array2dLayout::Aligned {
+			if let Some(e) = format_aligned(self, formatter) {
+				return e;
+			}
+		}
+
 		let mut elements = Vec::new();
 		let indices = self.column_indices().collect::<Vec<_>>();
 		let rows = self.rows().collect::<Vec<_>>();
@@ -45,7 +53,17 @@ impl Format for minizinc::ArrayLiteral2D {
 			Element::group(vec![
 				Element::indent(vec![
 					Element::line_break_or_space(),
-					Element::join(elements, vec![Element::text(" |"), Element::line_break()]),
+					Element::join(
+						elements,
+						vec![
+							Element::text(" |"),
+							if layout == Array2dLayout::Flowed {
+								Element::line_break_or_space()
+							} else {
+								Element::line_break()
+							},
+						],
+					),
 				]),
 				Element::line_break_or_space(),
 			]),
@@ -54,6 +72,98 @@ impl Format for minizinc::ArrayLiteral2D {
 	}
 }
 
+/// Format a 2D array literal with its columns aligned
+///
+/// Only possible if every member fits on a single line and there are no comments.
+fn format_aligned(
+	array: &minizinc::ArrayLiteral2D,
+	formatter: &mut MiniZincFormatter,
+) -> Option<Element> {
+	let node = array.cst_node().as_ref();
+	let mut cursor = node.walk();
+	let mut stack = vec![*node];
+	while let Some(n) = stack.pop() {
+		if n.is_extra() {
+			return None;
+		}
+		stack.extend(n.children(&mut cursor));
+	}
+
+	let options = formatter.options().core.clone();
+	let single_line = |e: Element| {
+		let text = e.format(&options);
+		(!text.contains('\n')).then_some(text)
+	};
+	let header = array
+		.column_indices()
+		.map(|i| single_line(i.format(formatter)).map(|i| format!("{}:", i)))
+		.collect::<Option<Vec<_>>>()?;
+	let rows = array
+		.rows()
+		.map(|r| {
+			let index = match r.index() {
+				Some(i) => Some(format!("{}: ", single_line(i.format(formatter))?)),
+				None => None,
+			};
+			let members = r
+				.members()
+				.map(|m| single_line(m.format(formatter)))
+				.collect::<Option<Vec<_>>>()?;
+			Some((index.unwrap_or_default(), members))
+		})
+		.collect::<Option<Vec<_>>>()?;
+
+	let width = |s: &String| s.chars().count();
+	let index_width = rows.iter().map(|(i, _)| width(i)).max().unwrap_or(0);
+	let mut widths = header.iter().map(width).collect::<Vec<_>>();
+	for (_, members) in rows.iter() {
+		for (i, m) in members.iter().enumerate() {
+			if i < widths.len() {
+				widths[i] = widths[i].max(width(m));
+			} else {
+				widths.push(width(m));
+			}
+		}
+	}
+
+	let mut lines = Vec::new();
+	if !header.is_empty() {
+		let columns = header
+			.iter()
+			.zip(widths.iter())
+			.map(|(h, w)| format!("{:>w$}", h, w = w))
+			.collect::<Vec<_>>();
+		lines.push(format!("{:w$}{}", "", columns.join("  "), w = index_width));
+	}
+	for (index, members) in rows.iter() {
+		let columns = members
+			.iter()
+			.zip(widths.iter())
+			.map(|(m, w)| format!("{:>w$}", m, w = w))
+			.collect::<Vec<_>>();
+		lines.push(format!(
+			"{:w$}{}",
+			index,
+			columns.join(", "),
+			w = index_width
+		));
+	}
+	Some(Element::sequence(vec![
+		Element::text("[|"),
+		Element::group(vec![
+			Element::indent(vec![
+				Element::line_break_or_space(),
+				Element::join(
+					lines.into_iter().map(Element::text),
+					vec![Element::text(" |"), Element::line_break()],
+				),
+			]),
+			Element::line_break_or_space(),
+		]),
+		Element::text("|]"),
+	]))
+}
+
 impl Format for minizinc::ArrayLiteral2DRow {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> Element {
 		let mut elements = Vec::new();
@@ -101,7 +211,7 @@ impl Format for minizinc::ArrayComprehension {
 			Element::text("["),
 			Element::group(vec![
 				Element::indent(vec![
-					Element::line_break_or_empty(),
+					formatter.bracket_space(),
 					Element::sequence(if let Some(indices) = self.indices() {
 						vec![indices.format(formatter), Element::text(": ")]
 					} else {
@@ -110,7 +220,7 @@ impl Format for minizinc::ArrayComprehension {
 					self.template().format(formatter),
 					Element::text(" |"),
 					Element::indent(vec![
-						Element::line_break_or_space(),
+						formatter.generators_break(),
 						Element::join(
 							self.generators().map(|g| g.format(formatter)),
 							vec![Element::text(","), Element::line_break_or_space()],
@@ -118,7 +228,7 @@ impl Format for minizinc::ArrayComprehension {
 						Element::if_broken(vec![Element::text(",")]),
 					]),
 				]),
-				Element::line_break_or_empty(),
+				formatter.bracket_space(),
 			]),
 			Element::text("]"),
 		])
@@ -127,7 +237,7 @@ impl Format for minizinc::ArrayComprehension {
 
 impl Format for minizinc::SetLiteral {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> Element {
-		formatter.format_list("{", "}", self.members())
+		formatter.format_collection("{", "}", self.members())
 	}
 }
 
@@ -137,11 +247,11 @@ impl Format for minizinc::SetComprehension {
 			Element::text("{"),
 			Element::group(vec![
 				Element::indent(vec![
-					Element::line_break_or_empty(),
+					formatter.bracket_space(),
 					self.template().format(formatter),
 					Element::text(" |"),
 					Element::indent(vec![
-						Element::line_break_or_space(),
+						formatter.generators_break(),
 						Element::join(
 							self.generators().map(|g| g.format(formatter)),
 							vec![Element::text(","), Element::line_break_or_space()],
@@ -149,7 +259,7 @@ impl Format for minizinc::SetComprehension {
 						Element::if_broken(vec![Element::text(",")]),
 					]),
 				]),
-				Element::line_break_or_empty(),
+				formatter.bracket_space(),
 			]),
 			Element::text("}"),
 		])
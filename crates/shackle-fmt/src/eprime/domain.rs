@@ -0,0 +1,60 @@
+use shackle_compiler::syntax::{ast::AstNode, eprime};
+
+use super::{EPrimeFormatter, Format};
+use crate::ir::Element;
+
+impl Format for eprime::Domain {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let e = match self {
+			eprime::Domain::BooleanDomain(_) => Element::text("bool"),
+			eprime::Domain::IntegerDomain(d) => d.format(formatter),
+			eprime::Domain::AnyDomain(_) => Element::text("any"),
+			eprime::Domain::MatrixDomain(d) => d.format(formatter),
+			eprime::Domain::DomainOperation(d) => d.format(formatter),
+			eprime::Domain::Identifier(i) => Element::text(i.name()),
+		};
+		formatter.attach_comments(self, vec![e])
+	}
+}
+
+impl Format for eprime::IntegerDomain {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let node = self.cst_node().as_ref();
+		let mut cursor = node.walk();
+		let has_parentheses = node.children(&mut cursor).any(|c| c.kind() == "(");
+		if has_parentheses {
+			Element::sequence(vec![
+				Element::text("int"),
+				formatter.format_list("(", ")", self.domain()),
+			])
+		} else {
+			Element::text("int")
+		}
+	}
+}
+
+impl Format for eprime::MatrixDomain {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			Element::text("matrix indexed by "),
+			formatter.format_list("[", "]", self.indexes()),
+			Element::text(" of "),
+			self.base().format(formatter),
+		])
+	}
+}
+
+impl Format for eprime::DomainOperation {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		// Domains cannot be parenthesised, so the tree already reflects the precedence
+		Element::group(vec![
+			self.left().format(formatter),
+			Element::indent(vec![
+				Element::text(" "),
+				Element::text(self.operator().name()),
+				Element::line_break_or_space(),
+				self.right().format(formatter),
+			]),
+		])
+	}
+}
@@ -0,0 +1,302 @@
+use shackle_compiler::syntax::{ast::AstNode, eprime};
+
+use super::{EPrimeFormatter, Format, Precedence, MAX_PRECEDENCE};
+use crate::ir::Element;
+
+impl Format for eprime::Expression {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let e = match self {
+			eprime::Expression::BooleanLiteral(b) => {
+				Element::text(if b.value() { "true" } else { "false" })
+			}
+			eprime::Expression::IntegerLiteral(i) => Element::text(i.cst_text()),
+			eprime::Expression::StringLiteral(s) => Element::text(s.cst_text()),
+			eprime::Expression::MatrixLiteral(m) => m.format(formatter),
+			eprime::Expression::Infinity(i) => Element::text(i.cst_text()),
+			eprime::Expression::Call(c) => c.format(formatter),
+			eprime::Expression::Identifier(i) => Element::text(i.name()),
+			eprime::Expression::ArrayAccess(a) => a.format(formatter),
+			eprime::Expression::InfixOperator(o) => o.format(formatter),
+			eprime::Expression::PrefixOperator(o) => o.format(formatter),
+			eprime::Expression::UnarySetConstructor(o) => o.format(formatter),
+			eprime::Expression::Quantification(q) => q.format(formatter),
+			eprime::Expression::MatrixComprehension(c) => c.format(formatter),
+			eprime::Expression::AbsoluteOperator(o) => o.format(formatter),
+			eprime::Expression::SetConstructor(s) => s.format(formatter),
+		};
+		formatter.attach_comments(self, vec![e])
+	}
+
+	fn has_brackets(&self, _formatter: &EPrimeFormatter) -> bool {
+		matches!(
+			self,
+			eprime::Expression::MatrixLiteral(_)
+				| eprime::Expression::MatrixComprehension(_)
+				| eprime::Expression::AbsoluteOperator(_)
+		)
+	}
+}
+
+impl Format for eprime::Identifier {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		formatter.attach_comments(self, vec![Element::text(self.name())])
+	}
+}
+
+impl Format for eprime::MatrixLiteral {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let members = self
+			.members()
+			.map(|m| m.format(formatter))
+			.collect::<Vec<_>>();
+		let mut elements = vec![Element::join(
+			members,
+			vec![Element::text(","), Element::line_break_or_space()],
+		)];
+		if let Some(index) = self.index() {
+			elements.push(Element::text(";"));
+			elements.push(Element::line_break_or_space());
+			elements.push(index.format(formatter));
+		}
+		let e = Element::group(vec![
+			Element::text("["),
+			Element::indent(vec![
+				Element::line_break_or_empty(),
+				Element::sequence(elements),
+			]),
+			Element::line_break_or_empty(),
+			Element::text("]"),
+		]);
+		formatter.attach_comments(self, vec![e])
+	}
+}
+
+impl Format for eprime::Call {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			self.function().format(formatter),
+			formatter.format_list("(", ")", self.arguments()),
+		])
+	}
+}
+
+impl Format for eprime::ArrayAccess {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let needs_parentheses = MAX_PRECEDENCE + 1 > formatter.precedence(&self.collection()).get();
+		Element::sequence(vec![
+			if needs_parentheses {
+				formatter.parenthesise(self.collection())
+			} else {
+				self.collection().format(formatter)
+			},
+			formatter.format_list("[", "]", self.indices()),
+		])
+	}
+}
+
+impl Format for eprime::ArrayIndex {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		match self {
+			eprime::ArrayIndex::IndexSlice(_) => {
+				formatter.attach_comments(self, vec![Element::text("..")])
+			}
+			eprime::ArrayIndex::Expression(e) => e.format(formatter),
+		}
+	}
+}
+
+/// Format an operand, adding parentheses if required by the precedence of the operator
+fn format_operand(
+	formatter: &mut EPrimeFormatter,
+	operand: eprime::Expression,
+	operator: &Precedence,
+	left: bool,
+) -> Element {
+	let needs_parentheses = match (formatter.precedence(&operand), operator) {
+		(Precedence::Left(i), Precedence::Left(j)) if i == *j => !left,
+		(Precedence::Right(i), Precedence::Right(j)) if i == *j => left,
+		(a, b) => a.get() <= b.get(),
+	};
+	if needs_parentheses {
+		formatter.parenthesise(operand)
+	} else {
+		operand.format(formatter)
+	}
+}
+
+enum InfixOperatorPart {
+	Left(eprime::Expression),
+	Operator(eprime::Operator),
+	Right(eprime::Expression),
+	Comments(Vec<Element>),
+}
+
+impl Format for eprime::InfixOperator {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let prec = Precedence::infix_operator(self.operator().name());
+		let mut todo = vec![
+			InfixOperatorPart::Right(self.right()),
+			InfixOperatorPart::Operator(self.operator()),
+			InfixOperatorPart::Left(self.left()),
+		];
+		let mut elements = Vec::new();
+		while let Some(p) = todo.pop() {
+			match p {
+				InfixOperatorPart::Left(e) => match (formatter.precedence(&e), &prec) {
+					(Precedence::Left(i), Precedence::Left(j)) if i == *j => {
+						// Flatten chains of left associative operators
+						if let Some(op) = e.cast_ref::<eprime::InfixOperator>() {
+							let comments = formatter.take_comments(op).unwrap_or_default();
+							todo.push(InfixOperatorPart::Comments(comments.after));
+							todo.push(InfixOperatorPart::Right(op.right()));
+							todo.push(InfixOperatorPart::Operator(op.operator()));
+							todo.push(InfixOperatorPart::Left(op.left()));
+							todo.push(InfixOperatorPart::Comments(comments.before));
+						} else {
+							elements.push(format_operand(formatter, e, &prec, true));
+						}
+					}
+					_ => elements.push(format_operand(formatter, e, &prec, true)),
+				},
+				InfixOperatorPart::Operator(op) => {
+					elements.push(Element::text(" "));
+					elements.push(Element::text(op.name()));
+					elements.push(Element::line_break_or_space());
+				}
+				InfixOperatorPart::Right(e) => match (formatter.precedence(&e), &prec) {
+					(Precedence::Right(i), Precedence::Right(j)) if i == *j => {
+						// Flatten chains of right associative operators
+						if let Some(op) = e.cast_ref::<eprime::InfixOperator>() {
+							let comments = formatter.take_comments(op).unwrap_or_default();
+							todo.push(InfixOperatorPart::Comments(comments.after));
+							todo.push(InfixOperatorPart::Right(op.right()));
+							todo.push(InfixOperatorPart::Operator(op.operator()));
+							todo.push(InfixOperatorPart::Left(op.left()));
+							todo.push(InfixOperatorPart::Comments(comments.before));
+						} else {
+							elements.push(format_operand(formatter, e, &prec, false));
+						}
+					}
+					_ => elements.push(format_operand(formatter, e, &prec, false)),
+				},
+				InfixOperatorPart::Comments(comments) => elements.extend(comments),
+			}
+		}
+		let mut iter = elements.into_iter();
+		let first = iter.next().unwrap();
+		Element::group(vec![first, Element::indent(iter)])
+	}
+}
+
+impl Format for eprime::PrefixOperator {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let prec = Precedence::prefix_operator(self.operator().name());
+		Element::sequence(vec![
+			Element::text(self.operator().name()),
+			format_operand(formatter, self.operand(), &prec, false),
+		])
+	}
+}
+
+impl Format for eprime::UnarySetConstructor {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let prec = formatter.precedence(&eprime::Expression::UnarySetConstructor(self.clone()));
+		if self.operator().name() == ".." {
+			Element::sequence(vec![
+				Element::text(".."),
+				format_operand(formatter, self.operand(), &prec, false),
+			])
+		} else {
+			Element::sequence(vec![
+				format_operand(formatter, self.operand(), &prec, true),
+				Element::text(".."),
+			])
+		}
+	}
+}
+
+impl Format for eprime::SetConstructor {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let prec = formatter.precedence(&eprime::Expression::SetConstructor(self.clone()));
+		Element::sequence(vec![
+			format_operand(formatter, self.left(), &prec, true),
+			Element::text(".."),
+			format_operand(formatter, self.right(), &prec, false),
+		])
+	}
+}
+
+impl Format for eprime::AbsoluteOperator {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			Element::text("|"),
+			self.operand().format(formatter),
+			Element::text("|"),
+		])
+	}
+}
+
+impl Format for eprime::Quantification {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::group(vec![
+			self.function().format(formatter),
+			Element::text(" "),
+			self.generator().format(formatter),
+			Element::text(" ."),
+			Element::indent(vec![
+				Element::line_break_or_space(),
+				self.template().format(formatter),
+			]),
+		])
+	}
+}
+
+impl Format for eprime::MatrixComprehension {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let mut clauses = self
+			.generators()
+			.map(|g| g.format(formatter))
+			.collect::<Vec<_>>();
+		clauses.extend(self.conditions().map(|c| c.format(formatter)));
+		let mut elements = vec![Element::join(
+			clauses,
+			vec![Element::text(","), Element::line_break_or_space()],
+		)];
+		if let Some(index) = self.indices() {
+			elements.push(Element::text(";"));
+			elements.push(Element::line_break_or_space());
+			elements.push(index.format(formatter));
+		}
+		Element::sequence(vec![
+			Element::text("["),
+			Element::group(vec![
+				Element::indent(vec![
+					Element::line_break_or_empty(),
+					self.template().format(formatter),
+					Element::text(" |"),
+					Element::indent(vec![
+						Element::line_break_or_space(),
+						Element::sequence(elements),
+					]),
+				]),
+				Element::line_break_or_empty(),
+			]),
+			Element::text("]"),
+		])
+	}
+}
+
+impl Format for eprime::Generator {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let names = self
+			.names()
+			.map(|n| n.format(formatter))
+			.collect::<Vec<_>>();
+		let e = Element::sequence(vec![
+			Element::join(names, vec![Element::text(", ")]),
+			Element::text(" : "),
+			self.collection().format(formatter),
+		]);
+		formatter.attach_comments(self, vec![e])
+	}
+}
@@ -0,0 +1,179 @@
+use shackle_compiler::syntax::{ast::AstNode, eprime};
+
+use super::{EPrimeFormatter, Format};
+use crate::ir::Element;
+
+impl Format for eprime::Item {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let mut elements = (0..formatter.blank_lines_before(self))
+			.map(|_| Element::line_break())
+			.collect::<Vec<_>>();
+		let element = match self {
+			eprime::Item::ParamDeclaration(x) => x.format(formatter),
+			eprime::Item::ConstDefinition(x) => x.format(formatter),
+			eprime::Item::DomainAlias(x) => x.format(formatter),
+			eprime::Item::DecisionDeclaration(x) => x.format(formatter),
+			eprime::Item::Solve(x) => x.format(formatter),
+			eprime::Item::Branching(x) => x.format(formatter),
+			eprime::Item::Heuristic(x) => x.format(formatter),
+			eprime::Item::Constraint(x) => x.format(formatter),
+			eprime::Item::Output(x) => x.format(formatter),
+		};
+		elements.push(element);
+		Element::sequence(vec![
+			formatter.attach_comments(self, elements),
+			Element::line_break(),
+		])
+	}
+}
+
+/// Format the names and domain of a declaration
+fn format_declaration(
+	formatter: &mut EPrimeFormatter,
+	keyword: &str,
+	names: impl Iterator<Item = eprime::Identifier>,
+	domain: eprime::Domain,
+) -> Element {
+	let names = names.map(|n| n.format(formatter)).collect::<Vec<_>>();
+	Element::sequence(vec![
+		Element::text(keyword),
+		Element::text(" "),
+		Element::join(names, vec![Element::text(", ")]),
+		Element::text(" :"),
+		Element::group(vec![Element::indent(vec![
+			Element::line_break_or_space(),
+			domain.format(formatter),
+		])]),
+	])
+}
+
+/// Format the definition of an item, on the next line if it does not fit
+fn format_definition(formatter: &mut EPrimeFormatter, definition: eprime::Expression) -> Element {
+	if definition.has_brackets(formatter) {
+		Element::sequence(vec![Element::text(" "), definition.format(formatter)])
+	} else {
+		Element::group(vec![Element::indent(vec![
+			Element::line_break_or_space(),
+			definition.format(formatter),
+		])])
+	}
+}
+
+impl Format for eprime::ParamDeclaration {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let mut elements = vec![format_declaration(
+			formatter,
+			"given",
+			self.names(),
+			self.domain(),
+		)];
+		for w in self.wheres() {
+			elements.push(Element::indent(vec![
+				Element::line_break(),
+				Element::text("where"),
+				format_definition(formatter, w),
+			]));
+		}
+		Element::sequence(elements)
+	}
+}
+
+impl Format for eprime::ConstDefinition {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let mut elements = vec![Element::text("letting "), self.name().format(formatter)];
+		if let Some(domain) = self.domain() {
+			elements.push(Element::text(" : "));
+			elements.push(domain.format(formatter));
+		}
+		// Keep the choice between `letting x = e` and `letting x be e`
+		let node = self.cst_node().as_ref();
+		let mut cursor = node.walk();
+		if node.children(&mut cursor).any(|c| c.kind() == "be") {
+			elements.push(Element::text(" be"));
+		} else {
+			elements.push(Element::text(" ="));
+		}
+		elements.push(format_definition(formatter, self.definition()));
+		Element::sequence(elements)
+	}
+}
+
+impl Format for eprime::DomainAlias {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			Element::text("letting "),
+			self.name().format(formatter),
+			Element::text(" be domain"),
+			Element::group(vec![Element::indent(vec![
+				Element::line_break_or_space(),
+				self.definition().format(formatter),
+			])]),
+		])
+	}
+}
+
+impl Format for eprime::DecisionDeclaration {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		format_declaration(formatter, "find", self.names(), self.domain())
+	}
+}
+
+impl Format for eprime::Solve {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let (keyword, objective) = match self.goal() {
+			eprime::Goal::Minimising(e) => ("minimising", e),
+			eprime::Goal::Maximising(e) => ("maximising", e),
+			eprime::Goal::Satisfy => unreachable!("Objective items always have a strategy"),
+		};
+		Element::sequence(vec![
+			Element::text(keyword),
+			format_definition(formatter, objective),
+		])
+	}
+}
+
+impl Format for eprime::Branching {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			Element::text("branching on "),
+			self.branching_array().format(formatter),
+		])
+	}
+}
+
+impl Format for eprime::Heuristic {
+	fn format(&self, _formatter: &mut EPrimeFormatter) -> Element {
+		match self.heuristic() {
+			Some(h) => Element::text(format!("heuristic {}", h.name())),
+			None => Element::text("heuristic"),
+		}
+	}
+}
+
+impl Format for eprime::Constraint {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let expressions = self
+			.expressions()
+			.map(|e| e.format(formatter))
+			.collect::<Vec<_>>();
+		Element::group(vec![
+			Element::text("such that"),
+			Element::indent(vec![
+				Element::line_break_or_space(),
+				Element::join(
+					expressions,
+					vec![Element::text(","), Element::line_break_or_space()],
+				),
+			]),
+		])
+	}
+}
+
+impl Format for eprime::Output {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		Element::sequence(vec![
+			Element::text("showing"),
+			format_definition(formatter, self.expression()),
+		])
+	}
+}
@@ -0,0 +1,250 @@
+//! Code formatting for Essence'
+
+use shackle_compiler::syntax::{
+	ast::AstNode,
+	eprime::{EPrimeModel, Expression},
+};
+use tree_sitter::Node;
+
+use crate::{
+	format::{blank_lines_before, CommentMap, Comments},
+	ir::Element,
+	MiniZincFormatOptions,
+};
+
+pub(crate) mod domain;
+pub(crate) mod expression;
+pub(crate) mod item;
+
+/// Trait for formatting Essence' nodes
+pub trait Format {
+	/// Format this node
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element;
+
+	/// Whether this node already has brackets around it
+	fn has_brackets(&self, _formatter: &EPrimeFormatter) -> bool {
+		false
+	}
+}
+
+impl Format for EPrimeModel {
+	fn format(&self, formatter: &mut EPrimeFormatter) -> Element {
+		let mut elements = Vec::new();
+		let root = self.cst().root_node();
+		if let Some(version) = root.child_by_field_name("lang_version") {
+			elements.push(formatter.format_lang_version(version));
+		}
+		elements.extend(self.items().map(|item| item.format(formatter)));
+		formatter.attach_model_comments(elements)
+	}
+}
+
+/// Formatter for Essence'
+///
+/// Only the core formatting options and the maximum number of blank lines apply to Essence'.
+pub struct EPrimeFormatter<'a> {
+	model: &'a EPrimeModel,
+	options: &'a MiniZincFormatOptions,
+	comments: CommentMap,
+}
+
+impl<'a> EPrimeFormatter<'a> {
+	/// Create a new formatter
+	pub fn new(model: &'a EPrimeModel, options: &'a MiniZincFormatOptions) -> Self {
+		Self {
+			model,
+			options,
+			comments: CommentMap::new(
+				model.cst(),
+				tree_sitter_eprime::COMMENTS_QUERY,
+				options.max_blank_lines,
+			),
+		}
+	}
+
+	/// Run the formatter
+	pub fn format(&mut self) -> String {
+		let element = self.model.format(self);
+		assert!(
+			self.comments.map.is_empty(),
+			"Did not attach all comments {:?}",
+			self.comments.map
+		);
+		element.format(&self.options.core)
+	}
+
+	/// Attach model comments to these elements
+	pub fn attach_model_comments(
+		&mut self,
+		elements: impl IntoIterator<Item = Element>,
+	) -> Element {
+		let root = self.model.cst().root_node();
+		self.attach_node_comments(root, elements)
+	}
+
+	/// Attach comments to these elements
+	pub fn attach_comments(
+		&mut self,
+		node: &impl AstNode,
+		elements: impl IntoIterator<Item = Element>,
+	) -> Element {
+		self.attach_node_comments(*node.cst_node().as_ref(), elements)
+	}
+
+	/// Take the comments for this node
+	pub fn take_comments(&mut self, node: &impl AstNode) -> Option<Comments> {
+		self.comments.map.remove(&node.cst_node().as_ref().id())
+	}
+
+	fn attach_node_comments(
+		&mut self,
+		node: Node<'_>,
+		elements: impl IntoIterator<Item = Element>,
+	) -> Element {
+		if let Some(c) = self.comments.map.remove(&node.id()) {
+			vec![
+				Element::sequence(c.before),
+				Element::sequence(elements),
+				Element::sequence(c.after),
+			]
+			.into()
+		} else {
+			Element::sequence(elements)
+		}
+	}
+
+	/// Get the number of blank lines to keep before this node
+	pub fn blank_lines_before(&self, node: &impl AstNode) -> usize {
+		blank_lines_before(*node.cst_node().as_ref(), self.options.max_blank_lines)
+	}
+
+	/// Format the language version line
+	fn format_lang_version(&mut self, node: Node<'_>) -> Element {
+		// The version is not a separate node, so just normalise the whitespace
+		let text = &self.model.cst().text()[node.byte_range()];
+		let version = text.split_whitespace().collect::<Vec<_>>().join(" ");
+		let element = self.attach_node_comments(node, vec![Element::text(version)]);
+		Element::sequence(vec![element, Element::line_break()])
+	}
+
+	/// Format items as a list
+	pub fn format_list(
+		&mut self,
+		open: &str,
+		close: &str,
+		items: impl Iterator<Item = impl Format>,
+	) -> Element {
+		let (mut elements, brackets) = items
+			.into_iter()
+			.map(|item| (item.format(self), item.has_brackets(self)))
+			.unzip::<_, _, Vec<_>, Vec<_>>();
+		if brackets.is_empty() {
+			return vec![Element::text(open), Element::text(close)].into();
+		}
+		if brackets.len() == 1 && brackets[0] {
+			return vec![
+				Element::text(open),
+				elements.pop().unwrap(),
+				Element::text(close),
+			]
+			.into();
+		}
+		Element::group(vec![
+			Element::text(open),
+			Element::indent(vec![
+				Element::line_break_or_empty(),
+				Element::join(
+					elements,
+					vec![Element::text(","), Element::line_break_or_space()],
+				),
+			]),
+			Element::line_break_or_empty(),
+			Element::text(close),
+		])
+	}
+
+	/// Parenthesise a node
+	pub fn parenthesise(&mut self, node: impl Format) -> Element {
+		if node.has_brackets(self) {
+			return vec![Element::text("("), node.format(self), Element::text(")")].into();
+		}
+		Element::group(vec![
+			Element::text("("),
+			Element::indent(vec![Element::line_break_or_empty(), node.format(self)]),
+			Element::line_break_or_empty(),
+			Element::text(")"),
+		])
+	}
+
+	/// Get precedence for the given expression
+	pub fn precedence(&self, expression: &Expression) -> Precedence {
+		match expression {
+			Expression::Call(_) | Expression::ArrayAccess(_) => {
+				Precedence::Prec(MAX_PRECEDENCE + 1)
+			}
+			Expression::InfixOperator(o) => Precedence::infix_operator(o.operator().name()),
+			Expression::PrefixOperator(o) => Precedence::prefix_operator(o.operator().name()),
+			Expression::SetConstructor(_) => Precedence::Left(RANGE_PRECEDENCE),
+			Expression::UnarySetConstructor(o) => {
+				if o.operator().name() == ".." {
+					Precedence::Left(RANGE_PRECEDENCE)
+				} else {
+					Precedence::Right(RANGE_PRECEDENCE)
+				}
+			}
+			Expression::Quantification(_) => Precedence::Prec(QUANTIFIER_PRECEDENCE),
+			_ => Precedence::Prec(i64::MAX),
+		}
+	}
+}
+
+const MAX_PRECEDENCE: i64 = 20;
+const RANGE_PRECEDENCE: i64 = 0;
+const QUANTIFIER_PRECEDENCE: i64 = -10;
+
+/// Grammar precedence value of an Essence' expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Precedence {
+	/// Left associative
+	Left(i64),
+	/// Non-associative
+	Prec(i64),
+	/// Right associative
+	Right(i64),
+}
+
+impl Precedence {
+	/// Get the precedence of the given infix operator
+	pub fn infix_operator(operator: &str) -> Self {
+		match operator {
+			"**" => Precedence::Right(18),
+			"*" | "/" | "%" => Precedence::Left(10),
+			"+" | "-" => Precedence::Left(1),
+			"=" | "!=" | "<" | "<=" | ">" | ">=" | "<lex" | "<=lex" | ">=lex" | ">lex" => {
+				Precedence::Left(-1)
+			}
+			"/\\" => Precedence::Left(-2),
+			"\\/" => Precedence::Left(-3),
+			"->" | "=>" => Precedence::Left(-4),
+			"<->" | "<=>" => Precedence::Left(-5),
+			"in" => Precedence::Left(0),
+			_ => unreachable!("Unknown infix operator {}", operator),
+		}
+	}
+
+	/// Get the precedence of the given prefix operator
+	pub fn prefix_operator(operator: &str) -> Self {
+		match operator {
+			"!" => Precedence::Left(20),
+			"-" => Precedence::Left(15),
+			_ => unreachable!("Unknown prefix operator {}", operator),
+		}
+	}
+
+	/// Get the precedence value
+	pub fn get(&self) -> i64 {
+		match self {
+			Precedence::Left(i) | Precedence::Prec(i) | Precedence::Right(i) => *i,
+		}
+	}
+}
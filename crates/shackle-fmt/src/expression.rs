@@ -7,6 +7,7 @@ use tree_sitter_minizinc::Precedence;
 use crate::{
 	format::{Format, MiniZincFormatter},
 	ir::Element,
+	OperatorPosition,
 };
 
 impl Format for minizinc::Expression {
@@ -203,6 +204,10 @@ impl Format for minizinc::InfixOperator {
 						elements.push(Element::if_broken(vec![Element::text(" ")]));
 						elements.push(Element::text(op.name()));
 						elements.push(Element::line_break_or_empty());
+					} else if formatter.options().operator_position == OperatorPosition::Leading {
+						elements.push(Element::line_break_or_space());
+						elements.push(Element::text(op.name()));
+						elements.push(Element::text(" "));
 					} else {
 						elements.push(Element::text(" "));
 						elements.push(Element::text(op.name()));
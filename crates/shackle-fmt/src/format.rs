@@ -1,7 +1,8 @@
 use rustc_hash::FxHashMap;
 use shackle_compiler::syntax::{
 	ast::AstNode,
-	minizinc::{Expression, MznModel},
+	cst::Cst,
+	minizinc::{Expression, Item, MznModel},
 };
 use tree_sitter::{Node, Query, QueryCursor};
 use tree_sitter_minizinc::Precedence;
@@ -21,8 +22,12 @@ pub trait Format {
 
 impl Format for MznModel {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> Element {
-		let elements = self
-			.items()
+		let mut items = self.items().collect::<Vec<_>>();
+		if formatter.options().sort_includes {
+			formatter.sort_includes(&mut items);
+		}
+		let elements = items
+			.iter()
 			.map(|item| item.format(formatter))
 			.collect::<Vec<_>>();
 		formatter.attach_model_comments(elements)
@@ -34,6 +39,7 @@ pub struct MiniZincFormatter<'a> {
 	model: &'a MznModel,
 	options: &'a MiniZincFormatOptions,
 	comments: CommentMap,
+	blank_lines: FxHashMap<usize, usize>,
 }
 
 impl<'a> MiniZincFormatter<'a> {
@@ -42,7 +48,12 @@ impl<'a> MiniZincFormatter<'a> {
 		Self {
 			model,
 			options,
-			comments: CommentMap::new(model),
+			comments: CommentMap::new(
+				model.cst(),
+				tree_sitter_minizinc::COMMENTS_QUERY,
+				options.max_blank_lines,
+			),
+			blank_lines: FxHashMap::default(),
 		}
 	}
 
@@ -103,12 +114,78 @@ impl<'a> MiniZincFormatter<'a> {
 		self.comments.map.remove(&node.cst_node().as_ref().id())
 	}
 
+	/// Get the number of blank lines to keep before this item
+	pub fn blank_lines_before(&self, item: &Item) -> usize {
+		let node = item.cst_node().as_ref();
+		self.blank_lines
+			.get(&node.id())
+			.copied()
+			.unwrap_or_else(|| blank_lines_before(*node, self.options.max_blank_lines))
+	}
+
+	/// Sort runs of consecutive include items by file name
+	///
+	/// The blank lines and comments before a run stay before its first item.
+	fn sort_includes(&mut self, items: &mut [Item]) {
+		let mut start = 0;
+		while start < items.len() {
+			let mut end = start;
+			while end < items.len()
+				&& matches!(items[end], Item::Include(_))
+				&& (end == start || follows_directly(&items[end - 1], &items[end]))
+			{
+				end += 1;
+			}
+			if end - start > 1 {
+				let first = items[start].clone();
+				items[start..end].sort_by_cached_key(|item| match item {
+					Item::Include(i) => i.file().value(),
+					_ => unreachable!(),
+				});
+				let new_first = &items[start];
+				if *new_first != first {
+					let blank_lines = self.blank_lines_before(&first);
+					let first_id = first.cst_node().as_ref().id();
+					let new_first_id = new_first.cst_node().as_ref().id();
+					self.blank_lines.insert(first_id, 0);
+					self.blank_lines.insert(new_first_id, blank_lines);
+					if let Some(c) = self.comments.map.get_mut(&first_id) {
+						let before = std::mem::take(&mut c.before);
+						self.comments.map.entry(new_first_id).or_default().before = before;
+					}
+				}
+			}
+			start = end.max(start + 1);
+		}
+	}
+
 	/// Format items as a list
 	pub fn format_list(
 		&mut self,
 		open: &str,
 		close: &str,
 		items: impl Iterator<Item = impl Format>,
+	) -> Element {
+		self.format_list_with_spacing(open, close, items, false)
+	}
+
+	/// Format the members of an array or set literal, with spaces inside the brackets if enabled
+	pub fn format_collection(
+		&mut self,
+		open: &str,
+		close: &str,
+		items: impl Iterator<Item = impl Format>,
+	) -> Element {
+		let spacing = self.options.bracket_spacing;
+		self.format_list_with_spacing(open, close, items, spacing)
+	}
+
+	fn format_list_with_spacing(
+		&mut self,
+		open: &str,
+		close: &str,
+		items: impl Iterator<Item = impl Format>,
+		spacing: bool,
 	) -> Element {
 		let (mut elements, brackets) = items
 			.into_iter()
@@ -117,25 +194,35 @@ impl<'a> MiniZincFormatter<'a> {
 		if brackets.is_empty() {
 			return vec![Element::text(open), Element::text(close)].into();
 		}
+		let space = if spacing { " " } else { "" };
 		if brackets.len() == 1 && brackets[0] {
 			return vec![
 				Element::text(open),
+				Element::text(space),
 				elements.pop().unwrap(),
+				Element::text(space),
 				Element::text(close),
 			]
 			.into();
 		}
+		let line_break = || {
+			if spacing {
+				Element::line_break_or_space()
+			} else {
+				Element::line_break_or_empty()
+			}
+		};
 		Element::group(vec![
 			Element::text(open),
 			Element::indent(vec![
-				Element::line_break_or_empty(),
+				line_break(),
 				Element::join(
 					elements,
 					vec![Element::text(","), Element::line_break_or_space()],
 				),
 				Element::if_broken(Element::text(",")),
 			]),
-			Element::line_break_or_empty(),
+			line_break(),
 			Element::text(close),
 		])
 	}
@@ -161,6 +248,24 @@ impl<'a> MiniZincFormatter<'a> {
 		}
 	}
 
+	/// Line break inside the brackets of array and set literals and comprehensions
+	pub fn bracket_space(&self) -> Element {
+		if self.options.bracket_spacing {
+			Element::line_break_or_space()
+		} else {
+			Element::line_break_or_empty()
+		}
+	}
+
+	/// Line break before the generators of a comprehension
+	pub fn generators_break(&self) -> Element {
+		if self.options.generators_on_new_line {
+			Element::line_break()
+		} else {
+			Element::line_break_or_space()
+		}
+	}
+
 	/// Parenthesise a node
 	pub fn parenthesise(&mut self, node: impl Format) -> Element {
 		if node.has_brackets(self) {
@@ -204,22 +309,18 @@ pub struct Comments {
 /// Keeps track of where to attach comments
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CommentMap {
-	map: FxHashMap<usize, Comments>,
+	pub(crate) map: FxHashMap<usize, Comments>,
 }
 
 impl CommentMap {
-	/// Create a comment map from the given model
-	pub fn new(model: &MznModel) -> Self {
+	/// Create a comment map from the given CST, finding the comments using the given query
+	pub fn new(cst: &Cst, query: &str, max_blank_lines: usize) -> Self {
 		let mut map: FxHashMap<usize, Comments> = FxHashMap::default();
 
-		let query = Query::new(
-			&tree_sitter_minizinc::language(),
-			tree_sitter_minizinc::COMMENTS_QUERY,
-		)
-		.expect("Failed to create query");
-		let text = model.cst().text().as_bytes();
+		let query = Query::new(&cst.language(), query).expect("Failed to create query");
+		let text = cst.text().as_bytes();
 		let mut cursor = QueryCursor::new();
-		let captures = cursor.captures(&query, model.cst().root_node(), text);
+		let captures = cursor.captures(&query, cst.root_node(), text);
 
 		for (c, _) in captures {
 			let node = c.captures[0].node;
@@ -239,13 +340,15 @@ impl CommentMap {
 				}
 				next_non_extra = n.next_sibling();
 			}
-			let blank_line_before =
-				node.prev_sibling()
-					.map(|n| n.end_position().row < node.start_position().row.saturating_sub(1))
-					.unwrap_or_default() && node
-					.parent()
-					.map(|n| n.kind() == "source_file")
-					.unwrap_or_default();
+			let blank_lines_before = if node
+				.parent()
+				.map(|n| n.kind() == "source_file")
+				.unwrap_or_default()
+			{
+				blank_lines_before(node, max_blank_lines)
+			} else {
+				0
+			};
 			let is_suffix =
 				prev.map(|p| p.end_position().row == node.start_position().row)
 					.unwrap_or_default() && (is_line
@@ -296,9 +399,9 @@ impl CommentMap {
 						attach_to.kind(),
 						attach_to.id(),
 					);
-					if blank_line_before {
-						comments.before.push(Element::line_break());
-					}
+					comments
+						.before
+						.extend((0..blank_lines_before).map(|_| Element::line_break()));
 					if is_line {
 						comments.before.push(Element::text(contents));
 						comments.before.push(Element::line_break());
@@ -321,9 +424,9 @@ impl CommentMap {
 						attach_to.kind(),
 						attach_to.id(),
 					);
-					if blank_line_before {
-						comments.after.push(Element::line_break());
-					}
+					comments
+						.after
+						.extend((0..blank_lines_before).map(|_| Element::line_break()));
 					comments.after.push(Element::line_break());
 					comments.after.push(Element::text(contents));
 				}
@@ -333,6 +436,39 @@ impl CommentMap {
 	}
 }
 
+/// Get the number of blank lines between a node and the previous one, up to the given maximum
+pub(crate) fn blank_lines_before(node: Node<'_>, max: usize) -> usize {
+	node.prev_sibling()
+		.map(|p| {
+			node.start_position()
+				.row
+				.saturating_sub(p.end_position().row + 1)
+		})
+		.unwrap_or_default()
+		.min(max)
+}
+
+/// Whether an item directly follows the previous one, with only the separator and comments on
+/// the line of the previous item in between
+fn follows_directly(prev: &Item, item: &Item) -> bool {
+	let prev = *prev.cst_node().as_ref();
+	let node = *item.cst_node().as_ref();
+	if blank_lines_before(node, usize::MAX) > 0 {
+		return false;
+	}
+	let mut sibling = node.prev_sibling();
+	while let Some(n) = sibling {
+		if n == prev {
+			return true;
+		}
+		if n.kind() != ";" && !(n.is_extra() && n.start_position().row == prev.end_position().row) {
+			return false;
+		}
+		sibling = n.prev_sibling();
+	}
+	false
+}
+
 fn ensure_valid_node(mut node: Node<'_>) -> Node<'_> {
 	while matches!(node.kind(), "strategy") {
 		node = node.parent().unwrap()
@@ -7,8 +7,11 @@
 
 use std::{collections::VecDeque, fmt::Debug};
 
+use serde::Deserialize;
+
 /// Formatting options
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
 pub struct FormatOptions {
 	/// Target maximum line length
 	pub line_width: usize,
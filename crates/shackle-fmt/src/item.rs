@@ -7,13 +7,9 @@ use crate::{
 
 impl Format for minizinc::Item {
 	fn format(&self, formatter: &mut MiniZincFormatter) -> crate::ir::Element {
-		let mut elements = Vec::new();
-		let node = self.cst_node().as_ref();
-		if let Some(p) = node.prev_sibling() {
-			if p.end_position().row < node.start_position().row.saturating_sub(1) {
-				elements.push(Element::line_break());
-			}
-		}
+		let mut elements = (0..formatter.blank_lines_before(self))
+			.map(|_| Element::line_break())
+			.collect::<Vec<_>>();
 		let element = match self {
 			minizinc::Item::Annotation(x) => x.format(formatter),
 			minizinc::Item::Assignment(x) => x.format(formatter),
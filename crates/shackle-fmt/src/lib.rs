@@ -2,14 +2,24 @@
 
 #![warn(missing_docs)]
 
+use std::{ops::Range, path::Path, sync::Arc};
+
+pub use config::{find_config_file, ConfigError, CONFIG_FILE_NAME};
 use format::Format;
 pub use ir::FormatOptions;
-use shackle_compiler::syntax::{cst, minizinc::MznModel};
+use serde::Deserialize;
+use shackle_compiler::{
+	diagnostics::{Error, InvalidModelLanguage},
+	file::{InputLang, SourceFile},
+	syntax::{cst::Cst, eprime::EPrimeModel, minizinc::MznModel},
+};
 use tree_sitter::Parser;
 
-use crate::format::MiniZincFormatter;
+use crate::{eprime::EPrimeFormatter, format::MiniZincFormatter};
 
+pub(crate) mod config;
 pub(crate) mod container;
+pub(crate) mod eprime;
 pub(crate) mod expression;
 pub(crate) mod format;
 pub(crate) mod ir;
@@ -18,23 +28,177 @@ pub(crate) mod pattern;
 pub(crate) mod types;
 
 /// Formatting options for MiniZinc
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
 pub struct MiniZincFormatOptions {
 	/// Core formatting options
+	#[serde(flatten)]
 	pub core: FormatOptions,
 	/// Keep parentheses (except double parentheses)
 	pub keep_parentheses: bool,
+	/// Where to place infix operators when breaking a line
+	pub operator_position: OperatorPosition,
+	/// Put spaces inside the brackets of array and set literals and comprehensions
+	pub bracket_spacing: bool,
+	/// How to lay out 2D array literals
+	pub array_2d_layout: Array2dLayout,
+	/// Always put the generators of comprehensions on their own line
+	pub generators_on_new_line: bool,
+	/// Sort consecutive include items by file name
+	pub sort_includes: bool,
+	/// Maximum number of consecutive blank lines to keep
+	pub max_blank_lines: usize,
+}
+
+impl Default for MiniZincFormatOptions {
+	fn default() -> Self {
+		Self {
+			core: FormatOptions::default(),
+			keep_parentheses: false,
+			operator_position: OperatorPosition::default(),
+			bracket_spacing: false,
+			array_2d_layout: Array2dLayout::default(),
+			generators_on_new_line: false,
+			sort_includes: false,
+			max_blank_lines: 1,
+		}
+	}
+}
+
+/// Placement of infix operators when breaking a line
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorPosition {
+	/// Operators end the broken line
+	#[default]
+	Trailing,
+	/// Operators start the new line
+	Leading,
+}
+
+/// Layout of 2D array literals
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Array2dLayout {
+	/// Each row on its own line
+	#[default]
+	Rows,
+	/// Each row on its own line, with the columns aligned
+	Aligned,
+	/// Rows follow each other, only breaking lines when necessary
+	Flowed,
+}
+
+/// Format the given source code written in the given language
+///
+/// Essence' models only use the core formatting options and the maximum number
+/// of blank lines. Gives `None` if the source code could not be parsed, or if
+/// the language is not a model language.
+pub fn format(source: &str, lang: InputLang, options: &MiniZincFormatOptions) -> Option<String> {
+	let cst = parse(source, lang)?;
+	match lang {
+		InputLang::EPrime => format_eprime_model(&EPrimeModel::new(cst), options),
+		_ => format_model(&MznModel::new(cst), options),
+	}
+}
+
+/// Format the given source code, giving the syntax error if it could not be parsed
+///
+/// The path of the source file, if any, is used when reporting the error.
+pub fn try_format(
+	source: &str,
+	lang: InputLang,
+	path: Option<&Path>,
+	options: &MiniZincFormatOptions,
+) -> Result<String, Error> {
+	let cst = parse(source, lang).ok_or_else(|| InvalidModelLanguage {
+		lang: format!("{lang:?}"),
+	})?;
+	let src = match path {
+		Some(path) => SourceFile::with_path(path.to_owned(), Arc::new(source.to_owned())),
+		None => SourceFile::from(Arc::new(source.to_owned())),
+	};
+	cst.error(|_| src.clone())?;
+	Ok(match lang {
+		InputLang::EPrime => EPrimeFormatter::new(&EPrimeModel::new(cst), options).format(),
+		_ => MiniZincFormatter::new(&MznModel::new(cst), options).format(),
+	})
+}
+
+/// Parse the given source code, giving `None` if the language is not a model language
+fn parse(source: &str, lang: InputLang) -> Option<Cst> {
+	let language = match lang {
+		InputLang::MiniZinc => tree_sitter_minizinc::language(),
+		InputLang::EPrime => tree_sitter_eprime::language(),
+		_ => return None,
+	};
+	let mut parser = Parser::new();
+	parser.set_language(&language).unwrap();
+	let tree = parser.parse(source.as_bytes(), None).unwrap();
+	Some(Cst::from_str(tree, source))
 }
 
-/// Format the given source code
-pub fn format(source: &str, options: &MiniZincFormatOptions) -> Option<String> {
+/// Format the items of the given source code which overlap the given byte range
+///
+/// Only the smallest set of whole items covering the range is formatted, the rest of the
+/// source code is left untouched.
+pub fn format_range(
+	source: &str,
+	range: Range<usize>,
+	options: &MiniZincFormatOptions,
+) -> Option<String> {
+	let (replaced, formatted) = format_range_edit(source, range, options)?;
+	let mut result = source.to_owned();
+	result.replace_range(replaced, &formatted);
+	Some(result)
+}
+
+/// Format the items of the given source code which overlap the given byte range
+///
+/// Gives the byte range of the source code which has to be replaced, along with its formatted
+/// replacement.
+pub fn format_range_edit(
+	source: &str,
+	range: Range<usize>,
+	options: &MiniZincFormatOptions,
+) -> Option<(Range<usize>, String)> {
 	let mut parser = Parser::new();
 	parser
 		.set_language(&tree_sitter_minizinc::language())
 		.unwrap();
 	let tree = parser.parse(source.as_bytes(), None).unwrap();
-	let cst = cst::Cst::from_str(tree, source);
-	format_model(&MznModel::new(cst), options)
+	let root = tree.root_node();
+
+	// The byte ranges of the items, including their separators
+	let mut items: Vec<Range<usize>> = Vec::new();
+	let mut cursor = root.walk();
+	for child in root.children(&mut cursor) {
+		if child.is_extra() {
+			continue;
+		}
+		if child.is_named() {
+			items.push(child.byte_range());
+		} else if let Some(item) = items.last_mut() {
+			item.end = child.end_byte();
+		}
+	}
+
+	let overlaps = |item: &Range<usize>| {
+		if range.is_empty() {
+			item.start <= range.start && range.start <= item.end
+		} else {
+			item.start < range.end && range.start < item.end
+		}
+	};
+	let first = items.iter().position(overlaps)?;
+	let last = items.iter().rposition(overlaps)?;
+	let replaced = items[first].start..items[last].end;
+	let formatted = format(&source[replaced.clone()], InputLang::MiniZinc, options)?;
+	let formatted = formatted
+		.strip_suffix('\n')
+		.map(|f| f.to_owned())
+		.unwrap_or(formatted);
+	Some((replaced, formatted))
 }
 
 /// Format an AST model
@@ -45,14 +209,19 @@ pub fn format_model(model: &MznModel, options: &MiniZincFormatOptions) -> Option
 	Some(MiniZincFormatter::new(model, options).format())
 }
 
+/// Format an Essence' AST model
+///
+/// Only the core formatting options and the maximum number of blank lines are used.
+pub fn format_eprime_model(model: &EPrimeModel, options: &MiniZincFormatOptions) -> Option<String> {
+	if model.cst().error_nodes().next().is_some() {
+		return None;
+	}
+	Some(EPrimeFormatter::new(model, options).format())
+}
+
 /// Get IR for debugging
 pub fn format_debug(source: &str, options: &MiniZincFormatOptions) -> Option<String> {
-	let mut parser = Parser::new();
-	parser
-		.set_language(&tree_sitter_minizinc::language())
-		.unwrap();
-	let tree = parser.parse(source.as_bytes(), None).unwrap();
-	let cst = cst::Cst::from_str(tree, source);
+	let cst = parse(source, InputLang::MiniZinc)?;
 	format_model_debug(&MznModel::new(cst), options)
 }
 
@@ -65,6 +234,21 @@ pub fn format_model_debug(model: &MznModel, options: &MiniZincFormatOptions) ->
 	Some(format!("{:#?}", model.format(&mut formatter)))
 }
 
+/// Get IR of an Essence' model for debugging
+pub fn format_eprime_model_debug(
+	model: &EPrimeModel,
+	options: &MiniZincFormatOptions,
+) -> Option<String> {
+	if model.cst().error_nodes().next().is_some() {
+		return None;
+	}
+	let mut formatter = EPrimeFormatter::new(model, options);
+	Some(format!(
+		"{:#?}",
+		eprime::Format::format(model, &mut formatter)
+	))
+}
+
 #[cfg(test)]
 mod test {
 	use expect_test::expect;
@@ -89,6 +273,7 @@ mod test {
 			l = [| a, b | c, d |];
 			m = [| a: b: | i: c, d | j: e, f |];
 			"#,
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -197,6 +382,7 @@ mod test {
 			c = -2 * 3;
 			d = -((2 * 3));
 			"#,
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -215,6 +401,7 @@ mod test {
 			a = (1 + (2 * 3) - 4) + 5;
 			b = -((2 * 3));
 			"#,
+			InputLang::MiniZinc,
 			&MiniZincFormatOptions {
 				keep_parentheses: true,
 				..Default::default()
@@ -237,6 +424,7 @@ mod test {
 			tuple(var 1..3,2..4,int): a;
 			record(var 1..3: a, var int:b): b;
 			"#,
+			InputLang::MiniZinc,
 			&MiniZincFormatOptions {
 				keep_parentheses: true,
 				..Default::default()
@@ -274,6 +462,7 @@ mod test {
 			/* one */
 			% Hello
 			"#,
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -305,6 +494,7 @@ mod test {
 			% Foo
 			int: y;
 			"#,
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -318,6 +508,7 @@ mod test {
 	fn test_format_comprehension() {
 		let actual = format(
 			"constraint [a_really_long_word_here_which_overflows_a_really_long_word_here | j in 1..max(country)];",
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -347,6 +538,7 @@ mod test {
 				kangaroo+
 				llama;
 			"#,
+			InputLang::MiniZinc,
 			&Default::default(),
 		);
 		let expected = expect![[r#"
@@ -433,4 +625,121 @@ mod test {
     )"#]];
 		expected.assert_eq(&actual.unwrap());
 	}
+
+	#[test]
+	fn test_format_range() {
+		let source = r#"int:   a=1;
+% Comment
+int:b  =  a+  1; constraint b>a  ;
+solve    satisfy;
+"#;
+		let start = source.find("b  =").unwrap();
+		let actual = format_range(source, start..start + 1, &Default::default());
+		let expected = expect![[r#"
+    int:   a=1;
+    % Comment
+    int: b = a + 1; constraint b>a  ;
+    solve    satisfy;
+"#]];
+		expected.assert_eq(&actual.unwrap());
+
+		let start = source.find("a+").unwrap();
+		let end = source.find("solve").unwrap() + 1;
+		let actual = format_range(source, start..end, &Default::default());
+		let expected = expect![[r#"
+    int:   a=1;
+    % Comment
+    int: b = a + 1;
+    constraint b > a;
+    solve satisfy;
+"#]];
+		expected.assert_eq(&actual.unwrap());
+	}
+
+	#[test]
+	fn test_format_options() {
+		let actual = format(
+			r#"
+% Libraries
+include "globals.mzn";
+include "all_different.mzn"; % Redundant
+include "count.mzn";
+
+
+include "b.mzn";
+include "a.mzn";
+a = [| 1, 2 | 3, 4 |];
+			"#,
+			InputLang::MiniZinc,
+			&MiniZincFormatOptions {
+				sort_includes: true,
+				max_blank_lines: 0,
+				array_2d_layout: Array2dLayout::Flowed,
+				..Default::default()
+			},
+		);
+		let expected = expect![[r#"
+    % Libraries
+    include "all_different.mzn"; % Redundant
+    include "count.mzn";
+    include "globals.mzn";
+    include "a.mzn";
+    include "b.mzn";
+    a = [| 1, 2 | 3, 4 |];
+"#]];
+		expected.assert_eq(&actual.unwrap());
+	}
+
+	#[test]
+	fn test_try_format() {
+		let options = MiniZincFormatOptions::default();
+		expect![[r#"
+    int: x = 1;
+"#]]
+		.assert_eq(&try_format("int:x=1;", InputLang::MiniZinc, None, &options).unwrap());
+
+		let err = try_format(
+			"int: x = ;",
+			InputLang::MiniZinc,
+			Some(Path::new("model.mzn")),
+			&options,
+		)
+		.unwrap_err();
+		let Error::SyntaxError(err) = err else {
+			panic!("expected a syntax error, got {err:?}");
+		};
+		expect![[r#"Unexpected ="#]].assert_eq(&err.msg);
+		assert_eq!(err.src.path(), Some(Path::new("model.mzn")));
+
+		let err = try_format("a = 1;", InputLang::DataZinc, None, &options).unwrap_err();
+		expect![[r#"DataZinc is not a model language"#]].assert_eq(&err.to_string());
+		assert!(format("a = 1;", InputLang::DataZinc, &options).is_none());
+	}
+
+	#[test]
+	fn test_format_eprime_options() {
+		let source = "language ESSENCE' 1.0\n\n\n\nfind x : bool\n";
+		let actual = format(
+			source,
+			InputLang::EPrime,
+			&MiniZincFormatOptions {
+				max_blank_lines: 2,
+				..Default::default()
+			},
+		);
+		expect![[r#"
+    language ESSENCE' 1.0
+
+
+    find x : bool
+"#]]
+		.assert_eq(&actual.unwrap());
+		let actual = format(source, InputLang::EPrime, &Default::default());
+		expect![[r#"
+    language ESSENCE' 1.0
+
+    find x : bool
+"#]]
+		.assert_eq(&actual.unwrap());
+	}
 }
@@ -1,31 +1,54 @@
-use std::path::Path;
+use std::{fmt::Debug, path::Path};
 
 use pretty_assertions::assert_str_eq;
-use shackle_compiler::syntax::{cst::Cst, minizinc::MznModel};
-use shackle_fmt::{format_model, MiniZincFormatOptions};
+use shackle_compiler::{
+	file::InputLang,
+	syntax::{cst::Cst, eprime::EPrimeModel, minizinc::MznModel},
+};
+use shackle_fmt::{format_eprime_model, format_model, MiniZincFormatOptions};
 use tree_sitter::Parser;
 
 pub fn check_format_file(path: &Path, options: &MiniZincFormatOptions) -> String {
+	match InputLang::from_extension(path.extension()) {
+		InputLang::EPrime => check_format(
+			path,
+			tree_sitter_eprime::language(),
+			EPrimeModel::new,
+			|model| format_eprime_model(model, options),
+		),
+		_ => check_format(
+			path,
+			tree_sitter_minizinc::language(),
+			MznModel::new,
+			|model| format_model(model, options),
+		),
+	}
+}
+
+fn check_format<M: Debug>(
+	path: &Path,
+	language: tree_sitter::Language,
+	new_model: impl Fn(Cst) -> M,
+	format: impl Fn(&M) -> Option<String>,
+) -> String {
 	let source = std::fs::read_to_string(path)
 		.unwrap_or_else(|err| panic!("Failed to read {} ({})", path.to_string_lossy(), err));
 	let mut parser = Parser::new();
-	parser
-		.set_language(&tree_sitter_minizinc::language())
-		.unwrap();
+	parser.set_language(&language).unwrap();
 	let tree = parser.parse(source.as_bytes(), None).unwrap();
-	let model = MznModel::new(Cst::from_str(tree, &source));
-	let formatted = format_model(&model, options).unwrap_or_else(|| {
+	let model = new_model(Cst::from_str(tree, &source));
+	let formatted = format(&model).unwrap_or_else(|| {
 		panic!("Failed to format {}", path.to_string_lossy());
 	});
 	let formatted_tree = parser.parse(formatted.as_bytes(), None).unwrap();
-	let formatted_model = MznModel::new(Cst::from_str(formatted_tree, &formatted));
+	let formatted_model = new_model(Cst::from_str(formatted_tree, &formatted));
 	assert_str_eq!(
 		format!("{:#?}", model),
 		format!("{:#?}", formatted_model),
 		"Formatting {} changed AST",
 		path.to_string_lossy(),
 	);
-	let reformatted = format_model(&formatted_model, options).unwrap_or_else(|| {
+	let reformatted = format(&formatted_model).unwrap_or_else(|| {
 		panic!("Failed to reformat {}", path.to_string_lossy());
 	});
 	assert_eq!(
@@ -7,9 +7,9 @@ mod common;
 
 #[test]
 fn format_1() {
-	let options = MiniZincFormatOptions::default();
 	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 	path.push("tests/formatting_1.mzn");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
 	let actual = check_format_file(&path, &options);
 	let expected = expect_file![path];
 	expected.assert_eq(&actual);
@@ -17,9 +17,9 @@ fn format_1() {
 
 #[test]
 fn format_2() {
-	let options = MiniZincFormatOptions::default();
 	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 	path.push("tests/formatting_2.mzn");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
 	let actual = check_format_file(&path, &options);
 	let expected = expect_file![path];
 	expected.assert_eq(&actual);
@@ -27,9 +27,9 @@ fn format_2() {
 
 #[test]
 fn format_3() {
-	let options = MiniZincFormatOptions::default();
 	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 	path.push("tests/formatting_3.mzn");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
 	let actual = check_format_file(&path, &options);
 	let expected = expect_file![path];
 	expected.assert_eq(&actual);
@@ -37,9 +37,29 @@ fn format_3() {
 
 #[test]
 fn format_4() {
-	let options = MiniZincFormatOptions::default();
 	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 	path.push("tests/formatting_4.mzn");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
+	let actual = check_format_file(&path, &options);
+	let expected = expect_file![path];
+	expected.assert_eq(&actual);
+}
+
+#[test]
+fn format_style() {
+	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	path.push("tests/style/formatting_style.mzn");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
+	let actual = check_format_file(&path, &options);
+	let expected = expect_file![path];
+	expected.assert_eq(&actual);
+}
+
+#[test]
+fn format_eprime() {
+	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	path.push("tests/formatting_eprime.eprime");
+	let options = MiniZincFormatOptions::for_file(&path).unwrap();
 	let actual = check_format_file(&path, &options);
 	let expected = expect_file![path];
 	expected.assert_eq(&actual);
@@ -0,0 +1,68 @@
+use serde::Deserialize;
+
+/// Language server settings
+///
+/// These are given by the client in the initialization options, and updated when the client
+/// changes its configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+	/// Which kinds of inlay hints to show
+	pub inlay_hints: InlayHintsConfig,
+}
+
+impl Config {
+	/// Parse the settings given by the client, using the defaults if they are invalid
+	pub fn from_value(value: Option<serde_json::Value>) -> Self {
+		value
+			.and_then(|v| {
+				serde_json::from_value(v)
+					.map_err(|e| log::warn!("invalid configuration: {}", e))
+					.ok()
+			})
+			.unwrap_or_default()
+	}
+}
+
+/// Settings for each kind of inlay hint
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InlayHintsConfig {
+	/// Show the types of top-level `any` declarations
+	pub declaration_types: bool,
+	/// Show the types of `let` locals declared as `any`
+	pub let_types: bool,
+	/// Show the types of comprehension generator variables
+	pub generator_types: bool,
+	/// Show parameter names at call sites of user-defined functions
+	pub parameter_names: bool,
+}
+
+impl Default for InlayHintsConfig {
+	fn default() -> Self {
+		Self {
+			declaration_types: true,
+			let_types: true,
+			generator_types: true,
+			parameter_names: true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Config;
+
+	#[test]
+	fn test_config() {
+		let config = Config::from_value(Some(serde_json::json!({
+			"inlayHints": { "parameterNames": false }
+		})));
+		assert!(config.inlay_hints.declaration_types);
+		assert!(config.inlay_hints.generator_types);
+		assert!(!config.inlay_hints.parameter_names);
+
+		let config = Config::from_value(Some(serde_json::json!({ "inlayHints": false })));
+		assert!(config.inlay_hints.parameter_names);
+	}
+}
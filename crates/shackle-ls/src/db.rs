@@ -1,14 +1,25 @@
-use std::{ops::Deref, path::Path, sync::Arc};
+use std::{
+	collections::HashMap,
+	ops::Deref,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
 
-use crossbeam_channel::{SendError, Sender};
+use crossbeam_channel::{unbounded, Receiver, SendError, Sender};
 use lsp_server::{Connection, ErrorCode, Message, ResponseError};
 use lsp_types::{TextDocumentIdentifier, Url};
 use shackle_compiler::{
-	db::{CompilerDatabase, FileReader, HasFileHandler, Inputs},
-	file::{InputFile, InputLang, ModelRef},
+	db::{CompilerDatabase, CompilerSettings, FileReader, HasFileHandler, Inputs},
+	file::{FileRef, InputFile, InputLang, ModelRef},
+	hir::db::Hir,
 };
 
-use crate::{diagnostics, vfs::Vfs};
+use crate::{
+	config::Config,
+	diagnostics,
+	vfs::Vfs,
+	workspace::{is_model_file, normalize, Workspace},
+};
 
 /// Trait for handler preparation
 pub trait LanguageServerContext: Deref<Target = CompilerDatabase> {
@@ -21,18 +32,38 @@ pub trait LanguageServerContext: Deref<Target = CompilerDatabase> {
 	/// Get the workspace URI
 	#[allow(dead_code)] // TODO
 	fn get_workspace_uri(&self) -> Option<&Url>;
+
+	/// Get the root model currently used as the input of the compiler database
+	fn active_file(&self) -> Option<&Path>;
+
+	/// Get the language server settings
+	fn config(&self) -> &Config;
 }
 
+/// Files included by a root model, or `None` if they could not be resolved
+type Includes = (PathBuf, Option<Vec<PathBuf>>);
+
 pub struct LanguageServerDatabase {
 	vfs: Vfs,
 	pool: threadpool::ThreadPool,
 	sender: Sender<Message>,
+	/// Database used while no root model is active
 	db: CompilerDatabase,
-	workspace: Option<Url>,
+	/// Database for each root model in the workspace
+	///
+	/// Every root model has its own database, so that checking one model
+	/// does not cancel the checks of the other models.
+	roots: HashMap<PathBuf, CompilerDatabase>,
+	workspace: Workspace,
+	/// Channel used to report the includes of root models discovered in the thread pool
+	discovered: (Sender<Includes>, Receiver<Includes>),
+	/// The root model currently used as the input of the compiler database
+	active: Option<PathBuf>,
+	config: Config,
 }
 
 impl LanguageServerDatabase {
-	pub fn new(connection: &Connection, workspace: Option<Url>) -> Self {
+	pub fn new(connection: &Connection, workspace: Option<Url>, config: Config) -> Self {
 		let fs = Vfs::new();
 		let db = CompilerDatabase::with_file_handler(Box::new(fs.clone()));
 		Self {
@@ -40,10 +71,19 @@ impl LanguageServerDatabase {
 			pool: threadpool::Builder::new().build(),
 			sender: connection.sender.clone(),
 			db,
-			workspace,
+			roots: HashMap::new(),
+			workspace: Workspace::new(workspace),
+			discovered: unbounded(),
+			active: None,
+			config,
 		}
 	}
 
+	pub fn set_config(&mut self, config: Config) {
+		log::info!("configuration changed: {:?}", config);
+		self.config = config;
+	}
+
 	pub fn send(&self, message: Message) -> Result<(), SendError<Message>> {
 		self.sender.send(message)
 	}
@@ -52,7 +92,7 @@ impl LanguageServerDatabase {
 	where
 		F: FnOnce(&CompilerDatabase, Sender<Message>) + Send + 'static,
 	{
-		let db = self.db.snapshot();
+		let db = self.deref().snapshot();
 		let sender = self.sender.clone();
 		self.pool.execute(move || {
 			f(&db, sender);
@@ -62,35 +102,184 @@ impl LanguageServerDatabase {
 	pub fn manage_file(&mut self, file: &Path, contents: &str) {
 		log::info!("detected file changed for file {:?}", file);
 		self.vfs.manage_file(file, contents);
-		self.db.on_file_change(file);
-		self.set_active_file(file);
+		self.on_file_change(file);
+		self.workspace.open(file);
+		self.check_file(file);
 	}
 
 	pub fn unmanage_file(&mut self, file: &Path) {
 		self.vfs.unmanage_file(file);
 		log::info!("detected file changed for file {:?}", file);
+		self.on_file_change(file);
+		self.workspace.close(file);
+		self.check_file(file);
+	}
+
+	/// Invalidate the contents of the given file in all databases
+	fn on_file_change(&mut self, file: &Path) {
 		self.db.on_file_change(file);
+		for (root, db) in self.roots.iter_mut() {
+			db.on_file_change(file);
+			if *root == normalize(file) {
+				// Contents of input files are only read again when the inputs are set
+				set_root_model(db, root);
+			}
+		}
 	}
 
-	pub fn set_active_file(&mut self, path: &Path) {
-		self.db.set_input_files(Arc::new(vec![InputFile::Path(
-			path.to_owned(),
-			InputLang::MiniZinc,
-		)]));
-		let path_filter = path.to_owned();
-		self.execute_async(move |db, sender| {
-			let notification = diagnostics::diagnostics_notification(db, path_filter.as_path());
-			sender
-				.send(Message::Notification(notification))
-				.expect("Failed to send diagnostics");
+	/// Check the models affected by a change to the given file, and publish
+	/// the diagnostics for all their files
+	fn check_file(&mut self, file: &Path) {
+		self.record_discovered();
+		if is_model_file(file) && !self.workspace.is_known(file) {
+			self.discover_roots(file);
+			return;
+		}
+		for root in self.workspace.affected_roots(file) {
+			self.update_root(&root);
+			let db = self.roots[&root].snapshot();
+			let files = self.workspace.includes(&root);
+			let data = self.workspace.data_files(&root);
+			let sender = self.sender.clone();
+			self.pool
+				.execute(move || publish_diagnostics(&db, &files, &data, &sender));
+		}
+	}
+
+	/// Find the model in the workspace that includes the given model file,
+	/// and publish its diagnostics
+	///
+	/// The candidate models are compiled in the thread pool, and the files they
+	/// include are recorded once the discovery has finished. Until then, the
+	/// given file is treated as its own root model.
+	fn discover_roots(&mut self, file: &Path) {
+		let file = normalize(file);
+		let candidates = self
+			.workspace
+			.candidate_roots(&file)
+			.into_iter()
+			.filter(|c| !self.workspace.is_known(c))
+			.chain([file.clone()])
+			.map(|c| {
+				let data = self.workspace.data_files(&c);
+				(c, data)
+			})
+			.collect::<Vec<_>>();
+		self.workspace.set_includes(&file, []);
+		let vfs = self.vfs.clone();
+		let sender = self.sender.clone();
+		let discovered = self.discovered.0.clone();
+		self.pool.execute(move || {
+			for (root, data) in candidates {
+				let mut db = CompilerDatabase::with_file_handler(Box::new(vfs.clone()));
+				set_root_model(&mut db, &root);
+				let includes = model_includes(&db);
+				let found = root == file || includes.as_ref().is_some_and(|i| i.contains(&file));
+				let mut files = includes.clone().unwrap_or_default();
+				if discovered.send((root.clone(), includes)).is_err() {
+					break;
+				}
+				if found {
+					files.push(root);
+					files.sort();
+					files.dedup();
+					publish_diagnostics(&db, &files, &data, &sender);
+					break;
+				}
+			}
+		});
+	}
+
+	/// Record the includes of the root models discovered in the thread pool
+	fn record_discovered(&mut self) {
+		while let Ok((root, includes)) = self.discovered.1.try_recv() {
+			self.record_includes(&root, includes);
+		}
+	}
+
+	/// Record the files included by the given root model
+	fn record_includes(&mut self, root: &Path, includes: Option<Vec<PathBuf>>) {
+		match includes {
+			Some(files) => self.workspace.set_includes(root, files),
+			None => {
+				// Keep the previously known includes until they can be resolved again
+				if !self.workspace.is_known(root) {
+					self.workspace.set_includes(root, []);
+				}
+			}
+		}
+		// Models that are included by another model no longer need their own database
+		let workspace = &self.workspace;
+		self.roots.retain(|r, _| workspace.is_root(r));
+	}
+
+	/// Ensure the given root model has a database, and record the files it includes
+	fn update_root(&mut self, root: &Path) {
+		let db = self.roots.entry(root.to_owned()).or_insert_with(|| {
+			let mut db = CompilerDatabase::with_file_handler(Box::new(self.vfs.clone()));
+			set_root_model(&mut db, root);
+			db
 		});
+		let includes = model_includes(db);
+		self.record_includes(root, includes);
+	}
+
+	/// Use the database of the given root model to answer requests
+	pub fn set_active_file(&mut self, path: &Path) {
+		if self.active.as_deref() == Some(path) && self.roots.contains_key(path) {
+			return;
+		}
+		self.update_root(path);
+		self.active = Some(path.to_owned());
+	}
+}
+
+/// Use the given root model as the input of the compiler database
+fn set_root_model(db: &mut CompilerDatabase, root: &Path) {
+	db.set_input_files(Arc::new(vec![InputFile::Path(
+		root.to_owned(),
+		InputLang::from_extension(root.extension()),
+	)]));
+}
+
+/// Get the workspace files (transitively) included by the input model of the database
+///
+/// Standard library files are not part of the workspace.
+fn model_includes(db: &CompilerDatabase) -> Option<Vec<PathBuf>> {
+	let search_dirs = db.include_search_dirs();
+	let models = db.resolve_includes().ok()?;
+	Some(
+		models
+			.iter()
+			.filter_map(|m| m.path(db))
+			.filter(|p| !search_dirs.iter().any(|d| p.starts_with(d)))
+			.map(|p| normalize(&p))
+			.collect(),
+	)
+}
+
+/// Publish the diagnostics for all files of a root model
+fn publish_diagnostics(
+	db: &CompilerDatabase,
+	files: &[PathBuf],
+	data: &[PathBuf],
+	sender: &Sender<Message>,
+) {
+	for (path, diagnostics) in diagnostics::workspace_diagnostics(db, files, data) {
+		let notification = diagnostics::diagnostics_notification(&path, diagnostics);
+		sender
+			.send(Message::Notification(notification))
+			.expect("Failed to send diagnostics");
 	}
 }
 
 impl Deref for LanguageServerDatabase {
 	type Target = CompilerDatabase;
 	fn deref(&self) -> &Self::Target {
-		&self.db
+		self.active
+			.as_ref()
+			.and_then(|root| self.roots.get(root))
+			.unwrap_or(&self.db)
 	}
 }
 
@@ -104,11 +293,97 @@ impl LanguageServerContext for LanguageServerDatabase {
 			data: None,
 			message: "Failed to convert URI to file path".to_owned(),
 		})?;
-		self.set_active_file(&requested_path);
-		Ok(self.input_models()[0])
+		self.record_discovered();
+		let root = self.workspace.root_model(&requested_path);
+		self.set_active_file(&root);
+		if root == normalize(&requested_path) {
+			return Ok(self.input_models()[0]);
+		}
+		// The requested file is included by the root model
+		let db: &CompilerDatabase = self;
+		let model = db
+			.resolve_includes()
+			.ok()
+			.and_then(|models| {
+				models
+					.iter()
+					.find(|m| m.path(db).map(|p| normalize(&p)) == Some(normalize(&requested_path)))
+					.copied()
+			})
+			.unwrap_or_else(|| FileRef::new(&requested_path, db).into());
+		Ok(model)
 	}
 
 	fn get_workspace_uri(&self) -> Option<&Url> {
-		self.workspace.as_ref()
+		self.workspace.uri()
+	}
+
+	fn active_file(&self) -> Option<&Path> {
+		self.active.as_deref()
+	}
+
+	fn config(&self) -> &Config {
+		&self.config
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+
+	use lsp_server::{Connection, Message};
+	use lsp_types::{PublishDiagnosticsParams, Url};
+
+	use super::LanguageServerDatabase;
+	use crate::{config::Config, workspace::normalize};
+
+	/// Receive whether diagnostics were published for each of the given number of files
+	fn published(client: &Connection, n: usize) -> HashMap<PathBuf, bool> {
+		(0..n)
+			.map(|_| {
+				let Ok(Message::Notification(notification)) =
+					client.receiver.recv_timeout(Duration::from_secs(60))
+				else {
+					panic!("expected diagnostics to be published")
+				};
+				let params: PublishDiagnosticsParams =
+					serde_json::from_value(notification.params).unwrap();
+				(
+					params.uri.to_file_path().unwrap(),
+					!params.diagnostics.is_empty(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_check_included_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let dir = normalize(dir.path());
+		let main = dir.join("main.mzn");
+		let helpers = dir.join("helpers.mzn");
+		fs::write(&main, "include \"helpers.mzn\";\nvar 1..3: x;\n").unwrap();
+		fs::write(&helpers, "").unwrap();
+		let (server, client) = Connection::memory();
+		let mut db = LanguageServerDatabase::new(
+			&server,
+			Url::from_directory_path(&dir).ok(),
+			Config::default(),
+		);
+
+		// The model including the file is discovered, and checked as a whole
+		db.manage_file(&helpers, "constraint y > 1;");
+		assert_eq!(
+			published(&client, 2),
+			HashMap::from([(helpers.clone(), true), (main.clone(), false)])
+		);
+
+		db.manage_file(&helpers, "constraint x > 1;");
+		assert_eq!(
+			published(&client, 2),
+			HashMap::from([(helpers.clone(), false), (main.clone(), false)])
+		);
+		assert!(db.workspace.is_root(&main));
+		assert!(!db.workspace.is_root(&helpers));
 	}
 }
@@ -1,22 +1,58 @@
 use std::{
+	collections::HashMap,
 	path::{Path, PathBuf},
 	str::FromStr,
 };
 
 use lsp_types::{notification::Notification, Url};
 use miette::{Diagnostic, Severity};
-use shackle_compiler::hir::db::Hir;
+use shackle::{check_model, DataSource};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::{FileRef, InputLang, SourceFile},
+	hir::db::Hir,
+};
 
-use crate::utils::span_contents_to_range;
+use crate::{utils::span_contents_to_range, workspace::normalize};
 
-pub fn diagnostics_notification(db: &dyn Hir, path: &Path) -> lsp_server::Notification {
-	let mut diagnostics = Vec::new();
-	for d in db.all_errors().iter() {
-		collect_diagnostic(path, d, &mut diagnostics);
+/// Collect the diagnostics for a root model and the data files that go with it
+///
+/// The result contains an entry for each of the given files, so that the
+/// diagnostics of files that no longer contain any errors are cleared. The
+/// data files are only checked once the model itself is free of errors.
+pub fn workspace_diagnostics(
+	db: &CompilerDatabase,
+	files: &[PathBuf],
+	data: &[PathBuf],
+) -> HashMap<PathBuf, Vec<lsp_types::Diagnostic>> {
+	let mut diagnostics = files
+		.iter()
+		.chain(data)
+		.map(|f| (f.clone(), Vec::new()))
+		.collect::<HashMap<_, _>>();
+	let errors = db.all_errors();
+	for d in errors.iter() {
+		collect_diagnostic(d, &mut diagnostics);
 	}
 	for d in db.all_warnings().iter() {
-		collect_diagnostic(path, d, &mut diagnostics);
+		collect_diagnostic(d, &mut diagnostics);
+	}
+	if errors.is_empty() {
+		for path in data {
+			let src = SourceFile::new(FileRef::new(path, db), db);
+			let lang = InputLang::from_extension(path.extension());
+			for d in check_model(db, &[DataSource::Source(src, lang)], false) {
+				collect_diagnostic(&d, &mut diagnostics);
+			}
+		}
 	}
+	diagnostics
+}
+
+pub fn diagnostics_notification(
+	path: &Path,
+	diagnostics: Vec<lsp_types::Diagnostic>,
+) -> lsp_server::Notification {
 	lsp_server::Notification {
 		method: lsp_types::notification::PublishDiagnostics::METHOD.to_owned(),
 		params: serde_json::to_value(lsp_types::PublishDiagnosticsParams {
@@ -29,9 +65,8 @@ pub fn diagnostics_notification(db: &dyn Hir, path: &Path) -> lsp_server::Notifi
 }
 
 fn collect_diagnostic(
-	path: &Path,
 	d: &dyn Diagnostic,
-	out: &mut Vec<lsp_types::Diagnostic>,
+	out: &mut HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
 ) -> Option<()> {
 	let sc = d.source_code()?;
 	let mut ls = d.labels()?;
@@ -39,11 +74,17 @@ fn collect_diagnostic(
 	let span = sc.read_span(first.inner(), 0, 0).ok()?;
 	let range = span_contents_to_range(span.as_ref());
 	let name = span.name()?;
-	let p = PathBuf::from_str(name).ok()?;
-	if p != path {
-		return None;
+	let mut path = PathBuf::from_str(name).ok()?;
+	if path.is_relative() {
+		// Source file names are relative to the current directory where possible
+		path = std::env::current_dir()
+			.ok()?
+			.canonicalize()
+			.ok()?
+			.join(path);
 	}
-	let uri = Url::from_file_path(path).ok()?;
+	let path = normalize(&path);
+	let uri = Url::from_file_path(&path).ok()?;
 	let related_info: Vec<_> = ls
 		.filter_map(|l| {
 			let label = l.label()?;
@@ -58,7 +99,7 @@ fn collect_diagnostic(
 			})
 		})
 		.collect();
-	out.push(lsp_types::Diagnostic {
+	out.get_mut(&path)?.push(lsp_types::Diagnostic {
 		code: d
 			.code()
 			.map(|c| lsp_types::NumberOrString::String(c.to_string())),
@@ -87,8 +128,88 @@ fn collect_diagnostic(
 	});
 	if let Some(related) = d.related() {
 		for d in related {
-			collect_diagnostic(path, d, out);
+			collect_diagnostic(d, out);
 		}
 	}
 	Some(())
 }
+
+#[cfg(test)]
+mod test {
+	use std::{
+		fs::File,
+		io::Write,
+		path::{Path, PathBuf},
+	};
+
+	use expect_test::{expect, Expect};
+	use shackle_compiler::{
+		db::{CompilerDatabase, Inputs},
+		file::{InputFile, InputLang},
+	};
+
+	use super::workspace_diagnostics;
+	use crate::{vfs::Vfs, workspace::normalize};
+
+	fn check_diagnostics(model: &Path, files: &[PathBuf], data: &[PathBuf], expected: Expect) {
+		let mut db = CompilerDatabase::with_file_handler(Box::new(Vfs::new()));
+		db.set_input_files(vec![InputFile::Path(model.to_owned(), InputLang::MiniZinc)].into());
+		let mut diagnostics = workspace_diagnostics(&db, files, data)
+			.into_iter()
+			.map(|(path, diagnostics)| {
+				let messages = diagnostics
+					.into_iter()
+					.map(|d| d.message.lines().next().unwrap().to_owned())
+					.collect::<Vec<_>>();
+				format!(
+					"{}: {messages:?}",
+					path.file_name().unwrap().to_string_lossy()
+				)
+			})
+			.collect::<Vec<_>>();
+		diagnostics.sort();
+		expected.assert_eq(&diagnostics.join("\n"));
+	}
+
+	#[test]
+	fn test_workspace_diagnostics() {
+		let dir = tempfile::tempdir().unwrap();
+		let dir = normalize(dir.path());
+		let (model, helper, data) = (
+			dir.join("main.mzn"),
+			dir.join("helper.mzn"),
+			dir.join("main.dzn"),
+		);
+		write!(
+			File::create(&model).unwrap(),
+			"include \"helper.mzn\";\nint: n;"
+		)
+		.unwrap();
+		write!(File::create(&helper).unwrap(), "int: m = n + x;").unwrap();
+		write!(File::create(&data).unwrap(), "n = 1.5;").unwrap();
+		let files = [helper.clone(), model.clone()];
+
+		// Errors in included files are reported for that file
+		check_diagnostics(
+			&model,
+			&files,
+			std::slice::from_ref(&data),
+			expect![[r#"
+    helper.mzn: ["Undefined identifier"]
+    main.dzn: []
+    main.mzn: []"#]],
+		);
+
+		// Data files are checked once the model is free of errors
+		write!(File::create(&helper).unwrap(), "int: m = n + 1;").unwrap();
+		check_diagnostics(
+			&model,
+			&files,
+			&[data],
+			expect![[r#"
+    helper.mzn: []
+    main.dzn: ["Type mismatch"]
+    main.mzn: []"#]],
+		);
+	}
+}
@@ -0,0 +1,672 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::CodeActionRequest, CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+	CodeActionResponse, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use miette::{SourceCode, SourceSpan};
+use shackle_compiler::{
+	db::{CompilerDatabase, CompilerSettings, FileReader},
+	diagnostics::{
+		Error, IdentifierShadowing, MultipleSolveItems, NoMatchingFunction,
+		NonExhaustivePatternMatching, UndefinedIdentifier, Warning,
+	},
+	file::{FileRef, InputLang, ModelRef, SourceFile},
+	hir::{
+		db::Hir,
+		ids::{EntityRef, LocalEntityRef, NodeRef, PatternRef},
+		source::{find_expression, find_node, Point},
+		Expression,
+	},
+	syntax::db::SourceParser,
+	utils::{levenshtein_distance, pretty_print_identifier},
+};
+
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{file_url, node_ref_to_location, span_contents_to_range},
+};
+
+/// Maximum number of similarly named identifiers to suggest
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Debug)]
+pub struct CodeActionsHandler;
+
+impl RequestHandler<CodeActionRequest, (ModelRef, Range, bool)> for CodeActionsHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: CodeActionParams,
+	) -> Result<(ModelRef, Range, bool), ResponseError> {
+		let model = db.set_active_file_from_document(&params.text_document)?;
+		// All of our code actions are quick fixes
+		let quick_fixes = params.context.only.is_none_or(|only| {
+			only.iter()
+				.any(|kind| CodeActionKind::QUICKFIX.as_str().starts_with(kind.as_str()))
+		});
+		Ok((model, params.range, quick_fixes))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, range, quick_fixes): (ModelRef, Range, bool),
+	) -> Result<Option<CodeActionResponse>, ResponseError> {
+		let (path, uri) = match model_ref.path(db).and_then(|p| Some((file_url(&p)?, p))) {
+			Some((uri, path)) if quick_fixes => (path, uri),
+			_ => return Ok(None),
+		};
+		let mut collector = CodeActionCollector {
+			db,
+			model_ref,
+			path,
+			uri,
+			range,
+			actions: Vec::new(),
+		};
+		for e in db.all_errors().iter() {
+			collector.error(e);
+		}
+		for w in db.all_warnings().iter() {
+			collector.warning(w);
+		}
+		Ok(Some(collector.actions))
+	}
+}
+
+struct CodeActionCollector<'a> {
+	db: &'a CompilerDatabase,
+	model_ref: ModelRef,
+	path: PathBuf,
+	uri: Url,
+	range: Range,
+	actions: CodeActionResponse,
+}
+
+impl CodeActionCollector<'_> {
+	/// Add the quick fixes for an error
+	fn error(&mut self, error: &Error) {
+		match error {
+			Error::UndefinedIdentifier(UndefinedIdentifier {
+				src,
+				span,
+				identifier,
+			}) => {
+				if let Some(range) = self.diagnostic_range(src, span) {
+					self.undefined_identifier(range, identifier, false);
+				}
+			}
+			Error::NoMatchingFunction(NoMatchingFunction {
+				src,
+				span,
+				undefined: Some(name),
+				..
+			}) => {
+				if let Some(range) = self.diagnostic_range(src, span) {
+					self.undefined_identifier(range, name, true);
+				}
+			}
+			Error::NonExhaustivePatternMatching(NonExhaustivePatternMatching {
+				src,
+				span,
+				pattern,
+				..
+			}) if self.diagnostic_range(src, span).is_some() => self.missing_case(src, span, pattern),
+			Error::MultipleSolveItems(MultipleSolveItems { others, .. }) => {
+				for other in others.iter() {
+					if self.diagnostic_range(&other.src, &other.span).is_some() {
+						self.remove_solve_item(&other.src, &other.span);
+					}
+				}
+			}
+			Error::MultipleErrors(e) => {
+				for e in e.errors.iter() {
+					self.error(e);
+				}
+			}
+			_ => (),
+		}
+	}
+
+	/// Add the quick fixes for a warning
+	fn warning(&mut self, warning: &Warning) {
+		if let Warning::IdentifierShadowing(w) = warning {
+			if let Some(range) = self.diagnostic_range(&w.src, &w.span) {
+				self.rename_shadowing(w, range);
+			}
+		}
+	}
+
+	/// Suggest including the library defining an undefined identifier, or a similarly named
+	/// identifier in scope
+	fn undefined_identifier(&mut self, range: Range, identifier: &str, function: bool) {
+		let name = identifier.trim_matches('\'');
+		if self.model_ref.lang(self.db) == InputLang::MiniZinc {
+			if let Some(file) = global_file(self.db, name) {
+				self.add_include(&file);
+			}
+		}
+
+		let expression = match find_expression(
+			self.db,
+			*self.model_ref,
+			to_point(range.start),
+			to_point(range.end),
+		) {
+			Some(e) => e,
+			None => return,
+		};
+		let scope = self.db.lookup_item_scope(expression.item());
+		let mut candidates = scope
+			.functions_in_scope(self.db, expression.expression())
+			.into_iter()
+			.map(|(i, _)| i)
+			.collect::<Vec<_>>();
+		if !function {
+			candidates.extend(
+				scope
+					.variables_in_scope(self.db, expression.expression())
+					.into_iter()
+					.map(|(i, _)| i),
+			);
+		}
+		let threshold = (name.chars().count() / 3).max(1);
+		let mut suggestions = candidates
+			.into_iter()
+			.filter_map(|i| {
+				let candidate = i.lookup(self.db);
+				let distance = levenshtein_distance(name, &candidate);
+				(distance > 0 && distance <= threshold).then_some((distance, candidate))
+			})
+			.collect::<Vec<_>>();
+		suggestions.sort();
+		suggestions.dedup();
+		for (_, suggestion) in suggestions.into_iter().take(MAX_SUGGESTIONS) {
+			let new_text = pretty_print_identifier(&suggestion);
+			self.add_action(
+				format!("Change to '{}'", suggestion),
+				vec![TextEdit { range, new_text }],
+			);
+		}
+	}
+
+	/// Add an include item after the existing includes
+	fn add_include(&mut self, file: &str) {
+		let line = self
+			.db
+			.cst(*self.model_ref)
+			.ok()
+			.and_then(|cst| {
+				let root = cst.root_node();
+				let mut cursor = root.walk();
+				let last = root
+					.named_children(&mut cursor)
+					.filter(|c| c.kind() == "include")
+					.last();
+				last.map(|include| include.end_position().row as u32 + 1)
+			})
+			.unwrap_or(0);
+		let position = Position { line, character: 0 };
+		self.add_action(
+			format!("Add include \"{}\"", file),
+			vec![TextEdit {
+				range: Range {
+					start: position,
+					end: position,
+				},
+				new_text: format!("include \"{}\";\n", file),
+			}],
+		);
+	}
+
+	/// Add the case which is not covered by a case expression
+	fn missing_case(&mut self, src: &SourceFile, span: &SourceSpan, pattern: &str) {
+		let cst = match self.db.cst(*self.model_ref) {
+			Ok(cst) => cst,
+			Err(_) => return,
+		};
+		let text = cst.text();
+		let scrutinee = span.offset()..span.offset() + span.len();
+		let mut node = cst
+			.root_node()
+			.descendant_for_byte_range(scrutinee.start, scrutinee.end);
+		while let Some(n) = node {
+			if n.kind() == "case_expression"
+				&& n.child_by_field_name("expression")
+					.is_some_and(|e| e.byte_range() == scrutinee)
+			{
+				break;
+			}
+			node = n.parent();
+		}
+		let last = match node.and_then(|n| {
+			let mut cursor = n.walk();
+			let last = n.children_by_field_name("case", &mut cursor).last();
+			last
+		}) {
+			Some(c) => c,
+			None => return,
+		};
+		// Use the value of the last arm as a placeholder for the value of the new arm
+		let value = match last.child_by_field_name("value") {
+			Some(v) => &text[v.byte_range()],
+			None => return,
+		};
+		let line_start = text[..last.start_byte()]
+			.rfind('\n')
+			.map(|i| i + 1)
+			.unwrap_or(0);
+		let indent = &text[line_start..last.start_byte()];
+		let separator = if indent.chars().all(char::is_whitespace) {
+			format!(",\n{}", indent)
+		} else {
+			", ".to_owned()
+		};
+		let end = match self.byte_range(src, last.end_byte(), last.end_byte()) {
+			Some(r) => r,
+			None => return,
+		};
+		for (title, pattern) in [
+			(format!("Add case '{}'", pattern), pattern),
+			("Add catch-all case '_'".to_owned(), "_"),
+		] {
+			self.add_action(
+				title,
+				vec![TextEdit {
+					range: end,
+					new_text: format!("{}{} => {}", separator, pattern, value),
+				}],
+			);
+		}
+	}
+
+	/// Remove an additional solve item along with its separator
+	fn remove_solve_item(&mut self, src: &SourceFile, span: &SourceSpan) {
+		let text = src.contents();
+		let mut end = span.offset() + span.len();
+		let skip_blanks = |i: usize| {
+			i + text[i..]
+				.find(|c: char| c != ' ' && c != '\t')
+				.unwrap_or(text.len() - i)
+		};
+		let after = skip_blanks(end);
+		if text[after..].starts_with(';') {
+			end = skip_blanks(after + 1);
+			if text[end..].starts_with('\n') {
+				end += 1;
+			} else if text[end..].starts_with("\r\n") {
+				end += 2;
+			}
+		}
+		if let Some(range) = self.byte_range(src, span.offset(), end) {
+			self.add_action(
+				"Remove additional solve item".to_owned(),
+				vec![TextEdit {
+					range,
+					new_text: String::new(),
+				}],
+			);
+		}
+	}
+
+	/// Rename a variable which shadows another identifier, along with its uses
+	fn rename_shadowing(&mut self, warning: &IdentifierShadowing, range: Range) {
+		let db = self.db;
+		let (item, pattern) = match find_node(
+			db,
+			*self.model_ref,
+			to_point(range.start),
+			to_point(range.end),
+		) {
+			Some(NodeRef::Entity(e)) => match e.entity(db) {
+				LocalEntityRef::Pattern(p) => (e.item(db), p),
+				_ => return,
+			},
+			_ => return,
+		};
+		let name = warning.name.trim_matches('\'');
+		let text = warning.src.contents();
+		let new_name = match (1..)
+			.map(|i| format!("{}_{}", name, i))
+			.find(|n| !text.contains(n.as_str()))
+		{
+			Some(n) => n,
+			None => return,
+		};
+		let new_text = pretty_print_identifier(&new_name);
+
+		let model = item.model(db);
+		let data = item.local_item_ref(db).data(&model);
+		let types = db.lookup_item_types(item);
+		let target = PatternRef::new(item, pattern);
+		let mut locations = vec![node_ref_to_location(db, EntityRef::new(db, item, pattern))];
+		for (e, expression) in data.expressions.iter() {
+			if matches!(expression, Expression::Identifier(_))
+				&& types.name_resolution(e) == Some(target)
+			{
+				locations.push(node_ref_to_location(db, EntityRef::new(db, item, e)));
+			}
+		}
+		let edits = locations
+			.into_iter()
+			.flatten()
+			.map(|location| TextEdit {
+				range: location.range,
+				new_text: new_text.clone(),
+			})
+			.collect();
+		self.add_action(format!("Rename '{}' to '{}'", name, new_name), edits);
+	}
+
+	/// Get the range of a diagnostic if it is in the active file and overlaps the requested range
+	fn diagnostic_range(&self, src: &SourceFile, span: &SourceSpan) -> Option<Range> {
+		let range = self.byte_range(src, span.offset(), span.offset() + span.len())?;
+		(range.start <= self.range.end && self.range.start <= range.end).then_some(range)
+	}
+
+	/// Get the range of the given bytes of the active file
+	fn byte_range(&self, src: &SourceFile, start: usize, end: usize) -> Option<Range> {
+		if src.path() != Some(self.path.as_path()) {
+			return None;
+		}
+		let span = SourceSpan::from(start..end);
+		let contents = src.read_span(&span, 0, 0).ok()?;
+		Some(span_contents_to_range(&*contents))
+	}
+
+	fn add_action(&mut self, title: String, edits: Vec<TextEdit>) {
+		let mut changes = HashMap::new();
+		changes.insert(self.uri.clone(), edits);
+		self.actions
+			.push(CodeActionOrCommand::CodeAction(CodeAction {
+				title,
+				kind: Some(CodeActionKind::QUICKFIX),
+				edit: Some(WorkspaceEdit {
+					changes: Some(changes),
+					..Default::default()
+				}),
+				..Default::default()
+			}));
+	}
+}
+
+/// Find the file of the global constraint library which defines the given function
+///
+/// Gives the file named after the function if there is one, otherwise the first file included
+/// by `globals.mzn` which defines the function, falling back to `globals.mzn` itself if it
+/// defines the function directly.
+fn global_file(db: &CompilerDatabase, name: &str) -> Option<String> {
+	let dirs = db.include_search_dirs();
+	let read = |file: &str| {
+		dirs.iter()
+			.find_map(|d| db.file_contents(FileRef::new(&d.join(file), db)).ok())
+	};
+	let globals = read("globals.mzn")?;
+	let files = globals
+		.lines()
+		.filter_map(|line| line.trim().strip_prefix("include \"")?.strip_suffix("\";"))
+		.collect::<Vec<_>>();
+	let own_file = format!("{}.mzn", name);
+	if files.contains(&own_file.as_str()) {
+		return Some(own_file);
+	}
+	let definitions = [
+		format!("predicate {}(", name),
+		format!("test {}(", name),
+		format!(": {}(", name),
+	];
+	let defines = |contents: &str| {
+		contents.lines().any(|line| {
+			let line = line.trim_start();
+			(line.starts_with("predicate")
+				|| line.starts_with("test")
+				|| line.starts_with("function"))
+				&& definitions.iter().any(|d| line.contains(d.as_str()))
+		})
+	};
+	files
+		.into_iter()
+		.find(|f| read(f).is_some_and(|contents| defines(&contents)))
+		.map(|f| f.to_owned())
+		.or_else(|| defines(&globals).then(|| "globals.mzn".to_owned()))
+}
+
+fn to_point(position: Position) -> Point {
+	Point {
+		row: position.line as usize,
+		column: position.character as usize,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::CodeActionsHandler;
+	use crate::handlers::test::test_handler;
+
+	fn params(start: u32, end: u32) -> lsp_types::CodeActionParams {
+		lsp_types::CodeActionParams {
+			text_document: lsp_types::TextDocumentIdentifier {
+				uri: Url::from_str("file:///test.mzn").unwrap(),
+			},
+			range: lsp_types::Range {
+				start: lsp_types::Position {
+					line: start,
+					character: 0,
+				},
+				end: lsp_types::Position {
+					line: end,
+					character: 0,
+				},
+			},
+			context: lsp_types::CodeActionContext::default(),
+			work_done_progress_params: lsp_types::WorkDoneProgressParams {
+				work_done_token: None,
+			},
+			partial_result_params: lsp_types::PartialResultParams {
+				partial_result_token: None,
+			},
+		}
+	}
+
+	#[test]
+	fn test_code_actions_undefined_identifier() {
+		test_handler::<CodeActionsHandler, _, _>(
+			r#"
+include "count.mzn";
+var 1..3: foo;
+var 1..3: bar;
+constraint alldifferent([foo, bar]);
+constraint fo < bar;
+			"#,
+			false,
+			params(4, 6),
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "title": "Add include \"all_different.mzn\"",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 2,
+                      "character": 0
+                    },
+                    "end": {
+                      "line": 2,
+                      "character": 0
+                    }
+                  },
+                  "newText": "include \"all_different.mzn\";\n"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "title": "Change to 'foo'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 5,
+                      "character": 11
+                    },
+                    "end": {
+                      "line": 5,
+                      "character": 13
+                    }
+                  },
+                  "newText": "foo"
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]),
+		)
+	}
+
+	#[test]
+	fn test_code_actions() {
+		test_handler::<CodeActionsHandler, _, _>(
+			r#"
+enum Foo = {A, B, C};
+Foo: e;
+int: v = case e of
+  A => 1,
+  B => 2
+endcase;
+int: x = 1;
+function int: f(int: x) = x + 1;
+solve satisfy;
+solve satisfy;
+			"#,
+			false,
+			params(0, 12),
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "title": "Add case 'C'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 5,
+                      "character": 8
+                    },
+                    "end": {
+                      "line": 5,
+                      "character": 8
+                    }
+                  },
+                  "newText": ",\n  C => 2"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "title": "Add catch-all case '_'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 5,
+                      "character": 8
+                    },
+                    "end": {
+                      "line": 5,
+                      "character": 8
+                    }
+                  },
+                  "newText": ",\n  _ => 2"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "title": "Remove additional solve item",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 10,
+                      "character": 0
+                    },
+                    "end": {
+                      "line": 11,
+                      "character": 0
+                    }
+                  },
+                  "newText": ""
+                }
+              ]
+            }
+          }
+        },
+        {
+          "title": "Rename 'x' to 'x_1'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 8,
+                      "character": 21
+                    },
+                    "end": {
+                      "line": 8,
+                      "character": 22
+                    }
+                  },
+                  "newText": "x_1"
+                },
+                {
+                  "range": {
+                    "start": {
+                      "line": 8,
+                      "character": 26
+                    },
+                    "end": {
+                      "line": 8,
+                      "character": 27
+                    }
+                  },
+                  "newText": "x_1"
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]),
+		)
+	}
+}
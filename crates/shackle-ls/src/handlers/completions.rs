@@ -212,7 +212,7 @@ mod test {
 	use lsp_types::Url;
 
 	use super::CompletionsHandler;
-	use crate::handlers::test::test_handler;
+	use crate::handlers::test::{test_handler, test_handler_eprime};
 
 	#[test]
 	fn test_completions() {
@@ -273,4 +273,50 @@ any: y = he
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_completions_eprime() {
+		test_handler_eprime::<CompletionsHandler, _, _>(
+			r#"
+language ESSENCE' 1.0
+given n: int
+find x: int(1..n)
+such that x != n
+			"#,
+			true,
+			lsp_types::CompletionParams {
+				context: None,
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.eprime").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 4,
+						character: 11,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "label": "n",
+          "kind": 6,
+          "detail": "int"
+        },
+        {
+          "label": "x",
+          "kind": 6,
+          "detail": "var int"
+        }
+      ]
+    }"#]),
+		)
+	}
 }
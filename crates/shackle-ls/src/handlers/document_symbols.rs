@@ -0,0 +1,507 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::DocumentSymbolRequest, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+	Range, SymbolKind,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{EntityRef, ItemRef, LocalEntityRef, LocalItemRef, NodeRef},
+		EnumConstructor, Expression, Goal, Identifier, ItemData, Pattern, PatternTy, TypeResult,
+	},
+	utils::arena::ArenaIndex,
+};
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+
+/// Maximum length of the source text shown for unnamed items
+const MAX_DETAIL_LENGTH: usize = 40;
+
+#[derive(Debug)]
+pub struct DocumentSymbolsHandler;
+
+impl RequestHandler<DocumentSymbolRequest, ModelRef> for DocumentSymbolsHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentSymbolParams,
+	) -> Result<ModelRef, ResponseError> {
+		db.set_active_file_from_document(&params.text_document)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		model_ref: ModelRef,
+	) -> Result<Option<DocumentSymbolResponse>, ResponseError> {
+		let symbols = db
+			.lookup_items(model_ref)
+			.iter()
+			.filter_map(|item| item_symbol(db, *item))
+			.collect();
+		Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+	}
+}
+
+/// Get the outline symbol for an item
+pub fn item_symbol(db: &CompilerDatabase, item: ItemRef) -> Option<DocumentSymbol> {
+	let model = item.model(db);
+	let local_item = item.local_item_ref(db);
+	let builder = SymbolBuilder {
+		db,
+		item,
+		data: local_item.data(&model),
+		types: &db.lookup_item_types(item),
+	};
+	let mut symbol = match local_item {
+		LocalItemRef::Annotation(a) => {
+			let pattern = model[a].constructor_pattern();
+			let detail = match builder.types.get_pattern(pattern)? {
+				PatternTy::AnnotationConstructor(f) => f
+					.overload
+					.pretty_print_item(db, builder.identifier(pattern)?),
+				_ => "ann".to_owned(),
+			};
+			builder.pattern(pattern, SymbolKind::CONSTRUCTOR, Some(detail))?
+		}
+		LocalItemRef::Constraint(c) => builder.unnamed(
+			"constraint",
+			builder.source_text(model[c].expression),
+			SymbolKind::BOOLEAN,
+		)?,
+		LocalItemRef::Declaration(d) => {
+			let pattern = model[d].pattern;
+			let detail = match builder.types.get_pattern(pattern)? {
+				PatternTy::Variable(ty) => Some(ty.pretty_print(db)),
+				_ => None,
+			};
+			builder.pattern(pattern, SymbolKind::VARIABLE, detail)?
+		}
+		LocalItemRef::Enumeration(e) => {
+			let mut symbol = builder.pattern(model[e].pattern, SymbolKind::ENUM, None)?;
+			symbol.children = model[e]
+				.definition
+				.as_ref()
+				.map(|cs| builder.constructors(cs));
+			symbol
+		}
+		LocalItemRef::EnumAssignment(a) => {
+			let name = match &builder.data[model[a].assignee] {
+				Expression::Identifier(i) => i.pretty_print(db),
+				_ => return None,
+			};
+			let range = builder.range(model[a].assignee)?;
+			let mut symbol = symbol(name, None, SymbolKind::ENUM, range);
+			symbol.children = Some(builder.constructors(&model[a].definition));
+			symbol
+		}
+		LocalItemRef::Function(f) => {
+			let pattern = model[f].pattern;
+			let detail = match builder.types.get_pattern(pattern)? {
+				PatternTy::Function(f) => Some(
+					f.overload
+						.pretty_print_item(db, builder.identifier(pattern)?),
+				),
+				_ => None,
+			};
+			builder.pattern(pattern, SymbolKind::FUNCTION, detail)?
+		}
+		LocalItemRef::Output(o) => builder.unnamed(
+			"output",
+			model[o].section.and_then(|s| builder.source_text(s)),
+			SymbolKind::STRING,
+		)?,
+		LocalItemRef::Solve(s) => {
+			let (name, objective) = match &model[s].goal {
+				Goal::Satisfy => ("solve satisfy", None),
+				Goal::Maximize { objective, .. } => ("solve maximize", Some(*objective)),
+				Goal::Minimize { objective, .. } => ("solve minimize", Some(*objective)),
+			};
+			builder.unnamed(
+				name,
+				objective.and_then(|o| builder.source_text(o)),
+				SymbolKind::EVENT,
+			)?
+		}
+		LocalItemRef::TypeAlias(t) => {
+			let pattern = model[t].name;
+			let detail = match builder.types.get_pattern(pattern)? {
+				PatternTy::TypeAlias { ty, .. } => Some(ty.pretty_print(db)),
+				_ => None,
+			};
+			builder.pattern(pattern, SymbolKind::TYPE_PARAMETER, detail)?
+		}
+		LocalItemRef::Assignment(_) => return None,
+	};
+	symbol.range = node_ref_to_location(db, item)?.range;
+	Some(symbol)
+}
+
+struct SymbolBuilder<'a> {
+	db: &'a CompilerDatabase,
+	item: ItemRef,
+	data: &'a ItemData,
+	types: &'a TypeResult,
+}
+
+impl SymbolBuilder<'_> {
+	/// Create a symbol for a named pattern
+	fn pattern(
+		&self,
+		pattern: ArenaIndex<Pattern>,
+		kind: SymbolKind,
+		detail: Option<String>,
+	) -> Option<DocumentSymbol> {
+		let name = self.identifier(pattern)?.pretty_print(self.db);
+		Some(symbol(name, detail, kind, self.range(pattern)?))
+	}
+
+	/// Create a symbol for an item without a name
+	fn unnamed(
+		&self,
+		name: &str,
+		detail: Option<String>,
+		kind: SymbolKind,
+	) -> Option<DocumentSymbol> {
+		let range = node_ref_to_location(self.db, self.item)?.range;
+		Some(symbol(name.to_owned(), detail, kind, range))
+	}
+
+	/// Create the symbols for the constructors of an enum
+	fn constructors(&self, constructors: &[EnumConstructor]) -> Vec<DocumentSymbol> {
+		constructors
+			.iter()
+			.filter_map(|c| {
+				let pattern = match c {
+					EnumConstructor::Named(c) => c.constructor_pattern(),
+					EnumConstructor::Anonymous { .. } => return None,
+				};
+				let detail = match self.types.get_pattern(pattern)? {
+					PatternTy::EnumAtom(ty) => ty.pretty_print(self.db),
+					PatternTy::EnumConstructor(cs) => cs
+						.first()?
+						.overload
+						.pretty_print_item(self.db, self.identifier(pattern)?),
+					_ => return None,
+				};
+				self.pattern(pattern, SymbolKind::ENUM_MEMBER, Some(detail))
+			})
+			.collect()
+	}
+
+	fn identifier(&self, pattern: ArenaIndex<Pattern>) -> Option<Identifier> {
+		match &self.data[pattern] {
+			Pattern::Identifier(i) => Some(*i),
+			_ => None,
+		}
+	}
+
+	fn range<T>(&self, entity: ArenaIndex<T>) -> Option<Range>
+	where
+		ArenaIndex<T>: Into<LocalEntityRef>,
+	{
+		node_ref_to_location(self.db, EntityRef::new(self.db, self.item, entity))
+			.map(|location| location.range)
+	}
+
+	/// Get the source text of an expression, shortened to fit in the outline
+	fn source_text(&self, expression: ArenaIndex<Expression>) -> Option<String> {
+		let node: NodeRef = EntityRef::new(self.db, self.item, expression).into();
+		let (src, span) = node.source_span(self.db);
+		let text = src
+			.contents()
+			.get(span.offset()..span.offset() + span.len())?
+			.split_whitespace()
+			.collect::<Vec<_>>()
+			.join(" ");
+		if text.chars().count() > MAX_DETAIL_LENGTH {
+			Some(format!(
+				"{}…",
+				text.chars().take(MAX_DETAIL_LENGTH).collect::<String>()
+			))
+		} else {
+			Some(text)
+		}
+	}
+}
+
+fn symbol(name: String, detail: Option<String>, kind: SymbolKind, range: Range) -> DocumentSymbol {
+	#[allow(deprecated)] // Required to construct the symbol
+	DocumentSymbol {
+		name,
+		detail,
+		kind,
+		tags: None,
+		deprecated: None,
+		range,
+		selection_range: range,
+		children: None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::DocumentSymbolsHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_document_symbols() {
+		test_handler::<DocumentSymbolsHandler, _, _>(
+			r#"
+enum Foo = {A, B} ++ F(1..2);
+var Foo: x;
+predicate bar(var Foo: a, int: b) = a = A;
+constraint bar(x, 1) /\ x != B;
+solve minimize x;
+output ["\(x)"];
+			"#,
+			false,
+			lsp_types::DocumentSymbolParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "name": "Foo",
+          "kind": 10,
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 0
+            },
+            "end": {
+              "line": 1,
+              "character": 28
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 1,
+              "character": 5
+            },
+            "end": {
+              "line": 1,
+              "character": 8
+            }
+          },
+          "children": [
+            {
+              "name": "A",
+              "detail": "Foo",
+              "kind": 22,
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 12
+                },
+                "end": {
+                  "line": 1,
+                  "character": 13
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 1,
+                  "character": 12
+                },
+                "end": {
+                  "line": 1,
+                  "character": 13
+                }
+              }
+            },
+            {
+              "name": "B",
+              "detail": "Foo",
+              "kind": 22,
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 15
+                },
+                "end": {
+                  "line": 1,
+                  "character": 16
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 1,
+                  "character": 15
+                },
+                "end": {
+                  "line": 1,
+                  "character": 16
+                }
+              }
+            },
+            {
+              "name": "F",
+              "detail": "function Foo: F(int)",
+              "kind": 22,
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 21
+                },
+                "end": {
+                  "line": 1,
+                  "character": 22
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 1,
+                  "character": 21
+                },
+                "end": {
+                  "line": 1,
+                  "character": 22
+                }
+              }
+            }
+          ]
+        },
+        {
+          "name": "x",
+          "detail": "var Foo",
+          "kind": 13,
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 0
+            },
+            "end": {
+              "line": 2,
+              "character": 10
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 2,
+              "character": 9
+            },
+            "end": {
+              "line": 2,
+              "character": 10
+            }
+          }
+        },
+        {
+          "name": "bar",
+          "detail": "predicate bar(var Foo, int)",
+          "kind": 12,
+          "range": {
+            "start": {
+              "line": 3,
+              "character": 0
+            },
+            "end": {
+              "line": 3,
+              "character": 41
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 3,
+              "character": 10
+            },
+            "end": {
+              "line": 3,
+              "character": 13
+            }
+          }
+        },
+        {
+          "name": "constraint",
+          "detail": "bar(x, 1) /\\ x != B",
+          "kind": 17,
+          "range": {
+            "start": {
+              "line": 4,
+              "character": 0
+            },
+            "end": {
+              "line": 4,
+              "character": 30
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 4,
+              "character": 0
+            },
+            "end": {
+              "line": 4,
+              "character": 30
+            }
+          }
+        },
+        {
+          "name": "solve minimize",
+          "detail": "x",
+          "kind": 24,
+          "range": {
+            "start": {
+              "line": 5,
+              "character": 0
+            },
+            "end": {
+              "line": 5,
+              "character": 16
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 5,
+              "character": 0
+            },
+            "end": {
+              "line": 5,
+              "character": 16
+            }
+          }
+        },
+        {
+          "name": "output",
+          "kind": 15,
+          "range": {
+            "start": {
+              "line": 6,
+              "character": 0
+            },
+            "end": {
+              "line": 6,
+              "character": 15
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 6,
+              "character": 0
+            },
+            "end": {
+              "line": 6,
+              "character": 15
+            }
+          }
+        }
+      ]
+    }"#]),
+		)
+	}
+}
@@ -0,0 +1,136 @@
+use lsp_server::ResponseError;
+use lsp_types::{request::FoldingRangeRequest, FoldingRange, FoldingRangeKind, FoldingRangeParams};
+use shackle_compiler::{db::CompilerDatabase, file::ModelRef, syntax::db::SourceParser};
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+
+/// Kinds of CST nodes which can be folded when they span multiple lines
+const FOLDABLE_NODES: [&str; 8] = [
+	"let_expression",
+	"array_comprehension",
+	"set_comprehension",
+	"generator_call",
+	"array_literal",
+	"array_literal_2d",
+	"matrix_comprehension",
+	"matrix_literal",
+];
+
+#[derive(Debug)]
+pub struct FoldingRangesHandler;
+
+impl RequestHandler<FoldingRangeRequest, ModelRef> for FoldingRangesHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: FoldingRangeParams,
+	) -> Result<ModelRef, ResponseError> {
+		db.set_active_file_from_document(&params.text_document)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		model_ref: ModelRef,
+	) -> Result<Option<Vec<FoldingRange>>, ResponseError> {
+		let cst = match db.cst(*model_ref) {
+			Ok(cst) => cst,
+			Err(_) => return Ok(None),
+		};
+		let mut ranges = Vec::new();
+		let mut cursor = cst.root_node().walk();
+		'walk: loop {
+			let node = cursor.node();
+			let kind = if node.kind() == "block_comment" {
+				Some(Some(FoldingRangeKind::Comment))
+			} else if FOLDABLE_NODES.contains(&node.kind()) {
+				Some(None)
+			} else {
+				None
+			};
+			let (start, end) = (node.start_position(), node.end_position());
+			if let Some(kind) = kind.filter(|_| end.row > start.row) {
+				ranges.push(FoldingRange {
+					start_line: start.row as u32,
+					start_character: None,
+					end_line: end.row as u32,
+					end_character: None,
+					kind,
+					collapsed_text: None,
+				});
+			}
+			if cursor.goto_first_child() {
+				continue;
+			}
+			while !cursor.goto_next_sibling() {
+				if !cursor.goto_parent() {
+					break 'walk;
+				}
+			}
+		}
+		Ok(Some(ranges))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::FoldingRangesHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_folding_ranges() {
+		test_handler::<FoldingRangesHandler, _, _>(
+			r#"
+/* A comment
+   over two lines */
+array [int] of int: x = [
+	1, 2, 3,
+	4, 5, 6,
+];
+any: y = let {
+	int: a = 1;
+	int: b = [i | i in x where
+		i > a][1];
+} in a + b;
+array [int] of int: z = [1, 2, 3];
+			"#,
+			true,
+			lsp_types::FoldingRangeParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "startLine": 1,
+          "endLine": 2,
+          "kind": "comment"
+        },
+        {
+          "startLine": 3,
+          "endLine": 6
+        },
+        {
+          "startLine": 7,
+          "endLine": 11
+        },
+        {
+          "startLine": 9,
+          "endLine": 10
+        }
+      ]
+    }"#]),
+		)
+	}
+}
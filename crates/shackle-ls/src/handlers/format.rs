@@ -1,13 +1,24 @@
-use lsp_server::ResponseError;
-use lsp_types::{request::Formatting, DocumentFormattingParams, Position, TextEdit};
+use lsp_server::{ErrorCode, ResponseError};
+use lsp_types::{
+	request::{Formatting, OnTypeFormatting, RangeFormatting},
+	DocumentFormattingParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+	FormattingOptions, Position, Range, TextDocumentIdentifier, TextEdit,
+};
 use shackle_compiler::{
 	db::CompilerDatabase,
 	file::ModelRef,
 	syntax::{ast::ConstraintModel, db::SourceParser},
 };
-use shackle_fmt::{format_model, FormatOptions, MiniZincFormatOptions};
+use shackle_fmt::{
+	find_config_file, format_eprime_model, format_model, format_range_edit, FormatOptions,
+	MiniZincFormatOptions,
+};
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{offset_to_position, position_to_offset},
+};
 
 #[derive(Debug)]
 pub struct FormatHandler;
@@ -19,39 +30,150 @@ impl RequestHandler<Formatting, (ModelRef, MiniZincFormatOptions)> for FormatHan
 	) -> Result<(ModelRef, MiniZincFormatOptions), ResponseError> {
 		Ok((
 			db.set_active_file_from_document(&params.text_document)?,
-			MiniZincFormatOptions {
-				core: FormatOptions {
-					use_tabs: !params.options.insert_spaces,
-					indent_size: params.options.tab_size as usize,
-					..Default::default()
-				},
+			format_options(&params.text_document, &params.options)?,
+		))
+	}
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, options): (ModelRef, MiniZincFormatOptions),
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		let (formatted, end) = match db.ast(*model_ref) {
+			Ok(ConstraintModel::MznModel(ast)) => (
+				format_model(&ast, &options),
+				ast.cst().root_node().end_position(),
+			),
+			Ok(ConstraintModel::EPrimeModel(ast)) => (
+				format_eprime_model(&ast, &options),
+				ast.cst().root_node().end_position(),
+			),
+			_ => return Ok(None),
+		};
+		let Some(formatted) = formatted else {
+			return Ok(None);
+		};
+		Ok(Some(vec![TextEdit {
+			range: lsp_types::Range {
+				end: Position::new(end.row as u32, end.column as u32),
 				..Default::default()
 			},
+			new_text: formatted,
+		}]))
+	}
+}
+
+#[derive(Debug)]
+pub struct RangeFormatHandler;
+
+impl RequestHandler<RangeFormatting, (ModelRef, Range, MiniZincFormatOptions)>
+	for RangeFormatHandler
+{
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentRangeFormattingParams,
+	) -> Result<(ModelRef, Range, MiniZincFormatOptions), ResponseError> {
+		Ok((
+			db.set_active_file_from_document(&params.text_document)?,
+			params.range,
+			format_options(&params.text_document, &params.options)?,
 		))
 	}
+
 	fn execute(
 		db: &CompilerDatabase,
-		(model_ref, options): (ModelRef, MiniZincFormatOptions),
+		(model_ref, range, options): (ModelRef, Range, MiniZincFormatOptions),
 	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
-		match db.ast(*model_ref) {
-			Ok(ConstraintModel::MznModel(ast)) => {
-				let Some(formatted) = format_model(&ast, &options) else {
-					return Ok(None);
-				};
-				let end = ast.cst().root_node().end_position();
-				Ok(Some(vec![TextEdit {
-					range: lsp_types::Range {
-						end: Position::new(end.row as u32, end.column as u32),
-						..Default::default()
-					},
-					new_text: formatted,
-				}]))
-			}
-			_ => Ok(None),
-		}
+		Ok(format_items(db, model_ref, range, &options))
 	}
 }
 
+#[derive(Debug)]
+pub struct OnTypeFormatHandler;
+
+impl RequestHandler<OnTypeFormatting, (ModelRef, Range, MiniZincFormatOptions)>
+	for OnTypeFormatHandler
+{
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentOnTypeFormattingParams,
+	) -> Result<(ModelRef, Range, MiniZincFormatOptions), ResponseError> {
+		// Format the item ended by the typed character
+		let document = &params.text_document_position.text_document;
+		let position = params.text_document_position.position;
+		Ok((
+			db.set_active_file_from_document(document)?,
+			Range {
+				start: position,
+				end: position,
+			},
+			format_options(document, &params.options)?,
+		))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, range, options): (ModelRef, Range, MiniZincFormatOptions),
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		Ok(format_items(db, model_ref, range, &options))
+	}
+}
+
+/// Get the formatting options for a document
+///
+/// These come from the project's configuration file if there is one, otherwise the
+/// editor's indentation settings are used.
+fn format_options(
+	document: &TextDocumentIdentifier,
+	options: &FormattingOptions,
+) -> Result<MiniZincFormatOptions, ResponseError> {
+	let config = document
+		.uri
+		.to_file_path()
+		.ok()
+		.and_then(|path| find_config_file(&path));
+	if let Some(config) = config {
+		return MiniZincFormatOptions::from_config_file(&config).map_err(|e| ResponseError {
+			code: ErrorCode::InvalidRequest as i32,
+			message: e.to_string(),
+			data: None,
+		});
+	}
+	Ok(MiniZincFormatOptions {
+		core: FormatOptions {
+			use_tabs: !options.insert_spaces,
+			indent_size: options.tab_size as usize,
+			..Default::default()
+		},
+		..Default::default()
+	})
+}
+
+/// Format the items of the model which overlap the given range
+fn format_items(
+	db: &CompilerDatabase,
+	model_ref: ModelRef,
+	range: Range,
+	options: &MiniZincFormatOptions,
+) -> Option<Vec<TextEdit>> {
+	let ast = match db.ast(*model_ref) {
+		Ok(ConstraintModel::MznModel(ast)) => ast,
+		_ => return None,
+	};
+	let text = ast.cst().text();
+	let start = position_to_offset(text, range.start);
+	let end = position_to_offset(text, range.end);
+	let (replaced, formatted) = format_range_edit(text, start..end, options)?;
+	if text[replaced.clone()] == formatted {
+		return Some(Vec::new());
+	}
+	Some(vec![TextEdit {
+		range: Range {
+			start: offset_to_position(text, replaced.start),
+			end: offset_to_position(text, replaced.end),
+		},
+		new_text: formatted,
+	}])
+}
+
 #[cfg(test)]
 mod test {
 	use std::str::FromStr;
@@ -59,8 +181,19 @@ mod test {
 	use expect_test::expect;
 	use lsp_types::Url;
 
-	use super::FormatHandler;
-	use crate::handlers::test::test_handler;
+	use super::{FormatHandler, OnTypeFormatHandler, RangeFormatHandler};
+	use crate::handlers::test::{test_handler, test_handler_eprime};
+
+	fn options() -> lsp_types::FormattingOptions {
+		lsp_types::FormattingOptions {
+			tab_size: 4,
+			insert_spaces: false,
+			properties: Default::default(),
+			trim_trailing_whitespace: None,
+			insert_final_newline: None,
+			trim_final_newlines: None,
+		}
+	}
 
 	#[test]
 	fn test_format() {
@@ -108,4 +241,135 @@ int: x   = (1 + 2) + 3 % foo
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_format_eprime() {
+		test_handler_eprime::<FormatHandler, _, _>(
+			r#"
+given n:int(1..)  $ size
+find x :matrix indexed by [int(1..n)] of int(1..n)
+such that allDiff(x),forAll i:int(1..n-1).x[i]<x[i+1]
+			"#,
+			false,
+			lsp_types::DocumentFormattingParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.eprime").unwrap(),
+				},
+				options: options(),
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 0,
+              "character": 0
+            },
+            "end": {
+              "line": 4,
+              "character": 3
+            }
+          },
+          "newText": "given n : int(1..) $ size\nfind x : matrix indexed by [int(1..n)] of int(1..n)\nsuch that allDiff(x), forAll i : int(1..n - 1) . x[i] < x[i + 1]\n"
+        }
+      ]
+    }"#]),
+		)
+	}
+
+	#[test]
+	fn test_range_format() {
+		test_handler::<RangeFormatHandler, _, _>(
+			r#"
+int: x   = 1;
+int: y   = (x + 2) + 3 % foo
+;
+int: z   = 3;
+			"#,
+			false,
+			lsp_types::DocumentRangeFormattingParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				range: lsp_types::Range {
+					start: lsp_types::Position {
+						line: 2,
+						character: 4,
+					},
+					end: lsp_types::Position {
+						line: 2,
+						character: 8,
+					},
+				},
+				options: options(),
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 0
+            },
+            "end": {
+              "line": 3,
+              "character": 1
+            }
+          },
+          "newText": "int: y = x + 2 + 3; % foo"
+        }
+      ]
+    }"#]),
+		)
+	}
+
+	#[test]
+	fn test_on_type_format() {
+		test_handler::<OnTypeFormatHandler, _, _>(
+			r#"
+int: x   = 1;
+int: y   = x+1;
+			"#,
+			false,
+			lsp_types::DocumentOnTypeFormattingParams {
+				text_document_position: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 2,
+						character: 14,
+					},
+				},
+				ch: ";".to_owned(),
+				options: options(),
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 0
+            },
+            "end": {
+              "line": 2,
+              "character": 15
+            }
+          },
+          "newText": "int: y = x + 1;"
+        }
+      ]
+    }"#]),
+		)
+	}
 }
@@ -74,7 +74,7 @@ mod test {
 	use lsp_types::Url;
 
 	use super::GotoDefinitionHandler;
-	use crate::handlers::test::test_handler;
+	use crate::handlers::test::{test_handler, test_handler_eprime};
 
 	#[test]
 	fn test_goto_definition_1() {
@@ -165,4 +165,50 @@ int: z = hello + let { int: hello = int; } in hello;
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_goto_definition_eprime() {
+		test_handler_eprime::<GotoDefinitionHandler, _, _>(
+			r#"
+language ESSENCE' 1.0
+given n: int
+find x: int(1..n)
+such that x != n
+			"#,
+			true,
+			lsp_types::GotoDefinitionParams {
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position_params: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.eprime").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 4,
+						character: 15,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": {
+        "uri": "file:///test.eprime",
+        "range": {
+          "start": {
+            "line": 2,
+            "character": 6
+          },
+          "end": {
+            "line": 2,
+            "character": 7
+          }
+        }
+      }
+    }"#]),
+		)
+	}
 }
@@ -95,7 +95,7 @@ mod test {
 	use lsp_types::Url;
 
 	use super::HoverHandler;
-	use crate::handlers::test::test_handler;
+	use crate::handlers::test::{test_handler, test_handler_eprime};
 
 	#[test]
 	fn test_hover() {
@@ -141,4 +141,50 @@ any: y = x.1;
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_hover_eprime() {
+		test_handler_eprime::<HoverHandler, _, _>(
+			r#"
+language ESSENCE' 1.0
+given n: int
+find x: int(1..n)
+such that x != n
+			"#,
+			true,
+			lsp_types::HoverParams {
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position_params: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.eprime").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 4,
+						character: 10,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": {
+        "contents": {
+          "language": "minizinc",
+          "value": "var int"
+        },
+        "range": {
+          "start": {
+            "line": 4,
+            "character": 10
+          },
+          "end": {
+            "line": 4,
+            "character": 11
+          }
+        }
+      }
+    }"#]),
+		)
+	}
 }
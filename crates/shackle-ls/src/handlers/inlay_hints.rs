@@ -0,0 +1,306 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::InlayHintRequest, InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams, Position,
+	Range,
+};
+use shackle_compiler::{
+	db::{CompilerDatabase, CompilerSettings},
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{EntityRef, ItemRef, LocalItemRef},
+		Declaration, Expression, Generator, ItemData, LetItem, Pattern, PatternTy, Type,
+		TypeResult,
+	},
+	utils::arena::ArenaIndex,
+};
+
+use crate::{
+	config::InlayHintsConfig, db::LanguageServerContext, dispatch::RequestHandler,
+	utils::node_ref_to_location,
+};
+
+#[derive(Debug)]
+pub struct InlayHintsHandler;
+
+impl RequestHandler<InlayHintRequest, (ModelRef, Range, InlayHintsConfig)> for InlayHintsHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: InlayHintParams,
+	) -> Result<(ModelRef, Range, InlayHintsConfig), ResponseError> {
+		let model = db.set_active_file_from_document(&params.text_document)?;
+		Ok((model, params.range, db.config().inlay_hints))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, range, config): (ModelRef, Range, InlayHintsConfig),
+	) -> Result<Option<Vec<InlayHint>>, ResponseError> {
+		let mut hints = Vec::new();
+		for item in db.lookup_items(model_ref).iter() {
+			let collector = InlayHintCollector {
+				db,
+				item: *item,
+				config,
+				types: &db.lookup_item_types(*item),
+			};
+			collector.collect(&mut hints);
+		}
+		hints.retain(|h| range.start <= h.position && h.position <= range.end);
+		hints.sort_by_key(|h| h.position);
+		Ok(Some(hints))
+	}
+}
+
+struct InlayHintCollector<'a> {
+	db: &'a CompilerDatabase,
+	item: ItemRef,
+	config: InlayHintsConfig,
+	types: &'a TypeResult,
+}
+
+impl InlayHintCollector<'_> {
+	fn collect(&self, hints: &mut Vec<InlayHint>) {
+		let model = self.item.model(self.db);
+		let local_item = self.item.local_item_ref(self.db);
+		let data = local_item.data(&model);
+		if let LocalItemRef::Declaration(d) = local_item {
+			if self.config.declaration_types {
+				hints.extend(self.declaration_type(data, &model[d]));
+			}
+		}
+		for (_, expression) in data.expressions.iter() {
+			match expression {
+				Expression::Let(l) if self.config.let_types => {
+					for item in l.items.iter() {
+						if let LetItem::Declaration(d) = item {
+							hints.extend(self.declaration_type(data, d));
+						}
+					}
+				}
+				Expression::ArrayComprehension(c) if self.config.generator_types => {
+					hints.extend(self.generator_types(data, &c.generators));
+				}
+				Expression::SetComprehension(c) if self.config.generator_types => {
+					hints.extend(self.generator_types(data, &c.generators));
+				}
+				Expression::Call(c) if self.config.parameter_names => {
+					hints.extend(self.parameter_names(data, c.function, &c.arguments));
+				}
+				_ => (),
+			}
+		}
+	}
+
+	/// Hint the type of a declaration with an inferred type
+	fn declaration_type(&self, data: &ItemData, declaration: &Declaration) -> Option<InlayHint> {
+		if !matches!(data[declaration.declared_type], Type::Any) {
+			return None;
+		}
+		self.pattern_type(data, declaration.pattern)
+	}
+
+	/// Hint the types of the variables of comprehension generators
+	fn generator_types(&self, data: &ItemData, generators: &[Generator]) -> Vec<InlayHint> {
+		generators
+			.iter()
+			.flat_map(|g| match g {
+				Generator::Iterator { patterns, .. } => patterns.to_vec(),
+				Generator::Assignment { pattern, .. } => vec![*pattern],
+			})
+			.filter_map(|p| self.pattern_type(data, p))
+			.collect()
+	}
+
+	/// Hint the type after the name of a variable
+	fn pattern_type(&self, data: &ItemData, pattern: ArenaIndex<Pattern>) -> Option<InlayHint> {
+		if !matches!(data[pattern], Pattern::Identifier(_)) {
+			return None;
+		}
+		let ty = match self.types.get_pattern(pattern)? {
+			PatternTy::Variable(ty) => *ty,
+			_ => return None,
+		};
+		if ty.contains_error(self.db) {
+			return None;
+		}
+		let location = node_ref_to_location(self.db, EntityRef::new(self.db, self.item, pattern))?;
+		Some(InlayHint {
+			position: location.range.end,
+			label: InlayHintLabel::String(format!(": {}", ty.pretty_print(self.db))),
+			kind: Some(InlayHintKind::TYPE),
+			text_edits: None,
+			tooltip: None,
+			padding_left: None,
+			padding_right: None,
+			data: None,
+		})
+	}
+
+	/// Hint the parameter names before the arguments of a call to a user-defined function
+	fn parameter_names(
+		&self,
+		data: &ItemData,
+		function: ArenaIndex<Expression>,
+		arguments: &[ArenaIndex<Expression>],
+	) -> Vec<InlayHint> {
+		(|| {
+			let db = self.db;
+			let resolved = self.types.name_resolution(function)?;
+			let f = match resolved.item().local_item_ref(db) {
+				LocalItemRef::Function(f) => f,
+				_ => return None,
+			};
+			let path = resolved.item().model_ref(db).path(db)?;
+			if db
+				.share_directory()
+				.is_ok_and(|share| path.starts_with(share.as_ref()))
+			{
+				// Function is from the standard library
+				return None;
+			}
+			let model = resolved.item().model(db);
+			let function_item = &model[f];
+			let call_start = self.position(function)?;
+			let mut hints = Vec::new();
+			for (arg, param) in arguments.iter().zip(function_item.parameters.iter()) {
+				let name = match param.pattern.map(|p| &function_item.data[p]) {
+					Some(Pattern::Identifier(name)) => *name,
+					_ => continue,
+				};
+				if matches!(&data[*arg], Expression::Identifier(i) if *i == name) {
+					continue;
+				}
+				let position = match self.position(*arg) {
+					// Arguments of generator calls do not appear after the function name
+					Some(position) if position > call_start => position,
+					_ => continue,
+				};
+				hints.push(InlayHint {
+					position,
+					label: InlayHintLabel::String(format!("{}:", name.pretty_print(db))),
+					kind: Some(InlayHintKind::PARAMETER),
+					text_edits: None,
+					tooltip: None,
+					padding_left: None,
+					padding_right: Some(true),
+					data: None,
+				});
+			}
+			Some(hints)
+		})()
+		.unwrap_or_default()
+	}
+
+	/// Get the start position of an expression
+	fn position(&self, expression: ArenaIndex<Expression>) -> Option<Position> {
+		node_ref_to_location(self.db, EntityRef::new(self.db, self.item, expression))
+			.map(|location| location.range.start)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::InlayHintsHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_inlay_hints() {
+		test_handler::<InlayHintsHandler, _, _>(
+			r#"
+function int: foo(int: a, bool: b) = a;
+array [int] of int: v = [1, 2, 3];
+any: x = [i + j | i in 1..3, j in v];
+any: y = let { any: z = foo(x[1], true) } in z;
+int: w = let { int: a = 1; bool: b = true } in foo(a, b);
+			"#,
+			false,
+			lsp_types::InlayHintParams {
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				range: lsp_types::Range {
+					start: lsp_types::Position {
+						line: 0,
+						character: 0,
+					},
+					end: lsp_types::Position {
+						line: 6,
+						character: 0,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "position": {
+            "line": 3,
+            "character": 6
+          },
+          "label": ": array [int] of int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 3,
+            "character": 19
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 3,
+            "character": 30
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 4,
+            "character": 6
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 4,
+            "character": 21
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 4,
+            "character": 28
+          },
+          "label": "a:",
+          "kind": 2,
+          "paddingRight": true
+        },
+        {
+          "position": {
+            "line": 4,
+            "character": 34
+          },
+          "label": "b:",
+          "kind": 2,
+          "paddingRight": true
+        }
+      ]
+    }"#]),
+		)
+	}
+}
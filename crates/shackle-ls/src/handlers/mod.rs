@@ -1,10 +1,15 @@
+mod code_actions;
 mod completions;
+mod document_symbols;
+mod folding_ranges;
 mod format;
 mod goto_definition;
 mod hover;
+mod inlay_hints;
 mod references;
 mod rename_symbol;
 mod semantic_tokens;
+mod signature_help;
 mod vfs;
 mod view_ast;
 mod view_cst;
@@ -12,11 +17,13 @@ mod view_format_ir;
 mod view_hir;
 mod view_pretty_print;
 mod view_scope;
+mod workspace_symbols;
 
 pub use self::{
-	completions::*, format::*, goto_definition::*, hover::*, references::*, rename_symbol::*,
-	semantic_tokens::*, vfs::*, view_ast::*, view_cst::*, view_format_ir::*, view_hir::*,
-	view_pretty_print::*, view_scope::*,
+	code_actions::*, completions::*, document_symbols::*, folding_ranges::*, format::*,
+	goto_definition::*, hover::*, inlay_hints::*, references::*, rename_symbol::*,
+	semantic_tokens::*, signature_help::*, vfs::*, view_ast::*, view_cst::*, view_format_ir::*,
+	view_hir::*, view_pretty_print::*, view_scope::*, workspace_symbols::*,
 };
 
 #[cfg(test)]
@@ -37,9 +44,9 @@ pub mod test {
 		file::{FileHandler, InputFile, InputLang},
 	};
 
-	use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+	use crate::{config::Config, db::LanguageServerContext, dispatch::RequestHandler};
 
-	struct MockFileHandler(String);
+	struct MockFileHandler(PathBuf, String);
 
 	impl FileHandler for MockFileHandler {
 		fn durable(&self) -> bool {
@@ -47,8 +54,8 @@ pub mod test {
 		}
 
 		fn read_file(&self, path: &Path) -> Result<Arc<String>, FileError> {
-			if path == PathBuf::from_str("test.mzn").unwrap() {
-				return Ok(Arc::new(self.0.clone()));
+			if path == self.0 {
+				return Ok(Arc::new(self.1.clone()));
 			}
 			std::fs::read_to_string(path)
 				.map(Arc::new)
@@ -67,6 +74,8 @@ pub mod test {
 	struct MockDatabase {
 		db: CompilerDatabase,
 		workspace: Option<lsp_types::Url>,
+		active: PathBuf,
+		config: Config,
 	}
 
 	impl Deref for MockDatabase {
@@ -86,6 +95,12 @@ pub mod test {
 		fn get_workspace_uri(&self) -> Option<&lsp_types::Url> {
 			self.workspace.as_ref()
 		}
+		fn active_file(&self) -> Option<&Path> {
+			Some(&self.active)
+		}
+		fn config(&self) -> &Config {
+			&self.config
+		}
 	}
 
 	pub fn run_handler<H, R, T>(
@@ -97,15 +112,36 @@ pub mod test {
 		H: RequestHandler<R, T>,
 		R: lsp_types::request::Request,
 	{
+		run_handler_lang::<H, R, T>(model, InputLang::MiniZinc, no_stdlib, params)
+	}
+
+	/// Run an LSP handler on a model written in the given language
+	pub fn run_handler_lang<H, R, T>(
+		model: &str,
+		lang: InputLang,
+		no_stdlib: bool,
+		params: R::Params,
+	) -> Result<R::Result, ResponseError>
+	where
+		H: RequestHandler<R, T>,
+		R: lsp_types::request::Request,
+	{
+		let path = match lang {
+			InputLang::EPrime => PathBuf::from_str("test.eprime").unwrap(),
+			_ => PathBuf::from_str("test.mzn").unwrap(),
+		};
 		let mut db = MockDatabase {
-			db: CompilerDatabase::with_file_handler(Box::new(MockFileHandler(model.to_string()))),
+			db: CompilerDatabase::with_file_handler(Box::new(MockFileHandler(
+				path.clone(),
+				model.to_string(),
+			))),
 			workspace: lsp_types::Url::from_str("file:///").ok(),
+			active: path.clone(),
+			config: Config::default(),
 		};
 		db.db.set_ignore_stdlib(no_stdlib);
-		db.db.set_input_files(Arc::new(vec![InputFile::Path(
-			PathBuf::from_str("test.mzn").unwrap(),
-			InputLang::MiniZinc,
-		)]));
+		db.db
+			.set_input_files(Arc::new(vec![InputFile::Path(path, lang)]));
 		H::prepare(&mut db, params).and_then(|t| H::execute(&db, t))
 	}
 
@@ -119,6 +155,20 @@ pub mod test {
 		expected.assert_eq(&serde_json::to_string_pretty(&actual).unwrap());
 	}
 
+	/// Test an LSP handler on an Essence' model
+	pub fn test_handler_eprime<H, R, T>(
+		model: &str,
+		no_stdlib: bool,
+		params: R::Params,
+		expected: Expect,
+	) where
+		H: RequestHandler<R, T>,
+		R: lsp_types::request::Request,
+	{
+		let actual = run_handler_lang::<H, R, T>(model, InputLang::EPrime, no_stdlib, params);
+		expected.assert_eq(&serde_json::to_string_pretty(&actual).unwrap());
+	}
+
 	/// Test an LSP handler which returns a string
 	pub fn test_handler_display<H, R, T>(
 		model: &str,
@@ -11,7 +11,11 @@ use shackle_compiler::{
 	syntax::db::SourceParser,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{identifiers_query, node_ref_to_location},
+};
 
 #[derive(Debug)]
 pub struct ReferencesHandler;
@@ -72,11 +76,7 @@ impl RequestHandler<References, ReferencesHandlerData> for ReferencesHandler {
 			let mut locations = Vec::new();
 			for m in models.iter().copied() {
 				let cst = db.cst(*m).ok()?;
-				let query = tree_sitter::Query::new(
-					&tree_sitter_minizinc::language(),
-					tree_sitter_minizinc::IDENTIFIERS_QUERY,
-				)
-				.expect("Failed to create query");
+				let query = identifiers_query(m.lang(db));
 				let mut cursor = tree_sitter::QueryCursor::new();
 				let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
 				let nodes = captures.map(|(c, _)| c.captures[0].node);
@@ -113,7 +113,7 @@ mod test {
 	use lsp_types::Url;
 
 	use super::ReferencesHandler;
-	use crate::handlers::test::test_handler;
+	use crate::handlers::test::{test_handler, test_handler_eprime};
 
 	#[test]
 	fn test_references() {
@@ -190,4 +190,81 @@ int: z = hello + let { int: hello = int; } in hello;
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_references_eprime() {
+		test_handler_eprime::<ReferencesHandler, _, _>(
+			r#"
+language ESSENCE' 1.0
+given n: int
+find x: int(1..n)
+such that x != n
+			"#,
+			true,
+			lsp_types::ReferenceParams {
+				context: lsp_types::ReferenceContext {
+					include_declaration: true,
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.eprime").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 2,
+						character: 6,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "uri": "file:///test.eprime",
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 6
+            },
+            "end": {
+              "line": 2,
+              "character": 7
+            }
+          }
+        },
+        {
+          "uri": "file:///test.eprime",
+          "range": {
+            "start": {
+              "line": 3,
+              "character": 15
+            },
+            "end": {
+              "line": 3,
+              "character": 16
+            }
+          }
+        },
+        {
+          "uri": "file:///test.eprime",
+          "range": {
+            "start": {
+              "line": 4,
+              "character": 15
+            },
+            "end": {
+              "line": 4,
+              "character": 16
+            }
+          }
+        }
+      ]
+    }"#]),
+		)
+	}
 }
@@ -14,7 +14,11 @@ use shackle_compiler::{
 	utils,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{identifiers_query, node_ref_to_location},
+};
 
 #[derive(Debug)]
 pub struct RenameHandler;
@@ -95,11 +99,7 @@ impl RequestHandler<Rename, SymbolHandlerData> for RenameHandler {
 		// loop over all the files included from the main file
 		for m in models.iter().copied() {
 			let cst = db.cst(*m).ok().unwrap();
-			let query = tree_sitter::Query::new(
-				&tree_sitter_minizinc::language(),
-				tree_sitter_minizinc::IDENTIFIERS_QUERY,
-			)
-			.expect("Failed to create query");
+			let query = identifiers_query(m.lang(db));
 			let mut cursor = tree_sitter::QueryCursor::new();
 			let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
 			let nodes = captures.map(|(c, _)| c.captures[0].node);
@@ -15,7 +15,11 @@ use shackle_compiler::{
 	syntax::db::SourceParser,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::span_contents_to_range};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{identifiers_query, span_contents_to_range},
+};
 
 #[derive(Debug)]
 pub struct SemanticTokensHandler;
@@ -32,11 +36,7 @@ impl RequestHandler<SemanticTokensFullRequest, ModelRef> for SemanticTokensHandl
 		model_ref: ModelRef,
 	) -> Result<Option<SemanticTokensResult>, ResponseError> {
 		if let Ok(cst) = db.cst(*model_ref) {
-			let query = tree_sitter::Query::new(
-				&tree_sitter_minizinc::language(),
-				tree_sitter_minizinc::IDENTIFIERS_QUERY,
-			)
-			.expect("Failed to create query");
+			let query = identifiers_query(model_ref.lang(db));
 			let mut cursor = tree_sitter::QueryCursor::new();
 			let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
 			let nodes = captures.map(|(c, _)| c.captures[0].node);
@@ -172,7 +172,7 @@ mod test {
 	use lsp_types::Url;
 
 	use super::SemanticTokensHandler;
-	use crate::handlers::test::test_handler;
+	use crate::handlers::test::{test_handler, test_handler_eprime};
 
 	#[test]
 	fn test_semantic_tokens() {
@@ -249,4 +249,60 @@ any: z = x + y;
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_semantic_tokens_eprime() {
+		test_handler_eprime::<SemanticTokensHandler, _, _>(
+			r#"
+language ESSENCE' 1.0
+given n: int
+find x: int(1..n)
+such that x != n
+			"#,
+			true,
+			lsp_types::SemanticTokensParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.eprime").unwrap(),
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": {
+        "data": [
+          2,
+          6,
+          1,
+          6,
+          1,
+          1,
+          5,
+          1,
+          6,
+          1,
+          0,
+          10,
+          1,
+          6,
+          1,
+          1,
+          10,
+          1,
+          6,
+          1,
+          0,
+          5,
+          1,
+          6,
+          1
+        ]
+      }
+    }"#]),
+		)
+	}
 }
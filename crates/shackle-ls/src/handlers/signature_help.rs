@@ -0,0 +1,426 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::SignatureHelpRequest, ParameterInformation, ParameterLabel, SignatureHelp,
+	SignatureHelpParams, SignatureInformation,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{LocalEntityRef, NodeRef},
+		source::Point,
+		Expression, Identifier, PatternTy,
+	},
+	syntax::db::SourceParser,
+	ty::{FunctionEntry, Ty},
+};
+use tree_sitter::Node;
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+
+#[derive(Debug)]
+pub struct SignatureHelpHandler;
+
+impl RequestHandler<SignatureHelpRequest, (ModelRef, Point)> for SignatureHelpHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: SignatureHelpParams,
+	) -> Result<(ModelRef, Point), ResponseError> {
+		let model =
+			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
+		let start = Point {
+			row: params.text_document_position_params.position.line as usize,
+			column: params.text_document_position_params.position.character as usize,
+		};
+		Ok((model, start))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, start): (ModelRef, Point),
+	) -> Result<Option<SignatureHelp>, ResponseError> {
+		let cst = match db.cst(*model_ref) {
+			Ok(cst) => cst,
+			Err(_) => return Ok(None),
+		};
+		Ok((|| {
+			let (function, active) = enclosing_call(cst.root_node(), start)?;
+			let (name, args) = match call_expression(db, model_ref, function) {
+				Some(c) => c,
+				None => {
+					// Call is not in the HIR, so just use the name of the function
+					(
+						Identifier::new(&cst.text()[function.byte_range()], db),
+						Vec::new(),
+					)
+				}
+			};
+
+			// Only use the arguments before the active one which have been typed
+			let typed = args
+				.iter()
+				.take(active)
+				.take_while(|ty| ty.is_some_and(|ty| !ty.contains_error(db)))
+				.map(|ty| ty.unwrap())
+				.collect::<Vec<_>>();
+			let overloads = function_overloads(db, name)
+				.into_iter()
+				.filter(|f| {
+					active < f.overload.params().len().max(1)
+						&& f.overload.matches_prefix(db, &typed)
+				})
+				.collect::<Vec<_>>();
+			if overloads.is_empty() {
+				return None;
+			}
+			// Prefer the overload matching all arguments which have been typed so far
+			let known = args
+				.iter()
+				.map_while(|ty| ty.filter(|ty| !ty.contains_error(db)))
+				.collect::<Vec<_>>();
+			let arity = args.len().max(active + 1);
+			let active_signature = overloads
+				.iter()
+				.position(|f| {
+					f.overload.params().len() == arity && f.overload.matches_prefix(db, &known)
+				})
+				.or_else(|| {
+					overloads
+						.iter()
+						.position(|f| f.overload.matches_prefix(db, &known))
+				})
+				.unwrap_or(0);
+			Some(SignatureHelp {
+				signatures: overloads
+					.iter()
+					.map(|f| signature_information(db, name, f))
+					.collect(),
+				active_signature: Some(active_signature as u32),
+				active_parameter: Some(active as u32),
+			})
+		})())
+	}
+}
+
+/// Find the function name of the innermost call whose argument list contains the given point,
+/// and the index of the argument being written.
+///
+/// This works on the tokens before the point rather than the call nodes, since a call which is
+/// still being written often doesn't parse.
+fn enclosing_call(root: Node<'_>, point: Point) -> Option<(Node<'_>, usize)> {
+	let mut tokens = Vec::new();
+	let mut cursor = root.walk();
+	'walk: loop {
+		let node = cursor.node();
+		if node.start_position() < point {
+			if node.child_count() == 0 {
+				if node.end_position() <= point && !node.is_missing() {
+					tokens.push(node);
+				}
+			} else if cursor.goto_first_child() {
+				continue;
+			}
+		}
+		while !cursor.goto_next_sibling() {
+			if !cursor.goto_parent() {
+				break 'walk;
+			}
+		}
+	}
+
+	let mut depth = 0;
+	let mut active = 0;
+	for (i, token) in tokens.iter().enumerate().rev() {
+		match token.kind() {
+			")" | "]" | "}" => depth += 1,
+			"(" if depth == 0 => {
+				let function = tokens[..i].last()?;
+				return (function.kind() == "identifier").then_some((*function, active));
+			}
+			"(" | "[" | "{" if depth > 0 => depth -= 1,
+			"," if depth == 0 => active += 1,
+			"[" | "{" | ";" => return None,
+			_ => (),
+		}
+	}
+	None
+}
+
+/// Get the name of the function and the types of the arguments of the HIR call expression for
+/// the call of the given function node.
+fn call_expression(
+	db: &CompilerDatabase,
+	model_ref: ModelRef,
+	function: Node<'_>,
+) -> Option<(Identifier, Vec<Option<Ty>>)> {
+	let call = function.parent().filter(|call| {
+		call.kind() == "call" && call.child_by_field_name("function") == Some(function)
+	})?;
+	let source_map = db.lookup_source_map(model_ref);
+	let e = match source_map.find_node(call)? {
+		NodeRef::Entity(e) => e,
+		_ => return None,
+	};
+	let item = e.item(db);
+	let expression = match e.entity(db) {
+		LocalEntityRef::Expression(e) => e,
+		_ => return None,
+	};
+	let model = item.model(db);
+	let data = item.local_item_ref(db).data(&model);
+	let c = match &data[expression] {
+		Expression::Call(c) => c,
+		_ => return None,
+	};
+	let name = match &data[c.function] {
+		Expression::Identifier(i) => *i,
+		_ => return None,
+	};
+	let types = db.lookup_item_types(item);
+	Some((
+		name,
+		c.arguments
+			.iter()
+			.map(|arg| types.get_expression(*arg))
+			.collect(),
+	))
+}
+
+/// Get all overloads of the global function with the given name
+fn function_overloads(db: &CompilerDatabase, name: Identifier) -> Vec<FunctionEntry> {
+	let mut overloads = Vec::new();
+	for p in db.lookup_global_function(name).iter() {
+		let types = db.lookup_item_types(p.item());
+		match &types[p.pattern()] {
+			PatternTy::Function(f)
+			| PatternTy::AnnotationConstructor(f)
+			| PatternTy::AnnotationDestructure(f) => overloads.push((**f).clone()),
+			PatternTy::EnumConstructor(ec) => {
+				overloads.extend(ec.iter().map(|c| c.constructor.clone()))
+			}
+			PatternTy::EnumDestructure(ed) => overloads.extend(ed.iter().cloned()),
+			_ => (),
+		}
+	}
+	overloads
+}
+
+/// Create the signature information for a function overload
+fn signature_information(
+	db: &CompilerDatabase,
+	name: Identifier,
+	function: &FunctionEntry,
+) -> SignatureInformation {
+	// The item form of the signature only adds a prefix to the call signature
+	let item = function.overload.pretty_print_item(db, name);
+	let call = function.overload.pretty_print_call_signature(db, name);
+	let mut label = item.strip_suffix(&call).unwrap_or_default().to_owned();
+	label.push_str(&name.lookup(db));
+	label.push('(');
+	// Parameter labels are given as UTF-16 offsets into the signature label
+	let mut parameters = Vec::new();
+	for (i, ty) in function.overload.params().iter().enumerate() {
+		if i > 0 {
+			label.push_str(", ");
+		}
+		let start = label.encode_utf16().count() as u32;
+		label.push_str(&ty.pretty_print(db));
+		let end = label.encode_utf16().count() as u32;
+		parameters.push(ParameterInformation {
+			label: ParameterLabel::LabelOffsets([start, end]),
+			documentation: None,
+		});
+	}
+	label.push(')');
+	SignatureInformation {
+		label,
+		documentation: None,
+		parameters: Some(parameters),
+		active_parameter: None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::SignatureHelpHandler;
+	use crate::handlers::test::test_handler;
+
+	fn params(line: u32, character: u32) -> lsp_types::SignatureHelpParams {
+		lsp_types::SignatureHelpParams {
+			context: None,
+			work_done_progress_params: lsp_types::WorkDoneProgressParams {
+				work_done_token: None,
+			},
+			text_document_position_params: lsp_types::TextDocumentPositionParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				position: lsp_types::Position { line, character },
+			},
+		}
+	}
+
+	#[test]
+	fn test_signature_help() {
+		test_handler::<SignatureHelpHandler, _, _>(
+			r#"
+function int: foo(int: x, bool: y) = x;
+function int: foo(float: x, float: y, int: z) = 1;
+function int: foo(bool: x) = 1;
+any: a = foo(1, true);
+			"#,
+			true,
+			params(4, 16),
+			expect!([r#"
+    {
+      "Ok": {
+        "signatures": [
+          {
+            "label": "function int: foo(int, bool)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  21
+                ]
+              },
+              {
+                "label": [
+                  23,
+                  27
+                ]
+              }
+            ]
+          },
+          {
+            "label": "function int: foo(float, float, int)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  23
+                ]
+              },
+              {
+                "label": [
+                  25,
+                  30
+                ]
+              },
+              {
+                "label": [
+                  32,
+                  35
+                ]
+              }
+            ]
+          }
+        ],
+        "activeSignature": 0,
+        "activeParameter": 1
+      }
+    }"#]),
+		)
+	}
+
+	#[test]
+	fn test_signature_help_incomplete() {
+		test_handler::<SignatureHelpHandler, _, _>(
+			r#"
+function int: foo(int: x, bool: y) = x;
+function int: foo(bool: x) = 1;
+any: a = foo([1, 2][1],
+			"#,
+			true,
+			params(3, 23),
+			expect!([r#"
+    {
+      "Ok": {
+        "signatures": [
+          {
+            "label": "function int: foo(int, bool)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  21
+                ]
+              },
+              {
+                "label": [
+                  23,
+                  27
+                ]
+              }
+            ]
+          }
+        ],
+        "activeSignature": 0,
+        "activeParameter": 1
+      }
+    }"#]),
+		)
+	}
+
+	#[test]
+	fn test_signature_help_active_signature() {
+		test_handler::<SignatureHelpHandler, _, _>(
+			r#"
+function int: foo(int: x, set of int: y) = x;
+function int: foo(int: x, float: y) = x;
+any: a = foo(1, 2.5);
+			"#,
+			true,
+			params(3, 13),
+			expect!([r#"
+    {
+      "Ok": {
+        "signatures": [
+          {
+            "label": "function int: foo(int, set of int)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  21
+                ]
+              },
+              {
+                "label": [
+                  23,
+                  33
+                ]
+              }
+            ]
+          },
+          {
+            "label": "function int: foo(int, float)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  21
+                ]
+              },
+              {
+                "label": [
+                  23,
+                  28
+                ]
+              }
+            ]
+          }
+        ],
+        "activeSignature": 1,
+        "activeParameter": 0
+      }
+    }"#]),
+		)
+	}
+}
@@ -1,8 +1,9 @@
 use lsp_types::{
-	DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+	DidChangeConfigurationParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+	DidOpenTextDocumentParams,
 };
 
-use crate::LanguageServerDatabase;
+use crate::{config::Config, LanguageServerDatabase};
 
 pub fn on_document_open(db: &mut LanguageServerDatabase, params: DidOpenTextDocumentParams) {
 	let file = params
@@ -37,3 +38,10 @@ pub fn on_document_closed(db: &mut LanguageServerDatabase, params: DidCloseTextD
 		.expect("Failed to convert URI to file path");
 	db.unmanage_file(file.as_path());
 }
+
+pub fn on_configuration_changed(
+	db: &mut LanguageServerDatabase,
+	params: DidChangeConfigurationParams,
+) {
+	db.set_config(Config::from_value(Some(params.settings)));
+}
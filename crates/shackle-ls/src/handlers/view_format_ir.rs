@@ -5,7 +5,7 @@ use shackle_compiler::{
 	file::ModelRef,
 	syntax::{ast::ConstraintModel, db::SourceParser},
 };
-use shackle_fmt::{format_model_debug, MiniZincFormatOptions};
+use shackle_fmt::{format_eprime_model_debug, format_model_debug, MiniZincFormatOptions};
 
 use crate::{db::LanguageServerContext, dispatch::RequestHandler, extensions::ViewFormatIr};
 
@@ -25,7 +25,11 @@ impl RequestHandler<ViewFormatIr, ModelRef> for ViewFormatIrHandler {
 				Ok(format_model_debug(&ast, &MiniZincFormatOptions::default())
 					.unwrap_or_else(|| "Failed to format".to_owned()))
 			}
-			Ok(_) => todo!("no formatter available for this file type"),
+			Ok(ConstraintModel::EPrimeModel(ast)) => Ok(format_eprime_model_debug(
+				&ast,
+				&MiniZincFormatOptions::default(),
+			)
+			.unwrap_or_else(|| "Failed to format".to_owned())),
 			Err(e) => Ok(e.to_string()),
 		}
 	}
@@ -37,9 +41,10 @@ mod test {
 
 	use expect_test::expect;
 	use lsp_types::Url;
+	use shackle_compiler::file::InputLang;
 
 	use super::ViewFormatIrHandler;
-	use crate::handlers::test::test_handler_display;
+	use crate::handlers::test::{run_handler_lang, test_handler_display};
 
 	#[test]
 	fn test_view_format_ir() {
@@ -191,4 +196,26 @@ mod test {
     )"#]),
 		)
 	}
+
+	#[test]
+	fn test_view_format_ir_eprime() {
+		let result = run_handler_lang::<ViewFormatIrHandler, _, _>(
+			"language ESSENCE' 1.0\nfind x: bool\n",
+			InputLang::EPrime,
+			true,
+			lsp_types::TextDocumentPositionParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.eprime").unwrap(),
+				},
+				position: lsp_types::Position {
+					line: 0,
+					character: 0,
+				},
+			},
+		);
+		let ir = result.expect("Essence' models should have a formatter");
+		assert!(ir.contains("\"find\""), "{ir}");
+		assert!(ir.contains("\"x\""), "{ir}");
+		assert!(ir.contains("\"bool\""), "{ir}");
+	}
 }
@@ -22,7 +22,7 @@ impl RequestHandler<ViewPrettyPrint, ModelRef> for ViewPrettyPrintHandler {
 	fn execute(db: &CompilerDatabase, _: ModelRef) -> Result<String, ResponseError> {
 		let errors = db.all_errors();
 		if errors.is_empty() {
-			let thir = match db.final_thir() {
+			let thir = match db.lowering_thir() {
 				Ok(m) => m,
 				Err(e) => return Ok(format!("%: THIR error: {}", e)),
 			};
@@ -33,3 +33,63 @@ impl RequestHandler<ViewPrettyPrint, ModelRef> for ViewPrettyPrintHandler {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::ViewPrettyPrintHandler;
+	use crate::handlers::test::test_handler_display;
+
+	#[test]
+	fn test_view_pretty_print_contexts() {
+		test_handler_display::<ViewPrettyPrintHandler, _, _>(
+			r#"
+annotation output;
+annotation ctx_root;
+annotation ctx_pos;
+annotation ctx_neg;
+annotation ctx_mix;
+function var bool: '\/'(var bool: x, var bool: y);
+function var bool: 'not'(var bool: x);
+function var bool: '>'(var int: x, var int: y);
+function var bool: '->'(var bool: x, var bool: y);
+function var bool: '<->'(var bool: x, var bool: y);
+predicate foo(var int: x) = x > 0;
+var int: x;
+constraint foo(x) \/ not foo(x);
+			"#,
+			true,
+			lsp_types::TextDocumentPositionParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				position: lsp_types::Position {
+					line: 0,
+					character: 0,
+				},
+			},
+			expect!([r#"
+    annotation output;
+    annotation ctx_root;
+    annotation ctx_pos;
+    annotation ctx_neg;
+    annotation ctx_mix;
+    function var bool: '\/'(var bool: x, var bool: y);
+    function var bool: 'not'(var bool: x);
+    function var bool: '>'(var int: x, var int: y);
+    function var bool: '->'(var bool: x, var bool: y);
+    function var bool: '<->'(var bool: x, var bool: y);
+    function var bool: foo(var int: x) = '>'(x, 0) :: (ctx_root);
+    function var bool: foo_imp(var int: x, var bool: _DECL_12) = '->'(_DECL_12, '>'(x, 0) :: (ctx_pos)) :: (ctx_root);
+    function var bool: foo_reif(var int: x, var bool: _DECL_14) = '<->'(_DECL_14, '>'(x, 0) :: (ctx_mix)) :: (ctx_root);
+    var int: x :: (output);
+    constraint '\/'(foo(x) :: (ctx_pos), 'not'(foo(x) :: (ctx_neg)) :: (ctx_pos)) :: (ctx_root);
+    solve satisfy;
+"#]),
+		)
+	}
+}
@@ -0,0 +1,171 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::WorkspaceSymbolRequest, DocumentSymbol, Location, OneOf, Url, WorkspaceSymbol,
+	WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	hir::{db::Hir, ids::LocalItemRef},
+};
+
+use crate::{
+	db::LanguageServerContext, dispatch::RequestHandler, handlers::item_symbol, utils::file_url,
+};
+
+#[derive(Debug)]
+pub struct WorkspaceSymbolsHandler;
+
+impl RequestHandler<WorkspaceSymbolRequest, Option<String>> for WorkspaceSymbolsHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: WorkspaceSymbolParams,
+	) -> Result<Option<String>, ResponseError> {
+		// Symbols are searched for in the active model, its includes, and the standard library
+		Ok(db.active_file().map(|_| params.query))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		query: Option<String>,
+	) -> Result<Option<WorkspaceSymbolResponse>, ResponseError> {
+		let query = match query {
+			Some(query) => query.to_lowercase(),
+			None => return Ok(None),
+		};
+		let models = match db.resolve_includes() {
+			Ok(models) => models,
+			Err(_) => return Ok(None),
+		};
+		let mut symbols = Vec::new();
+		for model in models.iter() {
+			let uri = match model.path(db).and_then(|p| file_url(&p)) {
+				Some(uri) => uri,
+				None => continue,
+			};
+			for item in db.lookup_items(*model).iter() {
+				if matches!(
+					item.local_item_ref(db),
+					LocalItemRef::Constraint(_) | LocalItemRef::Solve(_) | LocalItemRef::Output(_)
+				) {
+					continue;
+				}
+				if let Some(symbol) = item_symbol(db, *item) {
+					collect_symbols(&query, &uri, symbol, None, &mut symbols);
+				}
+			}
+		}
+		Ok(Some(WorkspaceSymbolResponse::Nested(symbols)))
+	}
+}
+
+/// Add the given symbol and its children to the results if they match the query
+fn collect_symbols(
+	query: &str,
+	uri: &Url,
+	symbol: DocumentSymbol,
+	container_name: Option<String>,
+	symbols: &mut Vec<WorkspaceSymbol>,
+) {
+	for child in symbol.children.into_iter().flatten() {
+		collect_symbols(query, uri, child, Some(symbol.name.clone()), symbols);
+	}
+	if symbol.name.to_lowercase().contains(query) {
+		symbols.push(WorkspaceSymbol {
+			name: symbol.name,
+			kind: symbol.kind,
+			tags: None,
+			container_name,
+			location: OneOf::Left(Location {
+				uri: uri.clone(),
+				range: symbol.selection_range,
+			}),
+			data: None,
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::WorkspaceSymbolsHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_workspace_symbols() {
+		test_handler::<WorkspaceSymbolsHandler, _, _>(
+			r#"
+enum Colour = {Red, Green, Reddish};
+function int: redness(Colour: c) = if c = Red then 1 else 0 endif;
+			"#,
+			true,
+			lsp_types::WorkspaceSymbolParams {
+				query: "red".to_owned(),
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": [
+        {
+          "name": "Red",
+          "kind": 22,
+          "containerName": "Colour",
+          "location": {
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 1,
+                "character": 15
+              },
+              "end": {
+                "line": 1,
+                "character": 18
+              }
+            }
+          }
+        },
+        {
+          "name": "Reddish",
+          "kind": 22,
+          "containerName": "Colour",
+          "location": {
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 1,
+                "character": 27
+              },
+              "end": {
+                "line": 1,
+                "character": 34
+              }
+            }
+          }
+        },
+        {
+          "name": "redness",
+          "kind": 12,
+          "location": {
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 2,
+                "character": 14
+              },
+              "end": {
+                "line": 2,
+                "character": 21
+              }
+            }
+          }
+        }
+      ]
+    }"#]),
+		)
+	}
+}
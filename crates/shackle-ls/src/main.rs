@@ -3,17 +3,23 @@ use std::error::Error;
 use db::LanguageServerDatabase;
 use lsp_server::{Connection, ExtractError, Message};
 use lsp_types::{
-	notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument},
-	CompletionOptions, HoverProviderCapability, InitializeParams, OneOf, SemanticTokensFullOptions,
-	SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
-	ServerCapabilities, TextDocumentSyncKind,
+	notification::{
+		DidChangeConfiguration, DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+	},
+	CodeActionProviderCapability, CompletionOptions, DocumentOnTypeFormattingOptions,
+	FoldingRangeProviderCapability, HoverProviderCapability, InitializeParams, OneOf,
+	SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+	SemanticTokensServerCapabilities, ServerCapabilities, SignatureHelpOptions,
+	TextDocumentSyncKind,
 };
 
 use crate::{
+	config::Config,
 	dispatch::{DispatchNotification, DispatchRequest},
 	handlers::*,
 };
 
+mod config;
 mod db;
 mod diagnostics;
 mod dispatch;
@@ -21,6 +27,7 @@ mod extensions;
 mod handlers;
 mod utils;
 mod vfs;
+mod workspace;
 
 fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
 	env_logger::Builder::new()
@@ -45,6 +52,10 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
 			trigger_characters: Some(vec![".".to_owned()]),
 			..Default::default()
 		}),
+		signature_help_provider: Some(SignatureHelpOptions {
+			trigger_characters: Some(vec!["(".to_owned(), ",".to_owned()]),
+			..Default::default()
+		}),
 		semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
 			SemanticTokensOptions {
 				full: Some(SemanticTokensFullOptions::Delta { delta: Some(false) }),
@@ -57,6 +68,16 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
 			},
 		)),
 		document_formatting_provider: Some(OneOf::Left(true)),
+		document_range_formatting_provider: Some(OneOf::Left(true)),
+		document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+			first_trigger_character: ";".to_owned(),
+			more_trigger_character: None,
+		}),
+		inlay_hint_provider: Some(OneOf::Left(true)),
+		document_symbol_provider: Some(OneOf::Left(true)),
+		workspace_symbol_provider: Some(OneOf::Left(true)),
+		folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+		code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
 		..Default::default()
 	})
 	.unwrap();
@@ -73,7 +94,11 @@ fn main_loop(
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
 	let params: InitializeParams = serde_json::from_value(params).unwrap();
 	#[allow(deprecated)] // TODO
-	let mut db = LanguageServerDatabase::new(&connection, params.root_uri);
+	let mut db = LanguageServerDatabase::new(
+		&connection,
+		params.root_uri,
+		Config::from_value(params.initialization_options),
+	);
 	for msg in &connection.receiver {
 		match msg {
 			Message::Request(req) => {
@@ -93,8 +118,16 @@ fn main_loop(
 					.on::<RenameHandler, _, _>()
 					.on::<HoverHandler, _, _>()
 					.on::<CompletionsHandler, _, _>()
+					.on::<SignatureHelpHandler, _, _>()
 					.on::<SemanticTokensHandler, _, _>()
 					.on::<FormatHandler, _, _>()
+					.on::<RangeFormatHandler, _, _>()
+					.on::<OnTypeFormatHandler, _, _>()
+					.on::<InlayHintsHandler, _, _>()
+					.on::<DocumentSymbolsHandler, _, _>()
+					.on::<WorkspaceSymbolsHandler, _, _>()
+					.on::<FoldingRangesHandler, _, _>()
+					.on::<CodeActionsHandler, _, _>()
 					.finish();
 
 				match result {
@@ -119,6 +152,9 @@ fn main_loop(
 					.on::<DidCloseTextDocument, _>(|db, params| {
 						handlers::on_document_closed(db, params)
 					})
+					.on::<DidChangeConfiguration, _>(|db, params| {
+						handlers::on_configuration_changed(db, params)
+					})
 					.finish();
 				match result {
 					Ok(()) => (),
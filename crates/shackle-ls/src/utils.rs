@@ -1,8 +1,11 @@
-use std::str::FromStr;
+use std::{path::Path, str::FromStr};
 
 use lsp_types::Url;
 use miette::{SourceCode, SpanContents};
-use shackle_compiler::hir::{db::Hir, ids::NodeRef};
+use shackle_compiler::{
+	file::InputLang,
+	hir::{db::Hir, ids::NodeRef},
+};
 
 pub fn span_contents_to_range(r: &dyn SpanContents) -> lsp_types::Range {
 	let mut range = lsp_types::Range::default();
@@ -32,9 +35,89 @@ pub fn node_ref_to_location<T: Into<NodeRef>>(
 ) -> Option<lsp_types::Location> {
 	let (src, span) = node.into().source_span(db);
 	let span_contents = src.read_span(&span, 0, 0).ok()?;
-	let uri = Url::from_file_path(src.path()?)
-		.ok()
-		.or_else(|| Url::from_str(&format!("file:///{}", src.path()?.to_string_lossy())).ok())?;
+	let uri = file_url(src.path()?)?;
 	let range = span_contents_to_range(&*span_contents);
 	Some(lsp_types::Location { uri, range })
 }
+
+/// Get the URL for a file
+pub fn file_url(path: &Path) -> Option<Url> {
+	Url::from_file_path(path)
+		.ok()
+		.or_else(|| Url::from_str(&format!("file:///{}", path.to_string_lossy())).ok())
+}
+
+/// Create a query matching the identifiers in a model of the given language
+pub fn identifiers_query(lang: InputLang) -> tree_sitter::Query {
+	let (language, source) = match lang {
+		InputLang::EPrime => (
+			tree_sitter_eprime::language(),
+			tree_sitter_eprime::IDENTIFIERS_QUERY,
+		),
+		_ => (
+			tree_sitter_minizinc::language(),
+			tree_sitter_minizinc::IDENTIFIERS_QUERY,
+		),
+	};
+	tree_sitter::Query::new(&language, source).expect("Failed to create query")
+}
+
+/// Get the byte offset of a position in the given text
+///
+/// The character of the position is given in UTF-16 code units.
+pub fn position_to_offset(text: &str, position: lsp_types::Position) -> usize {
+	let line_start = text
+		.split_inclusive('\n')
+		.take(position.line as usize)
+		.map(|line| line.len())
+		.sum::<usize>();
+	let line = text[line_start..].split('\n').next().unwrap_or_default();
+	let mut units = 0;
+	let column = line
+		.char_indices()
+		.find_map(|(i, c)| {
+			if units >= position.character as usize {
+				return Some(i);
+			}
+			units += c.len_utf16();
+			None
+		})
+		.unwrap_or(line.len());
+	line_start + column
+}
+
+/// Get the position of a byte offset in the given text
+///
+/// The character of the position is given in UTF-16 code units.
+pub fn offset_to_position(text: &str, offset: usize) -> lsp_types::Position {
+	let before = &text[..offset];
+	let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+	lsp_types::Position {
+		line: before.matches('\n').count() as u32,
+		character: before[line_start..].encode_utf16().count() as u32,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use lsp_types::Position;
+
+	use super::{offset_to_position, position_to_offset};
+
+	#[test]
+	fn test_utf16_positions() {
+		// The emoji takes up four bytes, but only two UTF-16 code units
+		let text = "a = \"\u{1F600}\";\nb = 1;\n";
+		for (position, offset) in [
+			(Position::new(0, 5), 5),
+			(Position::new(0, 7), 9),
+			(Position::new(0, 8), 10),
+			(Position::new(1, 2), 14),
+		] {
+			assert_eq!(position_to_offset(text, position), offset);
+			assert_eq!(offset_to_position(text, offset), position);
+		}
+		// Positions past the end of a line are clamped to the end of the line
+		assert_eq!(position_to_offset(text, Position::new(0, 20)), 11);
+	}
+}
@@ -0,0 +1,273 @@
+use std::{
+	collections::{HashMap, HashSet},
+	ffi::OsStr,
+	path::{Path, PathBuf},
+};
+
+use lsp_types::Url;
+
+/// Model file extensions, in order of preference
+const MODEL_EXTENSIONS: [&str; 2] = ["mzn", "eprime"];
+/// Data file extensions, in order of preference
+const DATA_EXTENSIONS: [&str; 3] = ["dzn", "json", "param"];
+
+/// Tracks the files that make up the models in the workspace
+///
+/// A model consists of a root model file, the files it (transitively)
+/// includes, and the data files that go with it. Data files are associated
+/// with a model by their name: `jobshop.dzn` and `jobshop_large.dzn` are both
+/// data files for `jobshop.mzn` in the same directory.
+#[derive(Debug, Default)]
+pub struct Workspace {
+	/// The root directory of the workspace
+	uri: Option<Url>,
+	/// Documents currently open in the editor
+	documents: HashSet<PathBuf>,
+	/// Files (transitively) included by each root model, including the root itself
+	includes: HashMap<PathBuf, HashSet<PathBuf>>,
+}
+
+impl Workspace {
+	pub fn new(uri: Option<Url>) -> Self {
+		Self {
+			uri,
+			..Default::default()
+		}
+	}
+
+	/// Get the workspace URI
+	pub fn uri(&self) -> Option<&Url> {
+		self.uri.as_ref()
+	}
+
+	/// Mark the given document as open in the editor
+	pub fn open(&mut self, path: &Path) {
+		self.documents.insert(normalize(path));
+	}
+
+	/// Mark the given document as closed in the editor
+	pub fn close(&mut self, path: &Path) {
+		self.documents.remove(&normalize(path));
+	}
+
+	/// Whether the given file is part of any known model
+	pub fn is_known(&self, path: &Path) -> bool {
+		let path = normalize(path);
+		self.includes.values().any(|files| files.contains(&path))
+	}
+
+	/// Whether the given file is currently used as a root model
+	pub fn is_root(&self, path: &Path) -> bool {
+		self.includes.contains_key(&normalize(path))
+	}
+
+	/// Record the files (transitively) included by the given root model
+	///
+	/// Files that were previously treated as root models, but are included
+	/// by this model, are no longer considered roots.
+	pub fn set_includes(&mut self, root: &Path, files: impl IntoIterator<Item = PathBuf>) {
+		let root = normalize(root);
+		let mut files = files
+			.into_iter()
+			.map(|f| normalize(&f))
+			.collect::<HashSet<_>>();
+		files.insert(root.clone());
+		for f in files.iter() {
+			if *f != root {
+				self.includes.remove(f);
+			}
+		}
+		self.includes.insert(root, files);
+	}
+
+	/// Get the files (transitively) included by the given root model
+	pub fn includes(&self, root: &Path) -> Vec<PathBuf> {
+		let root = normalize(root);
+		let mut files = self
+			.includes
+			.get(&root)
+			.map(|files| files.iter().cloned().collect::<Vec<_>>())
+			.unwrap_or_else(|| vec![root]);
+		files.sort();
+		files
+	}
+
+	/// Get the root model used to check the given file
+	///
+	/// Files included by a model are checked as part of that model, data
+	/// files are checked using their associated model, and any other file is
+	/// its own root model.
+	pub fn root_model(&self, path: &Path) -> PathBuf {
+		self.affected_roots(path)
+			.into_iter()
+			.next()
+			.unwrap_or_else(|| normalize(path))
+	}
+
+	/// Get the root models that have to be checked again when the given file changes
+	pub fn affected_roots(&self, path: &Path) -> Vec<PathBuf> {
+		let path = normalize(path);
+		if is_data_file(&path) {
+			return self.data_model(&path).into_iter().collect();
+		}
+		let mut roots = self
+			.includes
+			.iter()
+			.filter(|(_, files)| files.contains(&path))
+			.map(|(root, _)| root.clone())
+			.collect::<Vec<_>>();
+		if roots.is_empty() {
+			roots.push(path);
+		}
+		roots.sort();
+		roots
+	}
+
+	/// Get the model that the given data file provides data for
+	pub fn data_model(&self, data: &Path) -> Option<PathBuf> {
+		let dir = data.parent()?;
+		let mut stem = data.file_stem()?.to_str()?;
+		loop {
+			for ext in MODEL_EXTENSIONS {
+				let model = dir.join(format!("{stem}.{ext}"));
+				if self.documents.contains(&model) || model.exists() {
+					return Some(model);
+				}
+			}
+			// Strip a suffix such as `_large` or `-1` from the name
+			stem = &stem[..stem.rfind(['_', '-'])?];
+		}
+	}
+
+	/// Get the data files that go with the given root model
+	///
+	/// These are the open data documents associated with the model, as well as
+	/// the data files on disk with the same name as the model.
+	pub fn data_files(&self, root: &Path) -> Vec<PathBuf> {
+		let root = normalize(root);
+		let mut files = self
+			.documents
+			.iter()
+			.filter(|doc| is_data_file(doc) && self.data_model(doc).as_ref() == Some(&root))
+			.cloned()
+			.collect::<Vec<_>>();
+		for ext in DATA_EXTENSIONS {
+			let data = root.with_extension(ext);
+			if !files.contains(&data) && data.exists() {
+				files.push(data);
+			}
+		}
+		files.sort();
+		files
+	}
+
+	/// Get the model files that could include the given file
+	///
+	/// These are the model files in the directory of the given file and its
+	/// parent directories up to the root of the workspace.
+	pub fn candidate_roots(&self, path: &Path) -> Vec<PathBuf> {
+		let path = normalize(path);
+		let workspace = self.uri.as_ref().and_then(|uri| uri.to_file_path().ok());
+		let mut candidates = Vec::new();
+		let mut dir = path.parent();
+		while let Some(d) = dir {
+			if let Ok(entries) = d.read_dir() {
+				let mut models = entries
+					.filter_map(|e| Some(normalize(&e.ok()?.path())))
+					.filter(|p| *p != path && is_model_file(p))
+					.collect::<Vec<_>>();
+				models.sort();
+				candidates.extend(models);
+			}
+			if workspace.as_deref().is_none_or(|w| normalize(w) == d) {
+				break;
+			}
+			dir = d.parent();
+		}
+		candidates
+	}
+}
+
+/// Normalize a path, so that different paths to the same file can be compared
+pub fn normalize(path: &Path) -> PathBuf {
+	path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Whether the given file is a model file
+pub fn is_model_file(path: &Path) -> bool {
+	has_extension(path, &MODEL_EXTENSIONS)
+}
+
+/// Whether the given file is a data file
+pub fn is_data_file(path: &Path) -> bool {
+	has_extension(path, &DATA_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+	path.extension()
+		.and_then(OsStr::to_str)
+		.is_some_and(|ext| extensions.contains(&ext))
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs::File;
+
+	use super::{normalize, Workspace};
+
+	#[test]
+	fn test_workspace_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let dir = normalize(dir.path());
+		for f in [
+			"jobshop.mzn",
+			"jobshop.dzn",
+			"jobshop_large.dzn",
+			"helpers.mzn",
+			"other.dzn",
+		] {
+			File::create(dir.join(f)).unwrap();
+		}
+		let mut ws = Workspace::default();
+		ws.open(&dir.join("jobshop_large.dzn"));
+		ws.open(&dir.join("helpers.mzn"));
+
+		// Data files are associated by name
+		assert_eq!(
+			ws.data_model(&dir.join("jobshop_large.dzn")),
+			Some(dir.join("jobshop.mzn"))
+		);
+		assert_eq!(ws.data_model(&dir.join("other.dzn")), None);
+		assert_eq!(
+			ws.data_files(&dir.join("jobshop.mzn")),
+			[dir.join("jobshop.dzn"), dir.join("jobshop_large.dzn")]
+		);
+
+		// Included files are checked as part of their root model
+		assert_eq!(
+			ws.root_model(&dir.join("helpers.mzn")),
+			dir.join("helpers.mzn")
+		);
+		assert_eq!(
+			ws.candidate_roots(&dir.join("helpers.mzn")),
+			[dir.join("jobshop.mzn")]
+		);
+		ws.set_includes(&dir.join("helpers.mzn"), []);
+		ws.set_includes(&dir.join("jobshop.mzn"), [dir.join("helpers.mzn")]);
+		assert!(ws.is_known(&dir.join("helpers.mzn")));
+		assert!(!ws.is_root(&dir.join("helpers.mzn")));
+		assert!(ws.is_root(&dir.join("jobshop.mzn")));
+		assert_eq!(
+			ws.affected_roots(&dir.join("helpers.mzn")),
+			[dir.join("jobshop.mzn")]
+		);
+		assert_eq!(
+			ws.includes(&dir.join("jobshop.mzn")),
+			[dir.join("helpers.mzn"), dir.join("jobshop.mzn")]
+		);
+		assert_eq!(
+			ws.affected_roots(&dir.join("jobshop.dzn")),
+			[dir.join("jobshop.mzn")]
+		);
+	}
+}
@@ -19,7 +19,7 @@ use tree_sitter::Parser;
 
 use crate::{
 	data::ParserVal,
-	value::{EnumInner, Index, Polarity, Set},
+	value::{EnumInner, Polarity, Set},
 	Enum, OptType, Type, Value,
 };
 
@@ -46,6 +46,22 @@ pub(crate) fn parse_dzn(src: &SourceFile) -> Result<Vec<Assignment>, Error> {
 	Ok(it.collect())
 }
 
+/// Convert an DZN AST expression into a internal value of the given type, keeping its location
+///
+/// This is used for the members of collections, so that errors found when
+/// resolving the value refer to the member instead of the whole collection.
+fn collect_located_value(
+	file: &SourceFile,
+	val: &Expression,
+	ty: &Type,
+) -> Result<ParserVal, Error> {
+	let span = val.cst_node().as_ref().byte_range().into();
+	Ok(ParserVal::Located(
+		Box::new(collect_dzn_value(file, val, ty)?),
+		span,
+	))
+}
+
 /// Convert an DZN AST expression into a internal value of the given type
 pub(crate) fn collect_dzn_value(
 	file: &SourceFile,
@@ -159,7 +175,7 @@ pub(crate) fn collect_dzn_value(
 			Type::Set(_, elem_ty) => {
 				let c = sl
 					.members()
-					.map(|elem| collect_dzn_value(file, &elem, elem_ty))
+					.map(|elem| collect_located_value(file, &elem, elem_ty))
 					.collect::<Result<_, _>>()?;
 				Ok(ParserVal::SetList(c))
 			}
@@ -233,7 +249,13 @@ pub(crate) fn collect_dzn_value(
 					} else {
 						ParserVal::Integer(1)
 					};
-					elems.push(collect_dzn_value(file, &first.value(), element)?);
+					// Every mismatching element is reported, not just the first
+					let mut errors = Vec::new();
+					let mut push = |val: Result<ParserVal, Error>| match val {
+						Ok(v) => elems.push(v),
+						Err(e) => errors.push(e),
+					};
+					push(collect_located_value(file, &first.value(), element));
 					for m in iter {
 						if m.indices().is_some() {
 							return Err(InvalidArrayLiteral {
@@ -243,7 +265,10 @@ pub(crate) fn collect_dzn_value(
 							}
 							.into());
 						}
-						elems.push(collect_dzn_value(file, &m.value(), element)?);
+						push(collect_located_value(file, &m.value(), element));
+					}
+					if !errors.is_empty() {
+						return Err(Error::try_from(errors).unwrap());
 					}
 					let end = if let ParserVal::Integer(v) = &start {
 						ParserVal::Integer(v - 1 + elems.len() as i64)
@@ -261,7 +286,7 @@ pub(crate) fn collect_dzn_value(
 									Expression::TupleLiteral(v) => {
 										let mut i = 0;
 										for (idx, idx_ty) in v.members().zip_eq(dim.iter()) {
-											elems.push(collect_dzn_value(file, &idx, idx_ty)?);
+											elems.push(collect_located_value(file, &idx, idx_ty)?);
 											i += 1;
 										}
 										if i != dim.len() {
@@ -282,7 +307,7 @@ pub(crate) fn collect_dzn_value(
 											}
 											.into());
 										}
-										elems.push(collect_dzn_value(file,  &v, &dim[0])?);
+										elems.push(collect_located_value(file, &v, &dim[0])?);
 									},
 									_ => unreachable!(),
 								}
@@ -294,7 +319,7 @@ pub(crate) fn collect_dzn_value(
 							}
 							.into()),
 						}
-						elems.push(collect_dzn_value(file, &m.value(), element)?);
+						elems.push(collect_located_value(file, &m.value(), element)?);
 					}
 					debug_assert!(elems.len() % (dim.len() + 1) == 0);
 					Ok(ParserVal::IndexedArray(dim.len(), elems))
@@ -314,7 +339,7 @@ pub(crate) fn collect_dzn_value(
 				}
 				let col_indices = al
 					.column_indices()
-					.map(|i| collect_dzn_value(file, &i, &dim[1]))
+					.map(|i| collect_located_value(file, &i, &dim[1]))
 					.collect::<Result<Vec<_>, _>>()?;
 				let mut first = true;
 				let mut col_count = 0;
@@ -324,11 +349,11 @@ pub(crate) fn collect_dzn_value(
 				for row in al.rows() {
 					let members = row
 						.members()
-						.map(|m| collect_dzn_value(file, &m, element))
+						.map(|m| collect_located_value(file, &m, element))
 						.collect::<Result<Vec<_>, _>>()?;
 					let index = row.index();
 					if let Some(ref i) = index {
-						row_indices.push(collect_dzn_value(file, i, &dim[0])?);
+						row_indices.push(collect_located_value(file, i, &dim[0])?);
 					}
 
 					if first {
@@ -527,7 +552,6 @@ impl EnumInner {
 					let name: Arc<str> = c.function().cast::<Identifier>().unwrap().name().into();
 
 					let mut args = Vec::new();
-					let mut len = 0;
 					for arg in c.arguments() {
 						let int_set_ty =
 							Type::Set(OptType::NonOpt, Box::new(Type::Integer(OptType::NonOpt)));
@@ -536,13 +560,9 @@ impl EnumInner {
 						let Value::Set(Set::Int(x)) = val else {
 							unreachable!()
 						};
-						if x.len() != 1 {
-							todo!("handle non-continuous (and empty) integer sets for constructors")
-						}
-						args.push(Index::Integer(x[0].clone()));
-						len += args.last().unwrap().len();
+						args.push(x);
 					}
-					ctors.push((name, args.into_boxed_slice(), len))
+					ctors.push(EnumInner::constructor(name, args))
 				}
 				Expression::InfixOperator(op) => match op.operator().name() {
 					"++" => {
@@ -593,10 +613,12 @@ mod tests {
 	use std::sync::Arc;
 
 	use expect_test::{expect, Expect};
-	use shackle_compiler::file::SourceFile;
+	use itertools::Itertools;
+	use miette::Diagnostic;
+	use shackle_compiler::{file::SourceFile, syntax::ast::AstNode};
 
 	use super::parse_dzn;
-	use crate::{data::dzn::collect_dzn_value, Enum, OptType, Type};
+	use crate::{data::dzn::collect_dzn_value, value::EnumValue, Enum, OptType, Type};
 
 	fn check_serialization(input: &str, ty: &Type, expected: &Expect) {
 		let src = SourceFile::from(Arc::new(format!("x = {input};")));
@@ -1007,4 +1029,177 @@ mod tests {
 			],
 		);
 	}
+
+	/// Define an enumerated type `A` using the given DZN definition
+	fn define_enum(def: &str) -> Arc<Enum> {
+		let a = Arc::new(Enum::from_data("A".into()));
+		let src = SourceFile::from(Arc::new(format!("A = {def};")));
+		let assignments = parse_dzn(&src).expect("unexpected syntax error");
+		a.state
+			.lock()
+			.unwrap()
+			.collect_definition(&src, &assignments[0].definition())
+			.expect("unexpected error defining enum");
+		a
+	}
+
+	fn check_error(input: &str, ty: &Type, expected: &Expect) {
+		let src = SourceFile::from(Arc::new(format!("x = {input};")));
+		let assignments = parse_dzn(&src).expect("unexpected syntax error");
+		let def = assignments[0].definition();
+		let err = collect_dzn_value(&src, &def, ty)
+			.and_then(|val| {
+				val.resolve_value_at(ty, &src, def.cst_node().as_ref().byte_range().into())
+			})
+			.expect_err("expected an error");
+		let label = err.labels().unwrap().next().unwrap();
+		expected.assert_eq(&format!(
+			"{err}: {} at {}..{}",
+			label.label().unwrap(),
+			label.offset(),
+			label.offset() + label.len()
+		));
+	}
+
+	#[test]
+	fn test_parse_enum_constructor_sets() {
+		check_enum_serialization(
+			"X(1..3 union 5..6) ++ {Y}",
+			["X(1)", "X(3)", "X(5)", "Y"],
+			&[
+				expect!("A = X(1..3 ∪ 5..6) ++ {Y}"),
+				expect!("X(1)"),
+				expect!("X(3)"),
+				expect!("X(5)"),
+				expect!("Y"),
+			],
+		);
+		check_enum_serialization(
+			"X({1, 2, 4}, 2..3 ∪ 5..5)",
+			["X(1,2)", "X(1,5)", "X(2,3)", "X(4,5)"],
+			&[
+				expect!("A = X(1..2 ∪ 4..4,2..3 ∪ 5..5)"),
+				expect!("X(1,2)"),
+				expect!("X(1,5)"),
+				expect!("X(2,3)"),
+				expect!("X(4,5)"),
+			],
+		);
+		check_enum_serialization(
+			"X({}) ++ {Y} ++ Z(1..0, 1..3)",
+			["Y"],
+			&[expect!("A = X({}) ++ {Y} ++ Z({},1..3)"), expect!("Y")],
+		);
+
+		// Members are ordered by their arguments
+		let a = define_enum("X(1..2 union 4..4, 1..2 union 5..5)");
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [Type::Integer(OptType::NonOpt)].into(),
+			element: Type::Enum(OptType::NonOpt, a.clone()).into(),
+		};
+		let members = (1..=a.len())
+			.map(|i| EnumValue::from_enum_and_pos(a.clone(), i))
+			.format(", ");
+		check_serialization(
+			&format!("[{members}]"),
+			&ty,
+			&expect!["[X(1,1), X(1,2), X(1,5), X(2,1), X(2,2), X(2,5), X(4,1), X(4,2), X(4,5)]"],
+		);
+	}
+
+	#[test]
+	fn test_parse_enum_constructor_large_sets() {
+		// The argument domains of constructors are not enumerated
+		let a = define_enum("X(1..1000000000 union 2000000000..2000000000, {1, 3})");
+		assert_eq!(a.len(), 2_000_000_002);
+		expect!["A = X(1..1000000000 ∪ 2000000000..2000000000,1..1 ∪ 3..3)"]
+			.assert_eq(&a.to_string());
+		let last = a.member("X", &[2_000_000_000.into(), 3.into()]).unwrap();
+		assert_eq!(last.int_val(), 2_000_000_002);
+		let ty = Type::Enum(OptType::NonOpt, a.clone());
+		check_serialization("X(1000000000, 3)", &ty, &expect!["X(1000000000,3)"]);
+		check_serialization("X(2000000000, 1)", &ty, &expect!["X(2000000000,1)"]);
+	}
+
+	#[test]
+	fn test_parse_indexed_array() {
+		let int = Type::Integer(OptType::NonOpt);
+		let array2d = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [int.clone(), int.clone()].into(),
+			element: int.clone().into(),
+		};
+		check_serialization(
+			"[(2, 1): 3, (1, 2): 2, (1, 1): 1, (2, 2): 4]",
+			&array2d,
+			&expect!("[(1, 1): 1, (1, 2): 2, (2, 1): 3, (2, 2): 4]"),
+		);
+		check_serialization(
+			"[| 3: 4: | 0: 1, 2 | 1: 3, 4 |]",
+			&array2d,
+			&expect!("[(0, 3): 1, (0, 4): 2, (1, 3): 3, (1, 4): 4]"),
+		);
+
+		let a = define_enum("{P, Q} ++ R(1..2)");
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [Type::Enum(OptType::NonOpt, a.clone())].into(),
+			element: int.clone().into(),
+		};
+		check_serialization(
+			"[R(2): 4, P: 1, R(1): 3, Q: 2]",
+			&ty,
+			&expect!("[P: 1, Q: 2, R(1): 3, R(2): 4]"),
+		);
+		check_serialization("[Q: 2, 3]", &ty, &expect!("[Q: 2, R(1): 3]"));
+		check_serialization(
+			"[1, 2, 3, 4]",
+			&ty,
+			&expect!("[P: 1, Q: 2, R(1): 3, R(2): 4]"),
+		);
+	}
+
+	#[test]
+	fn test_resolve_errors() {
+		let a = define_enum("X(1..3 union 5..6) ++ {Y}");
+		let int = Type::Integer(OptType::NonOpt);
+		let e = Type::Enum(OptType::NonOpt, a.clone());
+		check_error(
+			"Z",
+			&e,
+			&expect!["Undefined identifier: Z is undefined at 4..5"],
+		);
+		check_error("X(4)", &e, &expect!["Type mismatch: X(4) is not a member of enumerated type A: the arguments are outside the domain of the constructor at 4..8"]);
+		check_error("X(1, 2)", &e, &expect!["Type mismatch: Constructor X of enumerated type A expects 1 argument(s), but 2 were given at 4..11"]);
+		check_error("Y(1)", &e, &expect!["Type mismatch: Constructor Y of enumerated type A expects 0 argument(s), but 1 were given at 4..8"]);
+
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [e.clone()].into(),
+			element: int.clone().into(),
+		};
+		let members = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [int.clone()].into(),
+			element: e.clone().into(),
+		};
+		check_error("[X(1), X(4)]", &members, &expect!["Type mismatch: X(4) is not a member of enumerated type A: the arguments are outside the domain of the constructor at 11..15"]);
+		check_error("[Y: 1, 2]", &ty, &expect!["Invalid array literal: Array literal cannot start at value Y. There are only 1 values from Y in its enumerated type, but the array literal has 2 members at 4..13"]);
+		check_error("[X(1): 1, X(1): 2]", &ty, &expect!["Invalid array literal: Index X(1) is assigned multiple times in the indexed array literal at 14..18"]);
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [int.clone(), int.clone()].into(),
+			element: int.clone().into(),
+		};
+		check_error("[(1, 1): 1, (2, 2): 2]", &ty, &expect!["Invalid array literal: Indexed array literal does not assign a value to every index in 1..2, 1..2 at 4..26"]);
+		check_error("[(1, 1): 1, (1, 1): 2]", &ty, &expect!["Invalid array literal: Index (1, 1) is assigned multiple times in the indexed array literal at 17..21"]);
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: [int.clone()].into(),
+			element: int.clone().into(),
+		};
+		// Sparse indices are rejected before the array is allocated
+		check_error("[1: 1, 1000000000: 2]", &ty, &expect!["Invalid array literal: Indexed array literal does not assign a value to every index in 1..1000000000 at 4..25"]);
+	}
 }
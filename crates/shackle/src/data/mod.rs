@@ -1,17 +1,511 @@
 //! Functionality related to the input and output of data
 
 pub(crate) mod dzn;
+pub(crate) mod model;
+pub(crate) mod param;
 pub(crate) mod serde;
 
-use std::sync::Arc;
+use std::{
+	cmp::{max, min},
+	ffi::OsStr,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
 
+use ::serde::Deserializer;
 use itertools::Itertools;
+use miette::SourceSpan;
+use rustc_hash::{FxHashMap, FxHashSet};
+use shackle_compiler::{
+	file::{InputLang, SourceFile},
+	syntax::{ast::AstNode, minizinc::Identifier},
+};
 
+use self::{
+	dzn::{collect_dzn_value, parse_dzn},
+	model::define_model_enums,
+	param::{collect_param_value, parse_param},
+	serde::{json_object_members, SerdeFileVisitor},
+};
 use crate::{
-	value::{Array, EnumRangeInclusive, EnumValue, Index, Polarity, Record, Set, Value},
-	Error, OptType, Result, Type,
+	error::{
+		EmptyErrorVec, FileError, IdentifierAlreadyDefined, InvalidArrayLiteral,
+		InvalidDataLanguage, TypeMismatch, UndefinedIdentifier,
+	},
+	value::{Array, EnumInner, EnumRangeInclusive, Index, Polarity, Record, Set, Value},
+	Enum, Error, OptType, Result, Type,
 };
 
+/// Source of data assigning the inputs of a model
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DataSource {
+	/// Data file, whose language is determined by its extension (i.e., `.dzn`,
+	/// `.json`, or `.param`)
+	File(PathBuf),
+	/// Data given as text in the given language
+	///
+	/// The language must be a data input language (i.e.,
+	/// [`InputLang::DataZinc`], [`InputLang::Json`], or
+	/// [`InputLang::EPrimeParam`]). Other languages are reported as an error
+	/// when the data is added.
+	Text(String, InputLang),
+	/// Data given as a source file in the given language
+	///
+	/// This allows data to be given using contents that differ from the file
+	/// on disk (e.g., an unsaved file in an editor), while errors still refer
+	/// to the file.
+	///
+	/// The language must be a data input language, as for [`DataSource::Text`].
+	Source(SourceFile, InputLang),
+}
+
+impl From<PathBuf> for DataSource {
+	fn from(path: PathBuf) -> Self {
+		DataSource::File(path)
+	}
+}
+
+/// Assignment collected from a data file, together with the location of the assigned value
+type CollectedAssignment<'a> = (&'a Arc<str>, &'a Type, ParserVal, (SourceFile, SourceSpan));
+
+/// Collects and checks the assignments made in data files
+///
+/// Errors are collected instead of returned directly, so that all problems in
+/// the data can be reported at once.
+pub(crate) struct DataCollector<'a> {
+	input_types: &'a FxHashMap<Arc<str>, Type>,
+	enum_types: &'a FxHashMap<Arc<str>, Arc<Enum>>,
+	/// Values that were assigned before the collector was created
+	input_data: &'a FxHashMap<Arc<str>, Value>,
+	/// Parsed, but not yet resolved, assignments
+	assignments: Vec<CollectedAssignment<'a>>,
+	/// Identifiers that have been assigned by the collected data
+	assigned: FxHashSet<&'a Arc<str>>,
+	/// Errors encountered while collecting the data
+	errors: Vec<Error>,
+}
+
+impl<'a> DataCollector<'a> {
+	/// Create a collector for data assigning the given inputs and enumerated types
+	pub(crate) fn new(
+		input_types: &'a FxHashMap<Arc<str>, Type>,
+		enum_types: &'a FxHashMap<Arc<str>, Arc<Enum>>,
+		input_data: &'a FxHashMap<Arc<str>, Value>,
+	) -> Self {
+		Self {
+			input_types,
+			enum_types,
+			input_data,
+			assignments: Vec::new(),
+			assigned: FxHashSet::default(),
+			errors: Vec::new(),
+		}
+	}
+
+	/// Read and parse the data from the given source
+	pub(crate) fn add_source(&mut self, data: &DataSource) {
+		match data {
+			DataSource::File(path) => self.add_file(path),
+			DataSource::Text(text, lang) => {
+				self.add_str(&SourceFile::from(Arc::new(text.clone())), *lang)
+			}
+			DataSource::Source(src, lang) => self.add_str(src, *lang),
+		}
+	}
+
+	/// Parse the given source in the given data input language
+	///
+	/// An error is recorded if the language is not a data input language.
+	pub(crate) fn add_str(&mut self, src: &SourceFile, lang: InputLang) {
+		match lang {
+			InputLang::DataZinc => self.add_dzn(src),
+			InputLang::Json => self.add_json(src),
+			InputLang::EPrimeParam => self.add_param(src),
+			_ => self.errors.push(
+				InvalidDataLanguage {
+					lang: format!("{lang:?}"),
+				}
+				.into(),
+			),
+		}
+	}
+
+	/// Read and parse the data file at the given path
+	pub(crate) fn add_file(&mut self, path: &Path) {
+		let src = match SourceFile::try_from(path) {
+			Ok(src) => src,
+			Err(err) => {
+				self.errors.push(err.into());
+				return;
+			}
+		};
+		match path.extension().and_then(OsStr::to_str) {
+			Some("dzn") => self.add_dzn(&src),
+			Some("json") => self.add_json(&src),
+			Some("param") => self.add_param(&src),
+			_ => self.errors.push(
+				FileError {
+					file: path.into(),
+					message: format!(
+						"Attempting to read data file using unknown extension \"{}\"",
+						path.display()
+					),
+					other: vec![],
+				}
+				.into(),
+			),
+		}
+	}
+
+	/// Parse the assignments in the given DataZinc source
+	pub(crate) fn add_dzn(&mut self, src: &SourceFile) {
+		let assignments = match parse_dzn(src) {
+			Ok(assignments) => assignments,
+			Err(err) => {
+				self.errors.push(err);
+				return;
+			}
+		};
+		self.assignments.reserve(assignments.len());
+		for asg in assignments {
+			let ident = asg.assignee().cast::<Identifier>().unwrap();
+			let span = asg.cst_node().as_ref().byte_range().into();
+			if let Some((k, ty)) = self.input_types.get_key_value::<str>(&ident.name()) {
+				// Identifier already seen
+				if !self.assigned.insert(k) || self.input_data.contains_key(k) {
+					self.errors.push(
+						IdentifierAlreadyDefined {
+							src: src.clone(),
+							span,
+							identifier: k.to_string(),
+						}
+						.into(),
+					);
+					continue;
+				}
+				let def = asg.definition();
+				match collect_dzn_value(src, &def, ty) {
+					Ok(val) => self.assignments.push((
+						k,
+						ty,
+						val,
+						(src.clone(), def.cst_node().as_ref().byte_range().into()),
+					)),
+					Err(Error::MultipleErrors(errs)) => self.errors.extend(errs.errors),
+					Err(err) => self.errors.push(err),
+				}
+			} else if let Some((k, e)) = self.enum_types.get_key_value::<str>(&ident.name()) {
+				let mut inner = e.state.lock().unwrap();
+				if matches!(*inner, EnumInner::NoDefinition) {
+					if let Err(err) = inner.collect_definition(src, &asg.definition()) {
+						self.errors.push(err);
+					}
+				} else {
+					self.errors.push(
+						IdentifierAlreadyDefined {
+							src: src.clone(),
+							span,
+							identifier: k.to_string(),
+						}
+						.into(),
+					);
+				}
+			} else {
+				// Unknown identifier
+				self.errors.push(
+					UndefinedIdentifier {
+						src: src.clone(),
+						span: ident.cst_node().as_ref().byte_range().into(),
+						identifier: ident.name().to_string(),
+					}
+					.into(),
+				);
+			}
+		}
+	}
+
+	/// Parse the assignments in the given JSON source
+	pub(crate) fn add_json(&mut self, src: &SourceFile) {
+		let assignments = match ::serde_json::Deserializer::from_str(src.contents())
+			.deserialize_map(SerdeFileVisitor {
+				input_types: self.input_types,
+				enum_types: self.enum_types,
+			})
+			.map_err(|err| Error::from_serde_json(err, src))
+		{
+			Ok(assignments) => assignments,
+			Err(err) => {
+				self.errors.push(err);
+				return;
+			}
+		};
+		// The assignments are deserialized in the order of their keys in the source
+		let mut members = json_object_members(src.contents()).into_iter();
+		self.assignments.reserve(assignments.len());
+		for (k, ty, val) in assignments {
+			let (key_span, value_span) = members
+				.by_ref()
+				.find(|(key, _, _)| key.as_str() == k.as_ref())
+				.map(|(_, key_span, value_span)| (key_span, value_span))
+				.unwrap_or_else(|| ((0, 0).into(), (0, 0).into()));
+			// Identifier already seen
+			if !self.assigned.insert(k) || self.input_data.contains_key(k) {
+				self.errors.push(
+					IdentifierAlreadyDefined {
+						src: src.clone(),
+						span: key_span,
+						identifier: k.to_string(),
+					}
+					.into(),
+				);
+				continue;
+			}
+			self.assignments
+				.push((k, ty, val, (src.clone(), value_span)));
+		}
+	}
+
+	/// Parse the `letting` statements in the given Essence' parameter source
+	pub(crate) fn add_param(&mut self, src: &SourceFile) {
+		let definitions = match parse_param(src) {
+			Ok(definitions) => definitions,
+			Err(err) => {
+				self.errors.push(err);
+				return;
+			}
+		};
+		self.assignments.reserve(definitions.len());
+		for def in definitions {
+			let name = def.name();
+			let span: SourceSpan = name.cst_node().as_ref().byte_range().into();
+			let Some((k, ty)) = self.input_types.get_key_value(name.cst_text()) else {
+				self.errors.push(
+					UndefinedIdentifier {
+						src: src.clone(),
+						span,
+						identifier: name.cst_text().to_string(),
+					}
+					.into(),
+				);
+				continue;
+			};
+			// Identifier already seen
+			if !self.assigned.insert(k) || self.input_data.contains_key(k) {
+				self.errors.push(
+					IdentifierAlreadyDefined {
+						src: src.clone(),
+						span,
+						identifier: k.to_string(),
+					}
+					.into(),
+				);
+				continue;
+			}
+			let val = def.definition();
+			match collect_param_value(src, &val, ty) {
+				Ok(v) => self.assignments.push((
+					k,
+					ty,
+					v,
+					(src.clone(), val.cst_node().as_ref().byte_range().into()),
+				)),
+				Err(err) => self.errors.push(err),
+			}
+		}
+	}
+
+	/// Whether the given input has been assigned by the collected data
+	pub(crate) fn is_assigned(&self, name: &Arc<str>) -> bool {
+		self.assigned.contains(&name)
+	}
+
+	/// Resolve all collected assignments into their final values
+	///
+	/// Returns (all) errors that were encountered while collecting and resolving
+	/// the data.
+	pub(crate) fn resolve(mut self) -> Result<Vec<(&'a Arc<str>, Value)>> {
+		let (values, unresolved) = self.resolve_in_order();
+		for (key, ty, _, (src, span)) in unresolved {
+			let e = undefined_enum(ty).unwrap();
+			self.errors.push(
+				TypeMismatch {
+					src,
+					msg: format!(
+						"Unable to determine the value of {key}: the members of enumerated type {} are not defined",
+						e.name()
+					),
+					span,
+				}
+				.into(),
+			);
+		}
+		match Error::try_from(self.errors) {
+			Ok(err) => Err(err),
+			Err(EmptyErrorVec) => Ok(values.into_iter().collect()),
+		}
+	}
+
+	/// Check the collected data, returning all errors that were encountered
+	///
+	/// Unlike [`Self::resolve`], values of enumerated types that have not been
+	/// defined are not checked.
+	pub(crate) fn check(mut self) -> Vec<Error> {
+		let _ = self.resolve_in_order();
+		self.errors
+	}
+
+	/// Resolve the collected assignments in the order of their dependencies
+	///
+	/// Values of enumerated types defined in the model can only be resolved
+	/// once the members of the enumerated type are known, which in turn depends
+	/// on the values of the inputs used in its definition. The assignments are
+	/// thus resolved in layers of a topological order: first the enumerated
+	/// types whose definitions can be evaluated are defined, and then all
+	/// assignments that do not depend on undefined enumerated types are
+	/// resolved, until no further assignments can be resolved. The assignments
+	/// that remain are returned together with the resolved values.
+	fn resolve_in_order(
+		&mut self,
+	) -> (FxHashMap<&'a Arc<str>, Value>, Vec<CollectedAssignment<'a>>) {
+		let mut values = FxHashMap::default();
+		let mut pending = std::mem::take(&mut self.assignments);
+		loop {
+			define_model_enums(self.enum_types, |name| {
+				let (key, _) = self.input_types.get_key_value(name)?;
+				values.get(key).or_else(|| self.input_data.get(name))
+			});
+			let (ready, waiting): (Vec<_>, Vec<_>) = pending
+				.into_iter()
+				.partition(|(_, ty, _, _)| undefined_enum(ty).is_none());
+			pending = waiting;
+			if ready.is_empty() {
+				break;
+			}
+			for (key, ty, val, (src, span)) in ready {
+				match val.resolve_value_at(ty, &src, span) {
+					Ok(val) => {
+						values.insert(key, val);
+					}
+					Err(err) => self.errors.push(err),
+				}
+			}
+		}
+		(values, pending)
+	}
+}
+
+/// Returns an enumerated type contained in the type whose members are not (yet) known
+fn undefined_enum(ty: &Type) -> Option<&Arc<Enum>> {
+	match ty {
+		Type::Enum(_, e) => {
+			if matches!(*e.state.lock().unwrap(), EnumInner::Constructors(_)) {
+				None
+			} else {
+				Some(e)
+			}
+		}
+		Type::Array { dim, element, .. } => dim
+			.iter()
+			.find_map(undefined_enum)
+			.or_else(|| undefined_enum(element)),
+		Type::Set(_, element) => undefined_enum(element),
+		Type::Tuple(_, members) => members.iter().find_map(undefined_enum),
+		Type::Record(_, members) => members.iter().find_map(|(_, ty)| undefined_enum(ty)),
+		_ => None,
+	}
+}
+
+/// Check whether a value (provided directly by the user) is a valid value of the given type
+///
+/// Returns a message describing the mismatch when the value does not match the type.
+pub(crate) fn check_value(ty: &Type, val: &Value) -> Result<(), String> {
+	let mismatch = || Err(format!("Expected '{}' but found {}", ty, value_kind(val)));
+	match (ty, val) {
+		(_, Value::Absent) if ty.is_opt() => Ok(()),
+		(Type::Integer(_) | Type::Float(_), Value::Infinity(_))
+		| (Type::Boolean(_), Value::Boolean(_))
+		| (Type::Integer(_), Value::Integer(_))
+		| (Type::Float(_), Value::Float(_))
+		| (Type::String(_), Value::String(_))
+		| (Type::Annotation(_), Value::Ann(_, _)) => Ok(()),
+		(Type::Enum(_, e), Value::Enum(v)) if e == &v.enum_type() => Ok(()),
+		(Type::Array { dim, element, .. }, Value::Array(arr)) => {
+			let index_match = |(ty, idx): (&Type, &Index)| match (ty, idx) {
+				(Type::Integer(_), Index::Integer(_)) => true,
+				(Type::Enum(_, e), Index::Enum(r)) => e == &r.enum_type(),
+				_ => false,
+			};
+			if dim.len() != arr.indices.len()
+				|| !dim.iter().zip(arr.indices.iter()).all(index_match)
+			{
+				return mismatch();
+			}
+			let mut card: usize = 1;
+			for idx in arr.indices.iter() {
+				card = idx.checked_len().and_then(|len| {
+					card.checked_mul(len)
+						.ok_or_else(|| "Index sets of the array are too large".to_owned())
+				})?;
+			}
+			if card != arr.members.len() {
+				return Err(format!(
+					"Expected an array with {card} element(s) for index set(s) {}, but found {} element(s)",
+					arr.indices.iter().format(", "),
+					arr.members.len()
+				));
+			}
+			arr.members.iter().try_for_each(|v| check_value(element, v))
+		}
+		(Type::Set(_, element), Value::Set(s)) => match (element.as_ref(), s) {
+			(Type::Integer(_), Set::Int(_)) | (Type::Float(_), Set::Float(_)) => Ok(()),
+			(Type::Enum(_, e), Set::Enum(ranges)) if ranges.iter().all(|r| e == &r.enum_type()) => {
+				Ok(())
+			}
+			_ => mismatch(),
+		},
+		(Type::Tuple(_, members), Value::Tuple(vals)) => {
+			if members.len() != vals.len() {
+				return mismatch();
+			}
+			members
+				.iter()
+				.zip(vals.iter())
+				.try_for_each(|(ty, v)| check_value(ty, v))
+		}
+		(Type::Record(_, fields), Value::Record(rec)) => {
+			if fields.len() != rec.len() {
+				return mismatch();
+			}
+			for (name, ty) in fields.iter() {
+				let Some((_, v)) = rec.iter().find(|(k, _)| k == name) else {
+					return mismatch();
+				};
+				check_value(ty, v)?;
+			}
+			Ok(())
+		}
+		_ => mismatch(),
+	}
+}
+
+/// Describe the kind of the given value for use in error messages
+fn value_kind(val: &Value) -> String {
+	match val {
+		Value::Absent => "absent".to_owned(),
+		Value::Infinity(_) => "infinity".to_owned(),
+		Value::Boolean(_) => "a Boolean value".to_owned(),
+		Value::Integer(_) => "an integer value".to_owned(),
+		Value::Float(_) => "a floating point value".to_owned(),
+		Value::String(_) => "a string value".to_owned(),
+		Value::Enum(v) => format!("a value of enumerated type {}", v.enum_type().name()),
+		Value::Ann(_, _) => "an annotation".to_owned(),
+		Value::Array(arr) => format!("an array with {} dimension(s)", arr.indices.len()),
+		Value::Set(_) => "a set".to_owned(),
+		Value::Tuple(vals) => format!("a tuple of length {}", vals.len()),
+		Value::Record(_) => "a record".to_owned(),
+	}
+}
+
 /// Value parsed in a data file.
 ///
 /// These values can still contain unmatched enum values or enum constructors,
@@ -47,14 +541,36 @@ pub(crate) enum ParserVal {
 	Tuple(Vec<ParserVal>),
 	/// A record of values
 	Record(Vec<(Arc<str>, ParserVal)>),
+	/// A value together with its location in the data file, used to report
+	/// errors at the offending value
+	Located(Box<ParserVal>, SourceSpan),
 }
 
 impl ParserVal {
+	/// Returns the location of the value in the data file, if it is known
+	fn location(&self) -> Option<SourceSpan> {
+		match self {
+			ParserVal::Located(_, span) => Some(*span),
+			_ => None,
+		}
+	}
+
 	/// Resolve parsed data value into final value for users and the interpreter
 	///
 	/// This is the final step in the parsing of data files, resolving enumerated types and creating
 	pub(crate) fn resolve_value(self, ty: &Type) -> Result<Value> {
+		self.resolve_value_at(ty, &SourceFile::introduced("data"), (0, 0).into())
+	}
+
+	/// Resolve parsed data value into final value, reporting errors at the given location
+	pub(crate) fn resolve_value_at(
+		self,
+		ty: &Type,
+		src: &SourceFile,
+		span: SourceSpan,
+	) -> Result<Value> {
 		match self {
+			ParserVal::Located(v, span) => v.resolve_value_at(ty, src, span),
 			ParserVal::Absent => Ok(Value::Absent),
 			ParserVal::Infinity(v) => Ok(Value::Infinity(v)),
 			ParserVal::Boolean(v) => Ok(Value::Boolean(v)),
@@ -63,42 +579,76 @@ impl ParserVal {
 			ParserVal::String(v) => Ok(Value::String(v.into())),
 			ParserVal::Enum(name, args) => {
 				let Type::Enum(_, e) = ty else { unreachable!() };
-				let Some((offset, doms)) = e.get(&name) else {
-					todo!("add location to throw error for undefined constructor")
+				let Some(doms) = e.signature(&name) else {
+					return Err(UndefinedIdentifier {
+						src: src.clone(),
+						span,
+						identifier: name,
+					}
+					.into());
 				};
 				if args.len() != doms.len() {
-					todo!("add error for non-matching constructor call");
+					return Err(TypeMismatch {
+						src: src.clone(),
+						msg: format!(
+							"Constructor {} of enumerated type {} expects {} argument(s), but {} were given",
+							name,
+							e.name(),
+							doms.len(),
+							args.len()
+						),
+						span,
+					}
+					.into());
 				}
-				let mut offset = offset;
-				for (arg, dom) in args.into_iter().zip_eq(doms.iter()) {
-					match dom {
-						Index::Integer(r) => {
-							let Value::Integer(arg) =
-								arg.resolve_value(&Type::Integer(OptType::NonOpt))?
-							else {
-								unreachable!()
-							};
-							if !r.contains(&arg) {
-								todo!("invalid argument - out of domain")
-							}
-							offset += (arg - r.start()) as usize;
-						}
-						Index::Enum(r) => {
-							let Value::Enum(arg) =
-								arg.resolve_value(&Type::Enum(OptType::NonOpt, r.enum_type()))?
+				let args = args
+					.into_iter()
+					.zip_eq(doms.iter())
+					.map(|(arg, dom)| {
+						let ty = match dom {
+							Index::Integer(_) => Type::Integer(OptType::NonOpt),
+							Index::Enum(r) => Type::Enum(OptType::NonOpt, r.enum_type()),
+						};
+						arg.resolve_value_at(&ty, src, span)
+					})
+					.collect::<Result<Vec<_>>>()?;
+				match e.member(&name, &args) {
+					Some(v) => Ok(Value::Enum(v)),
+					None => Err(TypeMismatch {
+						src: src.clone(),
+						msg: format!(
+							"{}({}) is not a member of enumerated type {}: the arguments are outside the domain of the constructor",
+							name,
+							args.iter().format(","),
+							e.name()
+						),
+						span,
+					}
+					.into()),
+				}
+			}
+			ParserVal::Ann(name, args) => {
+				// Annotation arguments are untyped: arrays are resolved as one dimensional
+				// arrays, and all other arguments do not depend on the given type.
+				let args = args
+					.into_iter()
+					.map(|arg| match arg {
+						ParserVal::SimpleArray(_, members) => {
+							let ann = ParserVal::Ann(String::new(), members);
+							let Value::Ann(_, members) = ann.resolve_value_at(ty, src, span)?
 							else {
 								unreachable!()
 							};
-							if !r.contains(&arg) {
-								todo!("invalid argument - out of domain")
-							}
-							offset += r.start().int_val() - arg.int_val();
+							Ok(
+								Array::new(vec![Index::Integer(1..=members.len() as i64)], members)
+									.into(),
+							)
 						}
-					}
-				}
-				Ok(Value::Enum(EnumValue::from_enum_and_pos(e.clone(), offset)))
+						arg => arg.resolve_value_at(ty, src, span),
+					})
+					.collect::<Result<Vec<_>>>()?;
+				Ok(Value::Ann(name.into(), args))
 			}
-			ParserVal::Ann(_, _) => todo!(),
 			ParserVal::SimpleArray(ranges, elements) => {
 				let Type::Array {
 					opt: _,
@@ -110,7 +660,7 @@ impl ParserVal {
 				};
 				let elements = elements
 					.into_iter()
-					.map(|el| el.resolve_value(element))
+					.map(|el| el.resolve_value_at(element, src, span))
 					.collect::<Result<Vec<_>, _>>()?;
 				if elements.is_empty() {
 					return Ok(Array::empty().into());
@@ -118,36 +668,52 @@ impl ParserVal {
 				let indices = ranges
 					.into_iter()
 					.zip_eq(dim.iter())
-					.map(|(range, ty)| match range {
-						(ParserVal::Integer(start), ParserVal::Integer(end)) => {
+					.map(|(range, ty)| match (range, ty) {
+						((ParserVal::Integer(start), ParserVal::Integer(end)), Type::Enum(_, e)) => {
+							// Positions of the members of the enumerated type (used in JSON)
+							if end as usize != e.len() {
+								return Err(InvalidArrayLiteral {
+									src: src.clone(),
+									msg: format!("Array literal has {} members in the dimension indexed by {}, but {} has {} members", end + 1 - start, e.name(), e.name(), e.len()),
+									span,
+								}
+								.into());
+							}
+							Ok(Index::Enum(EnumRangeInclusive::from_enum_and_positions(
+								e.clone(),
+								start as usize,
+								end as usize,
+							)))
+						}
+						((ParserVal::Integer(start), ParserVal::Integer(end)), _) => {
 							Ok::<_, Error>(Index::Integer(start..=end))
 						}
-						(start @ ParserVal::Enum(_, _), ParserVal::Infinity(Polarity::Pos)) => {
+						((start @ ParserVal::Enum(_, _), ParserVal::Infinity(Polarity::Pos)), _) => {
 							debug_assert_eq!(dim.len(), 1);
-							let Value::Enum(start) = start.resolve_value(ty)? else {
+							let Value::Enum(start) = start.resolve_value_at(ty, src, span)? else {
 								unreachable!()
 							};
-							if start.int_val() + elements.len() > start.enum_type().len() {
-								todo!()
-							// Err(InvalidArrayLiteral {
-							// 	msg: format!("Array literal cannot start at value {start}. There are only {} higher values in its enumerated type, but the array literal has {} members", start.enum_type().len() + 1 - start.int_val(), elements.len()),
-							// 	src: todo!(),
-							// 	span: todo!(),
-							// }
-							// .into())
+							let end = start.int_val() + elements.len() - 1;
+							if end > start.enum_type().len() {
+								Err(InvalidArrayLiteral {
+									src: src.clone(),
+									msg: format!("Array literal cannot start at value {start}. There are only {} values from {start} in its enumerated type, but the array literal has {} members", start.enum_type().len() + 1 - start.int_val(), elements.len()),
+									span,
+								}
+								.into())
 							} else {
 								Ok(Index::Enum(EnumRangeInclusive::from_enum_and_positions(
 									start.enum_type(),
 									start.int_val(),
-									start.int_val() + elements.len(),
+									end,
 								)))
 							}
 						}
-						(start @ ParserVal::Enum(_, _), end @ ParserVal::Enum(_, _)) => {
-							let Value::Enum(start) = start.resolve_value(ty)? else {
+						((start @ ParserVal::Enum(_, _), end @ ParserVal::Enum(_, _)), _) => {
+							let Value::Enum(start) = start.resolve_value_at(ty, src, span)? else {
 								unreachable!()
 							};
-							let Value::Enum(end) = end.resolve_value(ty)? else {
+							let Value::Enum(end) = end.resolve_value_at(ty, src, span)? else {
 								unreachable!()
 							};
 							Ok(Index::Enum((start, end).into()))
@@ -157,12 +723,111 @@ impl ParserVal {
 					.collect::<Result<Vec<_>, _>>()?;
 				Ok(Array::new(indices, elements).into())
 			}
-			ParserVal::IndexedArray(_, _) => todo!(),
+			ParserVal::IndexedArray(n, elems) => {
+				let Type::Array {
+					opt: _,
+					dim,
+					element,
+				} = ty
+				else {
+					unreachable!()
+				};
+				debug_assert_eq!(n, dim.len());
+				// Resolve the indices and value of each member
+				let mut entries = Vec::with_capacity(elems.len() / (n + 1));
+				let mut elems = elems.into_iter();
+				while elems.len() > 0 {
+					let idx = (0..n).map(|_| elems.next().unwrap()).collect::<Vec<_>>();
+					// Errors about the member are reported at its index
+					let entry_span = idx
+						.iter()
+						.filter_map(ParserVal::location)
+						.reduce(|a, b| {
+							let start = min(a.offset(), b.offset());
+							let end = max(a.offset() + a.len(), b.offset() + b.len());
+							(start..end).into()
+						})
+						.unwrap_or(span);
+					let idx = idx
+						.into_iter()
+						.zip(dim.iter())
+						.map(|(i, ty)| i.resolve_value_at(ty, src, span))
+						.collect::<Result<Vec<_>>>()?;
+					let val = elems.next().unwrap().resolve_value_at(element, src, span)?;
+					entries.push((idx, val, entry_span));
+				}
+				if entries.is_empty() {
+					return Ok(Array::empty().into());
+				}
+				// The index sets range from the smallest to the largest index used in each dimension
+				let indices = dim
+					.iter()
+					.enumerate()
+					.map(|(i, ty)| {
+						let (min, max) = entries
+							.iter()
+							.map(|(idx, _, _)| index_pos(&idx[i]))
+							.minmax()
+							.into_option()
+							.unwrap();
+						match ty {
+							Type::Enum(_, e) => {
+								Index::Enum(EnumRangeInclusive::from_enum_and_positions(
+									e.clone(),
+									min as usize,
+									max as usize,
+								))
+							}
+							_ => Index::Integer(min..=max),
+						}
+					})
+					.collect::<Vec<_>>();
+				// Every index must be assigned (exactly once), so the literal must have at least
+				// as many members as the index sets, which bounds the size of the array
+				let card = match indices.iter().try_fold(1_usize, |card, index| {
+					card.checked_mul(index.checked_len().ok()?)
+				}) {
+					Some(card) if card <= entries.len() => card,
+					_ => {
+						return Err(InvalidArrayLiteral {
+							src: src.clone(),
+							msg: format!(
+								"Indexed array literal does not assign a value to every index in {}",
+								indices.iter().format(", ")
+							),
+							span,
+						}
+						.into())
+					}
+				};
+				// Place the members in their (row-major) position
+				let mut members = vec![None; card];
+				for (idx, val, entry_span) in entries {
+					let pos = idx.iter().zip(indices.iter()).fold(0, |pos, (v, index)| {
+						pos * index.len() + (index_pos(v) - index_pos(&index.start())) as usize
+					});
+					if members[pos].is_some() {
+						return Err(InvalidArrayLiteral {
+							src: src.clone(),
+							msg: format!(
+								"Index {} is assigned multiple times in the indexed array literal",
+								format_index(&idx)
+							),
+							span: entry_span,
+						}
+						.into());
+					}
+					members[pos] = Some(val);
+				}
+				// All positions are filled: there are no duplicates, and as many members as positions
+				let members = members.into_iter().map(Option::unwrap).collect();
+				Ok(Array::new(indices, members).into())
+			}
 			ParserVal::SetList(li) => {
 				let Type::Set(_, ty) = ty else { unreachable!() };
 				let members = li
 					.into_iter()
-					.map(|m| m.resolve_value(ty))
+					.map(|m| m.resolve_value_at(ty, src, span))
 					.collect::<Result<Vec<_>, _>>()?;
 				// TODO: This could likely be optimised to not create ranges first
 				match **ty {
@@ -215,8 +880,8 @@ impl ParserVal {
 					.into(),
 					e @ Type::Enum(OptType::NonOpt, _) => Set::from_iter(
 						li.into_iter()
-							.map(|(a, b)| match a.resolve_value(e) {
-								Ok(a) => match b.resolve_value(e) {
+							.map(|(a, b)| match a.resolve_value_at(e, src, span) {
+								Ok(a) => match b.resolve_value_at(e, src, span) {
 									Ok(b) => {
 										let (Value::Enum(a), Value::Enum(b)) = (a, b) else {
 											unreachable!("invalid enum set")
@@ -237,10 +902,11 @@ impl ParserVal {
 				(ParserVal::Float(start), ParserVal::Float(end)) => (start..=end).into(),
 				(ParserVal::Integer(start), ParserVal::Integer(end)) => (start..=end).into(),
 				(from @ ParserVal::Enum(_, _), to @ ParserVal::Enum(_, _)) => {
-					let Value::Enum(a) = from.resolve_value(ty)? else {
+					let Type::Set(_, ty) = ty else { unreachable!() };
+					let Value::Enum(a) = from.resolve_value_at(ty, src, span)? else {
 						unreachable!()
 					};
-					let Value::Enum(b) = to.resolve_value(ty)? else {
+					let Value::Enum(b) = to.resolve_value_at(ty, src, span)? else {
 						unreachable!()
 					};
 					EnumRangeInclusive::new(a, b).into()
@@ -254,7 +920,7 @@ impl ParserVal {
 				let members = v
 					.into_iter()
 					.zip_eq(ty.iter())
-					.map(|(m, ty)| m.resolve_value(ty))
+					.map(|(m, ty)| m.resolve_value_at(ty, src, span))
 					.collect::<Result<Vec<_>, _>>()?;
 				Ok(Value::Tuple(members))
 			}
@@ -267,7 +933,7 @@ impl ParserVal {
 					.zip_eq(ty.iter())
 					.map(|((n, v), (name, ty))| {
 						debug_assert_eq!(&n, name);
-						Ok((name.clone(), v.resolve_value(ty)?))
+						Ok((name.clone(), v.resolve_value_at(ty, src, span)?))
 					})
 					.collect::<Result<Record>>()?;
 				Ok(Value::Record(rec))
@@ -275,3 +941,21 @@ impl ParserVal {
 		}
 	}
 }
+
+/// Position of an (integer or enumerated) index value, used to place members of indexed array literals
+fn index_pos(v: &Value) -> i64 {
+	match v {
+		Value::Integer(i) => *i,
+		Value::Enum(e) => e.int_val() as i64,
+		_ => unreachable!("invalid index value"),
+	}
+}
+
+/// Format the index of a member of an indexed array literal
+fn format_index(idx: &[Value]) -> String {
+	if idx.len() == 1 {
+		idx[0].to_string()
+	} else {
+		format!("({})", idx.iter().format(", "))
+	}
+}
@@ -0,0 +1,273 @@
+//! Evaluation of enumerated types whose definition in the model depends on data
+//!
+//! Enumerated types can be defined in the model in terms of the inputs of the
+//! model (e.g., `enum Task = T(1..n)`). The members of these enumerated types
+//! can only be determined once the values of these inputs are known, but they
+//! are required to resolve data values of the enumerated types.
+
+use std::{ops::RangeInclusive, sync::Arc};
+
+use rustc_hash::FxHashMap;
+use shackle_compiler::{
+	db::InternedString,
+	thir::{
+		self, db::Thir, Callable, DomainData, ExpressionData, FunctionName, ResolvedIdentifier,
+	},
+};
+
+use crate::{
+	value::{Constructor, EnumInner, EnumRangeInclusive, Index, Set},
+	Enum, Value,
+};
+
+/// Definition of an enumerated type given in the model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EnumDefinition(Box<[ConstructorDefinition]>);
+
+/// Name of a constructor and the (possibly empty) list of expressions that
+/// define the domains of its arguments
+type ConstructorDefinition = (Arc<str>, Box<[DefinitionExpr]>);
+
+/// Expression that can be used to define the argument domain of a constructor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DefinitionExpr {
+	/// Integer literal
+	Integer(i64),
+	/// Input of the model (provided by the data)
+	Input(Arc<str>),
+	/// All members of an enumerated type
+	Enum(Arc<str>),
+	/// Set literal
+	Set(Box<[DefinitionExpr]>),
+	/// Integer range `a..b`
+	Range(Box<DefinitionExpr>, Box<DefinitionExpr>),
+	/// Integer negation `-a`
+	Neg(Box<DefinitionExpr>),
+	/// Integer addition `a + b`
+	Add(Box<DefinitionExpr>, Box<DefinitionExpr>),
+	/// Integer subtraction `a - b`
+	Sub(Box<DefinitionExpr>, Box<DefinitionExpr>),
+	/// Integer multiplication `a * b`
+	Mul(Box<DefinitionExpr>, Box<DefinitionExpr>),
+}
+
+/// Result of evaluating a [`DefinitionExpr`]
+enum EvalResult {
+	Integer(i64),
+	/// Set of integers, or set of positions of members of an enumerated type
+	Set(Option<Arc<Enum>>, Vec<RangeInclusive<i64>>),
+}
+
+impl EnumDefinition {
+	/// Convert the constructors of an enumerated type defined in the model
+	///
+	/// Returns [`None`] when the definition uses expressions that cannot be
+	/// evaluated using only the data.
+	pub(crate) fn from_compiler<S: FnMut(InternedString) -> Arc<str>>(
+		db: &dyn Thir,
+		model: &thir::Model,
+		str_interner: &mut S,
+		constructors: &[thir::Constructor],
+	) -> Option<Self> {
+		let mut ctors = Vec::with_capacity(constructors.len());
+		for ctor in constructors {
+			let name = match ctor.name {
+				Some(ident) => str_interner(ident.0),
+				None => Arc::from("_"),
+			};
+			let mut args = Vec::new();
+			for param in ctor.parameters.iter().flatten() {
+				let DomainData::Bounded(dom) = &**model[*param].domain() else {
+					return None;
+				};
+				args.push(DefinitionExpr::from_compiler(db, model, str_interner, dom)?);
+			}
+			ctors.push((name, args.into_boxed_slice()));
+		}
+		Some(Self(ctors.into_boxed_slice()))
+	}
+
+	/// Evaluate the definition, creating the constructors of the enumerated type
+	///
+	/// Returns [`None`] when not all inputs and enumerated types used in the
+	/// definition are available (or when their values cannot be used to define
+	/// the enumerated type).
+	fn evaluate<'v>(
+		&self,
+		enums: &FxHashMap<Arc<str>, Arc<Enum>>,
+		inputs: &impl Fn(&str) -> Option<&'v Value>,
+	) -> Option<Box<[Constructor]>> {
+		let mut ctors = Vec::new();
+		for (name, args) in self.0.iter() {
+			let mut doms = Vec::with_capacity(args.len());
+			let mut enum_args = Vec::with_capacity(args.len());
+			for arg in args.iter() {
+				let EvalResult::Set(e, dom) = arg.evaluate(enums, inputs)? else {
+					return None;
+				};
+				doms.push(dom);
+				enum_args.push(e);
+			}
+			// Domains of arguments of enumerated types are given using the positions of their members
+			let (name, args, len) = EnumInner::constructor(name.clone(), doms);
+			let args = args
+				.into_vec()
+				.into_iter()
+				.zip(enum_args.iter())
+				.map(|(dom, e)| match e {
+					Some(e) => dom
+						.into_iter()
+						.map(|idx| {
+							let Index::Integer(r) = idx else {
+								unreachable!()
+							};
+							Index::Enum(EnumRangeInclusive::from_enum_and_positions(
+								e.clone(),
+								*r.start() as usize,
+								*r.end() as usize,
+							))
+						})
+						.collect(),
+					None => dom,
+				})
+				.collect();
+			ctors.push((name, args, len));
+		}
+		Some(ctors.into_boxed_slice())
+	}
+}
+
+impl DefinitionExpr {
+	/// Convert an expression used in the definition of an enumerated type
+	///
+	/// Declarations that have a definition in the model are replaced by their
+	/// definition. Returns [`None`] when the expression cannot be represented.
+	fn from_compiler<S: FnMut(InternedString) -> Arc<str>>(
+		db: &dyn Thir,
+		model: &thir::Model,
+		str_interner: &mut S,
+		expr: &thir::Expression,
+	) -> Option<Self> {
+		let mut convert = |e: &thir::Expression| -> Option<Box<Self>> {
+			Self::from_compiler(db, model, str_interner, e).map(Box::new)
+		};
+		match &**expr {
+			ExpressionData::IntegerLiteral(i) => Some(DefinitionExpr::Integer(i.0)),
+			ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => {
+				let decl = &model[*d];
+				match decl.definition() {
+					Some(def) => convert(def).map(|def| *def),
+					None if decl.top_level() => {
+						Some(DefinitionExpr::Input(str_interner(decl.name()?.0)))
+					}
+					None => None,
+				}
+			}
+			ExpressionData::Identifier(ResolvedIdentifier::Enumeration(e)) => Some(
+				DefinitionExpr::Enum(str_interner(model[*e].enum_type().name(db.upcast()))),
+			),
+			ExpressionData::SetLiteral(sl) => {
+				let mut members = Vec::with_capacity(sl.0.len());
+				for member in sl.0.iter() {
+					members.push(*convert(member)?);
+				}
+				Some(DefinitionExpr::Set(members.into_boxed_slice()))
+			}
+			ExpressionData::Call(c) => {
+				let Callable::Function(f) = c.function else {
+					return None;
+				};
+				let reg = db.identifier_registry();
+				let name = model[f].name();
+				match c.arguments.as_slice() {
+					[a] if name == FunctionName::new(reg.minus) => {
+						Some(DefinitionExpr::Neg(convert(a)?))
+					}
+					[a, b] if name == FunctionName::new(reg.dot_dot) => {
+						Some(DefinitionExpr::Range(convert(a)?, convert(b)?))
+					}
+					[a, b] if name == FunctionName::new(reg.plus) => {
+						Some(DefinitionExpr::Add(convert(a)?, convert(b)?))
+					}
+					[a, b] if name == FunctionName::new(reg.minus) => {
+						Some(DefinitionExpr::Sub(convert(a)?, convert(b)?))
+					}
+					[a, b] if name == FunctionName::new(reg.times) => {
+						Some(DefinitionExpr::Mul(convert(a)?, convert(b)?))
+					}
+					_ => None,
+				}
+			}
+			_ => None,
+		}
+	}
+
+	/// Evaluate the expression, returning [`None`] when the required inputs or
+	/// enumerated types are not (yet) available
+	fn evaluate<'v>(
+		&self,
+		enums: &FxHashMap<Arc<str>, Arc<Enum>>,
+		inputs: &impl Fn(&str) -> Option<&'v Value>,
+	) -> Option<EvalResult> {
+		let int = |e: &DefinitionExpr| match e.evaluate(enums, inputs)? {
+			EvalResult::Integer(i) => Some(i),
+			EvalResult::Set(_, _) => None,
+		};
+		Some(match self {
+			DefinitionExpr::Integer(i) => EvalResult::Integer(*i),
+			DefinitionExpr::Input(name) => match inputs(name)? {
+				Value::Integer(i) => EvalResult::Integer(*i),
+				Value::Set(Set::Int(ranges)) => EvalResult::Set(None, ranges.clone()),
+				_ => return None,
+			},
+			DefinitionExpr::Enum(name) => {
+				let e = enums.get(name)?;
+				if !matches!(*e.state.lock().unwrap(), EnumInner::Constructors(_)) {
+					return None;
+				}
+				let len = e.len() as i64;
+				EvalResult::Set(Some(e.clone()), vec![1..=len])
+			}
+			DefinitionExpr::Set(members) => {
+				let mut ranges = Vec::with_capacity(members.len());
+				for m in members.iter() {
+					let i = int(m)?;
+					ranges.push(i..=i);
+				}
+				EvalResult::Set(None, ranges)
+			}
+			DefinitionExpr::Range(a, b) => EvalResult::Set(None, vec![int(a)?..=int(b)?]),
+			DefinitionExpr::Neg(a) => EvalResult::Integer(int(a)?.checked_neg()?),
+			DefinitionExpr::Add(a, b) => EvalResult::Integer(int(a)?.checked_add(int(b)?)?),
+			DefinitionExpr::Sub(a, b) => EvalResult::Integer(int(a)?.checked_sub(int(b)?)?),
+			DefinitionExpr::Mul(a, b) => EvalResult::Integer(int(a)?.checked_mul(int(b)?)?),
+		})
+	}
+}
+
+/// Define the members of the enumerated types defined in the model for which
+/// all inputs used in the definition are available
+///
+/// Enumerated types can be defined in terms of other enumerated types, so
+/// definitions are evaluated until no further enumerated types can be defined.
+pub(crate) fn define_model_enums<'v>(
+	enums: &FxHashMap<Arc<str>, Arc<Enum>>,
+	inputs: impl Fn(&str) -> Option<&'v Value>,
+) {
+	loop {
+		let mut progress = false;
+		for e in enums.values() {
+			let def = match &*e.state.lock().unwrap() {
+				EnumInner::AwaitData(Some(def)) => def.clone(),
+				_ => continue,
+			};
+			if let Some(ctors) = def.evaluate(enums, &inputs) {
+				*e.state.lock().unwrap() = EnumInner::Constructors(ctors);
+				progress = true;
+			}
+		}
+		if !progress {
+			break;
+		}
+	}
+}
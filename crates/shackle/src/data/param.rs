@@ -0,0 +1,259 @@
+//! # The Essence' parameter file parser
+//!
+//! This module contains a parser for Essence' parameter (i.e., `.param`)
+//! files. These files provide the values of the parameters of Essence' models
+//! using `letting` statements.
+
+use shackle_compiler::{
+	diagnostics::{Error, InvalidArrayLiteral, InvalidNumericLiteral, SyntaxError, TypeMismatch},
+	file::SourceFile,
+	syntax::{
+		ast::{AstNode, Children},
+		cst::Cst,
+		eprime::{ConstDefinition, Domain, Expression, Item, MatrixLiteral},
+	},
+};
+use tree_sitter::Parser;
+
+use crate::{data::ParserVal, OptType, Type};
+
+/// Parses an Essence' parameter file, returning the `letting` statements that
+/// define the parameters.
+pub(crate) fn parse_param(src: &SourceFile) -> Result<Vec<ConstDefinition>, Error> {
+	let mut parser = Parser::new();
+	parser
+		.set_language(&tree_sitter_eprime::language())
+		.expect("Failed to set Tree Sitter parser language");
+	let tree = parser
+		.parse(src.contents().as_bytes(), None)
+		.expect("Essence' Tree Sitter parser did not return tree object");
+
+	let cst = Cst::from_str(tree, src.contents());
+	cst.error(|_| src.clone())?; // Check for any syntax errors
+
+	let root = cst.node(cst.root_node());
+	let mut definitions = Vec::new();
+	for item in Children::<Item>::from_cst(&root, "item") {
+		match item {
+			Item::ConstDefinition(def) => definitions.push(def),
+			_ => {
+				return Err(SyntaxError {
+					src: src.clone(),
+					msg: "Essence' parameter files can only contain letting statements".to_string(),
+					span: item.cst_node().as_ref().byte_range().into(),
+					other: Vec::new(),
+				}
+				.into())
+			}
+		}
+	}
+	Ok(definitions)
+}
+
+/// Convert an Essence' AST expression into a internal value of the given type
+pub(crate) fn collect_param_value(
+	file: &SourceFile,
+	val: &Expression,
+	ty: &Type,
+) -> Result<ParserVal, Error> {
+	let type_err = |val_kind| {
+		Err(TypeMismatch {
+			src: file.clone(),
+			msg: format!("Expected '{}' but found {}", ty, val_kind),
+			span: val.cst_node().as_ref().byte_range().into(),
+		}
+		.into())
+	};
+
+	match val {
+		Expression::IntegerLiteral(_) => match ty {
+			Type::Integer(_) => Ok(ParserVal::Integer(integer_value(file, val, false)?)),
+			_ => type_err("an integer literal"),
+		},
+		Expression::PrefixOperator(op) if op.operator().name() == "-" => match (ty, op.operand()) {
+			(Type::Integer(_), Expression::IntegerLiteral(_)) => Ok(ParserVal::Integer(
+				integer_value(file, &op.operand(), true)?,
+			)),
+			_ => type_err("a negation"),
+		},
+		Expression::BooleanLiteral(b) => match ty {
+			Type::Boolean(_) => Ok(ParserVal::Boolean(b.value())),
+			_ => type_err("a Boolean literal"),
+		},
+		Expression::MatrixLiteral(ml) => match ty {
+			Type::Array { dim, element, .. } => {
+				let mut ranges = vec![None; dim.len()];
+				let mut elems = Vec::new();
+				collect_matrix(file, ml, 0, element, &mut ranges, &mut elems)?;
+				// Inner dimensions of an empty matrix literal are empty
+				let ranges = ranges
+					.into_iter()
+					.map(|r| {
+						let (start, end) = r.unwrap_or((1, 0));
+						(ParserVal::Integer(start), ParserVal::Integer(end))
+					})
+					.collect();
+				Ok(ParserVal::SimpleArray(ranges, elems))
+			}
+			_ => type_err("a matrix literal"),
+		},
+		_ => type_err("an expression that is not a literal"),
+	}
+}
+
+/// Parse the value of an integer literal, which is negated when `neg` is set
+fn integer_value(file: &SourceFile, val: &Expression, neg: bool) -> Result<i64, Error> {
+	let text = val.cst_text();
+	let v = if neg {
+		format!("-{text}").parse()
+	} else {
+		text.parse()
+	};
+	v.map_err(|e: std::num::ParseIntError| {
+		InvalidNumericLiteral {
+			src: file.clone(),
+			span: val.cst_node().as_ref().byte_range().into(),
+			msg: e.to_string(),
+		}
+		.into()
+	})
+}
+
+/// Collect the members of a (nested) matrix literal in row-major order
+///
+/// The index range of each dimension is stored in `ranges`, which ensures all
+/// matrix literals of the same dimension have the same index range.
+fn collect_matrix(
+	file: &SourceFile,
+	ml: &MatrixLiteral,
+	depth: usize,
+	element: &Type,
+	ranges: &mut [Option<(i64, i64)>],
+	elems: &mut Vec<ParserVal>,
+) -> Result<(), Error> {
+	let invalid = |msg: &str| {
+		Err(InvalidArrayLiteral {
+			src: file.clone(),
+			msg: msg.to_owned(),
+			span: ml.cst_node().as_ref().byte_range().into(),
+		}
+		.into())
+	};
+	let start = match ml.index() {
+		None => 1,
+		Some(Domain::IntegerDomain(d)) => match d.domain().collect::<Vec<_>>().as_slice() {
+			[Expression::SetConstructor(r)] if r.operator().name() == ".." => {
+				let int_ty = Type::Integer(OptType::NonOpt);
+				let ParserVal::Integer(start) = collect_param_value(file, &r.left(), &int_ty)?
+				else {
+					unreachable!()
+				};
+				start
+			}
+			_ => return invalid("The index set of a matrix literal must be an integer range"),
+		},
+		Some(_) => return invalid("The index set of a matrix literal must be an integer range"),
+	};
+	let members: Vec<Expression> = ml.members().collect();
+	let range = (start, start + members.len() as i64 - 1);
+	match ranges[depth] {
+		None => ranges[depth] = Some(range),
+		Some(r) if r != range => return invalid("Non-uniform matrix literal"),
+		Some(_) => {}
+	}
+	for m in members {
+		if depth + 1 == ranges.len() {
+			elems.push(collect_param_value(file, &m, element)?);
+		} else if let Expression::MatrixLiteral(inner) = m {
+			collect_matrix(file, &inner, depth + 1, element, ranges, elems)?;
+		} else {
+			return invalid(&format!(
+				"Expected a matrix literal with {} dimension(s)",
+				ranges.len() - depth - 1
+			));
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use expect_test::{expect, Expect};
+	use shackle_compiler::file::SourceFile;
+
+	use super::{collect_param_value, parse_param};
+	use crate::{OptType, Type};
+
+	fn check_param(input: &str, ty: &Type, expected: Expect) {
+		let src = SourceFile::from(Arc::new(format!("letting x be {input}")));
+		let res = parse_param(&src).and_then(|defs| {
+			assert_eq!(defs.len(), 1);
+			collect_param_value(&src, &defs[0].definition(), ty)
+		});
+		let res = match res {
+			Ok(val) => match val.resolve_value(ty) {
+				Ok(val) => val.to_string(),
+				Err(err) => err.to_string(),
+			},
+			Err(err) => match err {
+				crate::Error::TypeMismatch(err) => err.msg,
+				crate::Error::InvalidArrayLiteral(err) => err.msg,
+				err => err.to_string(),
+			},
+		};
+		expected.assert_eq(&res);
+	}
+
+	#[test]
+	fn test_parse_param_values() {
+		let int = Type::Integer(OptType::NonOpt);
+		let array = |dims: usize| Type::Array {
+			opt: OptType::NonOpt,
+			dim: vec![int.clone(); dims].into(),
+			element: Box::new(int.clone()),
+		};
+		check_param("-3", &int, expect!["-3"]);
+		check_param("false", &Type::Boolean(OptType::NonOpt), expect!["false"]);
+		check_param("[4, 5, 6; int(0..2)]", &array(1), expect!["[0: 4, 5, 6]"]);
+		check_param(
+			"[[1, 2, 3], [4, 5, 6]]",
+			&array(2),
+			expect!["[(1, 1): 1, (1, 2): 2, (1, 3): 3, (2, 1): 4, (2, 2): 5, (2, 3): 6]"],
+		);
+		check_param("[]", &array(2), expect!["[]"]);
+
+		check_param(
+			"true",
+			&int,
+			expect!["Expected 'int' but found a Boolean literal"],
+		);
+		check_param(
+			"[[1, 2], [3]]",
+			&array(2),
+			expect!["Non-uniform matrix literal"],
+		);
+		check_param(
+			"[1, 2]",
+			&array(2),
+			expect!["Expected a matrix literal with 1 dimension(s)"],
+		);
+		check_param(
+			"n + 1",
+			&int,
+			expect!["Expected 'int' but found an expression that is not a literal"],
+		);
+	}
+
+	#[test]
+	fn test_parse_param_items() {
+		let src = SourceFile::from(Arc::new(
+			"language ESSENCE' 1.0\nletting n = 1\ngiven m: int".to_owned(),
+		));
+		let crate::Error::SyntaxError(err) = parse_param(&src).unwrap_err() else {
+			panic!("expected a syntax error")
+		};
+		expect!["Essence' parameter files can only contain letting statements"].assert_eq(&err.msg);
+	}
+}
@@ -1,19 +1,30 @@
-use std::sync::Arc;
+use std::{borrow::Cow, fmt::Display, sync::Arc};
 
 use itertools::Itertools;
+use miette::SourceSpan;
 use rustc_hash::FxHashMap;
 use serde::{
-	de::{DeserializeSeed, Error, IgnoredAny, Unexpected, Visitor},
+	de::{
+		value::{MapDeserializer, SeqDeserializer},
+		DeserializeSeed, EnumAccess, Error, IgnoredAny, IntoDeserializer, Unexpected,
+		VariantAccess, Visitor,
+	},
+	forward_to_deserialize_any,
 	ser::{SerializeMap, SerializeSeq},
-	Deserialize, Serialize,
+	Deserialize, Deserializer, Serialize,
 };
 
 use super::ParserVal;
 use crate::{
-	value::{Array, Constructor, EnumInner, EnumValue, Index, Record, Set},
+	value::{Array, Constructor, EnumInner, EnumValue, Index, Polarity, Record, Set},
 	Enum, OptType, Type, Value,
 };
 
+/// String used to represent positive infinity in JSON
+const INFINITY: &str = "infinity";
+/// String used to represent negative infinity in JSON
+const NEG_INFINITY: &str = "-infinity";
+
 #[derive(Clone)]
 pub(crate) struct SerdeValueVisitor<'a>(pub &'a Type);
 
@@ -71,6 +82,12 @@ impl<'de, 'a> Visitor<'de> for SerdeValueVisitor<'a> {
 			Type::Enum(_, _) => Ok(ParserVal::Enum(v, Vec::new())),
 			Type::String(_) => Ok(ParserVal::String(v)),
 			Type::Annotation(_) => Ok(ParserVal::Ann(v, Vec::new())),
+			Type::Integer(_) | Type::Float(_) if v == INFINITY => {
+				Ok(ParserVal::Infinity(Polarity::Pos))
+			}
+			Type::Integer(_) | Type::Float(_) if v == NEG_INFINITY => {
+				Ok(ParserVal::Infinity(Polarity::Neg))
+			}
 			_ => Err(Error::invalid_type(Unexpected::Str(v.as_str()), &self)),
 		}
 	}
@@ -111,7 +128,7 @@ impl<'de, 'a> Visitor<'de> for SerdeValueVisitor<'a> {
 				dim,
 				element,
 			} => {
-				let mut sizes = Vec::with_capacity(dim.len());
+				let mut sizes = vec![None; dim.len()];
 				let mut data = Vec::new();
 				let visitor = SerdeArrayVisitor {
 					data: &mut data,
@@ -124,14 +141,15 @@ impl<'de, 'a> Visitor<'de> for SerdeValueVisitor<'a> {
 				if data.is_empty() {
 					return Ok(ParserVal::SimpleArray(Vec::new(), Vec::new()));
 				}
-				debug_assert_eq!(dim.len(), sizes.len());
-				let mut indices = Vec::with_capacity(sizes.capacity());
-				for (ty, len) in dim.iter().zip_eq(sizes.into_iter()) {
+				let mut indices = Vec::with_capacity(sizes.len());
+				for (ty, len) in dim.iter().zip_eq(sizes) {
+					// Note: all dimensions have been visited when the array contains members
+					let len = len.unwrap();
 					match ty {
-						Type::Integer(OptType::NonOpt) => {
+						// Note: enumerated types are resolved to the positions of their members
+						Type::Integer(OptType::NonOpt) | Type::Enum(_, _) => {
 							indices.push((ParserVal::Integer(1), ParserVal::Integer(len)))
 						}
-						Type::Enum(_, _) => todo!(),
 						_ => unreachable!("invalid index type"),
 					}
 				}
@@ -217,6 +235,7 @@ impl<'de, 'a> Visitor<'de> for SerdeValueVisitor<'a> {
 				))
 			}
 			Type::Enum(_, _) => SerdeEnumVisitor.visit_map(map),
+			Type::Annotation(_) => SerdeAnnVisitor.visit_map(map),
 			_ => Err(Error::invalid_type(Unexpected::Map, &self)),
 		}
 	}
@@ -302,9 +321,112 @@ impl<'de> Visitor<'de> for SerdeEnumVisitor {
 	}
 }
 
+/// Visitor for annotation terms and their arguments
+///
+/// Annotations are represented in the same way as in FlatZinc JSON: atoms are
+/// strings, and calls are objects of the form `{"id": <name>, "args": [...]}`.
+/// The arguments of calls can be Booleans, numbers, strings (of the form
+/// `{"string": <value>}`), annotations, or lists of these values.
+#[derive(Clone)]
+struct SerdeAnnVisitor;
+
+impl<'de> DeserializeSeed<'de> for SerdeAnnVisitor {
+	type Value = ParserVal;
+
+	fn deserialize<D: serde::Deserializer<'de>>(
+		self,
+		deserializer: D,
+	) -> Result<Self::Value, D::Error> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'de> Visitor<'de> for SerdeAnnVisitor {
+	type Value = ParserVal;
+
+	fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(formatter, "an annotation or annotation argument")
+	}
+
+	fn visit_bool<E: Error>(self, v: bool) -> Result<Self::Value, E> {
+		Ok(ParserVal::Boolean(v))
+	}
+	fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+		Ok(ParserVal::Integer(v))
+	}
+	fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+		match v.try_into() {
+			Ok(x) => self.visit_i64(x),
+			Err(e) => Err(Error::custom(e.to_string())),
+		}
+	}
+	fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+		Ok(ParserVal::Float(v))
+	}
+
+	fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+		Ok(ParserVal::Ann(v, Vec::new()))
+	}
+	fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+		Ok(ParserVal::Ann(v.into(), Vec::new()))
+	}
+
+	fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut members = Vec::new();
+		while let Some(m) = seq.next_element_seed(self.clone())? {
+			members.push(m);
+		}
+		Ok(ParserVal::SimpleArray(
+			vec![(
+				ParserVal::Integer(1),
+				ParserVal::Integer(members.len() as i64),
+			)],
+			members,
+		))
+	}
+
+	fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+		const FIELDS: &[&str] = &["id", "args", "string"];
+		let mut id = None;
+		let mut args = None;
+		let mut string = None;
+
+		while let Some(k) = map.next_key::<&str>()? {
+			match k {
+				"id" => {
+					if id.is_some() {
+						return Err(Error::duplicate_field("id"));
+					}
+					id = Some(map.next_value::<String>()?);
+				}
+				"args" => {
+					if args.is_some() {
+						return Err(Error::duplicate_field("args"));
+					}
+					args = Some(map.next_value_seed(SerdeSeqVisitor(self.clone()))?);
+				}
+				"string" => {
+					if string.is_some() {
+						return Err(Error::duplicate_field("string"));
+					}
+					string = Some(map.next_value::<String>()?);
+				}
+				_ => return Err(Error::unknown_field(k, FIELDS)),
+			}
+		}
+
+		match (id, args, string) {
+			(None, None, Some(s)) => Ok(ParserVal::String(s)),
+			(Some(id), args, None) => Ok(ParserVal::Ann(id, args.unwrap_or_default())),
+			(None, _, None) => Err(Error::missing_field("id")),
+			(_, _, Some(_)) => Err(Error::unknown_field("string", &["id", "args"])),
+		}
+	}
+}
+
 struct SerdeArrayVisitor<'a> {
 	data: &'a mut Vec<ParserVal>,
-	size: &'a mut Vec<i64>,
+	size: &'a mut Vec<Option<i64>>,
 	element: &'a Type,
 	dim: u8,
 	depth: u8,
@@ -353,14 +475,13 @@ impl<'de, 'a> Visitor<'de> for SerdeArrayVisitor<'a> {
 				i += 1
 			}
 		}
-		if self.size.len() < self.depth as usize {
-			debug_assert_eq!(self.size.len() + 1, self.depth as usize);
-			self.size.push(i);
-		} else if self.size[self.depth as usize - 1] != i {
-			return Err(Error::invalid_length(
-				i as usize,
-				&self.size[self.depth as usize - 1].to_string().as_str(),
-			));
+		// Note: inner dimensions are completed before the outer dimensions
+		match self.size[self.depth as usize - 1] {
+			None => self.size[self.depth as usize - 1] = Some(i),
+			Some(len) if len != i => {
+				return Err(Error::invalid_length(i as usize, &len.to_string().as_str()));
+			}
+			Some(_) => {}
 		}
 		Ok(())
 	}
@@ -388,7 +509,7 @@ impl<'de> Deserialize<'de> for EnumInner {
 				self,
 				mut map: A,
 			) -> Result<Self::Value, A::Error> {
-				const FIELDS: &[&str] = &["k", "a"];
+				const FIELDS: &[&str] = &["c", "a"];
 				let mut name: Option<&str> = None;
 				let mut args = Vec::new();
 				while let Some(k) = map.next_key()? {
@@ -409,28 +530,21 @@ impl<'de> Deserialize<'de> for EnumInner {
 							};
 							args = x
 								.members
-								.iter()
+								.into_vec()
+								.into_iter()
 								.map(|i| {
 									let Value::Set(Set::Int(s)) = i else {
 										unreachable!()
 									};
-									if s.len() != 1 {
-										todo!("handle non-continuous (and empty) integer sets for constructors")
-									}
-									Index::Integer(s[0].clone())
+									s
 								})
 								.collect();
 						}
 						_ => return Err(Error::unknown_field(k, FIELDS)),
 					}
 				}
-				let count = if args.is_empty() {
-					1
-				} else {
-					args.iter().map(|x| x.len()).sum()
-				};
 				if let Some(name) = name {
-					Ok((name.into(), args.into_boxed_slice(), count))
+					Ok(EnumInner::constructor(name.into(), args))
 				} else {
 					Err(Error::missing_field("c"))
 				}
@@ -495,7 +609,9 @@ impl<'de, 'a> Visitor<'de> for SerdeFileVisitor<'a> {
 				if *inner == EnumInner::NoDefinition {
 					*inner = map.next_value::<EnumInner>()?;
 				} else {
-					todo!("create custom serde error that will be converted into Error")
+					return Err(Error::custom(format!(
+						"enumerated type {k} has already been defined"
+					)));
 				}
 			} else {
 				map.next_value::<IgnoredAny>()?; // Ignore unknown
@@ -505,6 +621,75 @@ impl<'de, 'a> Visitor<'de> for SerdeFileVisitor<'a> {
 	}
 }
 
+/// Locate the members of the top-level object in a JSON document
+///
+/// Returns, in order, the (unescaped) key of every member together with the
+/// byte ranges of the key and of its value. The document is assumed to be
+/// valid JSON (i.e., it has already been deserialized successfully).
+pub(crate) fn json_object_members(text: &str) -> Vec<(String, SourceSpan, SourceSpan)> {
+	let bytes = text.as_bytes();
+	let skip_ws = |mut i: usize| {
+		while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+			i += 1;
+		}
+		i
+	};
+	// Returns the position directly after the string starting at `i`
+	let skip_str = |mut i: usize| {
+		i += 1;
+		while i < bytes.len() && bytes[i] != b'"' {
+			i += if bytes[i] == b'\\' { 2 } else { 1 };
+		}
+		i + 1
+	};
+
+	let mut members = Vec::new();
+	let mut i = skip_ws(0);
+	if bytes.get(i) != Some(&b'{') {
+		return members;
+	}
+	i += 1;
+	loop {
+		i = skip_ws(i);
+		if bytes.get(i) != Some(&b'"') {
+			break;
+		}
+		let key_start = i;
+		i = skip_str(i);
+		let key = serde_json::from_str::<String>(&text[key_start..i]).unwrap_or_default();
+		let key_span = (key_start..i).into();
+		i = skip_ws(i) + 1; // ':'
+		let value_start = skip_ws(i);
+		let mut value_end = value_start;
+		let mut depth = 0;
+		i = value_start;
+		while i < bytes.len() {
+			match bytes[i] {
+				b'"' => {
+					i = skip_str(i);
+					value_end = i;
+					continue;
+				}
+				b'[' | b'{' => depth += 1,
+				b']' | b'}' if depth == 0 => break,
+				b']' | b'}' => depth -= 1,
+				b',' if depth == 0 => break,
+				_ => {}
+			}
+			i += 1;
+			if !bytes[i - 1].is_ascii_whitespace() {
+				value_end = i;
+			}
+		}
+		members.push((key, key_span, (value_start..value_end).into()));
+		if bytes.get(i) != Some(&b',') {
+			break;
+		}
+		i += 1;
+	}
+	members
+}
+
 struct SerdeSeqVisitor<X: Clone>(X);
 impl<'de, X: DeserializeSeed<'de> + Clone> DeserializeSeed<'de> for SerdeSeqVisitor<X> {
 	type Value = Vec<X::Value>;
@@ -677,13 +862,14 @@ impl Serialize for Value {
 	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
 		match self {
 			Value::Absent => serializer.serialize_none(),
-			Value::Infinity(_) => todo!(),
+			Value::Infinity(Polarity::Pos) => serializer.serialize_str(INFINITY),
+			Value::Infinity(Polarity::Neg) => serializer.serialize_str(NEG_INFINITY),
 			Value::Boolean(v) => serializer.serialize_bool(*v),
 			Value::Integer(v) => serializer.serialize_i64(*v),
 			Value::Float(v) => serializer.serialize_f64(*v),
 			Value::String(v) => serializer.serialize_str(v),
 			Value::Enum(v) => v.serialize(serializer),
-			Value::Ann(_, _) => todo!(),
+			Value::Ann(_, _) => AnnArgSerializer(self).serialize(serializer),
 			Value::Array(v) => v.serialize(serializer),
 			Value::Set(v) => v.serialize(serializer),
 			Value::Tuple(v) => {
@@ -747,7 +933,7 @@ impl Serialize for Index {
 	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
 		match self {
 			Index::Integer(r) => Set::Int(vec![r.clone()]).serialize(serializer),
-			Index::Enum(_) => todo!(),
+			Index::Enum(r) => Set::Enum(vec![r.clone()]).serialize(serializer),
 		}
 	}
 }
@@ -755,7 +941,7 @@ impl Serialize for Enum {
 	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
 		struct Ctor<'a> {
 			c: &'a str,
-			a: &'a [Index],
+			a: &'a [Set],
 		}
 		impl<'a> Serialize for Ctor<'a> {
 			fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -771,19 +957,39 @@ impl Serialize for Enum {
 
 		let mut map = serializer.serialize_map(Some(1))?;
 		map.serialize_key(&**self.name())?;
-		let lock = self.lock();
-		let v: Vec<Ctor> = lock
+		let def = self.lock().definition();
+		let v: Vec<Ctor> = def
 			.iter()
-			.map(|ctor| Ctor {
-				c: &ctor.0,
-				a: &ctor.1,
-			})
+			.map(|(name, doms)| Ctor { c: name, a: doms })
 			.collect();
 		map.serialize_value(&v)?;
 		map.end()
 	}
 }
 
+/// Serializer for annotations and their arguments, using the FlatZinc JSON representation
+struct AnnArgSerializer<'a>(&'a Value);
+impl<'a> Serialize for AnnArgSerializer<'a> {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self.0 {
+			Value::Ann(name, args) if args.is_empty() => serializer.serialize_str(name),
+			Value::Ann(name, args) => {
+				let mut map = serializer.serialize_map(Some(2))?;
+				map.serialize_entry("id", &**name)?;
+				map.serialize_entry("args", &args.iter().map(AnnArgSerializer).collect_vec())?;
+				map.end()
+			}
+			Value::String(v) => {
+				let mut map = serializer.serialize_map(Some(1))?;
+				map.serialize_entry("string", &**v)?;
+				map.end()
+			}
+			Value::Array(v) => serializer.collect_seq(v.members.iter().map(AnnArgSerializer)),
+			v => v.serialize(serializer),
+		}
+	}
+}
+
 struct ArraySliceSerializer<'a> {
 	indices: &'a [Index],
 	members: &'a [Value],
@@ -827,17 +1033,312 @@ impl<'a> Serialize for ArraySliceSerializer<'a> {
 	}
 }
 
+/// Error that occurs when a [`Value`] cannot be deserialized into the requested Rust type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializeError {
+	/// The error message
+	pub msg: String,
+	/// The name of the output which could not be deserialized (if known)
+	pub name: Option<Arc<str>>,
+}
+
+impl Display for DeserializeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.name {
+			Some(name) => write!(f, "{}: {}", name, self.msg),
+			None => write!(f, "{}", self.msg),
+		}
+	}
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl Error for DeserializeError {
+	fn custom<T: Display>(msg: T) -> Self {
+		Self {
+			msg: msg.to_string(),
+			name: None,
+		}
+	}
+}
+
+/// Deserializer that converts a [`Value`] into any Rust type implementing [`Deserialize`]
+///
+/// - Absent values are deserialized as `None`.
+/// - Values of enumerated types (and annotations) are deserialized as Rust
+///   enumerations using their constructor names, or as strings when they have
+///   no arguments.
+/// - Arrays are deserialized as (nested) sequences, one level for each dimension.
+/// - Sets are deserialized as sequences of their members.
+/// - Records are deserialized as maps (or structs) from field names to values.
+#[derive(Clone, Copy)]
+pub(crate) struct ValueDeserializer<'a>(pub(crate) &'a Value);
+
+impl<'de, 'a> Deserializer<'de> for ValueDeserializer<'a> {
+	type Error = DeserializeError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		match self.0 {
+			Value::Absent => visitor.visit_none(),
+			Value::Infinity(Polarity::Pos) => visitor.visit_f64(f64::INFINITY),
+			Value::Infinity(Polarity::Neg) => visitor.visit_f64(f64::NEG_INFINITY),
+			Value::Boolean(v) => visitor.visit_bool(*v),
+			Value::Integer(v) => visitor.visit_i64(*v),
+			Value::Float(v) => visitor.visit_f64(*v),
+			Value::String(v) => visitor.visit_str(v),
+			Value::Enum(v) => {
+				let (name, args) = v.constructor_and_args();
+				if args.is_empty() {
+					visitor.visit_str(&name)
+				} else {
+					visitor.visit_enum(EnumDeserializer {
+						name: name.to_string(),
+						args: args.into(),
+					})
+				}
+			}
+			Value::Ann(name, args) => {
+				if args.is_empty() {
+					visitor.visit_str(name)
+				} else {
+					visitor.visit_enum(EnumDeserializer {
+						name: name.to_string(),
+						args: args.into(),
+					})
+				}
+			}
+			Value::Array(v) => {
+				if v.is_empty() {
+					visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<Self>()))
+				} else {
+					ArraySliceDeserializer {
+						indices: &v.indices,
+						members: &v.members,
+					}
+					.deserialize_any(visitor)
+				}
+			}
+			Value::Set(v) => {
+				// Note: sets are expanded to the list of their members
+				let members: Vec<Value> = match v {
+					Set::Enum(ranges) => ranges
+						.iter()
+						.flat_map(|r| r.clone().map(Value::Enum))
+						.collect(),
+					Set::Int(ranges) => ranges
+						.iter()
+						.flat_map(|r| r.clone().map(Value::Integer))
+						.collect(),
+					Set::Float(ranges) => {
+						if ranges.iter().any(|r| r.start() != r.end()) {
+							return Err(DeserializeError::custom(
+								"cannot deserialize a float set containing (non-singleton) ranges",
+							));
+						}
+						ranges.iter().map(|r| Value::Float(*r.start())).collect()
+					}
+				};
+				visit_seq(visitor, members.iter().map(ValueDeserializer))
+			}
+			Value::Tuple(v) => visit_seq(visitor, v.iter().map(ValueDeserializer)),
+			Value::Record(v) => {
+				let mut map = MapDeserializer::new(
+					v.iter().map(|(k, v)| (k.to_string(), ValueDeserializer(v))),
+				);
+				let value = visitor.visit_map(&mut map)?;
+				map.end()?;
+				Ok(value)
+			}
+		}
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		match self.0 {
+			Value::Absent => visitor.visit_none(),
+			_ => visitor.visit_some(self),
+		}
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value, Self::Error> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		_variants: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, Self::Error> {
+		match self.0 {
+			Value::Enum(v) => {
+				let (name, args) = v.constructor_and_args();
+				visitor.visit_enum(EnumDeserializer {
+					name: name.to_string(),
+					args: args.into(),
+				})
+			}
+			Value::Ann(name, args) => visitor.visit_enum(EnumDeserializer {
+				name: name.to_string(),
+				args: args.into(),
+			}),
+			Value::String(name) => visitor.visit_enum(EnumDeserializer {
+				name: name.to_string(),
+				args: Cow::Borrowed(&[]),
+			}),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+		identifier ignored_any
+	}
+}
+
+impl<'de, 'a> IntoDeserializer<'de, DeserializeError> for ValueDeserializer<'a> {
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self::Deserializer {
+		self
+	}
+}
+
+/// Deserializer for (a slice of) a multi-dimensional array as nested sequences
+struct ArraySliceDeserializer<'a> {
+	indices: &'a [Index],
+	members: &'a [Value],
+}
+
+impl<'de, 'a> Deserializer<'de> for ArraySliceDeserializer<'a> {
+	type Error = DeserializeError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		debug_assert!(!self.indices.is_empty());
+		if self.indices.len() <= 1 {
+			visit_seq(visitor, self.members.iter().map(ValueDeserializer))
+		} else {
+			let step = self.members.len() / self.indices[0].len();
+			visit_seq(
+				visitor,
+				self.members
+					.chunks(step)
+					.map(|members| ArraySliceDeserializer {
+						indices: &self.indices[1..],
+						members,
+					}),
+			)
+		}
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
+
+impl<'de, 'a> IntoDeserializer<'de, DeserializeError> for ArraySliceDeserializer<'a> {
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self::Deserializer {
+		self
+	}
+}
+
+/// Visit the given deserializers as a sequence
+fn visit_seq<'de, V: Visitor<'de>, D: IntoDeserializer<'de, DeserializeError>>(
+	visitor: V,
+	iter: impl Iterator<Item = D>,
+) -> Result<V::Value, DeserializeError> {
+	let mut seq = SeqDeserializer::new(iter);
+	let value = visitor.visit_seq(&mut seq)?;
+	seq.end()?;
+	Ok(value)
+}
+
+/// Access to a constructor (and its arguments) when deserializing into a Rust enumeration
+struct EnumDeserializer<'a> {
+	name: String,
+	args: Cow<'a, [Value]>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for EnumDeserializer<'a> {
+	type Error = DeserializeError;
+	type Variant = Self;
+
+	fn variant_seed<V: DeserializeSeed<'de>>(
+		self,
+		seed: V,
+	) -> Result<(V::Value, Self::Variant), Self::Error> {
+		let variant = seed.deserialize(self.name.as_str().into_deserializer())?;
+		Ok((variant, self))
+	}
+}
+
+impl<'de, 'a> VariantAccess<'de> for EnumDeserializer<'a> {
+	type Error = DeserializeError;
+
+	fn unit_variant(self) -> Result<(), Self::Error> {
+		if self.args.is_empty() {
+			Ok(())
+		} else {
+			Err(DeserializeError::invalid_type(
+				Unexpected::TupleVariant,
+				&"unit variant",
+			))
+		}
+	}
+
+	fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+		self,
+		seed: T,
+	) -> Result<T::Value, Self::Error> {
+		match &self.args[..] {
+			[arg] => seed.deserialize(ValueDeserializer(arg)),
+			_ => Err(DeserializeError::invalid_length(
+				self.args.len(),
+				&"a constructor with a single argument",
+			)),
+		}
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(
+		self,
+		_len: usize,
+		visitor: V,
+	) -> Result<V::Value, Self::Error> {
+		visit_seq(visitor, self.args.iter().map(ValueDeserializer))
+	}
+
+	fn struct_variant<V: Visitor<'de>>(
+		self,
+		_fields: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, Self::Error> {
+		visit_seq(visitor, self.args.iter().map(ValueDeserializer))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use std::sync::Arc;
 
 	use expect_test::{expect, Expect};
+	use itertools::Itertools;
 	use rustc_hash::FxHashMap;
 	use serde::Deserializer;
 	use shackle_compiler::file::SourceFile;
 
 	use super::SerdeFileVisitor;
-	use crate::{Enum, Error, OptType, Type};
+	use crate::{
+		value::{Array, EnumInner, EnumRangeInclusive, EnumValue, Index, Polarity, Record, Set},
+		Enum, Error, OptType, Type, Value,
+	};
 
 	fn check_serialization(input: &str, ty: &Type, expected: &Expect) {
 		let input_types = FxHashMap::from_iter([("x".into(), ty.clone())]);
@@ -1273,5 +1774,285 @@ mod tests {
 				expect!("Z(3)"),
 			],
 		);
+
+		check_enum_serialization(
+			r#"[{"c": "X", "a": [{"set": [[1,3],[5,6]]}]}, {"c": "Y"}, {"c": "Z", "a": [{"set": []}]}]"#,
+			[
+				r#"{"e": "X", "a": [3]}"#,
+				r#"{"e": "X", "a": [5]}"#,
+				r#""Y""#,
+			],
+			&[
+				expect!("A = X(1..3 ∪ 5..6) ++ {Y} ++ Z({})"),
+				expect!("X(3)"),
+				expect!("X(5)"),
+				expect!("Y"),
+			],
+		);
+	}
+
+	/// Enumerated type `A = X(1..2, 1..3) ++ {Y, Z}` used in tests
+	fn test_enum() -> Arc<Enum> {
+		let e = Arc::new(Enum::from_data("A".into()));
+		*e.state.lock().unwrap() = EnumInner::Constructors(
+			[
+				(
+					"X".into(),
+					vec![vec![Index::Integer(1..=2)], vec![Index::Integer(1..=3)]].into(),
+					6,
+				),
+				("Y".into(), Box::default(), 1),
+				("Z".into(), Box::default(), 1),
+			]
+			.into(),
+		);
+		e
+	}
+
+	#[test]
+	fn test_parse_infinity() {
+		check_serialization(
+			r#""infinity""#,
+			&Type::Integer(OptType::NonOpt),
+			&expect!("∞"),
+		);
+		check_serialization(
+			r#""-infinity""#,
+			&Type::Float(OptType::NonOpt),
+			&expect!("-∞"),
+		);
+	}
+
+	#[test]
+	fn test_parse_annotation() {
+		let ty = Type::Annotation(OptType::NonOpt);
+		check_serialization(r#""output""#, &ty, &expect!("output"));
+		check_serialization(
+			r#"{"id": "int_search", "args": [[1, 2], "input_order", {"string": "x"}, 1.5, true]}"#,
+			&ty,
+			&expect![[r#"int_search([1, 2], input_order, "x", 1.5, true)"#]],
+		);
+	}
+
+	#[test]
+	fn test_parse_enum_values() {
+		let a = test_enum();
+		let ty = Type::Enum(OptType::NonOpt, a.clone());
+		check_serialization(r#""Y""#, &ty, &expect!("Y"));
+		check_serialization(r#"{"e": "X", "a": [2, 1]}"#, &ty, &expect!("X(2,1)"));
+		check_serialization(
+			r#"{"set": [[{"e": "X", "a": [1, 3]}, {"e": "Y"}]]}"#,
+			&Type::Set(OptType::NonOpt, Box::new(ty)),
+			&expect!("X(1,3)..Y"),
+		);
+		check_serialization(
+			"[[1, 2], [3, 4], [5, 6], [7, 8], [9, 10], [11, 12], [13, 14], [15, 16]]",
+			&Type::Array {
+				opt: OptType::NonOpt,
+				dim: Box::new([
+					Type::Enum(OptType::NonOpt, a),
+					Type::Integer(OptType::NonOpt),
+				]),
+				element: Box::new(Type::Integer(OptType::NonOpt)),
+			},
+			&expect!["[(X(1,1), 1): 1, (X(1,1), 2): 2, (X(1,2), 1): 3, (X(1,2), 2): 4, (X(1,3), 1): 5, (X(1,3), 2): 6, (X(2,1), 1): 7, (X(2,1), 2): 8, (X(2,2), 1): 9, (X(2,2), 2): 10, (X(2,3), 1): 11, (X(2,3), 2): 12, (Y, 1): 13, (Y, 2): 14, (Z, 1): 15, (Z, 2): 16]"],
+		);
+	}
+
+	/// Simple (xorshift) pseudo-random number generator used to generate test cases
+	struct Rng(u64);
+
+	impl Rng {
+		fn below(&mut self, n: u64) -> u64 {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			self.0 % n
+		}
+
+		fn int(&mut self) -> i64 {
+			self.below(21) as i64 - 10
+		}
+	}
+
+	/// Generate a random type, nested up to the given depth
+	fn random_type(rng: &mut Rng, a: &Arc<Enum>, depth: u8, allow_array: bool) -> Type {
+		let opt = if rng.below(4) == 0 {
+			OptType::Opt
+		} else {
+			OptType::NonOpt
+		};
+		let scalar = |rng: &mut Rng| match rng.below(3) {
+			0 => Type::Integer(OptType::NonOpt),
+			1 => Type::Float(OptType::NonOpt),
+			_ => Type::Enum(OptType::NonOpt, a.clone()),
+		};
+		match rng.below(if depth == 0 { 6 } else { 10 }) {
+			0 => Type::Boolean(opt),
+			1 => Type::Integer(opt),
+			2 => Type::Float(opt),
+			3 => Type::Enum(opt, a.clone()),
+			4 => Type::String(opt),
+			5 => Type::Annotation(opt),
+			6 if allow_array => Type::Array {
+				opt: OptType::NonOpt,
+				dim: (0..=rng.below(2))
+					.map(|_| match scalar(rng) {
+						Type::Float(_) => Type::Integer(OptType::NonOpt),
+						ty => ty,
+					})
+					.collect(),
+				element: Box::new(random_type(rng, a, depth - 1, false)),
+			},
+			6 | 7 => Type::Set(OptType::NonOpt, Box::new(scalar(rng))),
+			8 => Type::Tuple(
+				OptType::NonOpt,
+				(0..=rng.below(3))
+					.map(|_| random_type(rng, a, depth - 1, true))
+					.collect(),
+			),
+			_ => Type::Record(
+				OptType::NonOpt,
+				["a", "b", "c"][..=rng.below(3) as usize]
+					.iter()
+					.map(|&f| (f.into(), random_type(rng, a, depth - 1, true)))
+					.collect(),
+			),
+		}
+	}
+
+	/// Generate a random value of the given type
+	fn random_value(rng: &mut Rng, ty: &Type) -> Value {
+		if ty.is_opt() && rng.below(4) == 0 {
+			return Value::Absent;
+		}
+		let polarity = |rng: &mut Rng| {
+			if rng.below(2) == 0 {
+				Polarity::Pos
+			} else {
+				Polarity::Neg
+			}
+		};
+		let ann = |rng: &mut Rng| match rng.below(3) {
+			0 => Value::Ann("output".into(), Vec::new()),
+			1 => Value::Ann(
+				"int_search".into(),
+				vec![
+					Array::new(vec![Index::Integer(1..=2)], vec![1.into(), 2.into()]).into(),
+					Value::Ann("input_order".into(), Vec::new()),
+					Value::String("x".into()),
+				],
+			),
+			_ => Value::Ann("restart_luby".into(), vec![rng.int().into()]),
+		};
+		match ty {
+			Type::Boolean(_) => (rng.below(2) == 0).into(),
+			Type::Integer(_) if rng.below(8) == 0 => Value::Infinity(polarity(rng)),
+			Type::Integer(_) => rng.int().into(),
+			Type::Float(_) if rng.below(8) == 0 => Value::Infinity(polarity(rng)),
+			Type::Float(_) => (rng.int() as f64 * 0.25).into(),
+			Type::Enum(_, e) => {
+				EnumValue::from_enum_and_pos(e.clone(), 1 + rng.below(e.len() as u64) as usize)
+					.into()
+			}
+			Type::String(_) => Value::String(
+				["", "abc", "quote \" and \\ slash", "ünï©ødé\n"][rng.below(4) as usize].into(),
+			),
+			Type::Annotation(_) => ann(rng),
+			Type::Array { dim, element, .. } => {
+				let indices = dim
+					.iter()
+					.map(|d| match d {
+						Type::Enum(_, e) => Index::Enum(
+							EnumRangeInclusive::from_enum_and_positions(e.clone(), 1, e.len()),
+						),
+						_ => Index::Integer(1..=1 + rng.below(3) as i64),
+					})
+					.collect::<Vec<_>>();
+				let len = indices.iter().map(|i| i.len()).product();
+				let members = (0..len).map(|_| random_value(rng, element)).collect();
+				Array::new(indices, members).into()
+			}
+			Type::Set(_, element) => {
+				let n = rng.below(4);
+				match **element {
+					Type::Integer(_) => Set::from_iter((0..n).map(|_| {
+						let start = rng.int();
+						start..=start + rng.below(3) as i64
+					})),
+					Type::Float(_) => Set::from_iter((0..n).map(|_| {
+						let start = rng.int() as f64 * 0.5;
+						start..=start + rng.below(3) as f64 * 0.25
+					})),
+					Type::Enum(_, ref e) => Set::from_iter((0..n).map(|_| {
+						let start = 1 + rng.below(e.len() as u64) as usize;
+						let end = start + rng.below((e.len() - start + 1) as u64) as usize;
+						EnumRangeInclusive::from_enum_and_positions(e.clone(), start, end)
+					})),
+					_ => unreachable!(),
+				}
+				.into()
+			}
+			Type::Tuple(_, members) => {
+				Value::Tuple(members.iter().map(|ty| random_value(rng, ty)).collect())
+			}
+			Type::Record(_, fields) => Record::from_iter(
+				fields
+					.iter()
+					.map(|(name, ty)| (name.clone(), random_value(rng, ty))),
+			)
+			.into(),
+		}
+	}
+
+	#[test]
+	fn test_serialization_round_trip() {
+		let a = test_enum();
+		let mut rng = Rng(0x5eed_cafe_f00d_b00c);
+		for _ in 0..500 {
+			let ty = random_type(&mut rng, &a, 3, true);
+			let val = random_value(&mut rng, &ty);
+			let json = serde_json::to_string(&val).expect("unexpected serialization error");
+
+			let input_types = FxHashMap::from_iter([("x".into(), ty.clone())]);
+			let enum_types = FxHashMap::default();
+			let src = SourceFile::from(Arc::new(format!("{{ \"x\": {json} }}")));
+			let assignments = serde_json::Deserializer::from_str(src.contents())
+				.deserialize_map(SerdeFileVisitor {
+					input_types: &input_types,
+					enum_types: &enum_types,
+				})
+				.map_err(|err| Error::from_serde_json(err, &src))
+				.unwrap_or_else(|err| panic!("unable to parse {json} as {ty}: {err:?}"));
+			assert_eq!(assignments.len(), 1);
+			let val2 = assignments[0]
+				.2
+				.clone()
+				.resolve_value(&ty)
+				.unwrap_or_else(|err| panic!("unable to resolve {json} as {ty}: {err:?}"));
+			assert_eq!(val, val2, "value of type {ty} serialized as {json}");
+		}
+	}
+
+	#[test]
+	fn test_json_object_members() {
+		let text = r#"{
+	"x": [1, {"a": "}"}, 3] ,
+	"y\u0021": "a,b",
+	"z":{}
+}"#;
+		let members = super::json_object_members(text)
+			.into_iter()
+			.map(|(key, key_span, value_span)| {
+				let key_text = &text[key_span.offset()..key_span.offset() + key_span.len()];
+				let value_text = &text[value_span.offset()..value_span.offset() + value_span.len()];
+				format!("{key} ({key_text}): {value_text}")
+			})
+			.join("\n");
+		expect![[r#"
+    x ("x"): [1, {"a": "}"}, 3]
+    y! ("y\u0021"): "a,b"
+    z ("z"): {}"#]]
+		.assert_eq(&members);
 	}
 }
@@ -0,0 +1,328 @@
+//! Implementations of builtin functions for fixed arguments (E-Call-Builtin)
+
+use std::{cmp::Ordering, ops::RangeInclusive, rc::Rc};
+
+use super::{ArrayVal, Val};
+use crate::value::{self, Polarity, Set};
+
+/// Evaluate the builtin function with the given name
+///
+/// Returns `None` if there is no implementation for the given arguments, in
+/// which case the call is a native constraint.
+pub(super) fn call_builtin(name: &str, args: &[Val]) -> Option<Result<Val, String>> {
+	use Val::*;
+	let result = match (name, args) {
+		// Array functions that only need the structure of the array to be fixed
+		("[]", [array, index]) => {
+			if !index.is_par() {
+				return Some(Err(
+					"array access with a variable index is not supported".to_owned()
+				));
+			}
+			array_access(array, index)
+		}
+		("mzn_array_access_valid", [array, index]) if index.is_par() => {
+			Ok(Bool(array_access(array, index).is_ok()))
+		}
+		("length", [Array(a)]) => Ok(Int(a.members.len() as i64)),
+		("index_set", [Array(a)]) if a.index_sets.len() == 1 => Ok(int_range(&a.index_sets[0])),
+		("++", [Array(a), Array(b)]) => Ok(Val::array(
+			a.members.iter().chain(b.members.iter()).cloned().collect(),
+		)),
+		("array1d", [Array(a)]) => Ok(Val::array(a.members.clone())),
+		("is_fixed", [x]) => Ok(Bool(x.is_par())),
+		("fix", [x]) if !x.is_par() => {
+			Err("unable to fix the value of a decision variable".to_owned())
+		}
+		("fix", [x]) => Ok(x.clone()),
+		// Conjunctions with a fixed side (e.g. definedness conditions)
+		("/\\", [Bool(false), _] | [_, Bool(false)]) => Ok(Bool(false)),
+		("/\\", [Bool(true), x] | [x, Bool(true)]) => Ok(x.clone()),
+		_ if !args.iter().all(Val::is_par) => return None,
+
+		// Arithmetic
+		("+", [Int(a), Int(b)]) => a.checked_add(*b).map(Int).ok_or_else(overflow),
+		("-", [Int(a), Int(b)]) => a.checked_sub(*b).map(Int).ok_or_else(overflow),
+		("*", [Int(a), Int(b)]) => a.checked_mul(*b).map(Int).ok_or_else(overflow),
+		("-", [Int(a)]) => a.checked_neg().map(Int).ok_or_else(overflow),
+		("div", [Int(_), Int(0)]) | ("mod", [Int(_), Int(0)]) => Err("division by zero".to_owned()),
+		("div", [Int(a), Int(b)]) => a.checked_div(*b).map(Int).ok_or_else(overflow),
+		("mod", [Int(a), Int(b)]) => a.checked_rem(*b).map(Int).ok_or_else(overflow),
+		("abs", [Int(a)]) => a.checked_abs().map(Int).ok_or_else(overflow),
+		("pow", [Int(_), Int(b)]) if *b < 0 => Err("negative exponent".to_owned()),
+		("pow", [Int(a), Int(b)]) => u32::try_from(*b)
+			.ok()
+			.and_then(|b| a.checked_pow(b))
+			.map(Int)
+			.ok_or_else(overflow),
+		("+", [Float(a), Float(b)]) => Ok(Float(a + b)),
+		("-", [Float(a), Float(b)]) => Ok(Float(a - b)),
+		("*", [Float(a), Float(b)]) => Ok(Float(a * b)),
+		("/", [Float(_), Float(b)]) if *b == 0.0 => Err("division by zero".to_owned()),
+		("/", [Float(a), Float(b)]) => Ok(Float(a / b)),
+		("-", [Float(a)]) => Ok(Float(-a)),
+		("abs", [Float(a)]) => Ok(Float(a.abs())),
+		("-", [Infinity(Polarity::Pos)]) => Ok(Infinity(Polarity::Neg)),
+		("-", [Infinity(Polarity::Neg)]) => Ok(Infinity(Polarity::Pos)),
+		("min", [a, b]) | ("max", [a, b]) => compare(a, b).map(|ord| match (name, ord) {
+			("min", Ordering::Greater) | ("max", Ordering::Less) => b.clone(),
+			_ => a.clone(),
+		}),
+		("min", [Array(a)]) | ("max", [Array(a)]) => {
+			let mut members = a.members.iter();
+			let Some(first) = members.next() else {
+				return Some(Err(format!("{} of an empty array", name)));
+			};
+			members.try_fold(first.clone(), |acc, m| {
+				compare(&acc, m).map(|ord| match (name, ord) {
+					("min", Ordering::Greater) | ("max", Ordering::Less) => m.clone(),
+					_ => acc,
+				})
+			})
+		}
+		("sum", [Array(a)]) => a.members.iter().try_fold(Int(0), |acc, m| match (acc, m) {
+			(Int(x), Int(y)) => x.checked_add(*y).map(Int).ok_or_else(overflow),
+			(Int(x), Float(y)) => Ok(Float(x as f64 + y)),
+			(Float(x), Float(y)) => Ok(Float(x + y)),
+			_ => Err("invalid argument to sum".to_owned()),
+		}),
+		("product", [Array(a)]) => a.members.iter().try_fold(Int(1), |acc, m| match (acc, m) {
+			(Int(x), Int(y)) => x.checked_mul(*y).map(Int).ok_or_else(overflow),
+			(Int(x), Float(y)) => Ok(Float(x as f64 * y)),
+			(Float(x), Float(y)) => Ok(Float(x * y)),
+			_ => Err("invalid argument to product".to_owned()),
+		}),
+
+		// Comparisons
+		("=", [a, b]) => Ok(Bool(a == b)),
+		("!=", [a, b]) => Ok(Bool(a != b)),
+		("<", [a, b]) => compare(a, b).map(|o| Bool(o.is_lt())),
+		("<=", [a, b]) => compare(a, b).map(|o| Bool(o.is_le())),
+		(">", [a, b]) => compare(a, b).map(|o| Bool(o.is_gt())),
+		(">=", [a, b]) => compare(a, b).map(|o| Bool(o.is_ge())),
+
+		// Logical operators
+		("not", [Bool(a)]) => Ok(Bool(!a)),
+		("/\\", [Bool(a), Bool(b)]) => Ok(Bool(*a && *b)),
+		("\\/", [Bool(a), Bool(b)]) => Ok(Bool(*a || *b)),
+		("->", [Bool(a), Bool(b)]) => Ok(Bool(!a || *b)),
+		("<-", [Bool(a), Bool(b)]) => Ok(Bool(*a || !b)),
+		("<->", [Bool(a), Bool(b)]) => Ok(Bool(a == b)),
+		("xor", [Bool(a), Bool(b)]) => Ok(Bool(a != b)),
+		("forall", [Array(a)]) => bools(a).map(|mut bs| Bool(bs.all(|b| b))),
+		("exists", [Array(a)]) => bools(a).map(|mut bs| Bool(bs.any(|b| b))),
+
+		// Conversions
+		("bool2int", [Bool(a)]) => Ok(Int(*a as i64)),
+		("int2float", [Int(a)]) => Ok(Float(*a as f64)),
+
+		// Sets
+		("..", [Int(a), Int(b)]) => Ok(int_range(&(*a..=*b))),
+		("..", [Float(a), Float(b)]) => Ok(Val::Set(Rc::new(value::Set::from_iter([*a..=*b])))),
+		("in", [Int(x), Val::Set(s)]) => match &**s {
+			value::Set::Int(rs) => Ok(Bool(rs.iter().any(|r| r.contains(x)))),
+			_ => Ok(Bool(false)),
+		},
+		("in", [Float(x), Val::Set(s)]) => match &**s {
+			value::Set::Float(rs) => Ok(Bool(rs.iter().any(|r| r.contains(x)))),
+			_ => Ok(Bool(false)),
+		},
+		("card", [Val::Set(s)]) => {
+			int_ranges(s).map(|rs| Int(rs.iter().map(|r| r.end() - r.start() + 1).sum()))
+		}
+		("union", [Val::Set(a), Val::Set(b)]) => {
+			set_op(a, b, |a, b| a.iter().chain(b.iter()).cloned().collect())
+		}
+		("intersect", [Val::Set(a), Val::Set(b)]) => set_op(a, b, intersect),
+		("diff", [Val::Set(a), Val::Set(b)]) => set_op(a, b, |a, b| intersect(a, &complement(b))),
+		("subset", [Val::Set(a), Val::Set(b)]) => {
+			set_op(a, b, |a, b| intersect(a, &complement(b))).map(|d| Bool(d.is_empty_set()))
+		}
+		("superset", [Val::Set(a), Val::Set(b)]) => {
+			set_op(b, a, |b, a| intersect(b, &complement(a))).map(|d| Bool(d.is_empty_set()))
+		}
+		("array2set", [Array(a)]) => set_literal(a.members.clone()),
+		("set2array", [Val::Set(s)]) => {
+			int_ranges(s).map(|rs| Val::array(rs.iter().flat_map(|r| r.clone()).map(Int).collect()))
+		}
+
+		// Strings
+		("++", [String(a), String(b)]) => Ok(String(format!("{}{}", a, b).into())),
+		("concat", [Array(a)]) => a
+			.members
+			.iter()
+			.map(|m| match m {
+				String(s) => Ok(s.to_string()),
+				_ => Err("invalid argument to concat".to_owned()),
+			})
+			.collect::<Result<std::string::String, _>>()
+			.map(|s| String(s.into())),
+
+		// Assertions
+		("assert", [Bool(b), String(msg)]) => {
+			if *b {
+				Ok(Bool(true))
+			} else {
+				Err(format!("Assertion failed: {}", msg))
+			}
+		}
+		("assert", [Bool(b), String(msg), x]) => {
+			if *b {
+				Ok(x.clone())
+			} else {
+				Err(format!("Assertion failed: {}", msg))
+			}
+		}
+		("abort", [String(msg)]) => Err(format!("Abort: {}", msg)),
+		("trace", [String(_), x]) => Ok(x.clone()),
+
+		_ => return None,
+	};
+	Some(result)
+}
+
+/// Create a set literal from the given members
+pub(super) fn set_literal(members: Vec<Val>) -> Result<Val, String> {
+	if members.iter().all(|m| matches!(m, Val::Float(_))) && !members.is_empty() {
+		return Ok(Val::Set(Rc::new(
+			members
+				.into_iter()
+				.map(|m| match m {
+					Val::Float(f) => f..=f,
+					_ => unreachable!(),
+				})
+				.collect(),
+		)));
+	}
+	members
+		.into_iter()
+		.map(|m| match m {
+			Val::Int(i) => Ok(i..=i),
+			Val::Bool(b) => Ok(b as i64..=b as i64),
+			Val::Var(_) => Err("sets of decision variables are not supported".to_owned()),
+			_ => Err("invalid set member".to_owned()),
+		})
+		.collect::<Result<Set, _>>()
+		.map(|s| Val::Set(Rc::new(s)))
+}
+
+/// Access an array using the given (fixed) index
+pub(super) fn array_access(array: &Val, index: &Val) -> Result<Val, String> {
+	let Val::Array(a) = array else {
+		return Err("invalid array access".to_owned());
+	};
+	let indices = match index {
+		Val::Int(i) => vec![*i],
+		Val::Tuple(t) => t
+			.iter()
+			.map(|i| match i {
+				Val::Int(i) => Ok(*i),
+				_ => Err("invalid array index".to_owned()),
+			})
+			.collect::<Result<Vec<_>, _>>()?,
+		_ => return Err("invalid array index".to_owned()),
+	};
+	if indices.len() != a.index_sets.len() {
+		return Err("array access has the wrong number of indices".to_owned());
+	}
+	let mut pos = 0;
+	for (i, r) in indices.iter().zip(a.index_sets.iter()) {
+		if !r.contains(i) {
+			return Err(format!(
+				"array index {} out of bounds {}..{}",
+				i,
+				r.start(),
+				r.end()
+			));
+		}
+		pos = pos * (r.end() - r.start() + 1) + (i - r.start());
+	}
+	Ok(a.members[pos as usize].clone())
+}
+
+/// Create an integer range set
+fn int_range(r: &RangeInclusive<i64>) -> Val {
+	Val::Set(Rc::new(Set::from_iter([r.clone()])))
+}
+
+fn overflow() -> String {
+	"integer overflow".to_owned()
+}
+
+fn compare(a: &Val, b: &Val) -> Result<Ordering, String> {
+	match (a, b) {
+		(Val::Bool(a), Val::Bool(b)) => Ok(a.cmp(b)),
+		(Val::Int(a), Val::Int(b)) => Ok(a.cmp(b)),
+		(Val::Float(a), Val::Float(b)) => a
+			.partial_cmp(b)
+			.ok_or_else(|| "invalid float comparison".to_owned()),
+		(Val::String(a), Val::String(b)) => Ok(a.cmp(b)),
+		(Val::Infinity(a), Val::Infinity(b)) => Ok(b.cmp(a)),
+		(Val::Infinity(Polarity::Pos), _) | (_, Val::Infinity(Polarity::Neg)) => {
+			Ok(Ordering::Greater)
+		}
+		(Val::Infinity(Polarity::Neg), _) | (_, Val::Infinity(Polarity::Pos)) => Ok(Ordering::Less),
+		_ => Err("values cannot be compared".to_owned()),
+	}
+}
+
+fn bools(a: &ArrayVal) -> Result<impl Iterator<Item = bool> + '_, String> {
+	if a.members.iter().all(|m| matches!(m, Val::Bool(_))) {
+		Ok(a.members.iter().map(|m| matches!(m, Val::Bool(true))))
+	} else {
+		Err("expected an array of booleans".to_owned())
+	}
+}
+
+fn int_ranges(s: &Set) -> Result<&[RangeInclusive<i64>], String> {
+	match s {
+		Set::Int(rs) => Ok(rs),
+		_ => Err("expected a set of integers".to_owned()),
+	}
+}
+
+fn set_op(
+	a: &Set,
+	b: &Set,
+	op: impl FnOnce(&[RangeInclusive<i64>], &[RangeInclusive<i64>]) -> Set,
+) -> Result<Val, String> {
+	Ok(Val::Set(Rc::new(op(int_ranges(a)?, int_ranges(b)?))))
+}
+
+fn intersect(a: &[RangeInclusive<i64>], b: &[RangeInclusive<i64>]) -> Set {
+	a.iter()
+		.flat_map(|x| {
+			b.iter()
+				.map(move |y| *x.start().max(y.start())..=*x.end().min(y.end()))
+		})
+		.collect()
+}
+
+fn complement(a: &[RangeInclusive<i64>]) -> Vec<RangeInclusive<i64>> {
+	let mut result = Vec::with_capacity(a.len() + 1);
+	let mut start = i64::MIN;
+	for r in a {
+		if *r.start() > start {
+			result.push(start..=r.start() - 1);
+		}
+		match r.end().checked_add(1) {
+			Some(s) => start = s,
+			None => return result,
+		}
+	}
+	result.push(start..=i64::MAX);
+	result
+}
+
+impl Val {
+	fn is_empty_set(&self) -> bool {
+		match self {
+			Val::Set(s) => match &**s {
+				Set::Int(rs) => rs.is_empty(),
+				Set::Float(rs) => rs.is_empty(),
+				Set::Enum(rs) => rs.is_empty(),
+			},
+			_ => false,
+		}
+	}
+}
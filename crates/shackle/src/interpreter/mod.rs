@@ -0,0 +1,1880 @@
+//! Interpreter for MicroZinc (MIR) programs
+//!
+//! Evaluates the `main` function of the MIR against the input data of a
+//! [`Program`], producing a [`FlatZinc`] instance. Calls to functions with a
+//! body are evaluated by binding the parameters and evaluating the body
+//! (E-Call), calls to builtin functions with fixed arguments are evaluated
+//! directly (E-Call-Builtin), and other calls to functions without a body
+//! are emitted as native FlatZinc constraints.
+//!
+//! Constraints in `let` expressions are enforced when the `let` is in the root
+//! context. Otherwise, they are conjoined into the value of the nearest enclosing
+//! Boolean `let`, or enforced in the root if there is none (e.g. in the
+//! definition of a top-level declaration). The domain of a declaration with a
+//! definition is treated in the same way as such a constraint.
+
+mod builtins;
+
+use std::{ops::RangeInclusive, rc::Rc, sync::Arc};
+
+use flatzinc_serde::{
+	Annotation, Argument, Call, Domain as FznDomain, FlatZinc, Literal, Method, RangeList,
+	SolveObjective, Type, Variable,
+};
+use rustc_hash::FxHashMap;
+use shackle_compiler::{
+	db::Inputs,
+	diagnostics::error::EvaluationError,
+	hir::Identifier,
+	mir::{
+		db::Mir, ty::Ty, AnnotationId, AnnotationRef, Callable, Comprehension, Constraint,
+		Declaration, Domain, ExpressionData, FunctionId, Generator, Goal, Let, LetItem,
+		LiteralData, Model, Value as MirValue, ValueData,
+	},
+	thir::{db::Thir, source::Origin},
+};
+
+use self::builtins::{array_access, call_builtin, set_literal};
+use crate::{
+	value::{Index, Set},
+	Error, Polarity, Program, Result, Value,
+};
+
+impl Program {
+	/// Flatten the program into a [`FlatZinc`] instance
+	///
+	/// Unlike [`Program::run`], this does not require a MiniZinc installation.
+	///
+	/// This is experimental: the interpreter does not support the standard
+	/// library yet, so the model has to be compiled without it (see
+	/// [`Model::set_ignore_stdlib`](crate::Model::set_ignore_stdlib)) and
+	/// declare the FlatZinc builtins it uses. Otherwise,
+	/// [`Error::FlattenStandardLibraryUnsupported`] is returned.
+	pub fn flatten(&self) -> Result<FlatZinc> {
+		if !self.db.ignore_stdlib() {
+			return Err(Error::FlattenStandardLibraryUnsupported);
+		}
+		// Ensure that all required data has been provided
+		self.check_missing_inputs()?;
+		flatten(&self.db, &self.input_data)
+	}
+}
+
+/// Evaluate the MIR of the model in the given database using the given input
+/// data
+pub(crate) fn flatten(db: &dyn Mir, input: &FxHashMap<Arc<str>, Value>) -> Result<FlatZinc> {
+	let model = db.model_mir()?;
+	let mut interpreter = Interpreter::new(db.upcast(), &model);
+	interpreter.run(input)?;
+	Ok(interpreter.fzn)
+}
+
+/// A value computed by the interpreter
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Val {
+	/// The undefined value
+	Bottom,
+	/// Positive or negative infinity
+	Infinity(Polarity),
+	/// Boolean
+	Bool(bool),
+	/// Integer
+	Int(i64),
+	/// Float
+	Float(f64),
+	/// String
+	String(Rc<str>),
+	/// Fixed set of integers or floats
+	Set(Rc<Set>),
+	/// Array
+	Array(Rc<ArrayVal>),
+	/// Tuple
+	Tuple(Rc<[Val]>),
+	/// Annotation atom or call
+	Ann(Rc<str>, Rc<[Val]>),
+	/// Decision variable in the FlatZinc
+	Var(Rc<str>),
+}
+
+impl Val {
+	/// Create a one dimensional array value indexed from 1
+	pub(crate) fn array(members: Vec<Val>) -> Val {
+		Val::Array(Rc::new(ArrayVal {
+			index_sets: vec![1..=members.len() as i64],
+			members,
+		}))
+	}
+
+	/// Whether this value does not contain any decision variables
+	pub(crate) fn is_par(&self) -> bool {
+		match self {
+			Val::Var(_) => false,
+			Val::Array(a) => a.members.iter().all(Val::is_par),
+			Val::Tuple(fs) | Val::Ann(_, fs) => fs.iter().all(Val::is_par),
+			_ => true,
+		}
+	}
+}
+
+/// An array value
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ArrayVal {
+	/// The index set of each dimension
+	pub(crate) index_sets: Vec<RangeInclusive<i64>>,
+	/// The members in row-major order
+	pub(crate) members: Vec<Val>,
+}
+
+struct Interpreter<'a> {
+	db: &'a dyn Thir,
+	model: &'a Model,
+	/// Names of functions before name mangling
+	names: FxHashMap<FunctionId, Rc<str>>,
+	/// Annotation atoms
+	atoms: FxHashMap<Identifier, AnnotationId>,
+	/// The `output` annotation
+	output: Option<AnnotationId>,
+	/// Stack of scopes mapping identifiers to their values
+	scopes: Vec<FxHashMap<Identifier, Val>>,
+	/// Stack of the constraints of the `let` expressions being evaluated in
+	/// non-root contexts
+	conditions: Vec<Vec<Val>>,
+	/// Number of introduced variables
+	introduced: usize,
+	fzn: FlatZinc,
+}
+
+impl<'a> Interpreter<'a> {
+	fn new(db: &'a dyn Thir, model: &'a Model) -> Self {
+		let names = model
+			.functions()
+			.map(|(idx, f)| (idx, f.unmangled_name.lookup(db.upcast()).into()))
+			.collect();
+		let atoms: FxHashMap<_, _> = model
+			.annotations()
+			.filter(|(_, a)| a.parameter_count == 0)
+			.map(|(idx, a)| (a.name, idx))
+			.collect();
+		let output = atoms
+			.iter()
+			.find(|(name, _)| name.lookup(db.upcast()) == "output")
+			.map(|(_, idx)| *idx);
+		Self {
+			db,
+			model,
+			names,
+			atoms,
+			output,
+			scopes: Vec::new(),
+			conditions: Vec::new(),
+			introduced: 0,
+			fzn: FlatZinc::default(),
+		}
+	}
+
+	/// Evaluate the `main` function and solve item
+	fn run(&mut self, input: &FxHashMap<Arc<str>, Value>) -> Result<()> {
+		let main = &self.model[self.model.entrypoint()];
+		let mut scope = FxHashMap::default();
+		for param in main.parameters.iter() {
+			let name = param.name.lookup(self.db.upcast());
+			let value = self
+				.input_value(input.get(name.as_str()), &param.ty)
+				.map_err(|msg| self.error(param.origin, msg))?;
+			scope.insert(param.name, value);
+		}
+		self.scopes.push(scope);
+		// Evaluate the items of main directly so that the solve item can refer to them
+		match &main.body.as_ref().expect("main function has no body").data {
+			ExpressionData::Let(l) => {
+				for item in l.items.iter() {
+					self.eval_let_item(item, true)?;
+				}
+			}
+			_ => unreachable!("main function body must be a let expression"),
+		}
+		self.eval_solve()?;
+		self.scopes.pop();
+		Ok(())
+	}
+
+	/// Convert an input value to an interpreter value of the given type
+	fn input_value(&self, value: Option<&Value>, ty: &Ty) -> Result<Val, String> {
+		if let Ty::Tuple { dim: 0, fields } = ty {
+			// Optional values are represented as `(occurs, value)` tuples
+			if fields.len() == 2
+				&& matches!(fields[0], Ty::Bool { dim: 0, .. })
+				&& !matches!(value, Some(Value::Tuple(_)))
+			{
+				return Ok(match value {
+					None | Some(Value::Absent) => {
+						Val::Tuple(Rc::new([Val::Bool(false), default_value(&fields[1])]))
+					}
+					v => Val::Tuple(Rc::new([Val::Bool(true), self.input_value(v, &fields[1])?])),
+				});
+			}
+		}
+		let Some(value) = value else {
+			return Err("no value was provided for this input".to_owned());
+		};
+		Ok(match value {
+			Value::Absent => return Err("unexpected absent value".to_owned()),
+			Value::Infinity(p) => Val::Infinity(*p),
+			Value::Boolean(b) => Val::Bool(*b),
+			Value::Integer(i) => Val::Int(*i),
+			Value::Float(f) => Val::Float(*f),
+			Value::String(s) => Val::String(s.clone()),
+			Value::Enum(e) => Val::Int(e.int_val() as i64),
+			Value::Ann(_, _) => return Err("annotation inputs are not supported".to_owned()),
+			Value::Array(a) => {
+				let element = ty.clone().with_dim(0);
+				let members = a
+					.members
+					.iter()
+					.map(|m| self.input_value(Some(m), &element))
+					.collect::<Result<Vec<_>, _>>()?;
+				let index_sets = a
+					.indices
+					.iter()
+					.map(|i| match i {
+						Index::Integer(r) => r.clone(),
+						Index::Enum(r) => 1..=(r.end().int_val() - r.start().int_val() + 1) as i64,
+					})
+					.collect();
+				Val::Array(Rc::new(ArrayVal {
+					index_sets,
+					members,
+				}))
+			}
+			Value::Set(s) => Val::Set(Rc::new(match s {
+				Set::Enum(rs) => rs
+					.iter()
+					.map(|r| r.start().int_val() as i64..=r.end().int_val() as i64)
+					.collect(),
+				s => s.clone(),
+			})),
+			Value::Tuple(ms) => match ty {
+				Ty::Tuple { fields, .. } => Val::Tuple(
+					ms.iter()
+						.zip(fields.iter())
+						.map(|(m, f)| self.input_value(Some(m), f))
+						.collect::<Result<_, _>>()?,
+				),
+				_ => unreachable!("tuple value for non-tuple type"),
+			},
+			Value::Record(_) => return Err("record inputs are not supported".to_owned()),
+		})
+	}
+
+	fn eval_let_item(&mut self, item: &LetItem, top_level: bool) -> Result<()> {
+		match item {
+			LetItem::Constraint(c) => self.eval_constraint(c),
+			LetItem::Declaration(d) => self.eval_declaration(d, top_level, true),
+		}
+	}
+
+	/// Evaluate a constraint in root context
+	///
+	/// The annotations of the constraint are added to the FlatZinc constraints
+	/// it produces, other than those which define variables.
+	fn eval_constraint(&mut self, constraint: &Constraint) -> Result<()> {
+		let origin = constraint.expression.origin;
+		let ann = self.annotations(&constraint.annotations, origin)?;
+		let start = self.fzn.constraints.len();
+		let value = self.eval_expression(&constraint.expression, true)?;
+		self.enforce(value, origin)?;
+		if !ann.is_empty() {
+			for c in self.fzn.constraints[start..].iter_mut() {
+				let defines_var = c
+					.ann
+					.iter()
+					.any(|a| matches!(a, Annotation::Call(call) if call.id == "defines_var"));
+				if !defines_var {
+					c.ann.extend(ann.iter().cloned());
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Evaluate a declaration, with the domain of a defined variable enforced if
+	/// `root` is set, or added to the conditions of the enclosing `let` otherwise
+	fn eval_declaration(
+		&mut self,
+		declaration: &Declaration,
+		top_level: bool,
+		root: bool,
+	) -> Result<()> {
+		let origin = declaration.origin;
+		let value = match &declaration.definition {
+			Some(def) => {
+				let value = self.eval_expression(def, false)?;
+				if let Some(domain) = &declaration.domain {
+					let holds = self.in_domain(&value, &declaration.ty, domain, root, origin)?;
+					match self.conditions.last_mut() {
+						Some(conditions) if !root => conditions.push(holds),
+						_ => self.enforce(holds, origin)?,
+					}
+				}
+				value
+			}
+			None => {
+				let ann = self.annotations(&declaration.annotations, declaration.origin)?;
+				let name = if top_level {
+					Some(declaration.name)
+				} else {
+					None
+				};
+				self.new_variables(
+					name,
+					&declaration.ty,
+					declaration.domain.as_ref(),
+					&ann,
+					declaration.origin,
+				)?
+			}
+		};
+		if top_level
+			&& declaration
+				.annotations
+				.iter()
+				.any(|ann| matches!(ann, AnnotationRef::Reference(a) if Some(*a) == self.output))
+		{
+			self.add_output(declaration.name, &value, declaration.origin)?;
+		}
+		self.scopes
+			.last_mut()
+			.expect("no scope for declaration")
+			.insert(declaration.name, value);
+		Ok(())
+	}
+
+	/// Create fresh decision variables for a declaration without a definition
+	fn new_variables(
+		&mut self,
+		name: Option<Identifier>,
+		ty: &Ty,
+		domain: Option<&Domain>,
+		ann: &[Annotation],
+		origin: Origin,
+	) -> Result<Val> {
+		if ty.dim() > 0 {
+			let Some(Domain::Array(index_sets, element)) = domain else {
+				return Err(self.error(origin, "array of variables has no index sets"));
+			};
+			let index_sets = index_sets
+				.iter()
+				.map(|d| match self.eval_domain(d, origin)? {
+					Val::Set(s) => match &*s {
+						Set::Int(rs) if rs.is_empty() => Ok(RangeInclusive::new(1, 0)),
+						Set::Int(rs) if rs.len() == 1 => Ok(rs[0].clone()),
+						_ => Err(self.error(origin, "index set must be a contiguous range")),
+					},
+					_ => Err(self.error(origin, "index set must be a set of integers")),
+				})
+				.collect::<Result<Vec<_>>>()?;
+			let element_ty = ty.clone().with_dim(0);
+			let len = index_sets
+				.iter()
+				.map(|r| (r.end() - r.start() + 1).max(0) as usize)
+				.product();
+			let members = (0..len)
+				.map(|_| self.new_variables(None, &element_ty, element.as_deref(), ann, origin))
+				.collect::<Result<Vec<_>>>()?;
+			return Ok(Val::Array(Rc::new(ArrayVal {
+				index_sets,
+				members,
+			})));
+		}
+		let (ty, domain) = match ty {
+			Ty::Tuple { fields, .. } => {
+				return Ok(Val::Tuple(
+					fields
+						.iter()
+						.map(|f| self.new_variables(None, f, None, ann, origin))
+						.collect::<Result<_>>()?,
+				))
+			}
+			Ty::Bool {
+				is_var: true,
+				is_set: false,
+				..
+			} => (Type::Bool, None),
+			Ty::Int {
+				is_var: true,
+				is_set,
+				..
+			} => (
+				if *is_set { Type::IntSet } else { Type::Int },
+				domain.map(|d| self.eval_domain(d, origin)).transpose()?,
+			),
+			Ty::Float {
+				is_var: true,
+				is_set: false,
+				..
+			} => (
+				Type::Float,
+				domain.map(|d| self.eval_domain(d, origin)).transpose()?,
+			),
+			_ => {
+				return Err(self.error(
+					origin,
+					format!("unsupported variable type {}", ty.pretty_print()),
+				))
+			}
+		};
+		let domain = match domain {
+			Some(Val::Set(s)) => Some(match &*s {
+				Set::Float(rs) => FznDomain::Float(rs.iter().cloned().collect()),
+				Set::Int(rs) => FznDomain::Int(rs.iter().cloned().collect()),
+				Set::Enum(_) => unreachable!("enums have been erased"),
+			}),
+			Some(_) => return Err(self.error(origin, "domain must be a set")),
+			None => None,
+		};
+		let var = self.add_variable(name, ty, domain, None, ann.to_vec());
+		Ok(Val::Var(var))
+	}
+
+	/// Get whether the value of a defined variable is in its domain
+	///
+	/// For a decision variable in root context, the domain constraint is added
+	/// directly, otherwise it is reified.
+	fn in_domain(
+		&mut self,
+		value: &Val,
+		ty: &Ty,
+		domain: &Domain,
+		root: bool,
+		origin: Origin,
+	) -> Result<Val> {
+		if matches!(domain, Domain::Array(_, _)) {
+			return Err(self.error(origin, "unsupported domain for defined array"));
+		}
+		let domain = self.eval_domain(domain, origin)?;
+		let Val::Set(s) = &domain else {
+			return Err(self.error(origin, "domain must be a set"));
+		};
+		match (value, &**s) {
+			(Val::Int(i), Set::Int(rs)) => Ok(Val::Bool(rs.iter().any(|r| r.contains(i)))),
+			(Val::Float(f), Set::Float(rs)) => Ok(Val::Bool(rs.iter().any(|r| r.contains(f)))),
+			(Val::Set(v), Set::Int(rs)) => match &**v {
+				// Ranges are non-overlapping, so each range must be within a single domain range
+				Set::Int(vs) => Ok(Val::Bool(vs.iter().all(|v| {
+					v.is_empty()
+						|| rs
+							.iter()
+							.any(|r| r.contains(v.start()) && r.contains(v.end()))
+				}))),
+				_ => Err(self.error(origin, "unsupported domain for defined variable")),
+			},
+			(Val::Var(_), Set::Int(_)) => {
+				let id = if matches!(ty, Ty::Int { is_set: true, .. }) {
+					"set_subset"
+				} else {
+					"set_in"
+				};
+				let mut args = vec![
+					self.argument(value, origin)?,
+					self.argument(&domain, origin)?,
+				];
+				if root {
+					self.add_constraint(id.to_owned(), args, Vec::new());
+					return Ok(Val::Bool(true));
+				}
+				let b = self.add_variable(None, Type::Bool, None, None, Vec::new());
+				args.push(Argument::Literal(Literal::Identifier(b.to_string())));
+				self.define(&b, format!("{}_reif", id), args);
+				Ok(Val::Var(b))
+			}
+			_ => Err(self.error(origin, "unsupported domain for defined variable")),
+		}
+	}
+
+	fn eval_domain(&mut self, domain: &Domain, origin: Origin) -> Result<Val> {
+		match domain {
+			Domain::Identifier(i) => self.lookup(*i, origin),
+			Domain::Set(s) => {
+				let members = s
+					.members
+					.iter()
+					.map(|m| self.eval_value(m))
+					.collect::<Result<Vec<_>>>()?;
+				set_literal(members).map_err(|msg| self.error(origin, msg))
+			}
+			Domain::Array(_, _) => unreachable!("array domain for non-array variable"),
+		}
+	}
+
+	/// Evaluate an expression
+	///
+	/// When `root` is set, the expression is known to hold (i.e., it is a
+	/// constraint in the root context).
+	fn eval_expression(
+		&mut self,
+		expression: &shackle_compiler::mir::Expression,
+		root: bool,
+	) -> Result<Val> {
+		let origin = expression.origin;
+		match &expression.data {
+			ExpressionData::Let(l) => self.eval_let(l, root, origin),
+			ExpressionData::Call(c) => {
+				let args = c
+					.arguments
+					.iter()
+					.map(|a| self.eval_value(a))
+					.collect::<Result<Vec<_>>>()?;
+				match c.function {
+					Callable::Annotation(a) => Ok(Val::Ann(
+						self.model[a].name.lookup(self.db.upcast()).into(),
+						args.into(),
+					)),
+					Callable::Function(f) => self.call_function(f, args, root, origin),
+				}
+			}
+			ExpressionData::IfThenElse(ite) => match self.eval_value(&ite.condition)? {
+				Val::Bool(true) => self.eval_expression(&ite.then, root),
+				Val::Bool(false) => self.eval_expression(&ite.else_expression, root),
+				_ => Err(self.error(origin, "if-then-else condition must be fixed")),
+			},
+			ExpressionData::Comprehension(c) => {
+				let mut results = Vec::new();
+				self.eval_generators(c, 0, false, &mut results)?;
+				if c.indices.is_none() {
+					return Ok(Val::array(results.into_iter().map(|(_, v)| v).collect()));
+				}
+				// Indexed comprehensions must produce consecutive indices
+				let mut members = Vec::with_capacity(results.len());
+				let mut start = None;
+				for (expected, (idx, value)) in results.into_iter().enumerate() {
+					let Some(Val::Int(i)) = idx else {
+						return Err(self.error(origin, "invalid index in comprehension"));
+					};
+					let first = *start.get_or_insert(i);
+					if i != first + expected as i64 {
+						return Err(self.error(origin, "comprehension indices must be consecutive"));
+					}
+					members.push(value);
+				}
+				let start = start.unwrap_or(1);
+				Ok(Val::Array(Rc::new(ArrayVal {
+					index_sets: vec![start..=start + members.len() as i64 - 1],
+					members,
+				})))
+			}
+			ExpressionData::Value(v) => self.eval_value_data(v, origin),
+			ExpressionData::Forall(c) => {
+				let mut results = Vec::new();
+				self.eval_generators(c, 0, root, &mut results)?;
+				if !root {
+					return self.conjoin(results.into_iter().map(|(_, v)| v), origin);
+				}
+				for (_, value) in results {
+					self.enforce(value, origin)?;
+				}
+				Ok(Val::Bool(true))
+			}
+		}
+	}
+
+	fn eval_let(&mut self, l: &Let, root: bool, origin: Origin) -> Result<Val> {
+		self.scopes.push(FxHashMap::default());
+		if root {
+			let result = self.eval_let_inner(l, root);
+			self.scopes.pop();
+			return result;
+		}
+		self.conditions.push(Vec::new());
+		let result = self.eval_let_inner(l, root);
+		let conditions = self.conditions.pop().expect("no conditions for let");
+		self.scopes.pop();
+		let value = result?;
+		let is_bool = l.result.as_ref().is_none_or(|v| {
+			matches!(
+				v.ty,
+				Ty::Bool {
+					dim: 0,
+					is_set: false,
+					..
+				}
+			)
+		});
+		if is_bool {
+			return self.conjoin(conditions.into_iter().chain([value]), origin);
+		}
+		// The constraints float up to the nearest enclosing Boolean context
+		match self.conditions.last_mut() {
+			Some(outer) => outer.extend(conditions),
+			None => {
+				for c in conditions {
+					self.enforce(c, origin)?;
+				}
+			}
+		}
+		Ok(value)
+	}
+
+	fn eval_let_inner(&mut self, l: &Let, root: bool) -> Result<Val> {
+		for item in l.items.iter() {
+			match item {
+				LetItem::Constraint(c) if !root => {
+					let value = self.eval_expression(&c.expression, false)?;
+					self.conditions
+						.last_mut()
+						.expect("no conditions for let")
+						.push(value);
+				}
+				LetItem::Declaration(d) if !root => self.eval_declaration(d, false, false)?,
+				_ => self.eval_let_item(item, false)?,
+			}
+		}
+		match &l.result {
+			Some(v) => self.eval_value(v),
+			None => Ok(Val::Bool(true)),
+		}
+	}
+
+	/// Evaluate the generators of a comprehension starting from the given
+	/// generator, adding the (index, value) results
+	fn eval_generators(
+		&mut self,
+		c: &Comprehension,
+		generator: usize,
+		root: bool,
+		results: &mut Vec<(Option<Val>, Val)>,
+	) -> Result<()> {
+		let Some(g) = c.generators.get(generator) else {
+			let index = c
+				.indices
+				.as_ref()
+				.map(|i| self.eval_expression(i, false))
+				.transpose()?;
+			let value = self.eval_expression(&c.expression, root)?;
+			results.push((index, value));
+			return Ok(());
+		};
+		self.scopes.push(FxHashMap::default());
+		let result = match g {
+			Generator::Iterator {
+				names,
+				collection,
+				where_clause,
+			} => {
+				let items = match self.eval_expression(collection, false)? {
+					Val::Set(s) => match &*s {
+						Set::Int(rs) => rs.iter().flat_map(|r| r.clone()).map(Val::Int).collect(),
+						_ => {
+							return Err(
+								self.error(collection.origin, "cannot iterate over a float set")
+							)
+						}
+					},
+					Val::Array(a) => a.members.clone(),
+					_ => {
+						return Err(self.error(
+							collection.origin,
+							"generator collection must be a fixed set or array",
+						))
+					}
+				};
+				self.eval_iterator(
+					c,
+					generator,
+					names,
+					&items,
+					where_clause.as_ref(),
+					root,
+					results,
+				)
+			}
+			Generator::Assignment {
+				name,
+				definition,
+				where_clause,
+			} => {
+				let value = self.eval_expression(definition, false)?;
+				self.scopes.last_mut().unwrap().insert(*name, value);
+				if self.eval_where(where_clause.as_ref())? {
+					self.eval_generators(c, generator + 1, root, results)
+				} else {
+					Ok(())
+				}
+			}
+		};
+		self.scopes.pop();
+		result
+	}
+
+	/// Bind each of the given iterator names to each of the items in turn
+	#[allow(clippy::too_many_arguments)]
+	fn eval_iterator(
+		&mut self,
+		c: &Comprehension,
+		generator: usize,
+		names: &[Identifier],
+		items: &[Val],
+		where_clause: Option<&shackle_compiler::mir::Expression>,
+		root: bool,
+		results: &mut Vec<(Option<Val>, Val)>,
+	) -> Result<()> {
+		let Some((name, rest)) = names.split_first() else {
+			if self.eval_where(where_clause)? {
+				self.eval_generators(c, generator + 1, root, results)?;
+			}
+			return Ok(());
+		};
+		for item in items {
+			self.scopes.last_mut().unwrap().insert(*name, item.clone());
+			self.eval_iterator(c, generator, rest, items, where_clause, root, results)?;
+		}
+		Ok(())
+	}
+
+	fn eval_where(
+		&mut self,
+		where_clause: Option<&shackle_compiler::mir::Expression>,
+	) -> Result<bool> {
+		match where_clause {
+			Some(w) => match self.eval_expression(w, false)? {
+				Val::Bool(b) => Ok(b),
+				_ => Err(self.error(w.origin, "where clause must be fixed")),
+			},
+			None => Ok(true),
+		}
+	}
+
+	/// Evaluate a call to a function
+	fn call_function(
+		&mut self,
+		function: FunctionId,
+		args: Vec<Val>,
+		root: bool,
+		origin: Origin,
+	) -> Result<Val> {
+		let model = self.model;
+		let f = &model[function];
+		if let Some(body) = &f.body {
+			// (E-Call) Functions do not capture variables, so evaluate the body in a fresh environment
+			let scope = f.parameters.iter().map(|p| p.name).zip(args).collect();
+			let scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+			let result = self.eval_expression(body, root);
+			self.scopes = scopes;
+			return result;
+		}
+		let name = self.names[&function].clone();
+		if root && &*name == "forall" {
+			// A conjunction in root context enforces each of its members
+			if let [Val::Array(a)] = args.as_slice() {
+				for m in a.members.iter() {
+					self.enforce(m.clone(), origin)?;
+				}
+				return Ok(Val::Bool(true));
+			}
+		}
+		// (E-Call-Builtin)
+		if let Some(result) = call_builtin(&name, &args) {
+			return result.map_err(|msg| self.error(origin, msg));
+		}
+		if args.iter().all(Val::is_par) {
+			return Err(self.error(
+				origin,
+				format!("no implementation for builtin function '{}'", name),
+			));
+		}
+		// Native constraint
+		let mut fzn_args = args
+			.iter()
+			.map(|a| self.argument(a, origin))
+			.collect::<Result<Vec<_>>>()?;
+		let ty = match &f.return_type {
+			Ty::Bool {
+				dim: 0,
+				is_var: true,
+				is_set: false,
+			} => {
+				if root {
+					self.add_constraint(name.to_string(), fzn_args, Vec::new());
+					return Ok(Val::Bool(true));
+				}
+				let b = self.add_variable(None, Type::Bool, None, None, Vec::new());
+				fzn_args.push(Argument::Literal(Literal::Identifier(b.to_string())));
+				self.define(&b, format!("{}_reif", name), fzn_args);
+				return Ok(Val::Var(b));
+			}
+			Ty::Int {
+				dim: 0,
+				is_var: true,
+				is_set,
+			} => {
+				if *is_set {
+					Type::IntSet
+				} else {
+					Type::Int
+				}
+			}
+			Ty::Float {
+				dim: 0,
+				is_var: true,
+				is_set: false,
+			} => Type::Float,
+			_ => {
+				return Err(self.error(
+					origin,
+					format!(
+						"cannot call builtin function '{}' with decision variables",
+						name
+					),
+				))
+			}
+		};
+		let r = self.add_variable(None, ty, None, None, Vec::new());
+		fzn_args.push(Argument::Literal(Literal::Identifier(r.to_string())));
+		self.define(&r, name.to_string(), fzn_args);
+		Ok(Val::Var(r))
+	}
+
+	/// Enforce that the given boolean value holds
+	fn enforce(&mut self, value: Val, origin: Origin) -> Result<()> {
+		match value {
+			Val::Bool(true) => {}
+			Val::Bool(false) => self.add_constraint(
+				"bool_clause".to_owned(),
+				vec![Argument::Array(Vec::new()), Argument::Array(Vec::new())],
+				Vec::new(),
+			),
+			Val::Var(v) => self.add_constraint(
+				"bool_eq".to_owned(),
+				vec![
+					Argument::Literal(Literal::Identifier(v.to_string())),
+					Argument::Literal(Literal::Bool(true)),
+				],
+				Vec::new(),
+			),
+			_ => return Err(self.error(origin, "constraint must be a boolean")),
+		}
+		Ok(())
+	}
+
+	/// Get the conjunction of the given boolean values
+	fn conjoin(&mut self, values: impl IntoIterator<Item = Val>, origin: Origin) -> Result<Val> {
+		let mut vars = Vec::new();
+		for value in values {
+			match value {
+				Val::Bool(true) => {}
+				Val::Bool(false) => return Ok(Val::Bool(false)),
+				Val::Var(v) => vars.push(v),
+				_ => return Err(self.error(origin, "constraint must be a boolean")),
+			}
+		}
+		if vars.len() <= 1 {
+			return Ok(vars.pop().map_or(Val::Bool(true), Val::Var));
+		}
+		let b = self.add_variable(None, Type::Bool, None, None, Vec::new());
+		let args = vec![
+			Argument::Array(
+				vars.iter()
+					.map(|v| Literal::Identifier(v.to_string()))
+					.collect(),
+			),
+			Argument::Literal(Literal::Identifier(b.to_string())),
+		];
+		self.define(&b, "array_bool_and".to_owned(), args);
+		Ok(Val::Var(b))
+	}
+
+	fn eval_value(&mut self, value: &MirValue) -> Result<Val> {
+		self.eval_value_data(&value.data, value.origin)
+	}
+
+	fn eval_value_data(&mut self, value: &ValueData, origin: Origin) -> Result<Val> {
+		match value {
+			ValueData::Literal(l) => self.eval_literal(l, origin),
+			ValueData::Tuple(t) => Ok(Val::Tuple(
+				t.members
+					.iter()
+					.map(|m| self.eval_value(m))
+					.collect::<Result<_>>()?,
+			)),
+			ValueData::Set(s) => {
+				let members = s
+					.members
+					.iter()
+					.map(|m| self.eval_value(m))
+					.collect::<Result<Vec<_>>>()?;
+				set_literal(members).map_err(|msg| self.error(origin, msg))
+			}
+			ValueData::Array(a) => Ok(Val::array(
+				a.members
+					.iter()
+					.map(|m| self.eval_value(m))
+					.collect::<Result<_>>()?,
+			)),
+			ValueData::ArrayAccess(aa) => {
+				let array = self.lookup(aa.array, origin)?;
+				let mut indices = aa
+					.indices
+					.iter()
+					.map(|i| self.eval_literal(&i.data, i.origin))
+					.collect::<Result<Vec<_>>>()?;
+				let index = if indices.len() == 1 {
+					indices.pop().unwrap()
+				} else {
+					Val::Tuple(indices.into())
+				};
+				array_access(&array, &index).map_err(|msg| self.error(origin, msg))
+			}
+			ValueData::TupleAccess(ta) => match self.lookup(ta.tuple, origin)? {
+				Val::Tuple(fs) => Ok(fs[ta.field.0 as usize - 1].clone()),
+				_ => Err(self.error(origin, "invalid tuple access")),
+			},
+		}
+	}
+
+	fn eval_literal(&mut self, literal: &LiteralData, origin: Origin) -> Result<Val> {
+		Ok(match literal {
+			LiteralData::Bottom => Val::Bottom,
+			LiteralData::Boolean(b) => Val::Bool(b.0),
+			LiteralData::Integer(i) => Val::Int(i.0),
+			LiteralData::Float(f) => Val::Float(f.value()),
+			LiteralData::String(s) => Val::String(s.value(self.db.upcast()).into()),
+			LiteralData::Infinity => Val::Infinity(Polarity::Pos),
+			LiteralData::Identifier(i) => return self.lookup(*i, origin),
+		})
+	}
+
+	fn lookup(&self, identifier: Identifier, origin: Origin) -> Result<Val> {
+		if let Some(v) = self.scopes.iter().rev().find_map(|s| s.get(&identifier)) {
+			return Ok(v.clone());
+		}
+		if self.atoms.contains_key(&identifier) {
+			return Ok(Val::Ann(
+				identifier.lookup(self.db.upcast()).into(),
+				Rc::new([]),
+			));
+		}
+		Err(self.error(
+			origin,
+			format!(
+				"undefined identifier '{}'",
+				identifier.lookup(self.db.upcast())
+			),
+		))
+	}
+
+	fn eval_solve(&mut self) -> Result<()> {
+		let solve = self.model.solve();
+		let origin = self.model[self.model.entrypoint()].origin;
+		let ann = self.annotations(&solve.annotations, origin)?;
+		let (method, objective) = match &solve.goal {
+			Goal::Satisfy => (Method::Satisfy, None),
+			Goal::Minimize(i) => (Method::Minimize, Some(*i)),
+			Goal::Maximize(i) => (Method::Maximize, Some(*i)),
+		};
+		let objective = match objective {
+			Some(i) => {
+				let value = self.lookup(i, origin)?;
+				Some(self.literal(&value, origin)?)
+			}
+			None => None,
+		};
+		self.fzn.solve = SolveObjective {
+			method,
+			objective,
+			ann,
+		};
+		Ok(())
+	}
+
+	/// Evaluate the given annotations (other than `output`)
+	fn annotations(
+		&self,
+		annotations: &[AnnotationRef],
+		origin: Origin,
+	) -> Result<Vec<Annotation>> {
+		annotations
+			.iter()
+			.filter_map(|ann| match ann {
+				AnnotationRef::Reference(a) if Some(*a) == self.output => None,
+				AnnotationRef::Reference(a) => Some(Ok(Annotation::Atom(
+					self.model[*a].name.lookup(self.db.upcast()),
+				))),
+				AnnotationRef::Identifier(i) => Some(
+					self.lookup(*i, origin)
+						.and_then(|v| self.annotation(&v, origin)),
+				),
+			})
+			.collect()
+	}
+
+	fn annotation(&self, value: &Val, origin: Origin) -> Result<Annotation> {
+		match value {
+			Val::Ann(name, args) if args.is_empty() => Ok(Annotation::Atom(name.to_string())),
+			Val::Ann(name, args) => Ok(Annotation::Call(Call {
+				id: name.to_string(),
+				args: args
+					.iter()
+					.map(|a| self.argument(a, origin))
+					.collect::<Result<_>>()?,
+				ann: Vec::new(),
+			})),
+			_ => Err(self.error(origin, "expected an annotation")),
+		}
+	}
+
+	/// Add the output for a top-level declaration
+	fn add_output(&mut self, name: Identifier, value: &Val, origin: Origin) -> Result<()> {
+		let name = name.lookup(self.db.upcast());
+		match value {
+			Val::Array(a) => {
+				let contents = a
+					.members
+					.iter()
+					.map(|m| self.literal(m, origin))
+					.collect::<Result<_>>()?;
+				let index_sets = a
+					.index_sets
+					.iter()
+					.map(|r| Literal::IntSet(RangeList::from(r)))
+					.collect();
+				self.fzn.arrays.insert(
+					name.clone(),
+					flatzinc_serde::Array {
+						contents,
+						ann: vec![Annotation::Call(Call {
+							id: "output_array".to_owned(),
+							args: vec![Argument::Array(index_sets)],
+							ann: Vec::new(),
+						})],
+						defined: false,
+						introduced: false,
+					},
+				);
+			}
+			Val::Var(v) if **v == *name => (),
+			_ => {
+				let ty = match value {
+					Val::Var(v) => self.fzn.variables[&**v].ty.clone(),
+					Val::Bool(_) => Type::Bool,
+					Val::Int(_) => Type::Int,
+					Val::Float(_) => Type::Float,
+					Val::Set(s) if matches!(&**s, Set::Int(_)) => Type::IntSet,
+					_ => return Err(self.error(origin, "value cannot be output")),
+				};
+				let rhs = self.literal(value, origin)?;
+				self.fzn.variables.insert(
+					name.clone(),
+					Variable {
+						ty,
+						domain: None,
+						value: Some(rhs),
+						ann: Vec::new(),
+						defined: false,
+						introduced: false,
+					},
+				);
+			}
+		}
+		self.fzn.output.push(name);
+		Ok(())
+	}
+
+	fn argument(&self, value: &Val, origin: Origin) -> Result<Argument> {
+		match value {
+			Val::Array(a) => Ok(Argument::Array(
+				a.members
+					.iter()
+					.map(|m| self.literal(m, origin))
+					.collect::<Result<_>>()?,
+			)),
+			_ => Ok(Argument::Literal(self.literal(value, origin)?)),
+		}
+	}
+
+	fn literal(&self, value: &Val, origin: Origin) -> Result<Literal> {
+		Ok(match value {
+			Val::Bool(b) => Literal::Bool(*b),
+			Val::Int(i) => Literal::Int(*i),
+			Val::Float(f) => Literal::Float(*f),
+			Val::String(s) => Literal::String(s.to_string()),
+			Val::Set(s) => match &**s {
+				Set::Int(rs) => Literal::IntSet(rs.iter().cloned().collect()),
+				Set::Float(rs) => Literal::FloatSet(rs.iter().cloned().collect()),
+				Set::Enum(_) => unreachable!("enums have been erased"),
+			},
+			Val::Var(v) => Literal::Identifier(v.to_string()),
+			Val::Ann(name, args) if args.is_empty() => Literal::Identifier(name.to_string()),
+			_ => {
+				return Err(self.error(
+					origin,
+					format!("{:?} cannot be represented in FlatZinc", value),
+				))
+			}
+		})
+	}
+
+	/// Add a FlatZinc variable, using the given name if this is a top-level declaration
+	fn add_variable(
+		&mut self,
+		name: Option<Identifier>,
+		ty: Type,
+		domain: Option<FznDomain>,
+		value: Option<Literal>,
+		ann: Vec<Annotation>,
+	) -> Rc<str> {
+		let (name, introduced) = match name {
+			Some(name) => (name.lookup(self.db.upcast()), false),
+			None => {
+				self.introduced += 1;
+				(format!("X_INTRODUCED_{}_", self.introduced), true)
+			}
+		};
+		self.fzn.variables.insert(
+			name.clone(),
+			Variable {
+				ty,
+				domain,
+				value,
+				ann,
+				defined: false,
+				introduced,
+			},
+		);
+		name.into()
+	}
+
+	fn add_constraint(&mut self, id: String, args: Vec<Argument>, ann: Vec<Annotation>) {
+		self.fzn.constraints.push(Call { id, args, ann });
+	}
+
+	/// Add a constraint which functionally defines the given variable
+	fn define(&mut self, variable: &str, id: String, args: Vec<Argument>) {
+		self.fzn
+			.variables
+			.get_mut(variable)
+			.expect("defined variable does not exist")
+			.defined = true;
+		let ann = Annotation::Call(Call {
+			id: "defines_var".to_owned(),
+			args: vec![Argument::Literal(Literal::Identifier(variable.to_owned()))],
+			ann: Vec::new(),
+		});
+		self.add_constraint(id, args, vec![ann]);
+	}
+
+	fn error(&self, origin: Origin, msg: impl Into<String>) -> Error {
+		let (src, span) = origin.source_span(self.db);
+		EvaluationError {
+			src,
+			msg: msg.into(),
+			span,
+		}
+		.into()
+	}
+}
+
+/// The value used for the value of an absent optional input
+fn default_value(ty: &Ty) -> Val {
+	match ty {
+		ty if ty.dim() > 0 => Val::array(Vec::new()),
+		Ty::Bool { is_set: false, .. } => Val::Bool(false),
+		Ty::Int { is_set: false, .. } => Val::Int(0),
+		Ty::Float { is_set: false, .. } => Val::Float(0.0),
+		Ty::Bool { .. } | Ty::Int { .. } => Val::Set(Rc::new(Set::Int(Vec::new()))),
+		Ty::Float { .. } => Val::Set(Rc::new(Set::Float(Vec::new()))),
+		Ty::String { .. } => Val::String("".into()),
+		Ty::Tuple { fields, .. } => Val::Tuple(fields.iter().map(default_value).collect()),
+		Ty::Bottom { .. } | Ty::Ann { .. } | Ty::Any => Val::Bottom,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::{expect, Expect};
+	use flatzinc_serde::{Annotation, Argument, FlatZinc, Literal};
+	use tempfile::tempdir;
+
+	use crate::{
+		tests::{format_errors, test_solver},
+		InputLang, Model, Result,
+	};
+
+	/// Declarations of the builtins used by the tests, which do not use the standard library
+	const PRELUDE: &str = r#"
+		annotation output;
+		annotation input_order;
+		annotation indomain_min;
+		annotation int_search(array [int] of var int: x, ann: select, ann: choice);
+		predicate int_le(var int: a, var int: b);
+		predicate int_lin_le(array [int] of int: c, array [int] of var int: x, int: d);
+		function var int: int_plus(var int: a, var int: b);
+		function int: '+'(int: a, int: b);
+		function var int: '+'(var int: a, var int: b) = int_plus(a, b);
+		function int: '*'(int: a, int: b);
+		test '<'(int: a, int: b);
+		test '>'(int: a, int: b);
+		function set of int: '..'(int: a, int: b);
+		function int: sum(array [$X] of int: x);
+		function var int: '[]'(array [$X] of var int: x, $$E: i);
+		function int: '[]'(array [$X] of int: x, $$E: i);
+		test mzn_array_access_valid(array [$X] of var int: x, $$E: i);
+		test mzn_array_access_valid(array [$X] of int: x, $$E: i);
+		test '!='(int: a, int: b);
+		predicate '/\'(var bool: a, var bool: b);
+		test '/\'(bool: a, bool: b);
+		predicate '->'(var bool: a, var bool: b);
+		predicate '<->'(var bool: a, var bool: b);
+		predicate forall(array [$T] of var bool: x);
+		test forall(array [$T] of bool: x);
+	"#;
+
+	fn flatten(model: &str, data: &str) -> Result<FlatZinc> {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let mut model = Model::from_string(format!("{}{}", PRELUDE, model), InputLang::MiniZinc);
+		model.set_ignore_stdlib(true);
+		let mut program = model.compile(&slv).unwrap();
+		if !data.is_empty() {
+			program.add_data_str(data, InputLang::DataZinc).unwrap();
+		}
+		program.flatten()
+	}
+
+	fn check(model: &str, data: &str, expected: Expect) {
+		let result = match flatten(model, data) {
+			Ok(fzn) => serde_json::to_string_pretty(&fzn).unwrap(),
+			Err(e) => format_errors(&[e]),
+		};
+		expected.assert_eq(&result);
+	}
+
+	/// Get the value assigned to a FlatZinc variable
+	fn rhs<'a>(fzn: &'a FlatZinc, name: &str) -> Option<&'a Literal> {
+		fzn.variables[name].value.as_ref()
+	}
+
+	#[test]
+	fn test_flatten_variables() {
+		check(
+			r#"
+			int: n;
+			var 1..n: x;
+			array [1..n] of var 0..5: y;
+			constraint int_le(x, y[2]);
+			constraint int_le(x + 1, 5);
+			solve maximize x;
+			"#,
+			"n = 3;",
+			expect![[r#"
+    {
+      "variables": {
+        "X_INTRODUCED_1_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              5
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_2_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              5
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_3_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              5
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_4_": {
+          "type": "int",
+          "defined": true,
+          "introduced": true
+        },
+        "x": {
+          "type": "int",
+          "domain": [
+            [
+              1,
+              3
+            ]
+          ]
+        }
+      },
+      "arrays": {
+        "y": {
+          "a": [
+            "X_INTRODUCED_1_",
+            "X_INTRODUCED_2_",
+            "X_INTRODUCED_3_"
+          ],
+          "ann": [
+            {
+              "id": "output_array",
+              "args": [
+                [
+                  {
+                    "set": [
+                      [
+                        1,
+                        3
+                      ]
+                    ]
+                  }
+                ]
+              ]
+            }
+          ]
+        }
+      },
+      "constraints": [
+        {
+          "id": "int_le",
+          "args": [
+            "x",
+            "X_INTRODUCED_2_"
+          ]
+        },
+        {
+          "id": "int_plus",
+          "args": [
+            "x",
+            1,
+            "X_INTRODUCED_4_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_4_"
+              ]
+            }
+          ]
+        },
+        {
+          "id": "int_le",
+          "args": [
+            "X_INTRODUCED_4_",
+            5
+          ]
+        }
+      ],
+      "output": [
+        "x",
+        "y"
+      ],
+      "solve": {
+        "method": "maximize",
+        "objective": "x"
+      },
+      "version": "1.0"
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_flatten_function_call() {
+		check(
+			r#"
+			int: n = 4;
+			array [int] of int: a = [2 * i | i in 1..n where i > 1];
+			array [1..3] of var 0..10: x;
+			predicate all_le(array [int] of var int: xs, var int: y) =
+				forall (i in 1..3) (int_le(xs[i], y));
+			var 0..sum(a): m;
+			constraint all_le(x, m);
+			solve :: int_search(x, input_order, indomain_min) satisfy;
+			"#,
+			"",
+			expect![[r#"
+    {
+      "variables": {
+        "X_INTRODUCED_1_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              10
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_2_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              10
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_3_": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              10
+            ]
+          ],
+          "introduced": true
+        },
+        "X_INTRODUCED_4_": {
+          "type": "bool",
+          "defined": true,
+          "introduced": true
+        },
+        "X_INTRODUCED_5_": {
+          "type": "bool",
+          "defined": true,
+          "introduced": true
+        },
+        "X_INTRODUCED_6_": {
+          "type": "bool",
+          "defined": true,
+          "introduced": true
+        },
+        "X_INTRODUCED_7_": {
+          "type": "bool",
+          "defined": true,
+          "introduced": true
+        },
+        "m": {
+          "type": "int",
+          "domain": [
+            [
+              0,
+              18
+            ]
+          ]
+        }
+      },
+      "arrays": {
+        "x": {
+          "a": [
+            "X_INTRODUCED_1_",
+            "X_INTRODUCED_2_",
+            "X_INTRODUCED_3_"
+          ],
+          "ann": [
+            {
+              "id": "output_array",
+              "args": [
+                [
+                  {
+                    "set": [
+                      [
+                        1,
+                        3
+                      ]
+                    ]
+                  }
+                ]
+              ]
+            }
+          ]
+        }
+      },
+      "constraints": [
+        {
+          "id": "int_le_reif",
+          "args": [
+            "X_INTRODUCED_1_",
+            "m",
+            "X_INTRODUCED_4_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_4_"
+              ]
+            }
+          ]
+        },
+        {
+          "id": "int_le_reif",
+          "args": [
+            "X_INTRODUCED_2_",
+            "m",
+            "X_INTRODUCED_5_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_5_"
+              ]
+            }
+          ]
+        },
+        {
+          "id": "int_le_reif",
+          "args": [
+            "X_INTRODUCED_3_",
+            "m",
+            "X_INTRODUCED_6_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_6_"
+              ]
+            }
+          ]
+        },
+        {
+          "id": "forall_reif",
+          "args": [
+            [
+              "X_INTRODUCED_4_",
+              "X_INTRODUCED_5_",
+              "X_INTRODUCED_6_"
+            ],
+            "X_INTRODUCED_7_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_7_"
+              ]
+            }
+          ]
+        },
+        {
+          "id": "bool_eq",
+          "args": [
+            "X_INTRODUCED_7_",
+            true
+          ]
+        }
+      ],
+      "output": [
+        "x",
+        "m"
+      ],
+      "solve": {
+        "method": "satisfy",
+        "ann": [
+          {
+            "id": "int_search",
+            "args": [
+              [
+                "X_INTRODUCED_1_",
+                "X_INTRODUCED_2_",
+                "X_INTRODUCED_3_"
+              ],
+              "input_order",
+              "indomain_min"
+            ]
+          }
+        ]
+      },
+      "version": "1.0"
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_flatten_output() {
+		check(
+			r#"
+			int: n;
+			opt int: p;
+			var 1..n: x :: output;
+			var int: y :: output = x + 1;
+			int: z :: output = n * 2;
+			array [1..2] of var bool: b :: output;
+			"#,
+			"n = 3;",
+			expect![[r#"
+    {
+      "variables": {
+        "X_INTRODUCED_1_": {
+          "type": "int",
+          "defined": true,
+          "introduced": true
+        },
+        "X_INTRODUCED_2_": {
+          "type": "bool",
+          "introduced": true
+        },
+        "X_INTRODUCED_3_": {
+          "type": "bool",
+          "introduced": true
+        },
+        "x": {
+          "type": "int",
+          "domain": [
+            [
+              1,
+              3
+            ]
+          ]
+        },
+        "y": {
+          "type": "int",
+          "rhs": "X_INTRODUCED_1_"
+        },
+        "z": {
+          "type": "int",
+          "rhs": 6
+        }
+      },
+      "arrays": {
+        "b": {
+          "a": [
+            "X_INTRODUCED_2_",
+            "X_INTRODUCED_3_"
+          ],
+          "ann": [
+            {
+              "id": "output_array",
+              "args": [
+                [
+                  {
+                    "set": [
+                      [
+                        1,
+                        2
+                      ]
+                    ]
+                  }
+                ]
+              ]
+            }
+          ]
+        }
+      },
+      "constraints": [
+        {
+          "id": "int_plus",
+          "args": [
+            "x",
+            1,
+            "X_INTRODUCED_1_"
+          ],
+          "ann": [
+            {
+              "id": "defines_var",
+              "args": [
+                "X_INTRODUCED_1_"
+              ]
+            }
+          ]
+        }
+      ],
+      "output": [
+        "x",
+        "y",
+        "z",
+        "b"
+      ],
+      "solve": {
+        "method": "satisfy"
+      },
+      "version": "1.0"
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_flatten_undefined() {
+		check(
+			r#"
+			array [int] of int: a = [1, 2, 3];
+			int: i;
+			int: x = a[i];
+			"#,
+			"i = 5;",
+			expect![[r#"
+    {
+      "variables": {},
+      "arrays": {},
+      "constraints": [
+        {
+          "id": "bool_clause",
+          "args": [
+            [],
+            []
+          ]
+        }
+      ],
+      "output": [],
+      "solve": {
+        "method": "satisfy"
+      },
+      "version": "1.0"
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_flatten_stdlib() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			"var 1..3: x; constraint x > 1;".to_owned(),
+			InputLang::MiniZinc,
+		);
+		let program = model.compile(&slv).unwrap();
+		expect!["Flattening models which use the standard library is not supported yet."]
+			.assert_eq(&format_errors(&[program.flatten().unwrap_err()]));
+	}
+
+	#[test]
+	fn test_flatten_error() {
+		check(
+			r#"
+			int: i;
+			int: x = i * i;
+			"#,
+			"i = 4000000000;",
+			expect!["Evaluation error: integer overflow"],
+		);
+	}
+
+	#[test]
+	fn test_flatten_let_non_root() {
+		let fzn = flatten(
+			r#"
+			predicate bool_eq(var bool: a, var bool: b);
+			predicate at_most_three(var int: a) = let { constraint int_le(a, 3) } in true;
+			var bool: b;
+			var int: x;
+			constraint bool_eq(b, at_most_three(x));
+			"#,
+			"",
+		)
+		.unwrap();
+		// The constraint in the let must be reified rather than enforced
+		let ids = fzn
+			.constraints
+			.iter()
+			.map(|c| c.id.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(ids, ["int_le_reif", "bool_eq"]);
+		assert_eq!(fzn.constraints[1].args[1], fzn.constraints[0].args[2]);
+	}
+
+	#[test]
+	fn test_flatten_constraint_annotations() {
+		let fzn = flatten(
+			r#"
+			annotation domain;
+			var int: x;
+			constraint int_le(x, 1) :: domain;
+			"#,
+			"",
+		)
+		.unwrap();
+		assert_eq!(fzn.constraints.len(), 1);
+		assert_eq!(
+			fzn.constraints[0].ann,
+			[Annotation::Atom("domain".to_owned())]
+		);
+	}
+
+	#[test]
+	fn test_flatten_inlining() {
+		let fzn = flatten(
+			r#"
+			annotation mzn_inline_call_by_name;
+			function int: foo(bool: a, int: b, int: c) :: mzn_inline_call_by_name =
+				if a then b else c endif;
+			function int: bar(bool: a, int: b, int: c) :: mzn_inline_call_by_name =
+				let {
+					float: p = 2.5;
+				} in if a then b else c endif;
+			int: p = 1;
+			int: x :: output = foo(true, 1, 2);
+			int: y :: output = bar(false, p, 2);
+			"#,
+			"",
+		)
+		.unwrap();
+		assert_eq!(rhs(&fzn, "x"), Some(&Literal::Int(1)));
+		assert_eq!(rhs(&fzn, "y"), Some(&Literal::Int(2)));
+	}
+
+	#[test]
+	fn test_flatten_records() {
+		let fzn = flatten(
+			r#"
+			record(int: foo, float: bar): x = (bar: 2.5, foo: 1);
+			int: y :: output = x.foo;
+			float: z :: output = x.bar;
+			"#,
+			"",
+		)
+		.unwrap();
+		assert_eq!(rhs(&fzn, "y"), Some(&Literal::Int(1)));
+		assert_eq!(rhs(&fzn, "z"), Some(&Literal::Float(2.5)));
+	}
+
+	#[test]
+	fn test_flatten_capturing_functions() {
+		let fzn = flatten(
+			r#"
+			var int: x;
+			var int: y;
+			function var int: qux(var int: p, var int: q);
+			function var int: bar() = qux(foo(), y);
+			function var int: foo() = x;
+			var int: z :: output = bar();
+			var int: w :: output = foo();
+			"#,
+			"",
+		)
+		.unwrap();
+		assert_eq!(fzn.constraints.len(), 1);
+		let qux = &fzn.constraints[0];
+		assert_eq!(qux.id, "qux");
+		assert_eq!(
+			qux.args[..2],
+			[
+				Argument::Literal(Literal::Identifier("x".to_owned())),
+				Argument::Literal(Literal::Identifier("y".to_owned())),
+			]
+		);
+		assert_eq!(
+			rhs(&fzn, "z")
+				.map(|l| Argument::Literal(l.clone()))
+				.as_ref(),
+			qux.args.get(2)
+		);
+		assert_eq!(rhs(&fzn, "w"), Some(&Literal::Identifier("x".to_owned())));
+	}
+
+	#[test]
+	fn test_flatten_par_if_then_else() {
+		let fzn = flatten(
+			r#"
+			var int: x;
+			bool: p;
+			constraint if p then int_le(x, 7) else int_le(x, 8) endif;
+			"#,
+			"p = false;",
+		)
+		.unwrap();
+		assert_eq!(fzn.constraints.len(), 1);
+		assert_eq!(fzn.constraints[0].id, "int_le");
+		assert_eq!(
+			fzn.constraints[0].args[1],
+			Argument::Literal(Literal::Int(8))
+		);
+	}
+}
@@ -1,7 +1,6 @@
 use std::{
 	fmt::Display,
 	io::{BufRead, BufReader, Write},
-	ops::Deref,
 	path::PathBuf,
 	process::{Command, Stdio},
 	sync::Arc,
@@ -17,15 +16,21 @@ use tempfile::Builder;
 
 use crate::{
 	data::serde::SerdeValueVisitor,
-	error::{FileError, InternalError},
-	value::{Array, EnumInner, EnumRangeInclusive, EnumValue, Index, Polarity, Set, Value},
-	Enum, Error, Message, OptType, Program, Result, Status, Type,
+	error::{FileError, InternalError, ProcessError, UnsupportedSolverFlag},
+	solver::{minizinc_executable, MINIZINC_EXECUTABLE},
+	value::{Array, EnumRangeInclusive, EnumValue, Index, Polarity, Set, Value},
+	Enum, Error, Message, OptType, Program, Result, Solution, Status, Type,
 };
 
 impl Program {
 	/// Run the program in the current state
 	/// Solutions are emitted to the callback, and the resulting status is returned.
 	pub fn run<F: Fn(&Message) -> Result<()>>(&mut self, msg_callback: F) -> Result<Status> {
+		// Ensure that all required data has been provided
+		self.check_missing_inputs()?;
+		// Ensure that the solver supports all requested options
+		let solver_args = self.solver_args()?;
+
 		// Create new (temporary) file used as input for the interpreter
 		let tmpfile = Builder::new().suffix(".shackle.mzn").tempfile();
 		let mut tmpfile = match tmpfile {
@@ -53,24 +58,25 @@ impl Program {
 		self.write(file_mut).map_err(write_err)?;
 		// Write data to file
 		for (name, ty) in &self.input_types {
-			let val = if let Some(val) = self.input_data.get(name) {
-				val
-			} else if ty.is_opt() {
+			let val = self.input_data.get(name).unwrap_or_else(|| {
+				debug_assert!(ty.is_opt(), "missing input {name} was not reported");
 				&Value::Absent
-			} else {
-				todo!("add new error type - {} is not initialized", name)
-			};
+			});
 			writeln!(file_mut, "{name} = {};", LegacyValue { val, ty }).map_err(write_err)?;
 		}
 		for e in &self.legacy_enums {
-			if e.state.lock().unwrap().deref() == &EnumInner::NoDefinition {
-				todo!("add new error type - {} is not initialized", e.name())
-			}
 			writeln!(file_mut, "{};", LegacyEnum(e)).map_err(write_err)?;
 		}
 
 		// Construct command for the MiniZinc intepreter
-		let mut cmd = Command::new("minizinc");
+		let Some(minizinc) = minizinc_executable() else {
+			return Err(ProcessError {
+				command: MINIZINC_EXECUTABLE.to_owned(),
+				message: "unable to find the MiniZinc executable in PATH".to_owned(),
+			}
+			.into());
+		};
+		let mut cmd = Command::new(&minizinc);
 		cmd.stdin(Stdio::null())
 			.stdout(Stdio::piped())
 			.stderr(Stdio::inherit())
@@ -82,19 +88,25 @@ impl Program {
 				"--ignore-stdlib",
 				"--output-objective",
 				"--output-output-item",
-				"--intermediate-solutions",
 				"--solver",
-				self.slv.ident.as_str(),
-			]);
+			])
+			.arg(self.slv.config_file());
 		if let Some(time_limit) = self.time_limit {
 			cmd.args(["--time-limit", time_limit.as_millis().to_string().as_str()]);
 		}
 		if self.enable_stats {
 			cmd.arg("--statistics");
 		}
+		cmd.args(solver_args);
 
-		let mut child = cmd.spawn().unwrap(); // TODO: fix unwrap
-		let stdout = child.stdout.take().unwrap();
+		let mut child = cmd.spawn().map_err(|err| ProcessError {
+			command: minizinc.display().to_string(),
+			message: err.to_string(),
+		})?;
+		let stdout = child
+			.stdout
+			.take()
+			.expect("stdout of the MiniZinc process is piped");
 
 		let mut status = Status::Unknown;
 		for line in BufReader::new(stdout).lines() {
@@ -137,6 +149,54 @@ impl Program {
 			Err(e) => Err(InternalError::new(format!("process error: {}", e)).into()),
 		}
 	}
+
+	/// Command line arguments that pass the requested solver options to the solver
+	///
+	/// Returns an error for each option that is not supported by the solver.
+	pub(crate) fn solver_args(&self) -> Result<Vec<String>> {
+		let mut args = Vec::new();
+		let mut errors = Vec::new();
+		let mut add_arg = |flag: &str, value: Option<String>, supported: bool| {
+			if supported {
+				args.push(flag.to_owned());
+				args.extend(value);
+			} else {
+				errors.push(Error::from(UnsupportedSolverFlag {
+					solver: self.slv.ident().to_owned(),
+					flag: flag.to_owned(),
+				}));
+			}
+		};
+		let std_flag = |flag: &str| self.slv.supports_flag(flag);
+		// Intermediate solutions are already reported when all solutions are
+		// requested, and can only be requested if the solver supports them
+		if !self.all_solutions && (std_flag("-i") || std_flag("-a")) {
+			args.push("--intermediate-solutions".to_owned());
+		}
+		if self.all_solutions {
+			add_arg("-a", None, std_flag("-a"));
+		}
+		if let Some(n) = self.num_solutions {
+			add_arg("-n", Some(n.to_string()), std_flag("-n"));
+		}
+		if let Some(seed) = self.seed {
+			add_arg("-r", Some(seed.to_string()), std_flag("-r"));
+		}
+		if let Some(n) = self.threads {
+			add_arg("-p", Some(n.to_string()), std_flag("-p"));
+		}
+		if self.free_search {
+			add_arg("-f", None, std_flag("-f"));
+		}
+		for (flag, value) in &self.solver_flags {
+			let supported = std_flag(flag) || self.slv.extra_flags().contains(flag);
+			add_arg(flag, value.clone(), supported);
+		}
+		match Error::try_from(errors) {
+			Ok(err) => Err(err),
+			Err(_) => Ok(args),
+		}
+	}
 }
 
 struct LegacyValue<'a> {
@@ -324,19 +384,22 @@ impl<'a> Display for LegacyEnum<'a> {
 			self.0
 				.lock()
 				.iter()
-				.format_with(",", |(name, idxs, _), f| f(&format_args!(
+				.format_with(",", |(name, doms, _), f| f(&format_args!(
 					"({:?}, [{}])",
 					name,
-					idxs.iter().format_with(",", |idx, g| g(&format_args!(
-						"(0, {}..{})",
-						LegacyValue {
-							val: &idx.start(),
-							ty: &INT
-						},
-						LegacyValue {
-							val: &idx.end(),
-							ty: &INT
-						}
+					doms.iter().format_with(",", |dom, g| g(&format_args!(
+						"(0, {})",
+						dom.iter().format_with(" union ", |idx, h| h(&format_args!(
+							"{}..{}",
+							LegacyValue {
+								val: &idx.start(),
+								ty: &INT
+							},
+							LegacyValue {
+								val: &idx.end(),
+								ty: &INT
+							}
+						)))
 					)))
 				)))
 		)
@@ -351,7 +414,7 @@ enum LegacyOutput<'a> {
 	Error(Error),
 }
 
-impl<'de, 'a> Visitor<'de> for SerdeMessageVisitor<'a> {
+impl<'de, 'a: 'de> Visitor<'de> for SerdeMessageVisitor<'a> {
 	type Value = LegacyOutput<'de>;
 
 	fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -453,7 +516,9 @@ impl<'de, 'a> Visitor<'de> for SerdeMessageVisitor<'a> {
 		match msg_type {
 			Some("solution") => match solution {
 				None => Err(SerdeError::missing_field("output")),
-				Some(x) => Ok(LegacyOutput::Msg(Message::Solution(x))),
+				Some(x) => Ok(LegacyOutput::Msg(Message::Solution(Solution::new(
+					x, type_map,
+				)))),
 			},
 			Some("statistics") => match statistics {
 				None => Err(SerdeError::missing_field("statistics")),
@@ -5,11 +5,13 @@
 #![warn(variant_size_differences)]
 
 mod data;
+mod interpreter;
 mod legacy;
+mod solution;
+mod solver;
 mod value;
 
 use std::{
-	ffi::OsStr,
 	fmt::Display,
 	io::Write,
 	ops::Deref,
@@ -18,31 +20,39 @@ use std::{
 	time::Duration,
 };
 
+pub use data::DataSource;
 use data::{
-	dzn::{collect_dzn_value, parse_dzn},
-	serde::SerdeFileVisitor,
+	check_value,
+	model::{define_model_enums, EnumDefinition},
+	DataCollector,
 };
 // Result type for Shackle operations
 pub use error::{Error, Result};
+// Export FlatZinc produced by [`Program::flatten`]
+pub use flatzinc_serde::FlatZinc;
 use itertools::Itertools;
-use rustc_hash::{FxHashMap, FxHashSet};
-use serde::Deserializer;
+use miette::SourceSpan;
+use rustc_hash::FxHashMap;
+// Export InputLang enumeration used to select the language of models and data
+pub use shackle_compiler::file::InputLang;
 // Export OptType enumeration used in [`Type`]
 pub use shackle_compiler::ty::OptType;
 use shackle_compiler::{
 	db::{CompilerDatabase, Inputs, InternedString, Interner},
-	file::{InputFile, InputLang, SourceFile},
-	hir::db::Hir,
-	syntax::{ast::AstNode, minizinc::Identifier},
+	file::{InputFile, SourceFile},
 	thir::{self, db::Thir, pretty_print::PrettyPrinter, Declaration},
 	ty::{Ty, TyData},
 };
+pub use solution::Solution;
+pub use solver::Solver;
 use value::EnumInner;
-pub use value::{Enum, Value};
+pub use value::{Array, Enum, EnumRangeInclusive, EnumValue, Index, Polarity, Record, Set, Value};
 
 /// Shackle errors
 pub mod error {
 	pub use shackle_compiler::{diagnostics::error::*, Result};
+
+	pub use crate::data::serde::DeserializeError;
 }
 
 /// Shackle warnings
@@ -71,13 +81,21 @@ impl Model {
 		Model { db }
 	}
 
+	/// Set whether the standard library is ignored when compiling the model
+	///
+	/// When ignored, only the functions declared in the model itself are
+	/// available. This is currently required by [`Program::flatten`].
+	pub fn set_ignore_stdlib(&mut self, ignore: bool) {
+		self.db.set_ignore_stdlib(ignore);
+	}
+
 	/// Check whether a model contains any (non-runtime) errors
-	pub fn check(&self, _slv: &Solver, _data: &[PathBuf], _complete: bool) -> Vec<Error> {
-		// TODO: Check data files
-		self.db
-			.run_hir_phase()
-			.map(|_| Vec::new())
-			.unwrap_or_else(|e| e.iter().cloned().collect())
+	///
+	/// The given data files are checked against the inputs of the model. When
+	/// `complete` is set, an error is reported for every (non-optional) input
+	/// and enumerated type that is not assigned by the data.
+	pub fn check(&self, _slv: &Solver, data: &[DataSource], complete: bool) -> Vec<Error> {
+		check_model(&self.db, data, complete)
 	}
 
 	/// Compile current model into a [`Program`] that can be used by the Shackle interpreter
@@ -90,6 +108,7 @@ impl Model {
 			input,
 			output,
 			enums,
+			sources,
 		} = ModelIoInterface::new(&self.db);
 		let legacy_enums = enums
 			.iter()
@@ -113,27 +132,53 @@ impl Model {
 			enum_types: enums,
 			legacy_enums,
 			output_types: output,
+			sources,
 			enable_stats: false,
 			time_limit: None,
+			all_solutions: false,
+			num_solutions: None,
+			seed: None,
+			threads: None,
+			free_search: false,
+			solver_flags: Vec::new(),
 		})
 	}
 }
 
-/// Solver specification to compile and solve Model instances.
-#[derive(Clone)]
-pub struct Solver {
-	// TODO: actual information (Load from solver configurations)
-	/// Identifier of the solver
-	ident: String,
-}
-
-impl Solver {
-	/// Lookup a solver specification in default locations that best matches the given identifier
-	pub fn lookup(ident: &str) -> Option<Solver> {
-		Some(Solver {
-			ident: ident.into(),
-		})
+/// Check whether the model in the given compiler database contains any
+/// (non-runtime) errors, and check the given data against its inputs
+///
+/// This allows tools that manage their own [`CompilerDatabase`] (e.g., the
+/// language server) to check data. See [`Model::check`] for details.
+pub fn check_model(db: &dyn Thir, data: &[DataSource], complete: bool) -> Vec<Error> {
+	if let Err(e) = db.run_hir_phase() {
+		return e.iter().cloned().collect();
+	}
+	if data.is_empty() && !complete {
+		return Vec::new();
+	}
+	let interface = ModelIoInterface::new(db);
+	let no_data = FxHashMap::default();
+	let mut collector = DataCollector::new(&interface.input, &interface.enums, &no_data);
+	for d in data {
+		collector.add_source(d);
+	}
+	let mut missing = Vec::new();
+	if complete {
+		for (name, ty) in &interface.input {
+			if !ty.is_opt() && !collector.is_assigned(name) {
+				missing.push(MissingInput::Parameter(name.clone(), ty.clone()));
+			}
+		}
+		for e in interface.enums.values() {
+			if e.state.lock().unwrap().deref() == &EnumInner::NoDefinition {
+				missing.push(MissingInput::Enum(e.clone()));
+			}
+		}
 	}
+	let mut errors = collector.check();
+	errors.extend(missing_input_error(&interface.sources, missing));
+	errors
 }
 
 /// Structure to capture the result of succesful compilation of a Model object
@@ -152,9 +197,80 @@ pub struct Program {
 	legacy_enums: Vec<Arc<Enum>>,
 
 	output_types: FxHashMap<Arc<str>, Type>,
+	// Locations of the input declarations and enumerated types in the model
+	sources: FxHashMap<Arc<str>, (SourceFile, SourceSpan)>,
+
 	// run() options
 	enable_stats: bool,
 	time_limit: Option<Duration>,
+	all_solutions: bool,
+	num_solutions: Option<usize>,
+	seed: Option<u64>,
+	threads: Option<usize>,
+	free_search: bool,
+	solver_flags: Vec<(String, Option<String>)>,
+}
+
+/// An input of a [`Program`] for which no data has been provided
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MissingInput {
+	/// A (non-optional) parameter declaration
+	Parameter(Arc<str>, Type),
+	/// An enumerated type whose members have to be defined in the data
+	Enum(Arc<Enum>),
+}
+
+impl MissingInput {
+	/// Returns the name of the missing input
+	pub fn name(&self) -> &Arc<str> {
+		match self {
+			MissingInput::Parameter(name, _) => name,
+			MissingInput::Enum(e) => e.name(),
+		}
+	}
+}
+
+impl Display for MissingInput {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MissingInput::Parameter(name, ty) => write!(f, "{}: {}", ty, name),
+			MissingInput::Enum(e) => write!(f, "enum {}", e.name()),
+		}
+	}
+}
+
+/// Create an error reporting the given missing inputs at their declarations
+fn missing_input_error(
+	sources: &FxHashMap<Arc<str>, (SourceFile, SourceSpan)>,
+	missing: Vec<MissingInput>,
+) -> Option<Error> {
+	if missing.is_empty() {
+		return None;
+	}
+	let mut missing = missing
+		.into_iter()
+		.map(|input| {
+			// Inputs without a recorded declaration are reported without a span
+			let (src, span) = sources.get(input.name()).cloned().unwrap_or_else(|| {
+				(
+					SourceFile::introduced("model"),
+					SourceSpan::new(0.into(), 0),
+				)
+			});
+			let ty = match &input {
+				MissingInput::Parameter(_, ty) => ty.to_string(),
+				MissingInput::Enum(_) => "enum".to_owned(),
+			};
+			error::MissingAssignment {
+				src,
+				span,
+				identifier: input.name().to_string(),
+				ty,
+			}
+		})
+		.collect::<Vec<_>>();
+	missing.sort_by_key(|err| (err.src.name(), err.span.offset()));
+	Some(error::MissingInputData { missing }.into())
 }
 
 /// Status of running and solving a [`Program`]
@@ -167,6 +283,9 @@ pub enum Status {
 	/// A solution with the best possible objective value has been found
 	Optimal,
 	/// All possible solutions have been found
+	///
+	/// This status is only reported when all solutions have been requested
+	/// using [`Program::with_all_solutions`].
 	AllSolutions,
 	/// No result reached within the given limits
 	Unknown,
@@ -368,7 +487,7 @@ impl Display for Type {
 #[derive(Debug)]
 pub enum Message<'a> {
 	/// (Intermediate) solution emitted in the process
-	Solution(FxHashMap<&'a str, Value>),
+	Solution(Solution<'a>),
 	/// Statistical information of the shackle or solving process
 	Statistic(Vec<(&'a str, serde_json::Value)>),
 	/// Trace messages emitted during the shackle process
@@ -381,9 +500,7 @@ impl<'a> Display for Message<'a> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Message::Solution(sol) => {
-				for (name, val) in sol {
-					writeln!(f, "{} = {};", name, val)?;
-				}
+				write!(f, "{}", sol)?;
 				writeln!(f, "----------")
 			}
 			Message::Statistic(map) => {
@@ -409,116 +526,229 @@ impl Program {
 		self.time_limit = Some(dur);
 		self
 	}
+	/// Request the solver to find all solutions (or all improving solutions for optimisation problems)
+	pub fn with_all_solutions(mut self) -> Self {
+		self.all_solutions = true;
+		self
+	}
+	/// Request the solver to stop after finding the given number of solutions
+	pub fn with_num_solutions(mut self, n: usize) -> Self {
+		self.num_solutions = Some(n);
+		self
+	}
+	/// Set the seed used by the solver for its random number generator
+	pub fn with_seed(mut self, seed: u64) -> Self {
+		self.seed = Some(seed);
+		self
+	}
+	/// Set the number of threads the solver is allowed to use
+	pub fn with_threads(mut self, n: usize) -> Self {
+		self.threads = Some(n);
+		self
+	}
+	/// Allow the solver to ignore the search annotations of the model
+	pub fn with_free_search(mut self) -> Self {
+		self.free_search = true;
+		self
+	}
+	/// Pass a flag (with an optional value) that is supported by the solver directly to the solver
+	pub fn with_solver_flag(mut self, flag: impl Into<String>, value: Option<String>) -> Self {
+		self.solver_flags.push((flag.into(), value));
+		self
+	}
+	/// Returns the inputs of the program for which no data has been provided
+	///
+	/// The program can only be run once values have been provided for all of
+	/// these inputs.
+	pub fn missing_inputs(&self) -> Vec<MissingInput> {
+		let mut missing = self
+			.input_types
+			.iter()
+			.filter(|(name, ty)| !ty.is_opt() && !self.input_data.contains_key(*name))
+			.map(|(name, ty)| MissingInput::Parameter(name.clone(), ty.clone()))
+			.chain(
+				self.enum_types
+					.values()
+					.filter(|e| e.state.lock().unwrap().deref() == &EnumInner::NoDefinition)
+					.map(|e| MissingInput::Enum(e.clone())),
+			)
+			.collect::<Vec<_>>();
+		missing.sort_by(|a, b| a.name().cmp(b.name()));
+		missing
+	}
+
+	/// Returns an error listing all inputs for which no data has been provided
+	fn check_missing_inputs(&self) -> Result<()> {
+		match missing_input_error(&self.sources, self.missing_inputs()) {
+			Some(err) => Err(err),
+			None => Ok(()),
+		}
+	}
+
 	/// Output the [`Program`] using the given output interface, using the [`Write`] trait
 	pub fn write<W: Write>(&self, out: &mut W) -> Result<(), std::io::Error> {
 		let printer = PrettyPrinter::new_compat(&self.db, &self.code);
 		out.write_all(printer.pretty_print().as_bytes())
 	}
 
+	/// Returns the types of the inputs of the program
+	pub fn input_types(&self) -> &FxHashMap<Arc<str>, Type> {
+		&self.input_types
+	}
+
+	/// Returns the types of the outputs of the program
+	pub fn output_types(&self) -> &FxHashMap<Arc<str>, Type> {
+		&self.output_types
+	}
+
+	/// Returns the enumerated types declared in the program
+	pub fn enum_types(&self) -> &FxHashMap<Arc<str>, Arc<Enum>> {
+		&self.enum_types
+	}
+
+	/// Set the value of an input of the program
+	///
+	/// An error is returned if the input does not exist, if it has already
+	/// been assigned, or if the value does not match the type of the input.
+	pub fn set_input(&mut self, name: &str, value: Value) -> Result<()> {
+		// Errors are reported using the assignment that would be made in a data file
+		let src = || SourceFile::from(Arc::new(format!("{name} = {value}")));
+		let Some((key, ty)) = self.input_types.get_key_value(name) else {
+			return Err(error::UndefinedIdentifier {
+				src: src(),
+				span: (0, name.len()).into(),
+				identifier: name.to_owned(),
+			}
+			.into());
+		};
+		if self.input_data.contains_key(name) {
+			return Err(error::IdentifierAlreadyDefined {
+				src: src(),
+				span: (0, name.len()).into(),
+				identifier: name.to_owned(),
+			}
+			.into());
+		}
+		if let Err(msg) = check_value(ty, &value) {
+			let src = src();
+			let start = name.len() + 3;
+			let span = (start, src.contents().len() - start).into();
+			return Err(error::TypeMismatch { src, msg, span }.into());
+		}
+		self.input_data.insert(key.clone(), value);
+		define_model_enums(&self.enum_types, |name| self.input_data.get(name));
+		Ok(())
+	}
+
+	/// Define the members of an enumerated type of the program
+	///
+	/// The enumerated type must be declared in the model without a definition,
+	/// and the names of the constructors must be unique.
+	pub fn define_enum<S: Into<Arc<str>>>(
+		&mut self,
+		name: &str,
+		constructors: impl IntoIterator<Item = S>,
+	) -> Result<()> {
+		let constructors = constructors
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<Arc<str>>>();
+		// Errors are reported using the assignment that would be made in a data file
+		let src = || {
+			SourceFile::from(Arc::new(format!(
+				"{name} = {{{}}}",
+				constructors.iter().format(", ")
+			)))
+		};
+		let Some(e) = self.enum_types.get(name) else {
+			return Err(error::UndefinedIdentifier {
+				src: src(),
+				span: (0, name.len()).into(),
+				identifier: name.to_owned(),
+			}
+			.into());
+		};
+		let mut state = e.state.lock().unwrap();
+		if state.deref() != &EnumInner::NoDefinition {
+			return Err(error::IdentifierAlreadyDefined {
+				src: src(),
+				span: (0, name.len()).into(),
+				identifier: name.to_owned(),
+			}
+			.into());
+		}
+		let mut offset = name.len() + 4;
+		for (i, ctor) in constructors.iter().enumerate() {
+			if constructors[..i].contains(ctor) {
+				return Err(error::IdentifierAlreadyDefined {
+					src: src(),
+					span: (offset, ctor.len()).into(),
+					identifier: ctor.to_string(),
+				}
+				.into());
+			}
+			offset += ctor.len() + 2;
+		}
+		*state = EnumInner::Constructors(
+			constructors
+				.into_iter()
+				.map(|ctor| (ctor, Box::default(), 1))
+				.collect(),
+		);
+		drop(state);
+		define_model_enums(&self.enum_types, |name| self.input_data.get(name));
+		Ok(())
+	}
+
 	/// Add and parse data to be used by the program.
 	pub fn add_data_files<'a>(
 		&mut self,
 		files: impl Iterator<Item = &'a Path>,
 	) -> Result<(), Error> {
-		// First parse all files:
+		self.collect_data(|collector| {
+			for f in files {
+				collector.add_file(f);
+			}
+		})
+	}
+
+	/// Add and parse data, given as a string in the given language, to be used by the program.
+	///
+	/// An error is returned if the language is not a data input language (i.e.,
+	/// [`InputLang::DataZinc`], [`InputLang::Json`], or [`InputLang::EPrimeParam`])
+	pub fn add_data_str(&mut self, text: &str, format: InputLang) -> Result<(), Error> {
+		let src = SourceFile::from(Arc::new(text.to_owned()));
+		self.collect_data(|collector| collector.add_str(&src, format))
+	}
+
+	/// Add and parse data from the given sources to be used by the program.
+	///
+	/// The data from all sources is collected before it is resolved, so data
+	/// in one source can use enumerated types defined in another source.
+	pub fn add_data(&mut self, data: &[DataSource]) -> Result<(), Error> {
+		self.collect_data(|collector| {
+			for d in data {
+				collector.add_source(d);
+			}
+		})
+	}
+
+	/// Collect data using the given function and assign the resulting values to the inputs
+	fn collect_data(&mut self, collect: impl FnOnce(&mut DataCollector)) -> Result<(), Error> {
+		// First parse all data:
 		// - most values will be simple values that can be directly assigned
 		// - some values will be values of enumerated types, possible part of tuples, records, or indices.
-		// - files can also contain the constructors for enumerated types.
-		let mut data = Vec::new();
-		let mut names = FxHashSet::default();
-		for f in files {
-			let src = SourceFile::try_from(f)?;
-			match f.extension().and_then(OsStr::to_str) {
-				Some("dzn") => {
-					// Parse the DZN file
-					let assignments = parse_dzn(&src)?;
-					data.reserve(assignments.len());
-					names.reserve(assignments.len());
-					// Match the parser
-					for asg in assignments {
-						let ident = asg.assignee().cast::<Identifier>().unwrap();
-						if let Some((k, ty)) = self.input_types.get_key_value::<str>(&ident.name())
-						{
-							let val = collect_dzn_value(&src, &asg.definition(), ty)?;
-							data.push((k, ty, val));
-							// Identifier already seen
-							if names.contains(k) || self.input_data.contains_key(k) {
-								return Err(error::IdentifierAlreadyDefined {
-									src,
-									span: asg.cst_node().as_ref().byte_range().into(),
-									identifier: k.to_string(),
-								}
-								.into());
-							}
-							names.insert(k);
-						} else if let Some((k, e)) =
-							self.enum_types.get_key_value::<str>(&ident.name())
-						{
-							let mut inner = e.state.lock().unwrap();
-							if matches!(*inner, EnumInner::NoDefinition) {
-								(*inner).collect_definition(&src, &asg.definition())?
-							} else {
-								return Err(error::IdentifierAlreadyDefined {
-									src,
-									span: asg.cst_node().as_ref().byte_range().into(),
-									identifier: k.to_string(),
-								}
-								.into());
-							}
-						} else {
-							// Unknown identifier
-							return Err(error::UndefinedIdentifier {
-								src,
-								span: ident.cst_node().as_ref().byte_range().into(),
-								identifier: ident.name().to_string(),
-							}
-							.into());
-						}
-					}
-				}
-				Some("json") => {
-					let assignments = serde_json::Deserializer::from_str(src.contents())
-						.deserialize_map(SerdeFileVisitor {
-							input_types: &self.input_types,
-							enum_types: &self.enum_types,
-						})
-						.map_err(|err| Error::from_serde_json(err, &src))?;
-
-					data.reserve(assignments.len());
-					names.reserve(assignments.len());
-					for asg in assignments {
-						// Identifier already seen
-						if names.contains(asg.0) || self.input_data.contains_key(asg.0) {
-							return Err(error::IdentifierAlreadyDefined {
-								src,
-								span: (0, 0).into(), // TODO: actual byte range
-								identifier: asg.0.to_string(),
-							}
-							.into());
-						}
-						names.insert(asg.0);
-						data.push(asg);
-					}
-				}
-				_ => {
-					return Err(error::FileError {
-						file: f.into(),
-						message: format!(
-							"Attempting to read data file using unknown extension \"{}\"",
-							f.display()
-						),
-						other: vec![],
-					}
-					.into());
-				}
-			};
-		}
-		// Topologically sort the constructors to allow us to resolve the dependencies
-		// data.sort_by(|_a, _b| todo!());
-
-		// Itererate between initializing the enumerated types and creating the final values for the interpreter
-		for (key, ty, val) in data {
-			let _none = self.input_data.insert(key.clone(), val.resolve_value(ty)?);
+		// - data can also contain the constructors for enumerated types.
+		let mut collector =
+			DataCollector::new(&self.input_types, &self.enum_types, &self.input_data);
+		collect(&mut collector);
+		let values = collector
+			.resolve()?
+			.into_iter()
+			.map(|(key, val)| (key.clone(), val))
+			.collect::<Vec<_>>();
+		for (key, val) in values {
+			let _none = self.input_data.insert(key, val);
 			debug_assert_eq!(_none, None);
 		}
 
@@ -532,6 +762,8 @@ struct ModelIoInterface {
 	pub input: FxHashMap<Arc<str>, crate::Type>,
 	pub output: FxHashMap<Arc<str>, crate::Type>,
 	pub enums: FxHashMap<Arc<str>, Arc<crate::Enum>>,
+	/// Locations of the input declarations and enumerated types in the model
+	pub sources: FxHashMap<Arc<str>, (SourceFile, SourceSpan)>,
 }
 
 impl ModelIoInterface {
@@ -552,17 +784,24 @@ impl ModelIoInterface {
 
 		// Create a map of enumerations
 		let mut enums = FxHashMap::default();
+		let mut sources = FxHashMap::default();
 		for (_, e) in model.enumerations() {
 			let name = resolve_name(e.enum_type().name(db.upcast()));
-			if let Some(_ctor) = e.definition() {
-				log::warn!("TODO: enumerated type {} is defined in the model and member can currently not be constructed in data", name);
-				// TODO: determine dependencies or directly initialize the enumerated type
-				enums.insert(name.clone(), Arc::new(Enum::model_defined(name, [])));
+			sources.insert(name.clone(), e.origin().source_span(db));
+			if let Some(ctors) = e.definition() {
+				let def = EnumDefinition::from_compiler(db, model, &mut resolve_name, ctors);
+				if def.is_none() {
+					log::warn!("enumerated type {} is defined in the model using expressions that cannot be evaluated using the data, its members can currently not be constructed in data", name);
+				}
+				enums.insert(name.clone(), Arc::new(Enum::model_defined(name, def)));
 			} else {
 				enums.insert(name.clone(), Arc::new(Enum::from_data(name)));
 			}
 		}
 
+		// Define the enumerated types that do not depend on data
+		define_model_enums(&enums, |_| None);
+
 		// Find the annotation identifiers
 		let reg = db.identifier_registry();
 		let output_ann = reg.output;
@@ -580,7 +819,8 @@ impl ModelIoInterface {
 				&enums,
 				decl.domain().ty(),
 			);
-			map.insert(name, ty);
+			map.insert(name.clone(), ty);
+			name
 		};
 		for (_, decl) in model.all_declarations() {
 			// Determine whether declaration is part of input
@@ -588,7 +828,8 @@ impl ModelIoInterface {
 				&& decl.domain().ty().known_par(db.upcast())
 				&& decl.definition().is_none()
 			{
-				insert_decl(&mut input, decl)
+				let name = insert_decl(&mut input, decl);
+				sources.insert(name, decl.origin().source_span(db));
 			}
 
 			// Determine whether declaration is part of output
@@ -603,7 +844,7 @@ impl ModelIoInterface {
 					&& decl.top_level() && !decl.domain().ty().known_par(db.upcast())
 					&& decl.definition().is_none())
 			{
-				insert_decl(&mut output, decl)
+				insert_decl(&mut output, decl);
 			}
 		}
 
@@ -611,9 +852,556 @@ impl ModelIoInterface {
 			input,
 			output,
 			enums,
+			sources,
 		}
 	}
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+	use std::{fs::File, io::Write, path::Path};
+
+	use expect_test::{expect, Expect};
+	use itertools::Itertools;
+	use miette::Diagnostic;
+	use rustc_hash::FxHashMap;
+	use shackle_compiler::file::InputLang;
+	use tempfile::tempdir;
+
+	use crate::{
+		missing_input_error,
+		value::{Array, EnumRangeInclusive, Index, Set},
+		DataSource, Error, MissingInput, Model, OptType, Solver, Type, Value,
+	};
+
+	pub(crate) fn test_solver(dir: &Path) -> Solver {
+		let msc = dir.join("test.msc");
+		write!(
+			File::create(&msc).unwrap(),
+			r#"{{ "id": "org.shackle.test", "version": "0.1.0" }}"#
+		)
+		.unwrap();
+		Solver::from_file(&msc).unwrap()
+	}
+
+	pub(crate) fn format_errors(errors: &[Error]) -> String {
+		errors
+			.iter()
+			.map(|err| {
+				let labels = err
+					.labels()
+					.into_iter()
+					.flatten()
+					.filter_map(|l| l.label().map(|l| l.to_owned()))
+					.join(", ");
+				let related = err
+					.related()
+					.into_iter()
+					.flatten()
+					.map(|rel| {
+						let labels = rel
+							.labels()
+							.into_iter()
+							.flatten()
+							.filter_map(|l| l.label().map(|l| l.to_owned()))
+							.join(", ");
+						format!("\n  {rel}: {labels}")
+					})
+					.join("");
+				if labels.is_empty() {
+					format!("{err}{related}")
+				} else {
+					format!("{err}: {labels}{related}")
+				}
+			})
+			.join("\n")
+	}
+
+	fn check_data(model: &str, data: &[(&str, &str)], complete: bool, expected: Expect) {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+
+		let files = data
+			.iter()
+			.map(|(name, contents)| {
+				let path = dir.path().join(name);
+				write!(File::create(&path).unwrap(), "{}", contents).unwrap();
+				DataSource::File(path)
+			})
+			.collect::<Vec<_>>();
+
+		let model = Model::from_string(model.to_owned(), InputLang::MiniZinc);
+		let errors = model.check(&slv, &files, complete);
+		expected.assert_eq(&format_errors(&errors));
+	}
+
+	#[test]
+	fn test_check_data_errors() {
+		check_data(
+			r#"
+			int: n;
+			array[1..n] of int: x;
+			"#,
+			// Every mismatching element is reported, but `true` is coerced to an integer
+			&[
+				("a.dzn", "n = 4; x = [1, true, 3.0, \"a\"]; y = 1;"),
+				("b.json", r#"{"n": 4}"#),
+			],
+			false,
+			expect![[r#"
+    Type mismatch: Expected 'int' but found a floating point literal
+    Type mismatch: Expected 'int' but found a string literal
+    Undefined identifier: y is undefined
+    Identifier already defined: n already defined"#]],
+		);
+	}
+
+	#[test]
+	fn test_check_data_complete() {
+		check_data(
+			r#"
+			enum E;
+			int: n;
+			opt int: o;
+			E: e;
+			"#,
+			&[("a.dzn", "n = 3;")],
+			true,
+			expect![[r#"
+    Missing data for 2 input(s)
+      No value was assigned to 'E': E of type enum is declared here
+      No value was assigned to 'e': e of type E is declared here"#]],
+		);
+		check_data(
+			r#"
+			enum E;
+			int: n;
+			E: e;
+			"#,
+			&[("a.dzn", "n = 3; E = {A, B}; e = A;")],
+			true,
+			expect![[""]],
+		);
+	}
+
+	#[test]
+	fn test_missing_inputs() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			int: n;
+			opt int: o;
+			array[E] of int: x;
+			var 1..n: y;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		expect!["[enum E, int: n, array[E] of int: x]"].assert_eq(&format!(
+			"[{}]",
+			program.missing_inputs().iter().format(", ")
+		));
+
+		let err = program.run(|_| Ok(())).unwrap_err();
+		expect![[r#"
+    Missing data for 3 input(s)
+      No value was assigned to 'E': E of type enum is declared here
+      No value was assigned to 'n': n of type int is declared here
+      No value was assigned to 'x': x of type array[E] of int is declared here"#]]
+		.assert_eq(&format_errors(&[err]));
+
+		let data = dir.path().join("data.dzn");
+		write!(File::create(&data).unwrap(), "n = 3;").unwrap();
+		program
+			.add_data_files([data.as_path()].into_iter())
+			.unwrap();
+		expect!["[enum E, array[E] of int: x]"].assert_eq(&format!(
+			"[{}]",
+			program.missing_inputs().iter().format(", ")
+		));
+	}
+
+	#[test]
+	fn test_missing_inputs_without_source() {
+		let err = missing_input_error(
+			&FxHashMap::default(),
+			vec![MissingInput::Parameter(
+				"n".into(),
+				Type::Integer(OptType::NonOpt),
+			)],
+		)
+		.unwrap();
+		expect![[r#"
+    Missing data for 1 input(s)
+      No value was assigned to 'n': n of type int is declared here"#]]
+		.assert_eq(&format_errors(&[err]));
+	}
+
+	#[test]
+	fn test_set_input() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			int: n;
+			array[1..2] of E: x;
+			var 1..n: y;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		expect!["int"].assert_eq(&program.input_types()["n"].to_string());
+		expect!["int"].assert_eq(&program.output_types()["y"].to_string());
+
+		let errors = [
+			program.set_input("n", true.into()).unwrap_err(),
+			program.set_input("m", 1.into()).unwrap_err(),
+			program.define_enum("E", ["A", "B", "A"]).unwrap_err(),
+		];
+		expect![[r#"
+    Type mismatch: Expected 'int' but found a Boolean value
+    Undefined identifier: m is undefined
+    Identifier already defined: A already defined"#]]
+		.assert_eq(&format_errors(&errors));
+
+		program.set_input("n", 3.into()).unwrap();
+		program.define_enum("E", ["A", "B"]).unwrap();
+		let e = program.enum_types()["E"].clone();
+		let x = Array::new(
+			vec![Index::Integer(1..=2)],
+			vec![
+				e.member("B", &[]).unwrap().into(),
+				e.member("A", &[]).unwrap().into(),
+			],
+		);
+		program.set_input("x", x.into()).unwrap();
+		assert!(program.missing_inputs().is_empty());
+
+		let errors = [
+			program.set_input("n", 4.into()).unwrap_err(),
+			program.define_enum("E", ["C"]).unwrap_err(),
+		];
+		expect![[r#"
+    Identifier already defined: n already defined
+    Identifier already defined: E already defined"#]]
+		.assert_eq(&format_errors(&errors));
+	}
+
+	#[test]
+	fn test_set_input_array_shape() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			array[int] of int: x;
+			array[E] of int: y;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		program.define_enum("E", ["A", "B"]).unwrap();
+		let e = program.enum_types()["E"].clone();
+
+		// Arrays are created directly, as `Array::new` would reject them
+		let array = |indices: Vec<Index>, members: Vec<Value>| -> Value {
+			Array {
+				indices: indices.into_boxed_slice(),
+				members: members.into_boxed_slice(),
+			}
+			.into()
+		};
+		let out_of_range = EnumRangeInclusive::from_enum_and_positions(e.clone(), 1, 3);
+		let errors = [
+			program
+				.set_input(
+					"x",
+					array(vec![Index::Integer(1..=3)], vec![1.into(), 2.into()]),
+				)
+				.unwrap_err(),
+			program
+				.set_input(
+					"x",
+					array(vec![Index::Integer(i64::MIN..=i64::MAX)], vec![]),
+				)
+				.unwrap_err(),
+			program
+				.set_input(
+					"y",
+					array(
+						vec![Index::Enum(out_of_range)],
+						vec![1.into(), 2.into(), 3.into()],
+					),
+				)
+				.unwrap_err(),
+		];
+		expect![[r#"
+    Type mismatch: Expected an array with 3 element(s) for index set(s) 1..3, but found 2 element(s)
+    Type mismatch: Index set -9223372036854775808..9223372036854775807 is too large
+    Type mismatch: Index set covers positions 1..3, but enumerated type E has 2 member(s)"#]]
+		.assert_eq(&format_errors(&errors));
+
+		program
+			.set_input(
+				"y",
+				Array::new(
+					vec![Index::Enum(EnumRangeInclusive::new(
+						e.member("A", &[]).unwrap(),
+						e.member("B", &[]).unwrap(),
+					))],
+					vec![1.into(), 2.into()],
+				)
+				.into(),
+			)
+			.unwrap();
+	}
+
+	#[test]
+	fn test_add_data_str() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			int: n;
+			array[E] of int: x;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		program
+			.add_data_str("n = 3; E = {A, B};", InputLang::DataZinc)
+			.unwrap();
+		let err = program
+			.add_data_str(r#"{"n": 4}"#, InputLang::Json)
+			.unwrap_err();
+		expect!["Identifier already defined: n already defined"].assert_eq(&format_errors(&[err]));
+		let err = program
+			.add_data_str("x = [1, 2];", InputLang::MiniZinc)
+			.unwrap_err();
+		expect!["MiniZinc is not a data input language"].assert_eq(&format_errors(&[err]));
+		program
+			.add_data_str("x = [1, 2];", InputLang::DataZinc)
+			.unwrap();
+		assert!(program.missing_inputs().is_empty());
+	}
+
+	#[test]
+	fn test_json_error_location() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			array[E] of int: x;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		program.define_enum("E", ["A", "B"]).unwrap();
+		let json = r#"{ "x": [1, 2, 3] }"#;
+		let err = program.add_data_str(json, InputLang::Json).unwrap_err();
+		expect!["Invalid array literal: Array literal has 3 members in the dimension indexed by E, but E has 2 members"]
+			.assert_eq(&format_errors(std::slice::from_ref(&err)));
+		let label = err.labels().unwrap().next().unwrap();
+		expect!["[1, 2, 3]"].assert_eq(&json[label.offset()..label.offset() + label.len()]);
+	}
+
+	#[test]
+	fn test_model_defined_enum() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			int: n;
+			set of int: S;
+			int: m = n - 1;
+			enum Task = T(1..n);
+			enum Colour = {R, G} ++ C(S);
+			enum Pair = P(Task, 1..m);
+			enum Fixed = F(1..2 * 3);
+			array[Task] of int: d;
+			array[1..2] of Pair: p;
+			Colour: c;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		expect!["Fixed = F(1..6)"].assert_eq(&program.enum_types()["Fixed"].to_string());
+		expect!["[set of int: S, Colour: c, array[Task] of int: d, int: n, array[int] of Pair: p]"]
+			.assert_eq(&format!(
+				"[{}]",
+				program.missing_inputs().iter().format(", ")
+			));
+
+		let err = program
+			.add_data_str("d = [T(1): 5, T(2): 6, T(3): 7];", InputLang::DataZinc)
+			.unwrap_err();
+		expect!["Type mismatch: Unable to determine the value of d: the members of enumerated type Task are not defined"]
+			.assert_eq(&format_errors(&[err]));
+
+		program
+			.add_data_str(
+				"d = [T(1): 5, T(2): 6, T(3): 7]; p = [P(T(3), 2), P(T(1), 1)]; n = 3;",
+				InputLang::DataZinc,
+			)
+			.unwrap();
+		expect!["Task = T(1..3)"].assert_eq(&program.enum_types()["Task"].to_string());
+		expect!["Pair = P(T(1)..T(3),1..2)"].assert_eq(&program.enum_types()["Pair"].to_string());
+
+		program
+			.set_input("S", Set::from_iter([1..=1, 3..=4]).into())
+			.unwrap();
+		expect!["Colour = {R} ++ {G} ++ C(1..1 ∪ 3..4)"]
+			.assert_eq(&program.enum_types()["Colour"].to_string());
+		let err = program
+			.add_data_str("c = C(2);", InputLang::DataZinc)
+			.unwrap_err();
+		expect!["Type mismatch: C(2) is not a member of enumerated type Colour: the arguments are outside the domain of the constructor"]
+			.assert_eq(&format_errors(&[err]));
+		program
+			.add_data_str("c = C(4);", InputLang::DataZinc)
+			.unwrap();
+		assert!(program.missing_inputs().is_empty());
+	}
+
+	#[test]
+	fn test_add_data_sources() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			enum E;
+			int: n;
+			array[1..n, 1..2] of int: m;
+			bool: b;
+			array[E] of int: x;
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let mut program = model.compile(&slv).unwrap();
+		let param = dir.path().join("data.param");
+		write!(
+			File::create(&param).unwrap(),
+			"language ESSENCE' 1.0\nletting n be 2\nletting m = [[1, -2], [3, 4]; int(1..2)]"
+		)
+		.unwrap();
+		let err = program
+			.add_data(&[
+				DataSource::Text("x = [1, 2];".to_owned(), InputLang::DataZinc),
+				DataSource::Text("b = 1;".to_owned(), InputLang::DataZinc),
+			])
+			.unwrap_err();
+		expect![[r#"
+    Multiple errors
+      Type mismatch: Expected 'bool' but found an integer literal
+      Type mismatch: Unable to determine the value of x: the members of enumerated type E are not defined"#]]
+			.assert_eq(&format_errors(&[err]));
+		program
+			.add_data(&[
+				DataSource::Text("x = [1, 2];".to_owned(), InputLang::DataZinc),
+				DataSource::File(param),
+				DataSource::Text(r#"{"b": true}"#.to_owned(), InputLang::Json),
+				DataSource::Text("E = {A, B};".to_owned(), InputLang::DataZinc),
+			])
+			.unwrap();
+		assert!(program.missing_inputs().is_empty());
+	}
+
+	#[test]
+	fn test_write_contexts() {
+		let dir = tempdir().unwrap();
+		let slv = test_solver(dir.path());
+		let model = Model::from_string(
+			r#"
+			predicate foo(var int: x) = x > 1;
+			var 1..3: x;
+			constraint not foo(x);
+			"#
+			.to_owned(),
+			InputLang::MiniZinc,
+		);
+		let program = model.compile(&slv).unwrap();
+		let mut out = Vec::new();
+		program.write(&mut out).unwrap();
+		let code = String::from_utf8(out).unwrap();
+		// Contexts are only analysed when lowering into MIR, since MiniZinc performs its
+		// own context analysis
+		assert!(!code.contains(":: (ctx_"));
+		assert!(!code.contains("foo_reif"));
+		assert!(code.contains("'not'(foo(x))"));
+	}
+
+	#[test]
+	fn test_solver_flags() {
+		let dir = tempdir().unwrap();
+		let msc = dir.path().join("flags.msc");
+		write!(
+			File::create(&msc).unwrap(),
+			r#"{{
+				"id": "org.shackle.flags",
+				"version": "0.1.0",
+				"stdFlags": ["-a", "-r"],
+				"extraFlags": [["--restart", "Restart sequence type", "opt:none:luby", "none"]]
+			}}"#
+		)
+		.unwrap();
+		let slv = Solver::from_file(&msc).unwrap();
+		let model = || Model::from_string("var 1..3: x;".to_owned(), InputLang::MiniZinc);
+
+		// Intermediate solutions are only requested when the solver supports them
+		let program = model().compile(&slv).unwrap();
+		assert_eq!(program.solver_args().unwrap(), ["--intermediate-solutions"]);
+		let program = model().compile(&test_solver(dir.path())).unwrap();
+		assert!(program.solver_args().unwrap().is_empty());
+
+		let program = model()
+			.compile(&slv)
+			.unwrap()
+			.with_all_solutions()
+			.with_seed(42)
+			.with_solver_flag("--restart", Some("luby".to_owned()));
+		assert_eq!(
+			program.solver_args().unwrap(),
+			["-a", "-r", "42", "--restart", "luby"]
+		);
+
+		let program = model()
+			.compile(&slv)
+			.unwrap()
+			.with_num_solutions(3)
+			.with_solver_flag("-r", Some("7".to_owned()));
+		let Error::UnsupportedSolverFlag(err) = program.solver_args().unwrap_err() else {
+			panic!("expected a single error")
+		};
+		assert_eq!(err.flag, "-n");
+
+		let program = model()
+			.compile(&slv)
+			.unwrap()
+			.with_num_solutions(3)
+			.with_threads(4)
+			.with_free_search()
+			.with_solver_flag("--restart", None)
+			.with_solver_flag("--unknown", None);
+		let Error::MultipleErrors(err) = program.solver_args().unwrap_err() else {
+			panic!("expected multiple errors")
+		};
+		expect![[r#"
+    The solver org.shackle.flags does not support the -n flag
+    The solver org.shackle.flags does not support the -p flag
+    The solver org.shackle.flags does not support the -f flag
+    The solver org.shackle.flags does not support the --unknown flag"#]]
+		.assert_eq(&err.errors.iter().join("\n"));
+	}
+}
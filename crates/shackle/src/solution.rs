@@ -0,0 +1,358 @@
+//! Solutions emitted when running a [`Program`](crate::Program)
+
+use std::{fmt::Display, sync::Arc};
+
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
+use serde::{
+	de::{DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor},
+	forward_to_deserialize_any, Deserializer,
+};
+use shackle_compiler::file::SourceFile;
+
+use crate::{
+	data::serde::{DeserializeError, ValueDeserializer},
+	error::{TypeMismatch, UndefinedIdentifier},
+	Error, Result, Type, Value,
+};
+
+/// An (intermediate) solution emitted when running a [`Program`](crate::Program)
+///
+/// The values of the outputs can be accessed directly as [`Value`]s, or can
+/// be converted into any Rust type that implements [`serde::Deserialize`]
+/// using [`Solution::get`]. The solution as a whole can be converted using
+/// [`Solution::deserialize`], for example into a struct with a field for each
+/// output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<'a> {
+	values: FxHashMap<&'a str, Value>,
+	types: &'a FxHashMap<Arc<str>, Type>,
+}
+
+impl<'a> Solution<'a> {
+	pub(crate) fn new(
+		values: FxHashMap<&'a str, Value>,
+		types: &'a FxHashMap<Arc<str>, Type>,
+	) -> Self {
+		Self { values, types }
+	}
+
+	/// Returns the value assigned to the output with the given name
+	pub fn value(&self, name: &str) -> Option<&Value> {
+		self.values.get(name)
+	}
+
+	/// Returns the type of the output with the given name
+	pub fn output_type(&self, name: &str) -> Option<&Type> {
+		self.types.get(name)
+	}
+
+	/// Returns an iterator over the names and values of the outputs, ordered by name
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+		self.values
+			.iter()
+			.map(|(k, v)| (*k, v))
+			.sorted_by_key(|(k, _)| *k)
+	}
+
+	/// Returns the number of outputs assigned in the solution
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	/// Returns whether the solution does not assign any outputs
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Converts the value of the output with the given name into the requested Rust type
+	///
+	/// Primitive types, [`Option`] (for optional values), [`Vec`] (for arrays
+	/// and sets), [`std::collections::HashSet`], tuples, and any type
+	/// implementing [`serde::Deserialize`] are supported.
+	pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+		let Some((name, val)) = self.values.get_key_value(name) else {
+			return Err(UndefinedIdentifier {
+				src: SourceFile::from(Arc::new(name.to_owned())),
+				span: (0, name.len()).into(),
+				identifier: name.to_owned(),
+			}
+			.into());
+		};
+		T::deserialize(ValueDeserializer(val)).map_err(|err| {
+			self.deserialize_error(DeserializeError {
+				name: Some((*name).into()),
+				..err
+			})
+		})
+	}
+
+	/// Converts the solution into the requested Rust type
+	///
+	/// The solution is deserialized as a map from the names of the outputs to
+	/// their values, allowing it to be converted into a struct with a field
+	/// for (a subset of) the outputs.
+	pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+		T::deserialize(self).map_err(|err| self.deserialize_error(err))
+	}
+
+	/// Create a type mismatch error for a failed deserialization
+	///
+	/// Errors are reported using the assignments that would be made in a data file.
+	fn deserialize_error(&self, err: DeserializeError) -> Error {
+		match err.name.as_ref().and_then(|name| self.values.get(&**name)) {
+			Some(val) => {
+				let name = err.name.unwrap();
+				let src = SourceFile::from(Arc::new(format!("{name} = {val};")));
+				let start = name.len() + 3;
+				let span = (start, src.contents().len() - start - 1).into();
+				TypeMismatch {
+					src,
+					msg: err.msg,
+					span,
+				}
+				.into()
+			}
+			None => {
+				let src = SourceFile::from(Arc::new(self.to_string()));
+				let span = (0, src.contents().len()).into();
+				TypeMismatch {
+					src,
+					msg: err.msg,
+					span,
+				}
+				.into()
+			}
+		}
+	}
+}
+
+impl<'a> Display for Solution<'a> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (name, val) in self.iter() {
+			writeln!(f, "{} = {};", name, val)?;
+		}
+		Ok(())
+	}
+}
+
+impl<'de, 'b, 'a> Deserializer<'de> for &'b Solution<'a> {
+	type Error = DeserializeError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_map(SolutionMapAccess {
+			iter: self.iter().collect_vec().into_iter(),
+			current: None,
+		})
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
+
+/// Access to the outputs of a solution when deserializing it as a map
+struct SolutionMapAccess<'b> {
+	iter: std::vec::IntoIter<(&'b str, &'b Value)>,
+	current: Option<(&'b str, &'b Value)>,
+}
+
+impl<'de, 'b> MapAccess<'de> for SolutionMapAccess<'b> {
+	type Error = DeserializeError;
+
+	fn next_key_seed<K: DeserializeSeed<'de>>(
+		&mut self,
+		seed: K,
+	) -> Result<Option<K::Value>, Self::Error> {
+		match self.iter.next() {
+			Some((name, val)) => {
+				self.current = Some((name, val));
+				seed.deserialize(IntoDeserializer::<DeserializeError>::into_deserializer(
+					name,
+				))
+				.map(Some)
+			}
+			None => Ok(None),
+		}
+	}
+
+	fn next_value_seed<V: DeserializeSeed<'de>>(
+		&mut self,
+		seed: V,
+	) -> Result<V::Value, Self::Error> {
+		let (name, val) = self
+			.current
+			.take()
+			.expect("next_value_seed called before next_key_seed");
+		seed.deserialize(ValueDeserializer(val))
+			.map_err(|err| DeserializeError {
+				name: err.name.or_else(|| Some(name.into())),
+				..err
+			})
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.iter.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::HashSet, sync::Arc};
+
+	use expect_test::expect;
+	use rustc_hash::FxHashMap;
+	use serde::Deserialize;
+
+	use super::Solution;
+	use crate::{
+		value::{Array, EnumInner, Index, Record, Set},
+		Enum, Error, OptType, Type, Value,
+	};
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	enum Colour {
+		Red,
+		Green,
+		Mix(i64, i64),
+	}
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct Output {
+		x: Vec<Vec<i64>>,
+		s: HashSet<i64>,
+		o: Option<i64>,
+		c: Vec<Colour>,
+		r: Pair,
+	}
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct Pair {
+		a: bool,
+		b: f64,
+	}
+
+	#[test]
+	fn test_solution_deserialize() {
+		let colour = Arc::new(Enum::from_data("Colour".into()));
+		*colour.state.lock().unwrap() = EnumInner::Constructors(
+			[
+				("Red".into(), Box::default(), 1),
+				("Green".into(), Box::default(), 1),
+				(
+					"Mix".into(),
+					vec![vec![Index::Integer(1..=2)], vec![Index::Integer(1..=2)]].into(),
+					4,
+				),
+			]
+			.into(),
+		);
+		let int = Type::Integer(OptType::NonOpt);
+		let types = FxHashMap::from_iter([
+			(
+				"x".into(),
+				Type::Array {
+					opt: OptType::NonOpt,
+					dim: [int.clone(), int.clone()].into(),
+					element: int.clone().into(),
+				},
+			),
+			("s".into(), Type::Set(OptType::NonOpt, int.clone().into())),
+			("o".into(), Type::Integer(OptType::Opt)),
+			(
+				"c".into(),
+				Type::Array {
+					opt: OptType::NonOpt,
+					dim: [int.clone()].into(),
+					element: Type::Enum(OptType::NonOpt, colour.clone()).into(),
+				},
+			),
+			(
+				"r".into(),
+				Type::Record(
+					OptType::NonOpt,
+					[
+						("a".into(), Type::Boolean(OptType::NonOpt)),
+						("b".into(), Type::Float(OptType::NonOpt)),
+					]
+					.into(),
+				),
+			),
+			(
+				"t".into(),
+				Type::Tuple(OptType::NonOpt, [int.clone(), int].into()),
+			),
+		]);
+		let values = FxHashMap::from_iter([
+			(
+				"x",
+				Array::new(
+					vec![Index::Integer(1..=2), Index::Integer(1..=3)],
+					(1..=6).map(Value::Integer).collect(),
+				)
+				.into(),
+			),
+			("s", Set::from_iter([1..=3, 5..=5]).into()),
+			("o", Value::Absent),
+			(
+				"c",
+				Array::new(
+					vec![Index::Integer(1..=3)],
+					vec![
+						colour.member("Green", &[]).unwrap().into(),
+						colour.member("Red", &[]).unwrap().into(),
+						colour
+							.member("Mix", &[Value::Integer(2), Value::Integer(1)])
+							.unwrap()
+							.into(),
+					],
+				)
+				.into(),
+			),
+			(
+				"r",
+				Record::from_iter([
+					("a".into(), Value::Boolean(true)),
+					("b".into(), Value::Float(0.5)),
+				])
+				.into(),
+			),
+			(
+				"t",
+				Value::Tuple(vec![Value::Integer(1), Value::Integer(2)]),
+			),
+		]);
+		let sol = Solution::new(values, &types);
+
+		assert_eq!(sol.get::<Option<i64>>("o").unwrap(), None);
+		assert_eq!(sol.get::<(i64, i64)>("t").unwrap(), (1, 2));
+		assert_eq!(sol.get::<Vec<u8>>("s").unwrap(), vec![1, 2, 3, 5]);
+		assert_eq!(
+			sol.get::<Vec<String>>("c").unwrap_err().to_string(),
+			"Type mismatch"
+		);
+		assert_eq!(
+			sol.deserialize::<Output>().unwrap(),
+			Output {
+				x: vec![vec![1, 2, 3], vec![4, 5, 6]],
+				s: HashSet::from_iter([1, 2, 3, 5]),
+				o: None,
+				c: vec![Colour::Green, Colour::Red, Colour::Mix(2, 1)],
+				r: Pair { a: true, b: 0.5 },
+			}
+		);
+
+		let Error::TypeMismatch(err) = sol.get::<Vec<bool>>("x").unwrap_err() else {
+			panic!("expected a type mismatch error")
+		};
+		expect!["invalid type: sequence, expected a boolean"].assert_eq(&err.msg);
+		expect!["x = [(1, 1): 1, (1, 2): 2, (1, 3): 3, (2, 1): 4, (2, 2): 5, (2, 3): 6];"]
+			.assert_eq(err.src.contents());
+		let Error::UndefinedIdentifier(err) = sol.get::<i64>("y").unwrap_err() else {
+			panic!("expected an undefined identifier error")
+		};
+		expect!["y"].assert_eq(&err.identifier);
+	}
+}
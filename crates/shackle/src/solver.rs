@@ -0,0 +1,355 @@
+//! Discovery and representation of solver configurations
+//!
+//! Solvers are described using MiniZinc solver configuration (`.msc`) files.
+//! These files are searched for in (in order of priority):
+//! - the directories listed in the `MZN_SOLVER_PATH` environment variable,
+//! - the `.minizinc/solvers` directory in the user's home directory,
+//! - the `solvers` directory in the Shackle `share/minizinc` directory,
+//! - the `share/minizinc/solvers` directory of the `minizinc` installation.
+
+use std::{
+	cmp::Ordering,
+	env,
+	ffi::OsStr,
+	fs::read_dir,
+	path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use shackle_compiler::{
+	db::CompilerSettings, diagnostics::FileError, file::SourceFile, CompilerDatabase,
+};
+
+use crate::{Error, Result};
+
+/// Name of the MiniZinc executable used to run solvers
+pub(crate) const MINIZINC_EXECUTABLE: &str = "minizinc";
+
+/// Solver specification to compile and solve Model instances.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solver {
+	/// Identifier of the solver
+	ident: String,
+	/// Human readable name of the solver
+	name: String,
+	/// Version of the solver
+	version: String,
+	/// Alternative tags that can be used to identify the solver
+	tags: Vec<String>,
+	/// Standard flags supported by the solver
+	std_flags: Vec<String>,
+	/// Solver specific flags supported by the solver
+	extra_flags: Vec<String>,
+	/// Location of the solver specific library
+	mznlib: Option<PathBuf>,
+	/// Location of the solver executable
+	executable: Option<PathBuf>,
+	/// Location of the solver configuration file
+	config_file: PathBuf,
+}
+
+/// Contents of a MiniZinc solver configuration file
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SolverConfig {
+	id: String,
+	name: Option<String>,
+	version: String,
+	#[serde(default)]
+	tags: Vec<String>,
+	#[serde(default)]
+	std_flags: Vec<String>,
+	/// Solver specific flags, given as their name, description, type, and default value
+	#[serde(default)]
+	extra_flags: Vec<Vec<String>>,
+	#[serde(default)]
+	mznlib: String,
+	#[serde(default)]
+	executable: String,
+}
+
+impl Solver {
+	/// Lookup a solver specification in default locations that best matches the given identifier
+	///
+	/// The identifier can be the full identifier of the solver (e.g.,
+	/// `org.gecode.gecode`), its last component (e.g., `gecode`), or one of its
+	/// tags. A specific version can be requested by appending `@<version>`.
+	/// Returns `None` when no matching solver configuration can be found.
+	pub fn lookup(ident: &str) -> Option<Solver> {
+		let (ident, version) = match ident.split_once('@') {
+			Some((i, v)) => (i, Some(v)),
+			None => (ident, None),
+		};
+		Solver::available()
+			.into_iter()
+			.filter(|slv| version.is_none_or(|v| slv.version == v))
+			.filter_map(|slv| slv.match_rank(ident).map(|rank| (rank, slv)))
+			// Prefer the best match, then the highest version, then the earliest in the search path
+			.min_by(|(r1, s1), (r2, s2)| {
+				r1.cmp(r2)
+					.then_with(|| compare_versions(&s2.version, &s1.version))
+			})
+			.map(|(_, slv)| slv)
+	}
+
+	/// Load all solver configurations that can be found in the default locations
+	///
+	/// Configurations that fail to load are ignored (and reported as a warning
+	/// in the log).
+	pub fn available() -> Vec<Solver> {
+		let mut solvers = Vec::new();
+		for dir in solver_search_dirs() {
+			let Ok(entries) = read_dir(&dir) else {
+				continue;
+			};
+			let mut files = entries
+				.filter_map(|entry| entry.ok().map(|e| e.path()))
+				.filter(|p| p.extension() == Some(OsStr::new("msc")))
+				.collect::<Vec<_>>();
+			files.sort();
+			for file in files {
+				match Solver::from_file(&file) {
+					Ok(slv) => solvers.push(slv),
+					Err(e) => {
+						log::warn!("ignoring solver configuration `{}': {}", file.display(), e)
+					}
+				}
+			}
+		}
+		solvers
+	}
+
+	/// Load a solver specification from a MiniZinc solver configuration (`.msc`) file
+	pub fn from_file(path: &Path) -> Result<Solver> {
+		let src = SourceFile::try_from(path)?;
+		let config: SolverConfig = serde_json::from_str(src.contents())
+			.map_err(|err| Error::from_serde_json(err, &src))?;
+		if config.id.is_empty() {
+			return Err(FileError {
+				file: path.to_owned(),
+				message: "solver configuration does not contain a solver identifier".to_owned(),
+				other: Vec::new(),
+			}
+			.into());
+		}
+		// Paths in the configuration are relative to the configuration file
+		let base = path.parent().unwrap_or_else(|| Path::new(""));
+		let resolve = |p: String| {
+			if p.is_empty() {
+				None
+			} else {
+				Some(base.join(p))
+			}
+		};
+		Ok(Solver {
+			name: config.name.unwrap_or_else(|| config.id.clone()),
+			ident: config.id,
+			version: config.version,
+			tags: config.tags,
+			std_flags: config.std_flags,
+			extra_flags: config
+				.extra_flags
+				.into_iter()
+				.filter_map(|f| f.into_iter().next())
+				.collect(),
+			mznlib: resolve(config.mznlib),
+			executable: resolve(config.executable),
+			config_file: path.to_owned(),
+		})
+	}
+
+	/// Identifier of the solver
+	pub fn ident(&self) -> &str {
+		&self.ident
+	}
+
+	/// Human readable name of the solver
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Version of the solver
+	pub fn version(&self) -> &str {
+		&self.version
+	}
+
+	/// Alternative tags that can be used to identify the solver
+	pub fn tags(&self) -> &[String] {
+		&self.tags
+	}
+
+	/// Standard flags (e.g., `-a`, `-f`, `-r`) supported by the solver
+	pub fn std_flags(&self) -> &[String] {
+		&self.std_flags
+	}
+
+	/// Whether the solver supports the given standard flag
+	pub fn supports_flag(&self, flag: &str) -> bool {
+		self.std_flags.iter().any(|f| f == flag)
+	}
+
+	/// Solver specific flags supported by the solver
+	pub fn extra_flags(&self) -> &[String] {
+		&self.extra_flags
+	}
+
+	/// Location of the solver specific library (if any)
+	pub fn mznlib(&self) -> Option<&Path> {
+		self.mznlib.as_deref()
+	}
+
+	/// Location of the solver executable (if any)
+	pub fn executable(&self) -> Option<&Path> {
+		self.executable.as_deref()
+	}
+
+	/// Location of the solver configuration file
+	pub fn config_file(&self) -> &Path {
+		&self.config_file
+	}
+
+	/// Determine how well the solver matches the given identifier (lower is better)
+	fn match_rank(&self, ident: &str) -> Option<u8> {
+		if self.ident.eq_ignore_ascii_case(ident) {
+			Some(0)
+		} else if self
+			.ident
+			.rsplit('.')
+			.next()
+			.is_some_and(|last| last.eq_ignore_ascii_case(ident))
+		{
+			Some(1)
+		} else if self.tags.iter().any(|t| t.eq_ignore_ascii_case(ident)) {
+			Some(2)
+		} else {
+			None
+		}
+	}
+}
+
+/// Compare two version strings component by component (numerically where possible)
+fn compare_versions(a: &str, b: &str) -> Ordering {
+	let mut a_parts = a.split('.');
+	let mut b_parts = b.split('.');
+	loop {
+		match (a_parts.next(), b_parts.next()) {
+			(None, None) => return Ordering::Equal,
+			(None, Some(_)) => return Ordering::Less,
+			(Some(_), None) => return Ordering::Greater,
+			(Some(x), Some(y)) => {
+				let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+					(Ok(x), Ok(y)) => x.cmp(&y),
+					_ => x.cmp(y),
+				};
+				if ord != Ordering::Equal {
+					return ord;
+				}
+			}
+		}
+	}
+}
+
+/// Directories that are searched for solver configuration files, in order of priority
+fn solver_search_dirs() -> Vec<PathBuf> {
+	let mut dirs = Vec::new();
+	if let Some(paths) = env::var_os("MZN_SOLVER_PATH") {
+		dirs.extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+	}
+	if let Some(home) = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+		dirs.push(PathBuf::from(home).join(".minizinc").join("solvers"));
+	}
+	if let Ok(share) = CompilerDatabase::default().share_directory() {
+		dirs.push(share.join("solvers"));
+	}
+	if let Some(exe) = minizinc_executable() {
+		if let Some(prefix) = exe.parent().and_then(Path::parent) {
+			dirs.push(prefix.join("share").join("minizinc").join("solvers"));
+		}
+	}
+	dirs.dedup();
+	dirs
+}
+
+/// Locate the MiniZinc executable in the directories of the `PATH` environment variable
+pub(crate) fn minizinc_executable() -> Option<PathBuf> {
+	let paths = env::var_os("PATH")?;
+	env::split_paths(&paths).find_map(|dir| {
+		let exe = dir
+			.join(MINIZINC_EXECUTABLE)
+			.with_extension(env::consts::EXE_EXTENSION);
+		if exe.is_file() {
+			Some(exe.canonicalize().unwrap_or(exe))
+		} else {
+			None
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{cmp::Ordering, fs::File, io::Write};
+
+	use expect_test::expect;
+	use tempfile::tempdir;
+
+	use super::{compare_versions, Solver};
+	use crate::Error;
+
+	#[test]
+	fn test_load_solver_config() {
+		let dir = tempdir().unwrap();
+		let path = dir.path().join("gecode.msc");
+		let mut file = File::create(&path).unwrap();
+		write!(
+			file,
+			r#"{{
+				"id": "org.gecode.gecode",
+				"name": "Gecode",
+				"version": "6.3.0",
+				"mznlib": "../gecode",
+				"executable": "../../bin/fzn-gecode",
+				"tags": ["cp", "int", "float"],
+				"stdFlags": ["-a", "-n", "-p", "-r", "-s", "-t", "-f"],
+				"extraFlags": [["--restart", "Restart sequence type", "opt:none:luby", "none"]]
+			}}"#
+		)
+		.unwrap();
+
+		let slv = Solver::from_file(&path).unwrap();
+		assert_eq!(slv.ident(), "org.gecode.gecode");
+		assert_eq!(slv.name(), "Gecode");
+		assert_eq!(slv.version(), "6.3.0");
+		assert!(slv.supports_flag("-r"));
+		assert!(!slv.supports_flag("-i"));
+		assert_eq!(slv.extra_flags(), ["--restart"]);
+		assert_eq!(slv.mznlib(), Some(dir.path().join("../gecode").as_path()));
+		assert_eq!(slv.match_rank("org.gecode.gecode"), Some(0));
+		assert_eq!(slv.match_rank("gecode"), Some(1));
+		assert_eq!(slv.match_rank("CP"), Some(2));
+		assert_eq!(slv.match_rank("gecod"), None);
+	}
+
+	#[test]
+	fn test_invalid_solver_config() {
+		let dir = tempdir().unwrap();
+		let path = dir.path().join("broken.msc");
+		let mut file = File::create(&path).unwrap();
+		write!(file, r#"{{ "name": "Broken" }}"#).unwrap();
+
+		let Error::TypeMismatch(err) = Solver::from_file(&path).unwrap_err() else {
+			panic!("expected a type mismatch error")
+		};
+		expect!["missing field `id` at line 1 column 20"].assert_eq(&err.msg);
+	}
+
+	#[test]
+	fn test_compare_versions() {
+		assert_eq!(compare_versions("6.3.0", "6.3.0"), Ordering::Equal);
+		assert_eq!(compare_versions("6.10.0", "6.9.1"), Ordering::Greater);
+		assert_eq!(compare_versions("6.3", "6.3.0"), Ordering::Less);
+		assert_eq!(
+			compare_versions("2.8.0-beta", "2.8.0-alpha"),
+			Ordering::Greater
+		);
+	}
+}
@@ -11,6 +11,8 @@ use std::{
 
 use itertools::Itertools;
 
+use crate::data::model::EnumDefinition;
+
 /// Value types that can be part of a Solution
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
@@ -305,6 +307,32 @@ impl Index {
 		}
 	}
 
+	/// Returns the cardinality of the index set
+	///
+	/// Unlike [`Index::len`], an error message is returned if the cardinality
+	/// cannot be represented, or if an enumerated index set covers positions
+	/// that are not members of its enumerated type.
+	pub(crate) fn checked_len(&self) -> Result<usize, String> {
+		match self {
+			Index::Integer(r) if r.is_empty() => Ok(0),
+			Index::Integer(r) => r
+				.end()
+				.checked_sub(*r.start())
+				.and_then(|d| usize::try_from(d).ok())
+				.and_then(|d| d.checked_add(1))
+				.ok_or_else(|| format!("Index set {self} is too large")),
+			Index::Enum(e) if e.start > e.end => Ok(0),
+			Index::Enum(e) if e.start < 1 || e.end > e.ty.len() => Err(format!(
+				"Index set covers positions {}..{}, but enumerated type {} has {} member(s)",
+				e.start,
+				e.end,
+				e.ty.name(),
+				e.ty.len()
+			)),
+			Index::Enum(e) => Ok(e.end - e.start + 1),
+		}
+	}
+
 	fn iter(&self) -> IndexIter {
 		match self {
 			Index::Integer(x) => IndexIter::Integer(x.clone()),
@@ -372,10 +400,10 @@ impl Enum {
 		}
 	}
 
-	pub(crate) fn model_defined<I: IntoIterator<Item = Arc<str>>>(name: Arc<str>, deps: I) -> Self {
+	pub(crate) fn model_defined(name: Arc<str>, def: Option<EnumDefinition>) -> Self {
 		Self {
 			name,
-			state: EnumInner::AwaitData(Vec::from_iter(deps).into_boxed_slice()).into(),
+			state: EnumInner::AwaitData(def).into(),
 		}
 	}
 
@@ -400,21 +428,47 @@ impl Enum {
 		self.lock().iter().next().is_none()
 	}
 
+	/// Returns the member of the enumerated type created by the given constructor and arguments
+	///
+	/// Returns [`None`] if the enumerated type has no such constructor, or
+	/// when the arguments are not part of the domains of the constructor.
+	///
+	/// ## Warning
+	/// This function will panic if Enum type is uninitialized
+	pub fn member(self: &Arc<Self>, constructor: &str, args: &[Value]) -> Option<EnumValue> {
+		let mut offset = 1;
+		let mut found = None;
+		for (name, doms, len) in self.lock().iter() {
+			if &**name == constructor && args.len() == doms.len() {
+				let pos = args.iter().zip(doms.iter()).try_fold(0, |pos, (arg, dom)| {
+					Some(pos * domain_len(dom) + domain_position(dom, arg)?)
+				});
+				if let Some(pos) = pos {
+					found = Some(offset + pos);
+					break;
+				}
+			}
+			offset += len;
+		}
+		found.map(|pos| EnumValue::from_enum_and_pos(self.clone(), pos))
+	}
+
 	pub(crate) fn lock(&self) -> CtorLock {
 		CtorLock {
 			lock: self.state.lock().unwrap(),
 		}
 	}
 
-	pub(crate) fn get(&self, name: &str) -> Option<(usize, Box<[Index]>)> {
-		let mut offset = 1;
-		for ctor in self.lock().iter() {
-			if &*ctor.0 == name {
-				return Some((offset, ctor.1.clone()));
-			}
-			offset += ctor.2;
-		}
-		None
+	/// Returns the first range of each argument domain of the constructor with the given name
+	///
+	/// ## Warning
+	/// This function will panic if Enum type is uninitialized
+	pub(crate) fn signature(&self, name: &str) -> Option<Box<[Index]>> {
+		self.lock()
+			.constructors()
+			.iter()
+			.find(|ctor| &*ctor.0 == name)
+			.map(|ctor| ctor.1.iter().map(|dom| dom[0].clone()).collect())
 	}
 }
 
@@ -434,13 +488,23 @@ impl Display for Enum {
 				f,
 				"{} = {}",
 				self.name,
-				self.lock().iter().format_with(" ++ ", |ctor, f| {
-					if ctor.1.is_empty() {
-						f(&format_args!("{{{}}}", ctor.0)) // TODO: repeated constructors with no arguments should be grouped together
-					} else {
-						f(&format_args!("{}({})", ctor.0, ctor.1.iter().format(",")))
-					}
-				})
+				self.lock()
+					.definition()
+					.iter()
+					.format_with(" ++ ", |(name, doms), f| {
+						if doms.is_empty() {
+							f(&format_args!("{{{}}}", name)) // TODO: repeated constructors with no arguments should be grouped together
+						} else {
+							f(&format_args!(
+								"{}({})",
+								name,
+								doms.iter().format_with(",", |dom, f| match dom {
+									Set::Int(r) if r.is_empty() => f(&"{}"),
+									dom => f(dom),
+								})
+							))
+						}
+					})
 			)
 		}
 	}
@@ -456,21 +520,139 @@ impl<'a> CtorLock<'a> {
 	/// ## Warning
 	/// This function will panic if Enum type is uninitialized
 	pub fn iter(&self) -> impl Iterator<Item = &Constructor> {
+		self.constructors().iter().filter(|ctor| ctor.2 > 0)
+	}
+
+	/// Returns the list of the constructors of the enumerated type, including
+	/// constructors that do not create any members
+	///
+	/// ## Warning
+	/// This function will panic if Enum type is uninitialized
+	pub fn constructors(&self) -> &[Constructor] {
 		let EnumInner::Constructors(ref cons) = self.lock.deref() else {
 			panic!("cannot access constructors of an uninitialized enumerated type")
 		};
-		cons.iter().filter(|ctor| ctor.2 > 0)
+		cons
 	}
+
+	/// Returns the constructors of the enumerated type as they are written in its definition,
+	/// giving the domain of each argument as a set
+	///
+	/// ## Warning
+	/// This function will panic if Enum type is uninitialized
+	pub fn definition(&self) -> Vec<(Arc<str>, Vec<Set>)> {
+		self.constructors()
+			.iter()
+			.map(|(name, doms, _)| {
+				let doms = doms
+					.iter()
+					.map(|dom| match dom.first() {
+						Some(Index::Enum(_)) => Set::from_iter(dom.iter().map(|i| {
+							let Index::Enum(r) = i else { unreachable!() };
+							r.clone()
+						})),
+						_ => Set::Int(merge_ranges(dom.iter().map(|i| {
+							let Index::Integer(r) = i else { unreachable!() };
+							r.clone()
+						}))),
+					})
+					.collect();
+				(name.clone(), doms)
+			})
+			.collect()
+	}
+}
+
+/// Returns the number of values in the domain of a constructor argument
+fn domain_len(dom: &[Index]) -> usize {
+	dom.iter().map(Index::len).sum()
+}
+
+/// Returns the position of the given value in the domain of a constructor argument
+fn domain_position(dom: &[Index], val: &Value) -> Option<usize> {
+	let mut offset = 0;
+	for idx in dom {
+		match (idx, val) {
+			(Index::Integer(r), Value::Integer(v)) if r.contains(v) => {
+				return Some(offset + (v - r.start()) as usize)
+			}
+			(Index::Enum(r), Value::Enum(v)) if r.contains(v) => {
+				return Some(offset + v.pos - r.start)
+			}
+			_ => offset += idx.len(),
+		}
+	}
+	None
+}
+
+/// Returns the value at the given position in the domain of a constructor argument
+fn domain_value(dom: &[Index], mut pos: usize) -> Value {
+	for idx in dom {
+		if pos < idx.len() {
+			return match idx {
+				Index::Integer(r) => Value::Integer(r.start() + pos as i64),
+				Index::Enum(r) => EnumValue::from_enum_and_pos(r.enum_type(), r.start + pos).into(),
+			};
+		}
+		pos -= idx.len();
+	}
+	unreachable!("position outside of the domain of the constructor argument")
+}
+
+/// Sort the given integer ranges and combine the ones that overlap or are adjacent, removing empty ranges
+fn merge_ranges(ranges: impl IntoIterator<Item = RangeInclusive<i64>>) -> Vec<RangeInclusive<i64>> {
+	let mut merged: Vec<RangeInclusive<i64>> = Vec::new();
+	for r in ranges
+		.into_iter()
+		.filter(|r| !r.is_empty())
+		.sorted_by_key(|r| *r.start())
+	{
+		match merged.last_mut() {
+			Some(last) if *last.end() >= r.start() - 1 => {
+				*last = *last.start()..=max(*last.end(), *r.end())
+			}
+			_ => merged.push(r),
+		}
+	}
+	merged
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum EnumInner {
 	NoDefinition,
-	AwaitData(Box<[Arc<str>]>),
+	/// Defined in the model, awaiting the data used in the definition (or
+	/// [`None`] if the definition cannot be evaluated using the data)
+	AwaitData(Option<EnumDefinition>),
 	Constructors(Box<[Constructor]>),
 }
 
-pub(crate) type Constructor = (Arc<str>, Box<[Index]>, usize);
+impl EnumInner {
+	/// Create the constructor for a constructor function with the given (integer) argument domains
+	///
+	/// The domain of each argument is stored symbolically as its sorted,
+	/// non-overlapping ranges, so non-contiguous domains do not require the
+	/// members to be enumerated. An empty domain is stored as a single empty
+	/// range, in which case the constructor does not create any members.
+	pub(crate) fn constructor(name: Arc<str>, doms: Vec<Vec<RangeInclusive<i64>>>) -> Constructor {
+		let doms = doms
+			.into_iter()
+			.map(|dom| {
+				let ranges = merge_ranges(dom);
+				if ranges.is_empty() {
+					vec![Index::Integer(RangeInclusive::new(1, 0))]
+				} else {
+					ranges.into_iter().map(Index::Integer).collect()
+				}
+			})
+			.collect::<Box<[_]>>();
+		let len = doms.iter().map(|dom| domain_len(dom)).product();
+		(name, doms, len)
+	}
+}
+
+/// Constructor of an enumerated type: its name, the domain of each of its
+/// arguments (as sorted, non-overlapping ranges), and the number of members
+pub(crate) type Constructor = (Arc<str>, Box<[Vec<Index>]>, usize);
 
 /// Member declaration of an enumerated type
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -490,7 +672,7 @@ impl EnumValue {
 	pub(crate) fn constructor_and_args(&self) -> (Arc<str>, Vec<Value>) {
 		let mut val = self.pos - 1;
 		let lock = self.ty.lock();
-		let (name, idx, _) = lock
+		let (name, doms, _) = lock
 			.iter()
 			.skip_while(|(_, _, len)| {
 				if val >= *len {
@@ -503,16 +685,11 @@ impl EnumValue {
 			.take(1)
 			.next()
 			.unwrap();
-		let mut args = vec![Value::Absent; idx.len()];
-		for i in (0..idx.len()).rev() {
-			let offset = val % idx[i].len();
-			args[i] = match &idx[i] {
-				Index::Integer(ii) => Value::Integer(ii.start() + offset as i64),
-				Index::Enum(ii) => {
-					EnumValue::from_enum_and_pos(ii.enum_type(), ii.start + offset).into()
-				}
-			};
-			val /= idx[i].len();
+		let mut args = vec![Value::Absent; doms.len()];
+		for i in (0..doms.len()).rev() {
+			let len = domain_len(&doms[i]);
+			args[i] = domain_value(&doms[i], val % len);
+			val /= len;
 		}
 		(name.clone(), args)
 	}
@@ -744,7 +921,7 @@ impl DoubleEndedIterator for EnumRangeInclusive {
 }
 impl ExactSizeIterator for EnumRangeInclusive {
 	fn len(&self) -> usize {
-		self.end - self.start + 1
+		(self.end + 1).saturating_sub(self.start)
 	}
 }
 impl FusedIterator for EnumRangeInclusive {}
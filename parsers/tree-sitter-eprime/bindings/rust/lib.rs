@@ -40,6 +40,12 @@ pub const NODE_TYPES: &str = include_str!("../../src/node-types.json");
 // pub const LOCALS_QUERY: &'static str = include_str!("../../queries/locals.scm");
 // pub const TAGS_QUERY: &'static str = include_str!("../../queries/tags.scm");
 
+/// Get identifier names
+pub const IDENTIFIERS_QUERY: &str = include_str!("../../queries/identifiers.scm");
+
+/// Get comments
+pub const COMMENTS_QUERY: &str = include_str!("../../queries/comments.scm");
+
 #[cfg(test)]
 mod tests {
 	#[test]
@@ -0,0 +1 @@
+(line_comment) @comment
@@ -0,0 +1 @@
+(identifier) @identifier